@@ -0,0 +1,19 @@
+// disk functions, linked on demand when a program calls them
+
+int disk_request(int request, int block, int* buf){
+    int* DKS = 212 * sizeof(int);
+    int* DKB = 213 * sizeof(int);
+    int* DKA = 214 * sizeof(int);
+    *DKB = block;
+    *DKA = (int)buf / sizeof(int);
+    *DKS = request;
+    return *DKS;
+}
+
+int disk_read(int block, int* buf){
+    return disk_request(1, block, buf);
+}
+
+int disk_write(int block, int* buf){
+    return disk_request(2, block, buf);
+}
@@ -0,0 +1,8 @@
+// the disk is a block device, the os's file system is stored on it (see layout.rs)
+// writing blocks directly can corrupt the files
+#define DISK_BLOCK_SIZE 512
+
+// buf has DISK_BLOCK_SIZE ints, one for each byte of the block
+// return 0, or -1 if the block is out of the disk
+int disk_read(int block, int* buf);
+int disk_write(int block, int* buf);
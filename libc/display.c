@@ -0,0 +1,28 @@
+// display functions, linked on demand when a program calls them
+
+int* display_cell(int row, int column){
+    int* cells = 10000 * sizeof(int);
+    return cells + row * 80 + column;
+}
+
+void display_put(int row, int column, char c){
+    if(row < 0 || row >= 25 || column < 0 || column >= 80){
+        return;
+    }
+    *display_cell(row, column) = c;
+}
+
+void display_print(int row, int column, char* str){
+    while(*str != 0){
+        display_put(row, column, *str);
+        column++;
+        str++;
+    }
+}
+
+void display_clear(){
+    int* cells = display_cell(0, 0);
+    for(int i = 0; i < 80 * 25; i++){
+        *(cells + i) = 0;
+    }
+}
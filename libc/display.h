@@ -0,0 +1,10 @@
+// the display is 80x25 character cells after the stack, see layout.rs
+// the functions assume the default memory layout
+#define DISPLAY_COLUMNS 80
+#define DISPLAY_ROWS 25
+
+// puts c in the cell at (row, column), cells outside the display are ignored
+void display_put(int row, int column, char c);
+// puts the chars of str from (row, column) onwards, they don't wrap to the next row
+void display_print(int row, int column, char* str);
+void display_clear();
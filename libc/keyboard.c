@@ -0,0 +1,12 @@
+// keyboard functions, linked on demand when a program calls them
+
+int read_key(){
+    int* KBS = 210 * sizeof(int);
+    int* KBD = 211 * sizeof(int);
+    *KBS = 1;
+    return *KBD;
+}
+
+void on_key(void (*handler)()){
+    __syscall(11, handler);
+}
@@ -0,0 +1,7 @@
+// the keyboard queues key presses until they're read, see layout.rs
+
+// returns the ascii value of the next key press, or 0 if no key was pressed
+int read_key();
+// handler is called when keys are pressed, with interrupts disabled until it returns, 0 removes it
+// setting a handler enables interrupts
+void on_key(void (*handler)());
@@ -1,9 +1,113 @@
+#include <stdarg.h>
+
 void* malloc(int size);
 void free(void* addr);
+char* itos(int num);
+char* ftos(float num);
+
+// syscalls
+void exit(int status){
+    __syscall(1, status);
+}
+
+void abort(){
+    __syscall(1, 134);
+}
+
+int getchar(){
+    return __syscall(3);
+}
+
+// reads a line from stdin into buf, without the newline
+// returns buf, or 0 if there's no more input
+char* gets(char* buf){
+    int c = getchar();
+    if(c == -1){
+        return 0;
+    }
+    char* p = buf;
+    while(c != -1 && c != '\n'){
+        *p = c;
+        p++;
+        c = getchar();
+    }
+    *p = 0;
+    return buf;
+}
+
+void* sbrk(int increment){
+    return __syscall(4, increment);
+}
+
+// the arguments are next to each other on the stack, so syscalls with several arguments get the address of the first one
+// the OS addresses words, so with byte addressing the syscalls that take or return an address don't work
+int open(char* path, int flags){
+    return __syscall(6, &path);
+}
+
+int read(int fd, char* buf, int count){
+    return __syscall(7, &fd);
+}
+
+int write(int fd, char* buf, int count){
+    return __syscall(8, &fd);
+}
+
+int close(int fd){
+    return __syscall(9, fd);
+}
+
+int unlink(char* path){
+    return __syscall(10, path);
+}
+
+int spawn(char* path){
+    return __syscall(12, path);
+}
+
+int waitpid(int pid){
+    return __syscall(13, pid);
+}
+
+int shm_create(int key, int size){
+    return __syscall(14, &key);
+}
+
+void* shm_attach(int key){
+    return __syscall(15, key);
+}
+
+// a free mutex is taken without a syscall, the os blocks the caller until a locked one is handed over to it
+int mutex_lock(int* mutex){
+    if(__test_and_set(mutex)){
+        return __syscall(16, mutex);
+    }
+    return 0;
+}
+
+void mutex_unlock(int* mutex){
+    __syscall(17, mutex);
+}
+
+void sleep(int cycles){
+    __syscall(18, cycles);
+}
+
+int clock(){
+    return __syscall(19);
+}
+
+int rand(){
+    return __syscall(20);
+}
+
+void srand(int seed){
+    __syscall(21, seed);
+}
 
 void putc(char c){
-    int* COS = 200;
-    int* COD = 201;
+    int* COS = 200 * sizeof(int);
+    int* COD = 201 * sizeof(int);
     *COD = c;
     *COS = 1;
 }
@@ -14,6 +118,74 @@ void puts(char* str){
     }
 }
 
+int putchar(int c){
+    return __syscall(2, c);
+}
+
+// prints the digits of num in hex, without leading zeros
+// returns the number of characters written
+int print_hex(int num){
+    int written = 0;
+    for(int shift = 28; shift >= 0; shift -= 4){
+        int digit = (num >> shift) & 15;
+        if(digit != 0 || written > 0 || shift == 0){
+            putc(digit < 10 ? '0' + digit : 'a' + digit - 10);
+            written++;
+        }
+    }
+    return written;
+}
+
+// prints & frees a string that was allocated by itos or ftos
+// returns the number of characters written
+int print_allocated(char* str){
+    int written = 0;
+    for(char* c = str; *c != 0; c++){
+        putc(*c);
+        written++;
+    }
+    free(str);
+    return written;
+}
+
+// format specifiers: %d, %i, %x, %c, %s, %f, %%
+// returns the number of characters written
+int printf(char* fmt, ...){
+    va_list args;
+    va_start(args, fmt);
+    int written = 0;
+    for(; *fmt != 0; fmt++){
+        if(*fmt != '%'){
+            putc(*fmt);
+            written++;
+            continue;
+        }
+        fmt++;
+        if(*fmt == 'd' || *fmt == 'i'){
+            written += print_allocated(itos(va_arg(args, int)));
+        } else if(*fmt == 'x'){
+            written += print_hex(va_arg(args, int));
+        } else if(*fmt == 'f'){
+            written += print_allocated(ftos(va_arg(args, float)));
+        } else if(*fmt == 's'){
+            for(char* c = va_arg(args, char*); *c != 0; c++){
+                putc(*c);
+                written++;
+            }
+        } else if(*fmt == 'c'){
+            putc(va_arg(args, char));
+            written++;
+        } else if(*fmt == '%'){
+            putc('%');
+            written++;
+        } else if(*fmt == 0){
+            break;
+        }
+    }
+    va_end(args);
+    return written;
+}
+
 void revstr(char* str, int start, int end){
     end -= 1;
     for(; start < end; start++, end--){
@@ -28,11 +200,12 @@ void revstr(char* str, int start, int end){
 // integer to string
 char* itos(int num) {
     int sign = num >= 0;
-    num = num >= 0 ? num : -num;
+    // the digits are taken from the negative of the number, since the negative of INT_MIN doesn't fit in an int
+    num = num >= 0 ? -num : num;
     int orig_num = num;
 
     int str_len = 0;
-    for(; num > 0; num /= 10, str_len++) {
+    for(; num < 0; num /= 10, str_len++) {
     }
 
     str_len = orig_num != 0 ? str_len : 1;
@@ -40,7 +213,7 @@ char* itos(int num) {
     char* cur_char = str;
     num = orig_num;
     for(int i = 0; i < str_len; i++, cur_char++, num /= 10) {
-        *cur_char = '0' + (num % 10);
+        *cur_char = '0' - (num % 10);
     }
     if (!sign) {
         *cur_char = '-';
@@ -51,74 +224,53 @@ char* itos(int num) {
     return str;
 }
 
-struct FreeBlock {
-    struct FreeBlock* next_free;
-    struct FreeBlock* prev_free;
-    char* start; 
-    int size;
-};
-struct FreeBlock* free_root;
-
-
-void malloc_init(){
-    // yo we need #define
-    int HEAP_START = 4000;
-    int HEAP_END = 6000;
-    free_root = (struct FreeBlock*) (HEAP_START);
-    free_root->next_free = 0;
-    free_root->prev_free = 0;
-    free_root->start = (void*) (HEAP_START);
-    free_root->size = HEAP_END - HEAP_START;
-    // puts(itos(free_root->size));
-}
-
-void* malloc(int alloc_size){
-    if (!free_root) {
-        malloc_init();
-    }
-    // find free block with sufficient size & remove a chunk from it for allocation
-    struct FreeBlock* cur = free_root;
-    while (cur){
-        if (cur->size >= alloc_size + sizeof(struct FreeBlock) + sizeof(int)){
-            void* alloc_addr = cur->start + sizeof(int);
-            struct FreeBlock* rest_of_cur = (struct FreeBlock*) (alloc_addr + alloc_size);
-            if (cur->prev_free) {
-                cur->prev_free->next_free = rest_of_cur;
-            }
-            if (cur->next_free) {
-                cur->next_free->prev_free = rest_of_cur;
-            }
-            int* alloc_size_save = (int*) cur->start;
-            struct FreeBlock* cur_next = cur->next_free;
-            struct FreeBlock* cur_prev = cur->prev_free;
-            int cur_size = cur->size;
-            rest_of_cur->next_free = cur_next;
-            rest_of_cur->prev_free = cur_prev;
-            rest_of_cur->start = (char*) rest_of_cur;
-            rest_of_cur->size = cur_size - alloc_size - sizeof(int);
-            if (free_root == cur) {
-                free_root = rest_of_cur;
-            }
-            *alloc_size_save = alloc_size;
-            return alloc_addr;
-        }
-        cur = cur->next_free;
+// float to string, with 6 digits after the point like printf's %f
+// the whole part must fit in an int
+char* ftos(float num) {
+    int negative = num < 0;
+    num = negative ? -num : num;
+    int whole = num;
+    int frac = (num - whole) * 1000000 + 0.5;
+    if (frac >= 1000000) {
+        // e.g 0.9999999 is rounded up to 1.000000
+        whole++;
+        frac -= 1000000;
     }
-    return 0;
+    char* whole_str = itos(whole);
+    int whole_len = 0;
+    for(char* c = whole_str; *c != 0; c++, whole_len++) {
+    }
+    // the sign, the whole part, the point, 6 digits & the terminator
+    char* str = malloc((negative + whole_len + 8) * sizeof(char));
+    char* cur_char = str;
+    if (negative) {
+        *cur_char = '-';
+        cur_char++;
+    }
+    for(char* c = whole_str; *c != 0; c++, cur_char++) {
+        *cur_char = *c;
+    }
+    free(whole_str);
+    *cur_char = '.';
+    for(int i = 6; i > 0; i--, frac /= 10) {
+        *(cur_char + i) = '0' + (frac % 10);
+    }
+    *(cur_char + 7) = 0;
+    return str;
+}
+
+void* malloc(int size){
+    // heap is managed by the OS, which allocates words
+    int* MAS = 204 * sizeof(int);
+    int* MAD = 205 * sizeof(int);
+    *MAD = size;
+    *MAS = 1;
+    return *MAD * sizeof(int);
 }
 
 void free(void* addr){
-    // insert a new free block to the free list
-    struct FreeBlock* prev_root = free_root;
-    int alloc_size = *((int*)(addr-sizeof(int)));
-    struct FreeBlock* addr_block = (struct FreeBlock*) addr - sizeof(int);
-    if (alloc_size < sizeof(struct FreeBlock)) {
-        return; // freed chunk is too small insert into free list
-    }
-    addr_block->start = addr;
-    addr_block->size = alloc_size + sizeof(int);
-    addr_block->next_free = prev_root;
-    addr_block->prev_free = 0;
-    prev_root->prev_free = addr_block;
-    free_root = addr_block;
+    int* FRS = 206 * sizeof(int);
+    int* FRD = 207 * sizeof(int);
+    *FRD = (int)addr / sizeof(int);
+    *FRS = 1;
 }
@@ -1,5 +1,53 @@
 void putc(char c);
 void puts(char* str);
+int putchar(int c);
+int printf(char* fmt, ...);
 void* malloc(int size);
 void free(void* addr);
-char* itos(int num);
\ No newline at end of file
+// allocates size bytes in the frame of the calling function, they are freed when it returns
+void* alloca(int size);
+char* itos(int num);
+char* ftos(float num);
+// exit & abort are compiler builtins, they're also defined here so their address can be taken
+void exit(int status);
+// exits with status 134, like a process killed by SIGABRT
+void abort();
+int getchar();
+char* gets(char* buf);
+void* sbrk(int increment);
+
+// files, flags are as in POSIX: O_RDONLY 0, O_WRONLY 1, O_RDWR 2, O_CREAT 64, O_TRUNC 512, O_APPEND 1024
+// fd 0 reads from stdin & fd 1 writes to stdout
+int open(char* path, int flags);
+int read(int fd, char* buf, int count);
+int write(int fd, char* buf, int count);
+int close(int fd);
+int unlink(char* path);
+
+// processes, a program is a binary image file in the file system (see OS::link_image)
+// returns the pid of the child that runs the program, or -1
+int spawn(char* path);
+// blocks until the child exits, returns its exit value or -1 if pid isn't a child that wasn't waited for yet
+int waitpid(int pid);
+
+// shared memory, a block that every process can access at the same address. blocks are identified by keys
+// size is in words, the block is zeroed. returns 0, or -1 if the key is taken
+int shm_create(int key, int size);
+// returns the address of the block with the key, or 0 if there's none
+void* shm_attach(int key);
+// mutexes are ints that are 0 when they're unlocked, usually in shared memory
+// returns 0, or -1 if the mutex can never be unlocked
+int mutex_lock(int* mutex);
+void mutex_unlock(int* mutex);
+
+// time is measured in cpu cycles
+// the other processes run while the caller sleeps, sleep(0) lets them run before it continues
+void sleep(int cycles);
+// the number of cycles the cpu ran, e.g for timing code
+int clock();
+
+// pseudo random numbers, the same in each run unless the os's seed is changed (run --seed=<n>)
+// returns a number between 0 & RAND_MAX (2147483647)
+int rand();
+// restarts the numbers from the seed
+void srand(int seed);
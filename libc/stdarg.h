@@ -0,0 +1,14 @@
+// access to the args of a variadic function, e.g int printf(char* fmt, ...)
+// the caller pushes the args in reverse, so the variable args follow the last named param in memory
+#pragma once
+
+typedef char* va_list;
+
+// the size of an arg on the stack, args take whole words so e.g a char takes a word
+#define __va_size(x) ((sizeof(x) + sizeof(int) - 1) / sizeof(int) * sizeof(int))
+
+// points ap at the arg after the named param last
+#define va_start(ap, last) ((ap) = (char*)&(last) + __va_size(last))
+// the next arg, which has the given type
+#define va_arg(ap, type) ((ap) += __va_size(type), *(type*)((ap) - __va_size(type)))
+#define va_end(ap) ((ap) = 0)
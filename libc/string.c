@@ -0,0 +1,37 @@
+// string functions, linked on demand when a program calls them
+
+int strlen(char* str){
+    int len = 0;
+    while(*(str + len) != 0){
+        len++;
+    }
+    return len;
+}
+
+char* strcpy(char* dst, char* src){
+    char* cur = dst;
+    while(*src != 0){
+        *cur = *src;
+        cur++;
+        src++;
+    }
+    *cur = 0;
+    return dst;
+}
+
+int strcmp(char* s1, char* s2){
+    while(*s1 != 0 && *s1 == *s2){
+        s1++;
+        s2++;
+    }
+    return *s1 - *s2;
+}
+
+void* memcpy(void* dst, void* src, int n){
+    char* dst_chars = dst;
+    char* src_chars = src;
+    for(int i = 0; i < n; i++){
+        *(dst_chars + i) = *(src_chars + i);
+    }
+    return dst;
+}
@@ -0,0 +1,4 @@
+int strlen(char* str);
+char* strcpy(char* dst, char* src);
+int strcmp(char* s1, char* s2);
+void* memcpy(void* dst, void* src, int n);
\ No newline at end of file
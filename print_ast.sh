@@ -1,3 +0,0 @@
-#!/bin/bash
-
-src/operating_system/compiler/parser/venv/bin/python src/operating_system/compiler/parser/to_ast_json.py $1
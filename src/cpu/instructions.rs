@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Register {
     R1,
     R2,
@@ -12,6 +13,17 @@ pub enum Register {
     ZR,
 }
 
+pub const ALL_REGISTERS: [Register; 8] = [
+    Register::R1,
+    Register::R2,
+    Register::R3,
+    Register::R4,
+    Register::SP,
+    Register::BP,
+    Register::IR,
+    Register::ZR,
+];
+
 impl FromStr for Register {
     type Err = ();
     fn from_str(s: &str) -> Result<Register, ()> {
@@ -57,18 +69,36 @@ impl Register {
         )
     }
 }
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum BinArithOp {
     ADD,
     SUB,
     MUL,
-    DIV,
+    DIV, // DIV & MOD by zero fault, see Fault::DivisionByZero
     MOD,
     AND,
     OR,
     SHL,
     SHR,
     XOR,
+    // floating point, the registers hold the bits of f32 values
+    FADD,
+    FSUB,
+    FMUL,
+    FDIV,
+    FCMP, // -1, 0 or 1 if the first float is less than, equal to or greater than the second, 2 if they're unordered (NaN)
+    // multi-word arithmetic, ZR holds the carry (or borrow) in & out
+    ADC,
+    SBC,
+}
+
+/// the f32 whose bits are in the register
+fn to_float(x: i32) -> f32 {
+    f32::from_bits(x as u32)
+}
+
+fn from_float(x: f32) -> i32 {
+    x.to_bits() as i32
 }
 
 impl FromStr for BinArithOp {
@@ -85,6 +115,13 @@ impl FromStr for BinArithOp {
             "SHL" => Ok(BinArithOp::SHL),
             "SHR" => Ok(BinArithOp::SHR),
             "XOR" => Ok(BinArithOp::XOR),
+            "FADD" => Ok(BinArithOp::FADD),
+            "FSUB" => Ok(BinArithOp::FSUB),
+            "FMUL" => Ok(BinArithOp::FMUL),
+            "FDIV" => Ok(BinArithOp::FDIV),
+            "FCMP" => Ok(BinArithOp::FCMP),
+            "ADC" => Ok(BinArithOp::ADC),
+            "SBC" => Ok(BinArithOp::SBC),
             _ => Err(()),
         }
     }
@@ -92,29 +129,61 @@ impl FromStr for BinArithOp {
 impl BinArithOp {
     pub fn eval(&self, x: i32, y: i32) -> i32 {
         match &self {
-            BinArithOp::ADD => x + y,
-            BinArithOp::SUB => x - y,
-            BinArithOp::MUL => x * y,
-            BinArithOp::DIV => x / y,
-            BinArithOp::MOD => x % y,
+            // signed overflow wraps around, like two's complement hardware
+            BinArithOp::ADD => x.wrapping_add(y),
+            BinArithOp::SUB => x.wrapping_sub(y),
+            BinArithOp::MUL => x.wrapping_mul(y),
+            // division rounds toward zero and the remainder has the sign of the dividend, as in C
+            // the only overflowing case, i32::MIN / -1, wraps around
+            BinArithOp::DIV => x.wrapping_div(y),
+            BinArithOp::MOD => x.wrapping_rem(y),
             BinArithOp::AND => x & y,
             BinArithOp::OR => x | y,
-            BinArithOp::SHL => x << y,
-            BinArithOp::SHR => x >> y,
+            // only the low 5 bits of the shift count are used, like x86's shifts
+            BinArithOp::SHL => x.wrapping_shl(y as u32),
+            BinArithOp::SHR => x.wrapping_shr(y as u32),
             BinArithOp::XOR => x ^ y,
+            BinArithOp::FADD => from_float(to_float(x) + to_float(y)),
+            BinArithOp::FSUB => from_float(to_float(x) - to_float(y)),
+            BinArithOp::FMUL => from_float(to_float(x) * to_float(y)),
+            BinArithOp::FDIV => from_float(to_float(x) / to_float(y)),
+            // NaN is unordered, it isn't less than, equal to or greater than anything
+            BinArithOp::FCMP => match to_float(x).partial_cmp(&to_float(y)) {
+                Some(ordering) => ordering as i32,
+                None => 2,
+            },
+            BinArithOp::ADC | BinArithOp::SBC => self.eval_carry(x, y, 0).0,
         }
     }
+
+    /// evaluates ADC or SBC with the given carry in, returns the result & the carry out
+    /// the words are added or subtracted as unsigned values, the carry out of SBC is 1 if it borrowed
+    pub fn eval_carry(&self, x: i32, y: i32, carry: i32) -> (i32, i32) {
+        let (x, y, carry) = (x as u32 as i64, y as u32 as i64, (carry != 0) as i64);
+        let res = match &self {
+            BinArithOp::ADC => x + y + carry,
+            BinArithOp::SBC => x - y - carry,
+            _ => panic!("{:?} doesn't use the carry", self),
+        };
+        (res as u32 as i32, (res >> 32 != 0) as i32)
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum UnaryArithOp {
     NEG,
+    NOT, // bitwise not
+    ITOF, // converts the int to a float
+    FTOI, // converts the float to an int, rounding toward zero
 }
 impl FromStr for UnaryArithOp {
     type Err = ();
     fn from_str(s: &str) -> Result<UnaryArithOp, ()> {
         match s {
             "NEG" => Ok(UnaryArithOp::NEG),
+            "NOT" => Ok(UnaryArithOp::NOT),
+            "ITOF" => Ok(UnaryArithOp::ITOF),
+            "FTOI" => Ok(UnaryArithOp::FTOI),
             _ => Err(()),
         }
     }
@@ -123,17 +192,25 @@ impl FromStr for UnaryArithOp {
 impl UnaryArithOp {
     pub fn eval(&self, x: i32) -> i32 {
         match &self {
-            UnaryArithOp::NEG => -x,
+            // the negation of i32::MIN is itself
+            UnaryArithOp::NEG => x.wrapping_neg(),
+            UnaryArithOp::NOT => !x,
+            UnaryArithOp::ITOF => from_float(x as f32),
+            // out of range values saturate & NaN is 0
+            UnaryArithOp::FTOI => to_float(x) as i32,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum DataOp {
     LOAD,
     STR,
     MOV,
     LEA, // load efective address, for loading stuff from data section
+    LOADB, // loads the byte at a byte address, sign extended
+    STRB, // stores the low byte of the value at a byte address, the word's other bytes are kept
+    TSET, // test & set: loads the word at the address & sets it to 1, atomically since interrupts only come between instructions
 }
 
 impl FromStr for DataOp {
@@ -144,12 +221,15 @@ impl FromStr for DataOp {
             "STR" => Ok(DataOp::STR),
             "MOV" => Ok(DataOp::MOV),
             "LEA" => Ok(DataOp::LEA),
+            "LOADB" => Ok(DataOp::LOADB),
+            "STRB" => Ok(DataOp::STRB),
+            "TSET" => Ok(DataOp::TSET),
             _ => Err(()),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum StackOp {
     PUSH,
     POP,
@@ -165,7 +245,7 @@ impl FromStr for StackOp {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TestOp {
     TSTE,
     TSTN,
@@ -196,7 +276,7 @@ impl TestOp {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum FlowOp {
     JUMP,
     TJMP,
@@ -227,10 +307,31 @@ impl FlowOp {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// flow operations whose target address is in a register
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum FlowRegOp {
+    CALLR, // call the function whose (absolute) address is in the register
+    JMPR, // jump to the (absolute) address in the register, e.g from a switch's jump table
+}
+impl FromStr for FlowRegOp {
+    type Err = ();
+    fn from_str(s: &str) -> Result<FlowRegOp, ()> {
+        match s {
+            "CALLR" => Ok(FlowRegOp::CALLR),
+            "JMPR" => Ok(FlowRegOp::JMPR),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum OtherOp {
     HALT,
     RET,
+    SYSCALL, // syscall number in R1, argument in R2, result is written to R1
+    CLI, // disable interrupts
+    STI, // enable interrupts
+    IRET, // return from an interrupt handler, like RET & also enables interrupts
 }
 impl FromStr for OtherOp {
     type Err = ();
@@ -238,12 +339,16 @@ impl FromStr for OtherOp {
         match s {
             "HALT" => Ok(OtherOp::HALT),
             "RET" => Ok(OtherOp::RET),
+            "SYSCALL" => Ok(OtherOp::SYSCALL),
+            "CLI" => Ok(OtherOp::CLI),
+            "STI" => Ok(OtherOp::STI),
+            "IRET" => Ok(OtherOp::IRET),
             _ => Err(()),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum RegOrImm {
     Reg(Register),
     Val(i32),
@@ -285,7 +390,34 @@ impl RegOrImm {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// the opcodes of the binary encoding, an opcode is its operation's index in this table
+const OPCODES: [&str; 46] = [
+    "NEG", "NOT",
+    "ADD", "SUB", "MUL", "DIV", "MOD", "AND", "OR", "SHL", "SHR", "XOR",
+    "LOAD", "STR", "MOV", "LEA",
+    "PUSH", "POP",
+    "TSTE", "TSTN", "TSTG", "TSTL",
+    "JUMP", "TJMP", "FJMP", "CALL",
+    "CALLR",
+    "HALT", "RET", "SYSCALL", "CLI", "STI", "IRET",
+    "LOADB", "STRB",
+    "FADD", "FSUB", "FMUL", "FDIV", "FCMP", "ITOF", "FTOI",
+    "ADC", "SBC",
+    "TSET",
+    "JMPR",
+];
+
+// fields of an encoded instruction, see Instruction::encode
+const OPCODE_SHIFT: u32 = 56;
+const REG_SHIFTS: [u32; 2] = [52, 48];
+const OPERAND_REG_SHIFT: u32 = 44;
+const IMM_FLAG: u64 = 1 << 40;
+
+fn register_index(reg: &Register) -> u64 {
+    ALL_REGISTERS.iter().position(|r| r == reg).unwrap() as u64
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Instruction {
     UnaryArith {
         op: UnaryArithOp,
@@ -315,12 +447,48 @@ pub enum Instruction {
         op: FlowOp,
         offset: i32,
     },
+    FlowReg {
+        op: FlowRegOp,
+        reg: Register,
+    },
     Other {
         op: OtherOp,
     },
 }
 
 impl Instruction {
+    /// the number of cycles it takes to execute the instruction
+    /// memory accesses & multiplication are more expensive than register operations
+    pub fn cycles(&self) -> u32 {
+        match self {
+            Instruction::BinArith { op: BinArithOp::MUL, .. } | Instruction::BinArith { op: BinArithOp::FMUL, .. } => 3,
+            Instruction::BinArith { op: BinArithOp::DIV, .. } | Instruction::BinArith { op: BinArithOp::MOD, .. } => 4,
+            Instruction::BinArith { op: BinArithOp::FDIV, .. } => 4,
+            Instruction::Data { op: DataOp::LOAD, .. } | Instruction::Data { op: DataOp::STR, .. } => 2,
+            Instruction::Data { op: DataOp::LOADB, .. } | Instruction::Data { op: DataOp::STRB, .. } => 2,
+            Instruction::Data { op: DataOp::TSET, .. } => 3,
+            Instruction::Stack { .. } => 2,
+            // pushes or pops the return address & BP
+            Instruction::Flow { op: FlowOp::CALL, .. } | Instruction::FlowReg { op: FlowRegOp::CALLR, .. } => 3,
+            Instruction::Other { op: OtherOp::RET } | Instruction::Other { op: OtherOp::IRET } => 3,
+            _ => 1,
+        }
+    }
+
+    /// the name of the instruction's operation, e.g "ADD"
+    pub fn opcode(&self) -> String {
+        match &self {
+            Instruction::UnaryArith { op, .. } => format!("{:?}", op),
+            Instruction::BinArith { op, .. } => format!("{:?}", op),
+            Instruction::Data { op, .. } => format!("{:?}", op),
+            Instruction::Stack { op, .. } => format!("{:?}", op),
+            Instruction::Test { op, .. } => format!("{:?}", op),
+            Instruction::Flow { op, .. } => format!("{:?}", op),
+            Instruction::FlowReg { op, .. } => format!("{:?}", op),
+            Instruction::Other { op } => format!("{:?}", op),
+        }
+    }
+
     pub fn to_str(&self) -> String {
         match &self {
             Instruction::UnaryArith { op, arg } => format!("{:?} {:?}", op, arg),
@@ -334,10 +502,69 @@ impl Instruction {
             Instruction::Stack { op, dst } => format!("{:?} {:?}", op, dst),
             Instruction::Test { op, arg1, arg2 } => format!("{:?} {:?} {:?}", op, arg1, arg2),
             Instruction::Flow { op, offset } => format!("{:?} {:?}", op, offset),
+            Instruction::FlowReg { op, reg } => format!("{:?} {:?}", op, reg),
             Instruction::Other { op } => format!("{:?}", op),
         }
     }
 
+    /// encodes the instruction as a 64 bit word:
+    /// bits 56-63 are the opcode, bits 52-55 & 48-51 are the first & second registers,
+    /// the last operand is either a register in bits 44-47, or an immediate in bits 0-31 with bit 40 set
+    pub fn encode(&self) -> u64 {
+        let (regs, operand) = match self {
+            Instruction::UnaryArith { arg, .. } => (vec![arg], None),
+            Instruction::BinArith { dst, arg1, arg2, .. } => (vec![dst, arg1], Some(arg2.clone())),
+            Instruction::Data { dst, src, .. } => (vec![dst], Some(src.clone())),
+            Instruction::Stack { dst, .. } => (vec![dst], None),
+            Instruction::Test { arg1, arg2, .. } => (vec![arg1], Some(arg2.clone())),
+            Instruction::Flow { offset, .. } => (vec![], Some(RegOrImm::Val(*offset))),
+            Instruction::FlowReg { reg, .. } => (vec![reg], None),
+            Instruction::Other { .. } => (vec![], None),
+        };
+        let opcode = OPCODES.iter().position(|name| *name == self.opcode()).unwrap() as u64;
+        let mut word = opcode << OPCODE_SHIFT;
+        for (reg, shift) in regs.iter().zip(REG_SHIFTS.iter()) {
+            word |= register_index(reg) << shift;
+        }
+        match operand {
+            Some(RegOrImm::Reg(reg)) => word |= register_index(&reg) << OPERAND_REG_SHIFT,
+            Some(RegOrImm::Val(val)) => word |= IMM_FLAG | val as u32 as u64,
+            None => {},
+        }
+        word
+    }
+
+    /// the inverse of encode, returns None if the word isn't a valid instruction
+    pub fn decode(word: u64) -> Option<Instruction> {
+        let op = *OPCODES.get((word >> OPCODE_SHIFT) as usize)?;
+        let reg_at = |shift: u32| ALL_REGISTERS.get(((word >> shift) & 0xf) as usize).cloned();
+        let reg = |i: usize| reg_at(REG_SHIFTS[i]);
+        let operand = || -> Option<RegOrImm> {
+            if word & IMM_FLAG != 0 {
+                Some(RegOrImm::Val(word as u32 as i32))
+            } else {
+                Some(RegOrImm::Reg(reg_at(OPERAND_REG_SHIFT)?))
+            }
+        };
+        if let Ok(op) = UnaryArithOp::from_str(op) {
+            Some(Instruction::UnaryArith { op, arg: reg(0)? })
+        } else if let Ok(op) = BinArithOp::from_str(op) {
+            Some(Instruction::BinArith { op, dst: reg(0)?, arg1: reg(1)?, arg2: operand()? })
+        } else if let Ok(op) = DataOp::from_str(op) {
+            Some(Instruction::Data { op, dst: reg(0)?, src: operand()? })
+        } else if let Ok(op) = StackOp::from_str(op) {
+            Some(Instruction::Stack { op, dst: reg(0)? })
+        } else if let Ok(op) = TestOp::from_str(op) {
+            Some(Instruction::Test { op, arg1: reg(0)?, arg2: operand()? })
+        } else if let Ok(op) = FlowOp::from_str(op) {
+            Some(Instruction::Flow { op, offset: word as u32 as i32 })
+        } else if let Ok(op) = FlowRegOp::from_str(op) {
+            Some(Instruction::FlowReg { op, reg: reg(0)? })
+        } else {
+            Some(Instruction::Other { op: OtherOp::from_str(op).ok()? })
+        }
+    }
+
     pub fn from_str(instruction_str: &str) -> Result<Instruction, ()> {
         let args: Vec<&str> = instruction_str.split_whitespace().collect();
         let op = args[0];
@@ -382,6 +609,12 @@ impl Instruction {
                 op: op,
                 offset: args[1].parse::<i32>().unwrap(),
             });
+        } else if let Result::Ok(op) = FlowRegOp::from_str(&op) {
+            assert!(args.len() == 2);
+            return Ok(Instruction::FlowReg {
+                op: op,
+                reg: Register::from_str(args[1]).unwrap(),
+            });
         } else if let Result::Ok(op) = OtherOp::from_str(&op) {
             assert!(args.len() == 1);
             return Ok(Instruction::Other { op: op });
@@ -506,6 +739,103 @@ mod tests {
         )
     }
     #[test]
+    fn callr_from_str() {
+        assert_eq!(
+            Instruction::from_str("CALLR R2").unwrap(),
+            Instruction::FlowReg {
+                op: FlowRegOp::CALLR,
+                reg: Register::R2
+            }
+        )
+    }
+    #[test]
+    fn jmpr_from_str() {
+        assert_eq!(
+            Instruction::from_str("JMPR R1").unwrap(),
+            Instruction::FlowReg {
+                op: FlowRegOp::JMPR,
+                reg: Register::R1
+            }
+        )
+    }
+    #[test]
+    fn syscall_from_str() {
+        assert_eq!(
+            Instruction::from_str("SYSCALL").unwrap(),
+            Instruction::Other { op: OtherOp::SYSCALL }
+        )
+    }
+    #[test]
+    fn interrupt_ops_from_str() {
+        assert_eq!(Instruction::from_str("CLI").unwrap(), Instruction::Other { op: OtherOp::CLI });
+        assert_eq!(Instruction::from_str("STI").unwrap(), Instruction::Other { op: OtherOp::STI });
+        assert_eq!(Instruction::from_str("IRET").unwrap(), Instruction::Other { op: OtherOp::IRET });
+    }
+    #[test]
+    fn byte_ops_from_str() {
+        assert_eq!(
+            Instruction::from_str("LOADB R1 R2").unwrap(),
+            Instruction::Data {
+                op: DataOp::LOADB,
+                dst: Register::R1,
+                src: RegOrImm::Reg(Register::R2)
+            }
+        );
+        assert_eq!(
+            Instruction::from_str("STRB R1 65").unwrap(),
+            Instruction::Data {
+                op: DataOp::STRB,
+                dst: Register::R1,
+                src: RegOrImm::Val(65)
+            }
+        );
+    }
+    #[test]
+    fn float_ops() {
+        let (x, y) = (1.5f32.to_bits() as i32, (-4.0f32).to_bits() as i32);
+        assert_eq!(BinArithOp::FADD.eval(x, y), (-2.5f32).to_bits() as i32);
+        assert_eq!(BinArithOp::FMUL.eval(x, y), (-6.0f32).to_bits() as i32);
+        assert_eq!(BinArithOp::FDIV.eval(y, x), (-4.0f32 / 1.5).to_bits() as i32);
+        assert_eq!((BinArithOp::FCMP.eval(x, y), BinArithOp::FCMP.eval(y, x), BinArithOp::FCMP.eval(x, x)), (1, -1, 0));
+        let nan = f32::NAN.to_bits() as i32;
+        assert_eq!((BinArithOp::FCMP.eval(nan, x), BinArithOp::FCMP.eval(x, nan), BinArithOp::FCMP.eval(nan, nan)), (2, 2, 2));
+        assert_eq!(UnaryArithOp::ITOF.eval(-3), (-3.0f32).to_bits() as i32);
+        assert_eq!(UnaryArithOp::FTOI.eval((-2.75f32).to_bits() as i32), -2);
+        assert_eq!(
+            Instruction::from_str("FSUB R1 R2 R1").unwrap(),
+            Instruction::BinArith {
+                op: BinArithOp::FSUB,
+                dst: Register::R1,
+                arg1: Register::R2,
+                arg2: RegOrImm::Reg(Register::R1)
+            }
+        );
+    }
+    #[test]
+    fn carry_ops() {
+        assert_eq!(BinArithOp::ADC.eval_carry(-1, 1, 0), (0, 1));
+        assert_eq!(BinArithOp::ADC.eval_carry(1, 2, 1), (4, 0));
+        assert_eq!(BinArithOp::ADC.eval_carry(-1, -1, 1), (-1, 1));
+        assert_eq!(BinArithOp::SBC.eval_carry(0, 1, 0), (-1, 1));
+        assert_eq!(BinArithOp::SBC.eval_carry(5, 2, 1), (2, 0));
+        assert_eq!(BinArithOp::SBC.eval_carry(-1, 1, 0), (-2, 0));
+    }
+    #[test]
+    fn encode_decode() {
+        let instructions = ["NEG R1", "ADD R1 R2 R3", "SUB SP SP -7", "MOV ZR 2147483647", "MOV R4 -2147483647",
+            "LOAD R1 BP", "PUSH IR", "TSTL R2 R4", "TSTE R1 0", "JUMP -12", "CALL 300", "CALLR R3", "JMPR R4", "HALT", "IRET",
+            "LOADB R1 R2", "STRB R3 -1", "FADD R1 R2 R3", "FCMP R1 R1 0", "ITOF R2", "FTOI R4",
+            "ADC R1 R2 R3", "SBC R2 R2 0", "TSET R1 R2"];
+        for instr_str in instructions.iter() {
+            let instr = Instruction::from_str(instr_str).unwrap();
+            assert_eq!(Instruction::decode(instr.encode()), Some(instr));
+        }
+        // all the opcodes are distinct
+        assert_ne!(Instruction::from_str("ADD R1 R1 1").unwrap().encode(), Instruction::from_str("SUB R1 R1 1").unwrap().encode());
+        assert_eq!(Instruction::from_str("MOV R2 R1").unwrap().encode(), 14 << 56 | 1 << 52);
+        assert_eq!(Instruction::decode(0xff << 56), None);
+    }
+    #[test]
     fn reg_to_str() {
         assert_eq!(Register::R1.to_str(), "R1");
         assert_eq!(Register::R2.to_str(), "R2");
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// the address of interrupt i's handler is stored at INTERRUPT_VECTOR_ADDRESS + i
+/// a handler address of 0 means the interrupt is ignored
+pub const INTERRUPT_VECTOR_ADDRESS: u32 = 100;
+pub const INTERRUPT_VECTOR_SIZE: u32 = 16;
+
+// interrupt numbers
+pub const TIMER_INTERRUPT: u32 = 0;
+pub const KEYBOARD_INTERRUPT: u32 = 1; // raised when a key is pressed
+
+/// fires every interval cycles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timer {
+    interval: u32,
+    elapsed: u32, // cycles since the timer last fired
+}
+
+impl Timer {
+    pub fn new(interval: u32) -> Timer {
+        assert!(interval > 0, "timer interval must be positive");
+        Timer { interval, elapsed: 0 }
+    }
+
+    /// advances the timer, returns whether it fired
+    pub fn tick(&mut self, cycles: u32) -> bool {
+        self.elapsed += cycles;
+        if self.elapsed < self.interval {
+            return false;
+        }
+        self.elapsed %= self.interval;
+        true
+    }
+}
@@ -0,0 +1,48 @@
+use super::instructions::*;
+use super::interrupts::Timer;
+use super::{Fault, MemEntry};
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+
+/// the cpu's state before an instruction executed, enough to undo the instruction
+#[derive(Clone)]
+pub struct JournalEntry {
+    pub reg_writes: Vec<(Register, i32)>, // (register, value before) of the registers the instruction changed
+    pub mem_writes: Vec<(u32, Option<MemEntry>)>, // (physical address, entry before), in the order they were written
+    pub pending_syscall: bool,
+    pub fault: Option<Fault>,
+    pub interrupts_enabled: bool,
+    pub pending_interrupts: BTreeSet<u32>,
+    pub timer: Option<Timer>,
+}
+
+/// the last executed instructions, newest last
+/// only the last max_entries instructions can be undone
+pub struct Journal {
+    max_entries: usize,
+    entries: VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new(max_entries: usize) -> Journal {
+        Journal {
+            max_entries,
+            entries: VecDeque::with_capacity(max_entries),
+        }
+    }
+
+    pub fn record(&mut self, entry: JournalEntry) {
+        if self.max_entries == 0 {
+            return;
+        }
+        if self.entries.len() == self.max_entries {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// removes & returns the newest entry
+    pub fn pop(&mut self) -> Option<JournalEntry> {
+        self.entries.pop_back()
+    }
+}
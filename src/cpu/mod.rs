@@ -1,14 +1,37 @@
 pub mod instructions;
+pub mod interrupts;
+pub mod journal;
+pub mod poison;
+pub mod profile;
+pub mod stats;
+pub mod trace;
 
 use self::instructions::*;
+use self::interrupts::*;
+use self::journal::{Journal, JournalEntry};
+use self::poison::PoisonedSlots;
+use self::profile::Profile;
+use self::stats::ExecutionStats;
+use self::trace::{Trace, TraceConfig, TraceEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
 
+/// number of words in the memory, only the written words are stored so a large memory is cheap
+pub const DEFAULT_MEMORY_SIZE: u32 = 1 << 24;
+
+/// LOADB & STRB address bytes, byte address b is byte b % 4 of word b / 4 (little endian)
+pub const BYTES_PER_WORD: u32 = 4;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Registers {
     values: HashMap<Register, i32>,
 }
 
 impl Registers {
-    fn new() -> Registers {
+    pub fn new() -> Registers {
         let mut instance = Registers {
             values: HashMap::new(),
         };
@@ -40,28 +63,163 @@ impl Registers {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub enum MemEntry {
     Num(i32),
     Instruction(Instruction),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Permissions {
+    pub const ALL: Permissions = Permissions { read: true, write: true, execute: true };
+    pub const READ_ONLY: Permissions = Permissions { read: true, write: false, execute: false };
+    pub const READ_WRITE: Permissions = Permissions { read: true, write: true, execute: false };
+    pub const READ_EXECUTE: Permissions = Permissions { read: true, write: false, execute: true };
+
+    pub fn allows(&self, access: Access) -> bool {
+        match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+            Access::Execute => self.execute,
+        }
+    }
+}
+
+/// addresses [start, end) and how the cpu is allowed to access them
+#[derive(Clone, Serialize, Deserialize)]
+struct ProtectedRegion {
+    start: u32,
+    end: u32,
+    permissions: Permissions,
+}
+
+/// stops the cpu, IR is left at the faulting instruction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Fault {
+    SegmentationFault { address: u32, access: Access },
+    OutOfMemory { address: u32 }, // a physical address beyond the end of the memory
+    IllegalInstruction { address: u32 }, // IR points at data or at an address that was never written
+    UninitializedRead { address: u32 },
+    UninitializedStackRead { address: u32 }, // a stack slot that was allocated but never written, see Cpu::enable_uninit_check
+    InvalidHeapAccess { address: u32, access: Access }, // a heap block's red zone or a freed block, see Cpu::guard
+    InvalidFree { address: u32 }, // set by the os when a program frees an address that isn't an allocated block
+    InstructionRead { address: u32 }, // an instruction was loaded as data
+    DivisionByZero, // by DIV or MOD, FDIV by zero results in an infinity like in IEEE 754
+}
+
+fn access_name(access: &Access) -> &'static str {
+    match access {
+        Access::Read => "read from",
+        Access::Write => "write to",
+        Access::Execute => "execute at",
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::SegmentationFault { address, access } => write!(f, "segmentation fault: invalid {} address {}", access_name(access), address),
+            Fault::OutOfMemory { address } => write!(f, "out of memory: physical address {} is beyond the end of the memory", address),
+            Fault::IllegalInstruction { address } => write!(f, "illegal instruction: address {} doesn't hold an instruction", address),
+            Fault::UninitializedRead { address } => write!(f, "invalid memory access: read from address {} which was never written", address),
+            Fault::UninitializedStackRead { address } => write!(f, "uninitialized read: read from stack address {} which was allocated but never written", address),
+            Fault::InvalidHeapAccess { address, access } => write!(f, "invalid heap access: invalid {} address {} which isn't in an allocated block", access_name(access), address),
+            Fault::InvalidFree { address } => write!(f, "invalid free: address {} isn't the start of an allocated block", address),
+            Fault::InstructionRead { address } => write!(f, "invalid memory access: read from address {} which holds an instruction", address),
+            Fault::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+/// why the cpu stopped running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Halted,
+    Faulted, // see Cpu::fault
+    Timeout, // the cycle budget ran out, see Cpu::max_cycles
+}
+
+/// addresses [SHARED_MEMORY_START, SHARED_MEMORY_END) aren't translated, so they're the same memory in every segment
+/// they're beyond the memory's size, so the shared memory doesn't overlap the segments
+pub const SHARED_MEMORY_START: u32 = 100_000_000;
+pub const SHARED_MEMORY_END: u32 = SHARED_MEMORY_START + 4096;
+
+pub fn is_shared(address: u32) -> bool {
+    (SHARED_MEMORY_START..SHARED_MEMORY_END).contains(&address)
+}
+
+/// base & limit registers: the cpu adds base to the addresses that instructions access, addresses >= limit are invalid
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub base: u32,
+    pub limit: u32,
+}
+
+impl Segment {
+    /// the whole memory, without translation
+    pub const FULL: Segment = Segment { base: 0, limit: u32::MAX };
+
+    pub fn physical(&self, address: u32) -> u32 {
+        if is_shared(address) { address } else { self.base + address }
+    }
+}
+
+/// sparse, addresses that were never written aren't stored
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Memory {
     data: HashMap<u32, MemEntry>,
+    regions: Vec<ProtectedRegion>, // later regions take precedence
+    pub size: u32, // the cpu can only access addresses below it, get & set are always allowed
 }
 impl Memory {
-    fn new() -> Memory {
+    fn new(size: u32) -> Memory {
         Memory {
             data: HashMap::new(),
+            regions: Vec::new(),
+            size,
         }
     }
+    /// sets the permissions of addresses [start, end), overriding previously protected regions
+    /// only the cpu's accesses are checked, get & set are always allowed
+    pub fn protect(&mut self, start: u32, end: u32, permissions: Permissions) {
+        self.regions.push(ProtectedRegion { start, end, permissions });
+    }
+    /// addresses that were never protected can be accessed in any way
+    pub fn permissions(&self, address: u32) -> Permissions {
+        self.regions.iter().rev()
+            .find(|region| region.start <= address && address < region.end)
+            .map(|region| region.permissions)
+            .unwrap_or(Permissions::ALL)
+    }
     pub fn get(&self, address: u32) -> &MemEntry {
         self.data
             .get(&address)
             .expect(format!("Invalid memory access: {}", address).as_str())
     }
+    /// like get, but returns None instead of panicking on uninitialized addresses
+    pub fn try_get(&self, address: u32) -> Option<&MemEntry> {
+        self.data.get(&address)
+    }
     pub fn set(&mut self, address: u32, val: MemEntry) {
         self.data.insert(address, val);
     }
+    /// forgets the values of addresses [start, end), as if they were never written
+    pub fn clear(&mut self, start: u32, end: u32) {
+        self.data.retain(|address, _| *address < start || end <= *address);
+    }
     pub fn get_num(&self, address: u32) -> i32 {
         match self.get(address) {
             MemEntry::Num(x) => *x,
@@ -73,21 +231,332 @@ impl Memory {
 pub struct Cpu {
     pub mem: Memory,
     pub regs: Registers,
+    pub pending_syscall: bool, // set by SYSCALL, the OS handles the syscall & resets it
+    pub fault: Option<Fault>, // set if an instruction accessed memory it's not allowed to
+    pub segment: Segment, // the address space of the running program
+    pub interrupts_enabled: bool, // set by STI & cleared by CLI, pending interrupts wait until they're enabled
+    pending_interrupts: BTreeSet<u32>, // lower interrupt numbers are handled first
+    timer: Option<Timer>,
+    trace: Option<Trace>, // executed instructions are recorded only if tracing is enabled
+    stats: Option<ExecutionStats>, // collected only if enabled
+    profile: Option<Profile>, // collected only if enabled
+    poisoned_slots: Option<PoisonedSlots>, // reads of uninitialized stack slots fault only if enabled
+    guarded: HashSet<u32>, // physical addresses whose accesses fault, see guard
+    journal: Option<Journal>, // executed instructions can be undone only if journaling is enabled
+    journal_writes: Vec<(u32, Option<MemEntry>)>, // memory writes of the executing instruction, for its journal entry
+    watchpoints: HashSet<(u32, Access)>, // accesses to addresses that are reported in watch_hit
+    pub watch_hit: Option<(u32, Access)>, // set when an instruction accesses a watched address, the debugger then stops
+    pub cycles: u64, // of the instructions executed so far
+    pub max_cycles: Option<u64>, // the cpu stops once it executed this many cycles, so a program that loops forever doesn't hang the host
+    pub timed_out: bool, // set when the cpu stops because of max_cycles
+}
+
+/// a copy of the cpu's registers & memory, which can be serialized to save the cpu's state
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CpuSnapshot {
+    mem: Memory,
+    regs: Registers,
+    pending_syscall: bool,
+    fault: Option<Fault>,
+    segment: Segment,
+    interrupts_enabled: bool,
+    pending_interrupts: BTreeSet<u32>,
+    timer: Option<Timer>,
 }
 
 impl Cpu {
     pub fn new() -> Cpu {
+        Cpu::with_memory_size(DEFAULT_MEMORY_SIZE)
+    }
+
+    pub fn with_memory_size(memory_size: u32) -> Cpu {
         Cpu {
-            mem: Memory::new(),
+            mem: Memory::new(memory_size),
             regs: Registers::new(),
+            pending_syscall: false,
+            fault: None,
+            segment: Segment::FULL,
+            interrupts_enabled: false,
+            pending_interrupts: BTreeSet::new(),
+            timer: None,
+            trace: None,
+            stats: None,
+            profile: None,
+            poisoned_slots: None,
+            guarded: HashSet::new(),
+            journal: None,
+            journal_writes: Vec::new(),
+            watchpoints: HashSet::new(),
+            watch_hit: None,
+            cycles: 0,
+            max_cycles: None,
+            timed_out: false,
+        }
+    }
+
+    /// reports reads or writes (according to access) of the address in watch_hit
+    pub fn add_watchpoint(&mut self, address: u32, access: Access) {
+        assert!(access != Access::Execute, "only reads & writes can be watched");
+        self.watchpoints.insert((address, access));
+    }
+
+    /// removes the read & write watchpoints of the address, returns false if there were none
+    pub fn remove_watchpoints(&mut self, address: u32) -> bool {
+        let removed_read = self.watchpoints.remove(&(address, Access::Read));
+        let removed_write = self.watchpoints.remove(&(address, Access::Write));
+        removed_read || removed_write
+    }
+
+    fn check_watchpoint(&mut self, address: u32, access: Access) {
+        if self.watchpoints.contains(&(address, access)) {
+            self.watch_hit = Some((address, access));
+        }
+    }
+
+    /// starts collecting execution stats from zero
+    pub fn enable_stats(&mut self) {
+        self.stats = Some(ExecutionStats::new());
+    }
+
+    pub fn get_stats(&self) -> Option<&ExecutionStats> {
+        self.stats.as_ref()
+    }
+
+    /// starts counting the executions & cycles of each instruction address, & the calls between functions, from zero
+    pub fn enable_profile(&mut self) {
+        self.profile = Some(Profile::new());
+    }
+
+    pub fn get_profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// makes reads of stack slots that were allocated but not written yet fault with Fault::UninitializedStackRead,
+    /// allocations are the (physical) addresses of the instructions whose pushes allocate the pushed slot rather than write it
+    pub fn enable_uninit_check(&mut self, allocations: HashSet<u32>) {
+        self.poisoned_slots = Some(PoisonedSlots::new(allocations));
+    }
+
+    /// the os wrote the physical address, e.g a syscall that read into a buffer on the stack, so it's no longer uninitialized
+    pub fn mark_written(&mut self, physical: u32) {
+        if let Some(slots) = self.poisoned_slots.as_mut() {
+            slots.written(physical);
+        }
+    }
+
+    /// makes loads & stores of physical addresses [start, end) fault with Fault::InvalidHeapAccess,
+    /// e.g the red zones around a heap block, see heap_check.rs
+    pub fn guard(&mut self, start: u32, end: u32) {
+        self.guarded.extend(start..end);
+    }
+
+    pub fn unguard(&mut self, start: u32, end: u32) {
+        for physical in start..end {
+            self.guarded.remove(&physical);
+        }
+    }
+
+    /// enables tracing of executed instructions, replacing the current trace if there is one
+    pub fn set_trace(&mut self, config: TraceConfig) {
+        self.trace = Some(Trace::new(config));
+    }
+
+    pub fn get_trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// records the changes of the next executed instructions so they can be undone with step_back
+    /// replaces the current journal if there is one
+    pub fn set_journal(&mut self, max_entries: usize) {
+        self.journal = Some(Journal::new(max_entries));
+    }
+
+    /// undoes the last journaled instruction, returns false if there's no instruction to undo
+    /// changes that the OS made between instructions (e.g handling a syscall) aren't undone
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.journal.as_mut().and_then(|journal| journal.pop()) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        for (address, before) in entry.mem_writes.into_iter().rev() {
+            match before {
+                Some(before) => self.mem.set(address, before),
+                None => {
+                    self.mem.data.remove(&address);
+                },
+            }
+        }
+        for (reg, before) in entry.reg_writes.iter() {
+            self.regs.set(reg, *before);
+        }
+        self.pending_syscall = entry.pending_syscall;
+        self.fault = entry.fault;
+        self.interrupts_enabled = entry.interrupts_enabled;
+        self.pending_interrupts = entry.pending_interrupts;
+        self.timer = entry.timer;
+        true
+    }
+
+    pub fn snapshot(&self) -> CpuSnapshot {
+        CpuSnapshot {
+            mem: self.mem.clone(),
+            regs: self.regs.clone(),
+            pending_syscall: self.pending_syscall,
+            fault: self.fault.clone(),
+            segment: self.segment,
+            interrupts_enabled: self.interrupts_enabled,
+            pending_interrupts: self.pending_interrupts.clone(),
+            timer: self.timer.clone(),
+        }
+    }
+
+    /// replaces the cpu's state with the snapshot's, execution continues from the snapshot's IR
+    pub fn restore(&mut self, snapshot: CpuSnapshot) {
+        self.mem = snapshot.mem;
+        self.regs = snapshot.regs;
+        self.pending_syscall = snapshot.pending_syscall;
+        self.fault = snapshot.fault;
+        self.segment = snapshot.segment;
+        self.interrupts_enabled = snapshot.interrupts_enabled;
+        self.pending_interrupts = snapshot.pending_interrupts;
+        self.timer = snapshot.timer;
+    }
+
+    /// the interrupt is handled before the next instruction if interrupts are enabled
+    pub fn raise_interrupt(&mut self, num: u32) {
+        assert!(num < INTERRUPT_VECTOR_SIZE, "invalid interrupt: {}", num);
+        self.pending_interrupts.insert(num);
+    }
+
+    /// the timer raises TIMER_INTERRUPT every interval cycles, None stops the timer
+    pub fn set_timer(&mut self, interval: Option<u32>) {
+        self.timer = interval.map(Timer::new);
+    }
+
+    /// calls the handler of the next pending interrupt like CALL does, so the handler returns with IRET
+    /// interrupts are disabled until the handler returns
+    fn handle_interrupt(&mut self) {
+        if !self.interrupts_enabled {
+            return;
+        }
+        let num = match self.pending_interrupts.iter().next() {
+            Some(num) => *num,
+            None => return,
+        };
+        self.pending_interrupts.remove(&num);
+        let handler = match self.mem.try_get(self.segment.physical(INTERRUPT_VECTOR_ADDRESS + num)) {
+            Some(MemEntry::Num(handler)) if *handler != 0 => *handler,
+            _ => return,
+        };
+        let ir = self.regs.get(&Register::IR);
+        self.push_call_frame(ir); // the interrupted instruction hasn't run yet, so return to it
+        self.regs.set(&Register::IR, handler);
+        self.interrupts_enabled = false;
+        if let Some(profile) = self.profile.as_mut() {
+            profile.enter(ir as u32, handler as u32);
+        }
+    }
+
+    /// translates the address to a physical address
+    /// sets the fault & returns None if the address is out of the segment or the memory, or the access is not allowed
+    fn translate(&mut self, address: u32, access: Access) -> Option<u32> {
+        match self.check_access(address, access) {
+            Ok(physical) => Some(physical),
+            Err(fault) => {
+                self.fault = Some(fault);
+                None
+            },
         }
     }
 
-    pub fn fetch(&self) -> Instruction {
-        if let MemEntry::Instruction(instr) = self.mem.get(self.regs.get(&Register::IR) as u32) {
-            return instr.clone();
+    /// the physical address of an access by the running program, or the fault that the access causes
+    /// the os checks the buffers that syscalls access on behalf of the program with it
+    pub fn check_access(&self, address: u32, access: Access) -> Result<u32, Fault> {
+        if address < self.segment.limit || is_shared(address) {
+            let physical = self.segment.physical(address);
+            if physical >= self.mem.size && !is_shared(address) {
+                return Err(Fault::OutOfMemory { address: physical });
+            }
+            if self.mem.permissions(physical).allows(access) {
+                return Ok(physical);
+            }
+        }
+        Err(Fault::SegmentationFault { address, access })
+    }
+
+    fn load(&mut self, address: u32) -> i32 {
+        self.check_watchpoint(address, Access::Read);
+        let physical = match self.translate(address, Access::Read) {
+            Some(physical) => physical,
+            None => return 0,
+        };
+        if self.guarded.contains(&physical) {
+            self.fault = Some(Fault::InvalidHeapAccess { address, access: Access::Read });
+            return 0;
+        }
+        if self.poisoned_slots.as_ref().is_some_and(|slots| slots.is_poisoned(physical)) {
+            self.fault = Some(Fault::UninitializedStackRead { address });
+            return 0;
+        }
+        match self.mem.try_get(physical) {
+            Some(MemEntry::Num(val)) => *val,
+            Some(MemEntry::Instruction(_)) => {
+                self.fault = Some(Fault::InstructionRead { address });
+                0
+            },
+            None => {
+                self.fault = Some(Fault::UninitializedRead { address });
+                0
+            },
+        }
+    }
+
+    fn load_byte(&mut self, address: u32) -> i32 {
+        let word = self.load(address / BYTES_PER_WORD);
+        (word >> (8 * (address % BYTES_PER_WORD))) as i8 as i32
+    }
+
+    fn store_byte(&mut self, address: u32, val: i32) {
+        let word_address = address / BYTES_PER_WORD;
+        let shift = 8 * (address % BYTES_PER_WORD);
+        // the word's other bytes are kept, an uninitialized word is zero
+        // an address out of the segment faults in store
+        let in_segment = if is_shared(word_address) { word_address } else { word_address.min(self.segment.limit) };
+        let word = match self.mem.try_get(self.segment.physical(in_segment)) {
+            Some(MemEntry::Num(word)) => *word,
+            _ => 0,
+        };
+        self.store(word_address, (word & !(0xff << shift)) | ((val & 0xff) << shift));
+    }
+
+    fn store(&mut self, address: u32, val: i32) {
+        self.check_watchpoint(address, Access::Write);
+        if let Some(physical) = self.translate(address, Access::Write) {
+            if self.guarded.contains(&physical) {
+                self.fault = Some(Fault::InvalidHeapAccess { address, access: Access::Write });
+                return;
+            }
+            if self.journal.is_some() {
+                self.journal_writes.push((physical, self.mem.try_get(physical).cloned()));
+            }
+            self.mem.set(physical, MemEntry::Num(val));
+            if let Some(slots) = self.poisoned_slots.as_mut() {
+                slots.written(physical);
+            }
+        }
+    }
+
+    /// the instruction at IR, sets the fault & returns None if IR doesn't point at an instruction
+    fn fetch(&mut self) -> Option<Instruction> {
+        let ir = self.regs.get(&Register::IR) as u32;
+        let physical = self.translate(ir, Access::Execute)?;
+        match self.mem.try_get(physical) {
+            Some(MemEntry::Instruction(instr)) => Some(instr.clone()),
+            _ => {
+                self.fault = Some(Fault::IllegalInstruction { address: ir });
+                None
+            },
         }
-        panic!("cannot execute data!");
     }
     fn execute_unary_arith(&mut self, op: &UnaryArithOp, arg: &Register) {
         let reg_val = self.regs.get(arg);
@@ -103,6 +572,16 @@ impl Cpu {
     ) {
         let arg1_val = self.regs.get(arg1);
         let arg2_val = self.regs.get_reg_or_imm(arg2);
+        if let BinArithOp::ADC | BinArithOp::SBC = op {
+            let (res, carry) = op.eval_carry(arg1_val, arg2_val, self.regs.get(&Register::ZR));
+            self.regs.set(&Register::ZR, carry);
+            self.regs.set(dst, res);
+            return;
+        }
+        if matches!(op, BinArithOp::DIV | BinArithOp::MOD) && arg2_val == 0 {
+            self.fault = Some(Fault::DivisionByZero);
+            return;
+        }
         let res = op.eval(arg1_val, arg2_val);
         self.regs.set(dst, res);
     }
@@ -110,12 +589,25 @@ impl Cpu {
         let src_val = self.regs.get_reg_or_imm(src);
         match op {
             DataOp::LOAD => {
-                let mem_src_val = self.mem.get_num(src_val as u32);
+                let mem_src_val = self.load(src_val as u32);
                 self.regs.set(dst, mem_src_val);
             }
             DataOp::STR => {
-                self.mem
-                    .set(self.regs.get(dst) as u32, MemEntry::Num(src_val));
+                self.store(self.regs.get(dst) as u32, src_val);
+            }
+            DataOp::LOADB => {
+                let byte = self.load_byte(src_val as u32);
+                self.regs.set(dst, byte);
+            }
+            DataOp::STRB => {
+                self.store_byte(self.regs.get(dst) as u32, src_val);
+            }
+            DataOp::TSET => {
+                let val = self.load(src_val as u32);
+                if self.fault.is_none() {
+                    self.store(src_val as u32, 1);
+                }
+                self.regs.set(dst, val);
             }
             DataOp::MOV | DataOp::LEA => {
                 self.regs.set(dst, src_val);
@@ -127,11 +619,16 @@ impl Cpu {
         match op {
             StackOp::PUSH => {
                 let dst_val = self.regs.get(dst);
-                self.mem.set(sp as u32, MemEntry::Num(dst_val));
+                self.store(sp as u32, dst_val);
                 self.regs.set(&Register::SP, sp - 1);
+                let ir = self.segment.physical(self.regs.get(&Register::IR) as u32);
+                if let Some(slots) = self.poisoned_slots.as_mut().filter(|slots| slots.is_allocation(ir)) {
+                    slots.poison(self.segment.physical(sp as u32));
+                }
             }
             StackOp::POP => {
-                self.regs.set(dst, self.mem.get_num(sp as u32 + 1));
+                let val = self.load(sp as u32 + 1);
+                self.regs.set(dst, val);
                 self.regs.set(&Register::SP, sp + 1);
             }
         }
@@ -146,29 +643,55 @@ impl Cpu {
     fn execute_flow(&mut self, op: &FlowOp, offset: i32) {
         if op.should_take(self.regs.get(&Register::ZR)) {
             if let FlowOp::CALL = op {
-                let sp = self.regs.get(&Register::SP);
-                // push ret address
-                self.mem
-                    .set(sp as u32, MemEntry::Num(self.regs.get(&Register::IR) + 1));
-                // push caller BP
-                self.mem
-                    .set(sp as u32 - 1, MemEntry::Num(self.regs.get(&Register::BP)));
-                self.regs.set(&Register::BP, sp - 1);
-                self.regs.set(&Register::SP, sp - 2);
+                self.push_call_frame(self.regs.get(&Register::IR) + 1);
             }
             let ir = self.regs.get(&Register::IR);
             self.regs.set(&Register::IR, ir + offset - 1);
         }
     }
+    fn execute_flow_reg(&mut self, op: &FlowRegOp, reg: &Register) {
+        let target = self.regs.get(reg);
+        match op {
+            FlowRegOp::CALLR => {
+                self.push_call_frame(self.regs.get(&Register::IR) + 1);
+                self.regs.set(&Register::IR, target - 1); // IR will be increment at end of cycle
+            }
+            FlowRegOp::JMPR => {
+                self.regs.set(&Register::IR, target - 1);
+            }
+        }
+    }
+    /// pushes the return address & the caller's BP, and updates BP
+    fn push_call_frame(&mut self, ret_addr: i32) {
+        let sp = self.regs.get(&Register::SP);
+        self.store(sp as u32, ret_addr);
+        // push caller BP
+        self.store(sp as u32 - 1, self.regs.get(&Register::BP));
+        self.regs.set(&Register::BP, sp - 1);
+        self.regs.set(&Register::SP, sp - 2);
+    }
     fn execute_other(&mut self, op: &OtherOp) {
         match op {
             OtherOp::HALT => {}
-            OtherOp::RET => {
+            OtherOp::RET | OtherOp::IRET => {
                 let bp = self.regs.get(&Register::BP);
                 self.regs.set(&Register::SP, bp + 1);
-                let ret_addr = self.mem.get_num(bp as u32 + 1);
-                self.regs.set(&Register::BP, self.mem.get_num(bp as u32));
+                let ret_addr = self.load(bp as u32 + 1);
+                let caller_bp = self.load(bp as u32);
+                self.regs.set(&Register::BP, caller_bp);
                 self.regs.set(&Register::IR, ret_addr - 1); // IR will be increment at end of cycle
+                if let OtherOp::IRET = op {
+                    self.interrupts_enabled = true;
+                }
+            }
+            OtherOp::SYSCALL => {
+                self.pending_syscall = true;
+            }
+            OtherOp::CLI => {
+                self.interrupts_enabled = false;
+            }
+            OtherOp::STI => {
+                self.interrupts_enabled = true;
             }
         }
     }
@@ -207,6 +730,10 @@ impl Cpu {
                 self.execute_flow(op, *offset);
                 return true;
             }
+            Instruction::FlowReg { op, reg } => {
+                self.execute_flow_reg(op, reg);
+                return true;
+            }
             Instruction::Other { op } => {
                 self.execute_other(op);
                 return if let OtherOp::HALT = op { false } else { true };
@@ -214,21 +741,167 @@ impl Cpu {
         }
     }
 
-    pub fn step(&mut self) -> bool{
-        let instr = self.fetch();
-        let keep_running = self.execute(&instr);
-        let ir = self.regs.get(&Register::IR);
-        self.regs.set(&Register::IR, ir + 1);
+    /// returns Ok(false) if the cpu halted or ran out of cycles
+    /// returns the fault if the instruction faulted, it's also kept in fault
+    pub fn step(&mut self) -> Result<bool, Fault> {
+        let keep_running = self.step_journaled();
+        match &self.fault {
+            Some(fault) => Err(fault.clone()),
+            None => Ok(keep_running),
+        }
+    }
 
+    /// executes the next instruction & records it in the journal if journaling is enabled
+    fn step_journaled(&mut self) -> bool {
+        if self.max_cycles.is_some_and(|max_cycles| self.cycles >= max_cycles) {
+            self.timed_out = true;
+            return false;
+        }
+        if self.journal.is_none() {
+            return self.execute_next();
+        }
+        let mut entry = JournalEntry {
+            reg_writes: Vec::new(),
+            mem_writes: Vec::new(),
+            pending_syscall: self.pending_syscall,
+            fault: self.fault.clone(),
+            interrupts_enabled: self.interrupts_enabled,
+            pending_interrupts: self.pending_interrupts.clone(),
+            timer: self.timer.clone(),
+        };
+        let regs_before = self.regs.clone();
+        let keep_running = self.execute_next();
+        entry.reg_writes = ALL_REGISTERS.iter()
+            .filter(|reg| regs_before.get(reg) != self.regs.get(reg))
+            .map(|reg| (reg.clone(), regs_before.get(reg)))
+            .collect();
+        entry.mem_writes = std::mem::take(&mut self.journal_writes);
+        if let Some(journal) = self.journal.as_mut() {
+            journal.record(entry);
+        }
         keep_running
     }
 
-    pub fn start(&mut self) {
-        loop {
-            let keep_running = self.step();
-            if !keep_running {
-                break;
+    /// handles a pending interrupt & executes the next instruction, see step
+    fn execute_next(&mut self) -> bool {
+        self.handle_interrupt();
+        if self.fault.is_some() {
+            return false;
+        }
+        let ir = self.regs.get(&Register::IR);
+        let instr = match self.fetch() {
+            Some(instr) => instr,
+            None => return false,
+        };
+        let cycles = instr.cycles();
+        let regs_before = self.trace.as_ref().map(|_| self.regs.clone());
+        if let Some(stats) = self.stats.as_mut() {
+            let branch_taken = match &instr {
+                Instruction::Flow { op: op @ FlowOp::TJMP, .. } | Instruction::Flow { op: op @ FlowOp::FJMP, .. } =>
+                    Some(op.should_take(self.regs.get(&Register::ZR))),
+                _ => None,
+            };
+            stats.record(&instr, branch_taken);
+        }
+        // whether the instruction calls (true) or returns (false), for the profile's call graph
+        let call = match &instr {
+            Instruction::Flow { op: FlowOp::CALL, .. } | Instruction::FlowReg { op: FlowRegOp::CALLR, .. } => Some(true),
+            Instruction::Other { op: OtherOp::RET } | Instruction::Other { op: OtherOp::IRET } => Some(false),
+            _ => None,
+        };
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record(ir as u32, cycles);
+        }
+        let keep_running = self.execute(&instr);
+        self.cycles += cycles as u64;
+        if let Some(regs_before) = regs_before {
+            self.record_trace(ir as u32, instr, &regs_before);
+        }
+        if self.fault.is_some() {
+            self.regs.set(&Register::IR, ir); // a jump may have already changed IR
+            return false;
+        }
+        if let Some(timer) = self.timer.as_mut() {
+            if timer.tick(cycles) {
+                self.pending_interrupts.insert(TIMER_INTERRUPT);
+            }
+        }
+        let next_ir = self.regs.get(&Register::IR) + 1;
+        self.regs.set(&Register::IR, next_ir);
+        if let (Some(profile), Some(call)) = (self.profile.as_mut(), call) {
+            if call {
+                profile.enter(ir as u32, next_ir as u32);
+            } else {
+                profile.leave();
             }
         }
+
+        keep_running
+    }
+
+    fn record_trace(&mut self, address: u32, instruction: Instruction, regs_before: &Registers) {
+        let reg_changes = ALL_REGISTERS.iter()
+            .filter(|reg| **reg != Register::IR)
+            .map(|reg| (reg.clone(), regs_before.get(reg), self.regs.get(reg)))
+            .filter(|(_, before, after)| before != after)
+            .collect();
+        if let Some(trace) = self.trace.as_mut() {
+            trace.record(TraceEntry { address, instruction, reg_changes });
+        }
+    }
+
+    pub fn start(&mut self) -> RunOutcome {
+        while let Ok(true) = self.step() {}
+        self.outcome()
+    }
+
+    /// why the cpu stopped, should be called after step returned false
+    pub fn outcome(&self) -> RunOutcome {
+        if self.fault.is_some() {
+            RunOutcome::Faulted
+        } else if self.timed_out {
+            RunOutcome::Timeout
+        } else {
+            RunOutcome::Halted
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchpoints() {
+        let mut cpu = Cpu::new();
+        let program = ["MOV R1 500", "LOAD R2 R1", "STR R1 7", "MOV R3 501", "STR R3 8", "LOAD R4 R3", "HALT"];
+        for (instr_i, instr) in program.iter().enumerate() {
+            cpu.mem.set(1000 + instr_i as u32, MemEntry::Instruction(Instruction::from_str(instr).unwrap()));
+        }
+        cpu.mem.set(500, MemEntry::Num(0));
+        cpu.regs.set(&Register::IR, 1000);
+        cpu.add_watchpoint(500, Access::Write);
+        cpu.add_watchpoint(501, Access::Read);
+        let mut hits = Vec::new();
+        while cpu.step().unwrap() {
+            hits.extend(cpu.watch_hit.take().map(|hit| (cpu.regs.get(&Register::IR), hit)));
+        }
+        // the write to 501 isn't reported since only its reads are watched
+        assert_eq!(hits, vec![(1003, (500, Access::Write)), (1006, (501, Access::Read))]);
+        assert_eq!(cpu.mem.get_num(500), 7);
+        assert!(cpu.remove_watchpoints(500));
+        assert!(!cpu.remove_watchpoints(500));
+    }
+
+    #[test]
+    fn test_max_cycles() {
+        let mut cpu = Cpu::new();
+        for (instr_i, instr) in ["ADD R1 R1 1", "JUMP -1"].iter().enumerate() {
+            cpu.mem.set(1000 + instr_i as u32, MemEntry::Instruction(Instruction::from_str(instr).unwrap()));
+        }
+        cpu.regs.set(&Register::IR, 1000);
+        cpu.max_cycles = Some(100);
+        assert_eq!(cpu.start(), RunOutcome::Timeout);
+        assert_eq!((cpu.cycles, cpu.regs.get(&Register::R1)), (100, 50));
     }
 }
@@ -0,0 +1,47 @@
+use std::collections::HashSet;
+
+/// stack slots that were allocated but not written yet, see Cpu::enable_uninit_check
+/// a slot is poisoned when it's pushed by an allocating instruction, & is valid again once it's written
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoisonedSlots {
+    allocations: HashSet<u32>, // physical addresses of the instructions whose pushes allocate a slot, e.g the PUSH ZR of the compiler's prologues
+    poisoned: HashSet<u32>, // physical addresses
+}
+
+impl PoisonedSlots {
+    pub fn new(allocations: HashSet<u32>) -> PoisonedSlots {
+        PoisonedSlots { allocations, poisoned: HashSet::new() }
+    }
+
+    pub fn is_allocation(&self, instr_address: u32) -> bool {
+        self.allocations.contains(&instr_address)
+    }
+
+    pub fn poison(&mut self, address: u32) {
+        self.poisoned.insert(address);
+    }
+
+    pub fn written(&mut self, address: u32) {
+        self.poisoned.remove(&address);
+    }
+
+    pub fn is_poisoned(&self, address: u32) -> bool {
+        self.poisoned.contains(&address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poison() {
+        let mut slots = PoisonedSlots::new(HashSet::from([10]));
+        assert!(slots.is_allocation(10) && !slots.is_allocation(11));
+        slots.poison(500);
+        slots.poison(499);
+        assert!(slots.is_poisoned(500));
+        slots.written(500);
+        assert!(!slots.is_poisoned(500) && slots.is_poisoned(499));
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// how many times each instruction was executed & how many cycles it took, see Cpu::enable_profile
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AddressCounts {
+    pub executions: u64,
+    pub cycles: u64,
+}
+
+/// the calls from a call site to a function
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CallCounts {
+    pub calls: u64,
+    pub cycles: u64, // spent in the callee & its children during these calls, not counting recursive calls
+}
+
+/// a function call that hasn't returned yet
+#[derive(Debug, Clone, PartialEq)]
+struct Frame {
+    call_site: u32,
+    entry: u32,
+    start_cycles: u64,
+}
+
+/// the execution counts of each address that was executed, by the address of the instruction,
+/// & the calls of each call site, recorded on CALL, RET & interrupts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub counts: BTreeMap<u32, AddressCounts>,
+    pub calls: BTreeMap<(u32, u32), CallCounts>, // by the address of the call & the callee's first instruction
+    cycles: u64, // of the recorded instructions
+    frames: Vec<Frame>,
+    active: HashMap<u32, u32>, // the number of frames of each callee, more than one if it's recursive
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    pub fn record(&mut self, address: u32, cycles: u32) {
+        let counts = self.counts.entry(address).or_default();
+        counts.executions += 1;
+        counts.cycles += cycles as u64;
+        self.cycles += cycles as u64;
+    }
+
+    /// the instruction at call_site called the function whose first instruction is at entry
+    pub fn enter(&mut self, call_site: u32, entry: u32) {
+        self.calls.entry((call_site, entry)).or_default().calls += 1;
+        *self.active.entry(entry).or_default() += 1;
+        self.frames.push(Frame { call_site, entry, start_cycles: self.cycles });
+    }
+
+    /// the current function returned
+    pub fn leave(&mut self) {
+        // e.g main, which isn't called
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let active = self.active.get_mut(&frame.entry).unwrap();
+        *active -= 1;
+        // the cycles of a recursive call are already counted in its outermost call
+        if *active == 0 {
+            self.calls.get_mut(&(frame.call_site, frame.entry)).unwrap().cycles += self.cycles - frame.start_cycles;
+        }
+    }
+
+    /// the profile as if all the calls that haven't returned returned now, e.g if the program exited from a function
+    pub fn finished(&self) -> Profile {
+        let mut profile = self.clone();
+        while !profile.frames.is_empty() {
+            profile.leave();
+        }
+        profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut profile = Profile::new();
+        profile.record(10, 2);
+        profile.record(10, 2);
+        profile.record(11, 1);
+        assert_eq!(profile.counts[&10], AddressCounts { executions: 2, cycles: 4 });
+        assert_eq!(profile.counts[&11], AddressCounts { executions: 1, cycles: 1 });
+    }
+
+    #[test]
+    fn test_calls() {
+        let mut profile = Profile::new();
+        profile.record(0, 2); // calls 10
+        profile.enter(0, 10);
+        profile.record(10, 1); // calls itself
+        profile.enter(10, 10);
+        profile.record(10, 1); // calls 20
+        profile.enter(10, 20);
+        profile.record(20, 3);
+        profile.leave();
+        profile.record(11, 1);
+        profile.leave();
+        profile.record(11, 1);
+        profile.leave();
+        profile.record(1, 1); // calls 20 & exits before it returns
+        profile.enter(1, 20);
+        profile.record(20, 3);
+
+        let profile = profile.finished();
+        assert_eq!(profile.calls, BTreeMap::from([
+            ((0, 10), CallCounts { calls: 1, cycles: 7 }),
+            ((10, 10), CallCounts { calls: 1, cycles: 0 }),
+            ((10, 20), CallCounts { calls: 1, cycles: 3 }),
+            ((1, 20), CallCounts { calls: 1, cycles: 3 }),
+        ]));
+    }
+}
@@ -0,0 +1,44 @@
+use super::instructions::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// performance counters of an execution, see Instruction::cycles for the cost of each instruction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionStats {
+    pub cycles: u64,
+    pub instructions: u64,
+    pub opcode_counts: BTreeMap<String, u64>,
+    pub branches: u64, // executed conditional jumps
+    pub branches_taken: u64,
+}
+
+impl ExecutionStats {
+    pub fn new() -> ExecutionStats {
+        ExecutionStats::default()
+    }
+
+    /// branch_taken is only given for conditional jumps
+    pub fn record(&mut self, instr: &Instruction, branch_taken: Option<bool>) {
+        self.cycles += instr.cycles() as u64;
+        self.instructions += 1;
+        *self.opcode_counts.entry(instr.opcode()).or_insert(0) += 1;
+        if let Some(taken) = branch_taken {
+            self.branches += 1;
+            if taken {
+                self.branches_taken += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExecutionStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "cycles: {}", self.cycles)?;
+        writeln!(f, "instructions: {}", self.instructions)?;
+        writeln!(f, "branches: {} ({} taken)", self.branches, self.branches_taken)?;
+        for (opcode, count) in self.opcode_counts.iter() {
+            writeln!(f, "{}: {}", opcode, count)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use super::instructions::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// configures which instructions the cpu keeps in its trace
+#[derive(Debug, Clone)]
+pub struct TraceConfig {
+    pub max_entries: usize, // only the last max_entries executed instructions are kept
+}
+
+impl TraceConfig {
+    pub fn new(max_entries: usize) -> TraceConfig {
+        TraceConfig { max_entries }
+    }
+}
+
+/// an executed instruction, and the registers it changed
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub address: u32,
+    pub instruction: Instruction,
+    pub reg_changes: Vec<(Register, i32, i32)>, // (register, value before, value after), IR is not included
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.address, self.instruction.to_str())?;
+        for (reg, before, after) in self.reg_changes.iter() {
+            write!(f, " | {}: {} -> {}", reg, before, after)?;
+        }
+        Ok(())
+    }
+}
+
+/// ring buffer of the last executed instructions
+pub struct Trace {
+    config: TraceConfig,
+    entries: VecDeque<TraceEntry>,
+}
+
+impl Trace {
+    pub fn new(config: TraceConfig) -> Trace {
+        Trace {
+            entries: VecDeque::with_capacity(config.max_entries),
+            config,
+        }
+    }
+
+    pub fn record(&mut self, entry: TraceEntry) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+        if self.entries.len() == self.config.max_entries {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// oldest entry first
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+}
@@ -1,3 +1,9 @@
 #[macro_use] extern crate matches;
 pub mod cpu;
+pub mod logging;
 pub mod operating_system;
+pub mod test_runner;
+pub mod vm;
+
+pub use operating_system::RunResult;
+pub use vm::{Vm, VmBuilder};
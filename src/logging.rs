@@ -0,0 +1,54 @@
+// the vm's messages for debugging it are logged with the log crate, each subsystem with its own target, e.g
+//     log::trace!(target: logging::COMPILER, "regs used: {:?}", regs);
+// they're logged only if the subsystem is verbose (see CompilerOptions::verbose & OS::verbose),
+// & shown only if a logger is installed, e.g with init
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+pub const COMPILER: &str = "compiler";
+pub const CPU: &str = "cpu";
+pub const OS: &str = "os";
+
+/// writes the messages of the targets to stderr, all targets if there are none
+struct StderrLogger {
+    targets: Vec<String>,
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.targets.is_empty() || self.targets.iter().any(|target| target == metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// installs a logger that writes the messages up to the level of the targets to stderr
+/// returns false if a logger was already installed
+pub fn init(level: LevelFilter, targets: &[&str]) -> bool {
+    let logger = StderrLogger { targets: targets.iter().map(|target| target.to_string()).collect() };
+    if log::set_boxed_logger(Box::new(logger)).is_err() {
+        return false;
+    }
+    log::set_max_level(level);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets() {
+        let logger = StderrLogger { targets: vec![COMPILER.to_string()] };
+        let metadata = |target| Metadata::builder().target(target).build();
+        assert!(logger.enabled(&metadata(COMPILER)));
+        assert!(!logger.enabled(&metadata(OS)));
+        assert!(StderrLogger { targets: Vec::new() }.enabled(&metadata(CPU)));
+    }
+}
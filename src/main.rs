@@ -1,36 +1,297 @@
 #[macro_use] extern crate matches;
 mod cpu;
+mod logging;
 mod operating_system;
 
-use crate::operating_system::compiler::Compiler;
+use crate::operating_system::compiler::{format_errors, Compiler, CompilerOptions, OptLevel};
+use crate::operating_system::filesystem::FileSystem;
+use crate::operating_system::linker::ObjectFile;
 use crate::operating_system::OS;
+use log::LevelFilter;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "Usage:
+    simple_vm compile <file.c> [-o <file.asm>]  compile a C file to assembly, & its debug info to <file.dbg>
+    simple_vm compile <file.c> --emit-ast[=json] [-o <file>]
+                                                print the AST of a C file instead of compiling it, as a tree or as JSON
+    simple_vm run <file.c>... [-- <arg>...]     compile, link & run C files, main's argv is the first file & the args
+    simple_vm debug <file.c>...                 compile, link & debug C files
+    simple_vm profile <file.c>... [-o <file.json>]
+                                                like run, & report the time spent in each function & source line, & the call graph, as JSON with -o
+    simple_vm coverage <file.c>... [-o <file.info>]
+                                                like run, & print the C files with the number of times each line ran, or write an lcov tracefile with -o
+    simple_vm debug-remote <addr> <file.c>...   like debug, with the commands read from a TCP client, e.g 127.0.0.1:1234
+    simple_vm asm <file.asm>...                 assemble, link & run assembly files
+    simple_vm build <file.c>... -o <file.bin>   compile & link C files into a binary image
+    simple_vm exec <file.bin>                   run a binary image
+    simple_vm shell                             start an interactive shell
+
+Compiler options:
+    -O0, -O1, -O2            optimization level, -O1 runs the peephole optimizer, -O2 also inlines small functions & turns tail calls into jumps
+    --inline-threshold=<n>   max number of instructions in a function that is inlined
+    --comments               put the source line in a comment before each statement's code
+    --no-line-labels         don't emit the .loc directives that map the code to source lines
+    --stack-check            exit the program if the stack overflows
+    -I<dir>                  search dir for included files, before the std dir for <file.h>
+    -D<name>[=<value>]       define a macro before the program, its value is 1 if it isn't given
+    --byte-addressing        char is a byte & int is 4 bytes, instead of everything taking a word
+
+Run options:
+    --display                draw the program's display on the terminal while it runs, see libc/display.h
+    --keyboard               send stdin to the keyboard device instead of the program's stdin, see libc/keyboard.h
+    --disk=<file.img>        keep the file system on a disk image, so the files persist across runs (created if missing)
+    --seed=<n>               the seed of the program's random numbers, see rand in libc/libc.h
+    --check-uninit           fault when the program reads a local variable before initializing it, & name the variable
+    --check-heap             fault on accesses out of heap blocks' bounds, use after free & invalid frees, & report leaks at exit
+    --verbose[=<targets>]    log what the compiler, the cpu & the os do to stderr, or only the comma separated targets of them";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn read_file(path: &String) -> String {
+    fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(1);
+    })
+}
+
+/// compiles each C file into an object file, exits with the compile errors if there are any
+fn compile_objects(os: &mut OS, paths: &[String]) -> Vec<ObjectFile> {
+    let mut objects = Vec::new();
+    for path in paths.iter() {
+        log::info!(target: logging::COMPILER, "compiling {}", path);
+        let object = os.try_compile_object(path).unwrap_or_else(|errors| {
+            eprintln!("{}", format_errors(&errors));
+            process::exit(1);
+        });
+        for (line_i, line) in object.code.split("\n").enumerate() {
+            log::trace!(target: logging::COMPILER, "{}: {}", line_i, line);
+        }
+        objects.push(object);
+    }
+    objects
+}
+
+/// removes the compiler options from args & returns them
+fn parse_compiler_options(args: &mut Vec<String>) -> CompilerOptions {
+    let mut options = CompilerOptions::new();
+    args.retain(|arg| {
+        options = match arg.as_str() {
+            "-O0" => options.clone().opt_level(OptLevel::O0),
+            "-O1" => options.clone().opt_level(OptLevel::O1),
+            "-O2" => options.clone().opt_level(OptLevel::O2),
+            "--comments" => options.clone().emit_comments(true),
+            "--no-line-labels" => options.clone().emit_line_labels(false),
+            "--stack-check" => options.clone().stack_check(true),
+            "--byte-addressing" => options.clone().byte_addressing(true),
+            _ if arg.starts_with("-I") && arg.len() > 2 => options.clone().include_dir(&arg[2..]),
+            _ if arg.starts_with("-D") && arg.len() > 2 => {
+                let (name, value) = arg[2..].split_once('=').unwrap_or((&arg[2..], "1"));
+                options.clone().define(name, value)
+            },
+            _ if arg.starts_with("--inline-threshold=") => match arg["--inline-threshold=".len()..].parse() {
+                Ok(threshold) => options.clone().inline_threshold(threshold),
+                Err(_) => usage(),
+            },
+            _ => return true,
+        };
+        false
+    });
+    options
+}
+
+/// prints the AST of the C file, or writes it to the output file
+fn emit_ast(path: &str, options: &CompilerOptions, format: &str, output: Option<&String>) {
+    let ast = Compiler::parse(path, options).unwrap_or_else(|errors| {
+        eprintln!("{}", format_errors(&errors));
+        process::exit(1);
+    });
+    let dump = match format {
+        "--emit-ast" => ast.to_string(),
+        "--emit-ast=json" => serde_json::to_string_pretty(&ast).unwrap(),
+        _ => usage(),
+    };
+    match output {
+        Some(output) => fs::write(output, dump).unwrap_or_else(|err| {
+            eprintln!("{}: {}", output, err);
+            process::exit(1);
+        }),
+        None => print!("{}", dump),
+    }
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3{
-        panic!("Usage: [run|debug] path_to_c_file/s")
+    let mut args: Vec<String> = env::args().collect();
+    // the program's args may look like options, so they're removed first
+    let program_args = args.iter().position(|arg| arg == "--").map(|arg_i| args.split_off(arg_i)[1..].to_vec()).unwrap_or_default();
+    let compiler_options = parse_compiler_options(&mut args);
+    let ast_format = args.iter().position(|arg| arg.starts_with("--emit-ast")).map(|arg_i| args.remove(arg_i));
+    let show_display = args.iter().position(|arg| arg == "--display").map(|arg_i| args.remove(arg_i)).is_some();
+    let keyboard = args.iter().position(|arg| arg == "--keyboard").map(|arg_i| args.remove(arg_i)).is_some();
+    let disk_image = args.iter().position(|arg| arg.starts_with("--disk=")).map(|arg_i| args.remove(arg_i)["--disk=".len()..].to_string());
+    let check_uninit = args.iter().position(|arg| arg == "--check-uninit").map(|arg_i| args.remove(arg_i)).is_some();
+    let check_heap = args.iter().position(|arg| arg == "--check-heap").map(|arg_i| args.remove(arg_i)).is_some();
+    let random_seed = args.iter().position(|arg| arg.starts_with("--seed=")).map(|arg_i| {
+        let arg = args.remove(arg_i);
+        arg["--seed=".len()..].parse::<u64>().unwrap_or_else(|_| usage())
+    });
+    let verbose = args.iter().position(|arg| arg == "--verbose" || arg.starts_with("--verbose=")).map(|arg_i| args.remove(arg_i));
+    if let Some(verbose) = &verbose {
+        let targets: Vec<&str> = verbose.strip_prefix("--verbose=").map(|targets| targets.split(',').collect()).unwrap_or_default();
+        if !targets.iter().all(|target| [logging::COMPILER, logging::CPU, logging::OS].contains(target)) {
+            usage();
+        }
+        logging::init(LevelFilter::Trace, &targets);
     }
-    let mut os = OS::new();
-    let mut programs = Vec::new();
-    for program_i in 2..args.len(){
-        println!("compiling: {}", args[program_i]);
-        let program = os.compile(&args[program_i]);
-        let lines: Vec<&str> = program.split("\n").collect();
-        for (line_i, line) in lines.iter().enumerate(){
-            println!("{}: {}", line_i, line);
+    if args.len() == 2 && args[1] == "shell" {
+        let mut os = OS::new();
+        os.verbose = verbose.is_some();
+        os.run_shell().unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        return;
+    }
+    if args.len() < 3 {
+        usage();
+    }
+    let files = &args[2..];
+    if let Some(ast_format) = ast_format {
+        match files {
+            [path] if args[1] == "compile" => emit_ast(path, &compiler_options, &ast_format, None),
+            [path, flag, output] if args[1] == "compile" && flag == "-o" => emit_ast(path, &compiler_options, &ast_format, Some(output)),
+            _ => usage(),
         }
-        programs.push(program);
+        return;
+    }
+    let mut os = OS::new();
+    os.compiler_options = compiler_options;
+    os.verbose = verbose.is_some();
+    os.check_uninit = check_uninit;
+    os.check_heap = check_heap;
+    os.args = files.first().cloned().into_iter().chain(program_args).collect();
+    os.show_display(show_display);
+    if keyboard {
+        os.keyboard.connect_stdin();
+    }
+    if let Some(seed) = random_seed {
+        os.set_random_seed(seed);
     }
-    let programs = programs.iter().map(|s| s.as_str()).collect();
-    let mut res = -1;
-    if args[1] == "run"{
-        res = os.assemble_link_and_run(programs);
-    } else if args[1] == "debug"{
-        res = os.assemble_and_debug(programs);
-    }else{
-        panic!("invalid run mode")
+    if let Some(path) = disk_image {
+        os.fs = FileSystem::open_image(&path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path, err);
+            process::exit(1);
+        });
     }
+    let res = match args[1].as_str() {
+        "compile" => {
+            // the assembly is written next to the source file by default, e.g foo.c -> foo.asm
+            let (path, output) = match files {
+                [path] => (path, Path::new(path).with_extension("asm")),
+                [path, flag, output] if flag == "-o" => (path, PathBuf::from(output)),
+                _ => usage(),
+            };
+            let object = compile_objects(&mut os, std::slice::from_ref(path)).remove(0);
+            // the debug info is written next to the assembly, e.g foo.asm -> foo.dbg
+            let debug_info_output = output.with_extension("dbg");
+            let debug_info = serde_json::to_string(&object.debug_info).unwrap();
+            for (path, contents) in [(&output, &object.code), (&debug_info_output, &debug_info)].iter() {
+                fs::write(path, contents).unwrap_or_else(|err| {
+                    eprintln!("{}: {}", path.display(), err);
+                    process::exit(1);
+                });
+            }
+            return;
+        },
+        "run" => {
+            let objects = compile_objects(&mut os, files);
+            os.link_and_run(objects.iter().collect())
+        },
+        "debug" => {
+            let objects = compile_objects(&mut os, files);
+            os.link_and_debug(objects.iter().collect())
+        },
+        "profile" => {
+            let (paths, output) = match files {
+                [paths @ .., flag, output] if flag == "-o" && !paths.is_empty() => (paths, Some(output)),
+                paths => (paths, None),
+            };
+            let objects = compile_objects(&mut os, paths);
+            let report = os.link_and_profile(objects.iter().collect());
+            match output {
+                Some(output) => fs::write(output, report.to_json()).unwrap_or_else(|err| {
+                    eprintln!("{}: {}", output, err);
+                    process::exit(1);
+                }),
+                None => print!("\n--------\n{}", report),
+            }
+            report.exit_code
+        },
+        "coverage" => {
+            let (paths, output) = match files {
+                [paths @ .., flag, output] if flag == "-o" && !paths.is_empty() => (paths, Some(output)),
+                paths => (paths, None),
+            };
+            let objects = compile_objects(&mut os, paths);
+            let (res, report) = os.link_and_run_with_coverage(objects.iter().collect());
+            match output {
+                Some(output) => fs::write(output, report.to_lcov()).unwrap_or_else(|err| {
+                    eprintln!("{}: {}", output, err);
+                    process::exit(1);
+                }),
+                None => {
+                    println!("\n--------");
+                    for path in paths.iter() {
+                        print!("{}", report.annotate(path, &read_file(path)));
+                    }
+                },
+            }
+            res
+        },
+        "debug-remote" => {
+            let (addr, paths) = match files {
+                [addr, paths @ ..] if !paths.is_empty() => (addr, paths),
+                _ => usage(),
+            };
+            let objects = compile_objects(&mut os, paths);
+            os.link_and_debug_remote(objects.iter().collect(), addr).unwrap_or_else(|err| {
+                eprintln!("{}: {}", addr, err);
+                process::exit(1);
+            })
+        },
+        "build" => {
+            let (paths, output) = match files {
+                [paths @ .., flag, output] if flag == "-o" && !paths.is_empty() => (paths, output),
+                _ => usage(),
+            };
+            let objects = compile_objects(&mut os, paths);
+            fs::write(output, os.link_image(objects.iter().collect())).unwrap_or_else(|err| {
+                eprintln!("{}: {}", output, err);
+                process::exit(1);
+            });
+            return;
+        },
+        "exec" => {
+            let path = match files {
+                [path] => path,
+                _ => usage(),
+            };
+            os.run_image_file(path).unwrap_or_else(|err| {
+                eprintln!("{}: {}", path, err);
+                process::exit(1);
+            })
+        },
+        "asm" => {
+            let programs: Vec<String> = files.iter().map(read_file).collect();
+            os.assemble_link_and_run(programs.iter().map(|program| program.as_str()).collect())
+        },
+        _ => usage(),
+    };
     println!("\n--------");
     println!("Return code:{}", res);
 }
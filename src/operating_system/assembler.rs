@@ -1,67 +1,258 @@
 use crate::cpu::instructions::*;
-use super::layout::DATA_INIT_ADDRESS;
+use crate::cpu::BYTES_PER_WORD;
+use super::compiler::literals::unescape;
+use super::debug_info::DebugInfo;
+use super::layout::{DATA_INIT_ADDRESS, PROGRAM_INIT_ADDRESS};
+use super::symbol_table::SymbolTable;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::convert::TryInto;
 use std::collections::hash_set::Intersection;
+use std::path::Path;
 use std::str::FromStr;
 
+/// removes a comment from the line, comments start with a semicolon, e.g "MOV R1 3 ; x = 3"
+/// data lines are kept as is since a string may contain a semicolon
+fn strip_comment(line: &str) -> &str {
+    if is_data(line) {
+        return line;
+    }
+    line.split(';').next().unwrap()
+}
+
 fn is_label(line: &str) -> bool {
-    line.contains(":")
+    // a string in a .stringz directive or a comment may contain a colon
+    let line = strip_comment(line);
+    !is_data(line) && line.contains(":")
 }
 
 fn get_label_from_line(line: &str) -> Option<String> {
     if is_label(line) {
-        return Some(line.trim().replace(":", ""));
+        return Some(strip_comment(line).trim().replace(":", ""));
     }
     None
 }
 
-fn is_instruction(line: &str) -> bool {
-    !is_label(line) && !is_data(line) && line.trim() != ""
+pub fn is_instruction(line: &str) -> bool {
+    !is_label(line) && !is_data(line) && strip_comment(line).trim() != ""
 }
 
-fn maybe_parse_instruction(
-    line: &str,
-    symbol_table: &HashMap<String, u32>,
-    data_table: &HashMap<String, u32>,
-    cur_rel_address: u32,
-) -> Option<Instruction> {
-    if is_instruction(line) {
-        let args: Vec<&str> = line.split_whitespace().collect();
-        // if line is flow instruction
-        if let Result::Ok(_) = FlowOp::from_str(args[0]) {
-            // replace label string with numeric offset
-            let label = String::from(args[1]);
-            assert!(symbol_table.contains_key(&label), format!("label:{} does not exist in symbol table", label));
-            let offset = (*symbol_table.get(&label).unwrap() as i32) - (cur_rel_address as i32);
-            return Some(Instruction::from_str(&format!("{} {}", args[0], offset)).unwrap());
+#[derive(Debug, PartialEq)]
+enum ExprToken {
+    Num(i64), // wider than a word, so the magnitude of i32::MIN can be negated
+    Name(String),
+    Op(char),
+}
+
+fn tokenize_expr(expr: &str) -> Option<Vec<ExprToken>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if chars[i].is_ascii_digit() {
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            tokens.push(ExprToken::Num(chars[start..i].iter().collect::<String>().parse().ok()?));
+        } else if chars[i].is_alphabetic() || chars[i] == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(ExprToken::Name(chars[start..i].iter().collect()));
+        } else if "+-*/()".contains(chars[i]) {
+            tokens.push(ExprToken::Op(chars[i]));
+            i += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+/// sum := product (('+' | '-') product)*
+fn parse_sum(tokens: &[ExprToken], pos: &mut usize, constants: &HashMap<String, i32>) -> Option<i64> {
+    let mut val = parse_product(tokens, pos, constants)?;
+    while let Some(ExprToken::Op(op)) = tokens.get(*pos) {
+        if *op != '+' && *op != '-' {
+            break;
+        }
+        *pos += 1;
+        let rhs = parse_product(tokens, pos, constants)?;
+        val = if *op == '+' { val.checked_add(rhs)? } else { val.checked_sub(rhs)? };
+    }
+    Some(val)
+}
+
+/// product := atom (('*' | '/') atom)*
+fn parse_product(tokens: &[ExprToken], pos: &mut usize, constants: &HashMap<String, i32>) -> Option<i64> {
+    let mut val = parse_atom(tokens, pos, constants)?;
+    while let Some(ExprToken::Op(op)) = tokens.get(*pos) {
+        if *op != '*' && *op != '/' {
+            break;
         }
-        if let Result::Ok(lea) = DataOp::from_str(args[0]){
-            if matches!(lea, DataOp::LEA) {
-                let dst = String::from(args[1]);
-                let label = String::from(args[2]);
-                assert!(data_table.contains_key(&label), format!("label:{} does not exist in data table", label));
-                let label_addr = data_table.get(&label).unwrap() + DATA_INIT_ADDRESS;
-                return Some(Instruction::from_str(&format!("LEA {} {}", dst, label_addr)).unwrap());
+        *pos += 1;
+        let rhs = parse_atom(tokens, pos, constants)?;
+        val = if *op == '*' { val.checked_mul(rhs)? } else { val.checked_div(rhs)? };
+    }
+    Some(val)
+}
+
+/// atom := number | constant | '-' atom | '(' sum ')'
+fn parse_atom(tokens: &[ExprToken], pos: &mut usize, constants: &HashMap<String, i32>) -> Option<i64> {
+    let token = tokens.get(*pos)?;
+    *pos += 1;
+    match token {
+        ExprToken::Num(val) => Some(*val),
+        ExprToken::Name(name) => constants.get(name).map(|val| *val as i64),
+        ExprToken::Op('-') => parse_atom(tokens, pos, constants)?.checked_neg(),
+        ExprToken::Op('(') => {
+            let val = parse_sum(tokens, pos, constants)?;
+            if tokens.get(*pos) != Some(&ExprToken::Op(')')) {
+                return None;
             }
+            *pos += 1;
+            Some(val)
+        },
+        ExprToken::Op(_) => None,
+    }
+}
+
+/// evaluates an integer expression such as "STACK_TOP-4"
+/// names in the expression are constants defined with ".define <name> <expression>"
+/// returns None if the value doesn't fit in a word
+fn eval_expr(expr: &str, constants: &HashMap<String, i32>) -> Option<i32> {
+    let tokens = tokenize_expr(expr)?;
+    let mut pos = 0;
+    let val = parse_sum(&tokens, &mut pos, constants)?;
+    if pos != tokens.len() {
+        return None;
+    }
+    val.try_into().ok()
+}
+
+/// splits an operand such as "table+2" into the label & the constant that's added to its address
+/// returns None if the operand doesn't start with a label, e.g if it's a constant expression
+fn parse_label_expr(expr: &str, constants: &HashMap<String, i32>) -> Option<(String, i32)> {
+    let tokens = tokenize_expr(expr)?;
+    let label = match tokens.first() {
+        Some(ExprToken::Name(name)) if !constants.contains_key(name) => name.clone(),
+        _ => return None,
+    };
+    let sign = match tokens.get(1) {
+        None => return Some((label, 0)),
+        Some(ExprToken::Op('+')) => 1,
+        Some(ExprToken::Op('-')) => -1,
+        Some(_) => return None,
+    };
+    let mut pos = 2;
+    let addend = parse_sum(&tokens, &mut pos, constants)?;
+    if pos != tokens.len() {
+        return None;
+    }
+    Some((label, addend.checked_mul(sign)?.try_into().ok()?))
+}
+
+/// the labels in an operand, e.g of an instruction that references "table+2"
+pub fn referenced_labels(expr: &str) -> Vec<String> {
+    tokenize_expr(expr).unwrap_or_default().into_iter()
+        .filter_map(|token| match token {
+            ExprToken::Name(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// collects the constants defined in the program, in order, so a definition can use the constants defined before it
+fn extract_constants(program: &str) -> HashMap<String, i32> {
+    let mut constants = HashMap::new();
+    for line in program.split("\n") {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.first() != Some(&".define") {
+            continue;
+        }
+        assert!(parts.len() >= 3, "invalid constant definition: {}", line.trim());
+        let expr = parts[2..].join("");
+        let val = eval_expr(&expr, &constants).unwrap_or_else(|| panic!("invalid expression in constant definition: {}", line.trim()));
+        if constants.insert(parts[1].to_string(), val).is_some() {
+            panic!("duplicate constant: {}", parts[1]);
         }
-        if let Ok(instr) = Instruction::from_str(line) {
-            return Some(instr);
-        } 
     }
-    None
+    constants
+}
+
+/// collects the symbols marked with ".global <name>", which other programs can reference
+fn extract_globals(program: &str) -> HashSet<String> {
+    let mut globals = HashSet::new();
+    for line in program.split("\n") {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.first() != Some(&".global") {
+            continue;
+        }
+        assert!(parts.len() == 2, "invalid global symbol declaration: {}", line.trim());
+        globals.insert(parts[1].to_string());
+    }
+    globals
+}
+
+/// replaces the instruction's operands that are expressions with their values
+/// returns None if an operand isn't a valid expression, e.g if it uses an undefined constant
+fn eval_operands(args: &[&str], constants: &HashMap<String, i32>) -> Option<String> {
+    let mut evaluated = vec![args[0].to_string()];
+    for arg in args[1..].iter() {
+        if Register::from_str(arg).is_ok() {
+            evaluated.push(arg.to_string());
+        } else {
+            evaluated.push(eval_expr(arg, constants)?.to_string());
+        }
+    }
+    Some(evaluated.join(" "))
+}
+
+/// parses an instruction of a program whose labels are in symbol_table
+/// references to labels that aren't in the program, and to absolute addresses (LEA), are left for the linker to fill in
+/// a label can be followed by a constant that's added to its address, e.g "LEA R1 table+2"
+fn parse_instruction(
+    line: &str,
+    symbol_table: &SymbolTable,
+    constants: &HashMap<String, i32>,
+    instr_i: u32,
+) -> Option<(Instruction, Option<Relocation>)> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    // if line is flow instruction
+    if let Result::Ok(_) = FlowOp::from_str(args[0]) {
+        // replace label string with numeric offset
+        let (label, addend) = parse_label_expr(args.get(1)?, constants)?;
+        return Some(match symbol_table.address_of(&label) {
+            Some(label_addr) => (Instruction::from_str(&format!("{} {}", args[0], label_addr as i32 + addend - instr_i as i32)).ok()?, None),
+            None => (
+                Instruction::from_str(&format!("{} 0", args[0])).ok()?,
+                Some(Relocation { instr_i, kind: RelocationKind::Relative, symbol: label, addend }),
+            ),
+        });
+    }
+    if let Result::Ok(DataOp::LEA) = DataOp::from_str(args[0]) {
+        // either a data label, or a code label (e.g for function pointers)
+        let (label, addend) = parse_label_expr(args.get(2)?, constants)?;
+        return Some((
+            Instruction::from_str(&format!("LEA {} 0", args[1])).ok()?,
+            Some(Relocation { instr_i, kind: RelocationKind::Absolute, symbol: label, addend }),
+        ));
+    }
+    Some((Instruction::from_str(&eval_operands(&args, constants)?).ok()?, None))
 }
 
 
 /// returns program's symbol table & # of instructions
-pub fn gen_symbol_table(program: &str, start_addr: u32) -> (HashMap<String, u32>, u32){
-    let mut symbol_table = HashMap::new();
+pub fn gen_symbol_table(program: &str, start_addr: u32) -> (SymbolTable, u32){
+    let mut symbol_table = SymbolTable::new();
     let mut cur_address = start_addr;
 
     let lines: Vec<&str> = program.split("\n").collect();
     for (line_i, line) in lines.iter().enumerate() {
         if let Some(label) = get_label_from_line(line) {
-            symbol_table.insert(label, cur_address);
+            symbol_table.insert(&label, cur_address, false);
         } else if is_instruction(line) {
             cur_address += 1;
         }
@@ -74,113 +265,387 @@ fn is_data(line: &str) -> bool{
     line.trim().starts_with(".")
 }
 
-pub fn extract_data(program: &str, cur_data_size: u32) -> (Vec<i32>, HashMap<String, u32>){
+/// the label & the (unescaped) string of a .stringz or .bytez line
+fn parse_string_directive(line: &str) -> (String, String) {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let (directive, string_label) = (parts[0], parts[1]);
+    // the string is the rest of the line after a single space, so it keeps its spaces
+    let rest = &line.trim_start()[directive.len()..].trim_start()[string_label.len()..];
+    let string = rest.strip_prefix(' ').unwrap_or("");
+    let string = unescape(string).unwrap_or_else(|| panic!("invalid escape sequence in: {}", line));
+    (string_label.to_string(), string)
+}
+
+/// the data of the program, its labels, the words that hold addresses of labels, which the linker fills in,
+/// & the alignment the data needs, see .align
+pub fn extract_data(program: &str, cur_data_size: u32, constants: &HashMap<String, i32>) -> (Vec<i32>, SymbolTable, Vec<DataRelocation>, u32){
     let mut data = Vec::new();
-    let mut data_table = HashMap::new();
+    let mut data_table = SymbolTable::new();
+    let mut relocations = Vec::new();
+    let mut align = 1;
+    let eval_size = |expr: &str| -> u32 {
+        eval_expr(expr, constants).filter(|size| *size >= 0).map(|size| size as u32).unwrap_or_else(|| panic!("invalid size: {}", expr))
+    };
     let lines: Vec<&str> = program.split("\n").collect();
     for line in lines.iter() {
         if is_data(line){
             let parts: Vec<&str> = line.split_whitespace().collect();
             match parts[0]{
-                ".stringz" => { // zero terminated string
-                    let string_label = &parts[1];
-                    let string_parts = &parts[2..];
-                    let string = &string_parts.join(" ");
-                    data_table.insert(string_label.to_string(), cur_data_size + data.len() as u32);
+                ".stringz" => { // zero terminated string, may contain C escape sequences
+                    let (string_label, string) = parse_string_directive(line);
+                    data_table.insert(&string_label, cur_data_size + data.len() as u32, false);
                     for val in string.chars() {
                         data.push(val as i32);
                     }
                     data.push(0);
                 },
+                ".bytez" => { // like .stringz, but the string is packed 4 bytes per word, for LOADB
+                    let (string_label, string) = parse_string_directive(line);
+                    data_table.insert(&string_label, cur_data_size + data.len() as u32, false);
+                    let mut bytes: Vec<u8> = string.chars().map(|c| c as u8).collect();
+                    bytes.push(0);
+                    for word in bytes.chunks(BYTES_PER_WORD as usize) {
+                        let mut word_bytes = [0; BYTES_PER_WORD as usize];
+                        word_bytes[..word.len()].copy_from_slice(word);
+                        data.push(i32::from_le_bytes(word_bytes));
+                    }
+                },
                 ".block" => { // allocate a block of data
                     let block_label = &parts[1];
                     let block_size = &parts[2];
                     let block_size : u32 = block_size.parse().unwrap();
-                    data_table.insert(block_label.to_string(), cur_data_size + data.len() as u32);
+                    data_table.insert(block_label, cur_data_size + data.len() as u32, false);
                     for _ in 0..block_size{
                         data.push(0);
                     }
 
                 }
+                ".word" => { // initialized words: constant expressions, or labels whose address (plus a constant) is filled in by the linker
+                    assert!(parts.len() >= 3, "invalid .word directive: {}", line.trim());
+                    data_table.insert(parts[1], cur_data_size + data.len() as u32, false);
+                    for value in parts[2..].iter() {
+                        if let Some(val) = eval_expr(value, constants) {
+                            data.push(val);
+                        } else if let Some((symbol, addend)) = parse_label_expr(value, constants) {
+                            relocations.push(DataRelocation { data_i: data.len() as u32, symbol, addend });
+                            data.push(0);
+                        } else {
+                            panic!("invalid value: {}", value);
+                        }
+                    }
+                }
+                ".space" => { // zeroed words without a label, e.g padding after a .word
+                    assert!(parts.len() == 2, "invalid .space directive: {}", line.trim());
+                    data.resize(data.len() + eval_size(parts[1]) as usize, 0);
+                }
+                ".align" => { // pads the data with zeros so the next word's address is a multiple of n
+                    assert!(parts.len() == 2, "invalid .align directive: {}", line.trim());
+                    let n = eval_size(parts[1]);
+                    assert!(n > 0, "invalid alignment: {}", line.trim());
+                    while !(cur_data_size + data.len() as u32).is_multiple_of(n) {
+                        data.push(0);
+                    }
+                    align = lcm(align, n);
+                }
+                ".loc" => {}, // source map entry, handled when parsing instructions
+                ".define" => {}, // constant definition, see extract_constants
+                ".global" => {}, // exported symbol, see extract_globals
                 _ => panic!("invalid data instruction")
             }
         } 
     }
-    (data, data_table)
+    (data, data_table, relocations, align)
+}
+
+fn lcm(a: u32, b: u32) -> u32 {
+    let gcd = |mut a: u32, mut b: u32| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
 }
 
 pub fn assemble(program: &str) -> Executable{
-    assemble_and_link(vec![program])
+    assemble_and_link_at(vec![program], PROGRAM_INIT_ADDRESS, DATA_INIT_ADDRESS)
 }
 
-pub struct Executable{
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RelocationKind {
+    Relative, // offset from the instruction to the label, for flow instructions
+    Absolute, // address of the label, for LEA
+}
+
+/// an instruction operand that the linker fills in once the address of the label is known
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Relocation {
+    pub instr_i: u32,
+    pub kind: RelocationKind,
+    pub symbol: String,
+    #[serde(default)]
+    pub addend: i32, // added to the label's address, e.g 2 for "table+2"
+}
+
+/// a word of data that the linker fills in with the address of the label plus the addend, see .word
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataRelocation {
+    pub data_i: u32,
+    pub symbol: String,
+    pub addend: i32,
+}
+
+impl Relocation {
+    fn apply(&self, instr: &mut Instruction, value: i32) {
+        match instr {
+            Instruction::Flow { offset, .. } => *offset = value,
+            Instruction::Data { src, .. } => *src = RegOrImm::Val(value),
+            _ => panic!("cannot relocate instruction: {}", instr.to_str()),
+        }
+    }
+}
+
+/// an assembled program that can be linked to run from any address
+/// addresses in the symbol tables are relative to the start of the object's code & data
+/// symbols are private to the object unless they're exported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelocatableObject {
     pub code: Vec<Instruction>,
     pub data: Vec<i32>,
-    pub symbol_table: HashMap<String, u32>,
-    pub data_table: HashMap<String, u32>,
+    pub relocations: Vec<Relocation>,
+    pub data_relocations: Vec<DataRelocation>,
+    pub symbol_table: SymbolTable,
+    pub data_table: SymbolTable,
+    data_align: u32, // the object's data is linked at a multiple of it, so its .align directives hold
+    instr_locs: Vec<Option<(String, u32)>>,
+    line_addresses: Vec<u32>, // assembly line -> address of the next instruction, for breakpoints in the debugger
 }
 
-fn hashmaps_key_intersection(set1: &HashMap<String, u32>, set2: &HashMap<String, u32>) -> Vec<String>{
-    let keyset1 : HashSet<String> = set1.keys().into_iter().map(|s| s.clone()).collect();
-    let keyset2 : HashSet<String> = set2.keys().into_iter().map(|s| s.clone()).collect();
-    keyset1.intersection(&keyset2).into_iter().map(|s| s.clone()).collect()
+pub fn assemble_object(program: &str) -> RelocatableObject {
+    let constants = extract_constants(program);
+    let (mut symbol_table, _) = gen_symbol_table(program, 0);
+    let (data, mut data_table, data_relocations, data_align) = extract_data(program, 0, &constants);
+    for symbol in extract_globals(program) {
+        if !symbol_table.set_exported(&symbol) && !data_table.set_exported(&symbol) {
+            panic!("global symbol {} is not defined", symbol);
+        }
+    }
+    let mut code = Vec::new();
+    let mut relocations = Vec::new();
+    let mut instr_locs = Vec::new();
+    let mut line_addresses = Vec::new();
+    let mut cur_loc = None;
+    for line in program.split("\n") {
+        line_addresses.push(code.len() as u32);
+        if let Some(loc) = parse_loc_directive(line) {
+            cur_loc = Some(loc);
+        } else if is_instruction(line) {
+            let (instr, relocation) = parse_instruction(strip_comment(line), &symbol_table, &constants, code.len() as u32)
+                .unwrap_or_else(|| panic!("Invalid instruction: {}", line));
+            code.push(instr);
+            relocations.extend(relocation);
+            instr_locs.push(cur_loc.clone());
+        }
+    }
+    RelocatableObject { code, data, relocations, data_relocations, symbol_table, data_table, data_align, instr_locs, line_addresses }
 }
 
-pub fn assemble_and_link(programs: Vec<&str>) -> Executable {
-    let mut symbol_table = HashMap::new();
-    let mut data_table = HashMap::new();
-    let mut instructions = Vec::new();
+/// links the objects into an executable whose code & data will be loaded at the given addresses
+/// a label is looked up in the object that references it, & then in the global symbols of all objects
+pub fn link_objects(objects: &[&RelocatableObject], code_address: u32, data_address: u32) -> Executable {
+    let mut code = Vec::new();
     let mut data = Vec::new();
-    let mut cur_rel_address = 0;
-    let mut cur_data_size = 0;
-
-    // create a symbol table for each program separately 
-    // and add it to global symbol table
-    // side note: we create a separate symobl table for each file instead of just concatenating all of the programs
-    // in order to be able to support source-level breakpoints in the future
-    for program in programs.iter(){
-        let (program_symbol_table, program_size) = gen_symbol_table(*program, cur_rel_address);
-        let (mut program_data, program_data_table) = extract_data(*program, cur_data_size);
-        cur_rel_address += program_size;
-        cur_data_size += program_data.len() as u32;
-        data.append(&mut program_data);
-        let symbol_intersect = hashmaps_key_intersection(&symbol_table, &program_symbol_table);
-        let data_intersect = hashmaps_key_intersection(&data_table, &program_data_table);
-        if symbol_intersect.len() != 0{
-            panic!("duplicate symbols between programs: {:?}", symbol_intersect);
+    let mut instr_locs = Vec::new();
+    let mut offsets = Vec::new(); // (code offset, data offset) of each object
+    for object in objects.iter() {
+        while !(data.len() as u32).is_multiple_of(object.data_align) {
+            data.push(0);
         }
-        if data_intersect.len() != 0{
-            panic!("duplicate data labels between programs: {:?}", data_intersect);
+        offsets.push((code.len() as u32, data.len() as u32));
+        code.extend(object.code.iter().cloned());
+        data.extend(object.data.iter().cloned());
+        instr_locs.extend(object.instr_locs.iter().cloned());
+    }
+    let mut global_symbols = SymbolTable::new();
+    let mut global_data = SymbolTable::new();
+    for (object, (code_offset, data_offset)) in objects.iter().zip(offsets.iter()) {
+        // sorted so that the reported error doesn't depend on which table the symbol is in
+        let mut globals: Vec<(&str, bool)> = object.symbol_table.exported().map(|symbol| (symbol.name.as_str(), true))
+            .chain(object.data_table.exported().map(|symbol| (symbol.name.as_str(), false)))
+            .collect();
+        globals.sort();
+        for (symbol, is_code) in globals {
+            let duplicate = if is_code {
+                global_symbols.insert(symbol, object.symbol_table.address_of(symbol).unwrap() + code_offset, true).is_some()
+            } else {
+                global_data.insert(symbol, object.data_table.address_of(symbol).unwrap() + data_offset, true).is_some()
+            };
+            if duplicate || (global_symbols.contains(symbol) && global_data.contains(symbol)) {
+                panic!("duplicate symbols between programs: {}", symbol);
+            }
         }
-        symbol_table.extend(program_symbol_table);
-        data_table.extend(program_data_table);
-    }
-    let whole_program = programs.join("\n");
-    println!("--------");
-    for (line_i, line) in whole_program.split("\n").collect::<Vec<&str>>().iter().enumerate(){
-        println!("{}: {}", line_i, line);
-    }
-    println!("--------");
-    // second pass, parse instructions & calc relative offsets
-    cur_rel_address = 0;
-    let lines: Vec<&str> = whole_program.split("\n").collect();
-    for (line_i, line) in lines.iter().enumerate() {
-        symbol_table.insert(format!("_LINE_{}", line_i.to_string()), cur_rel_address); // for setting breakpoints in debugger
-        if let Some(instr) = maybe_parse_instruction(line, &symbol_table, &data_table, cur_rel_address) {
-            instructions.push(instr);
-            cur_rel_address += 1;
-        } else if !is_label(line) && !is_data(line) && line.trim().len() != 0 {
-            panic!("Invalid instruction: {}", line);
+    }
+    for (object, (code_offset, data_offset)) in objects.iter().zip(offsets.iter()) {
+        let symbol_addr = |label: &str| object.symbol_table.address_of(label).map(|addr| addr + code_offset)
+            .or_else(|| global_symbols.address_of(label));
+        // the address in memory of a data label, or of a code label (e.g for function pointers)
+        let absolute_addr = |label: &str| {
+            let data_addr = object.data_table.address_of(label).map(|addr| addr + data_offset)
+                .or_else(|| global_data.address_of(label));
+            match (data_addr, symbol_addr(label)) {
+                (Some(data_addr), _) => (data_addr + data_address) as i32,
+                (None, Some(symbol_addr)) => (symbol_addr + code_address) as i32,
+                (None, None) => panic!("label:{} does not exist in data table or symbol table", label),
+            }
+        };
+        for relocation in object.relocations.iter() {
+            let instr_i = code_offset + relocation.instr_i;
+            let label = &relocation.symbol;
+            let value = match relocation.kind {
+                RelocationKind::Relative => {
+                    let label_addr = symbol_addr(label).unwrap_or_else(|| panic!("label:{} does not exist in symbol table", label));
+                    label_addr as i32 - instr_i as i32
+                },
+                RelocationKind::Absolute => absolute_addr(label),
+            };
+            relocation.apply(&mut code[instr_i as usize], value + relocation.addend);
         }
+        for relocation in object.data_relocations.iter() {
+            data[(data_offset + relocation.data_i) as usize] = absolute_addr(&relocation.symbol) + relocation.addend;
+        }
+    }
+    // the executable's tables are for debugging, private symbols that collide with another object's symbols are left out
+    let mut symbol_table = global_symbols;
+    let mut data_table = global_data;
+    let mut line_addresses = Vec::new();
+    for (object, (code_offset, data_offset)) in objects.iter().zip(offsets.iter()) {
+        for symbol in object.symbol_table.iter() {
+            if !symbol_table.contains(&symbol.name) {
+                symbol_table.insert(&symbol.name, symbol.address + code_offset, false);
+            }
+        }
+        for symbol in object.data_table.iter() {
+            if !data_table.contains(&symbol.name) {
+                data_table.insert(&symbol.name, symbol.address + data_offset, false);
+            }
+        }
+        line_addresses.extend(object.line_addresses.iter().map(|addr| addr + code_offset));
     }
     Executable{
-        code: instructions,
+        code,
         data,
         symbol_table,
         data_table,
+        line_addresses,
+        debug_info: DebugInfo::default(),
+        source_map: SourceMap { instr_locs },
     }
 }
 
+pub struct Executable{
+    pub code: Vec<Instruction>,
+    pub data: Vec<i32>,
+    pub symbol_table: SymbolTable, // the exported code symbols are the functions, e.g those compiled from C
+    pub data_table: SymbolTable,
+    pub line_addresses: Vec<u32>, // line of the assembly (of all the programs) -> address of the next instruction, for breakpoints in the debugger
+    pub debug_info: DebugInfo, // empty unless the executable was linked from object files
+    pub source_map: SourceMap,
+}
+
+/// the first bytes of a binary image, see Executable::to_image
+const IMAGE_MAGIC: &[u8; 4] = b"SVM\x01";
+
+impl Executable {
+    /// a flat binary image of the executable:
+    /// the magic, the # of instructions & the # of data words, followed by the encoded instructions & the data
+    /// numbers are little endian, the symbols & source map aren't included
+    pub fn to_image(&self) -> Vec<u8> {
+        let mut image = IMAGE_MAGIC.to_vec();
+        image.extend_from_slice(&(self.code.len() as u32).to_le_bytes());
+        image.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        for instr in self.code.iter() {
+            image.extend_from_slice(&instr.encode().to_le_bytes());
+        }
+        for val in self.data.iter() {
+            image.extend_from_slice(&val.to_le_bytes());
+        }
+        image
+    }
+
+    /// the name of the function that contains the instruction, i.e the closest global code symbol before it
+    pub fn function_at(&self, instr_i: u32) -> Option<&String> {
+        self.symbol_table.nearest_exported(instr_i).map(|symbol| &symbol.name)
+    }
+
+    /// the inverse of to_image, returns None if the image is invalid
+    pub fn from_image(image: &[u8]) -> Option<Executable> {
+        if !image.starts_with(IMAGE_MAGIC) {
+            return None;
+        }
+        let read_u32 = |offset: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(image.get(offset..offset + 4)?.try_into().ok()?))
+        };
+        let code_len = read_u32(4)? as usize;
+        let data_len = read_u32(8)? as usize;
+        let data_start = 12 + code_len * 8;
+        if image.len() != data_start + data_len * 4 {
+            return None;
+        }
+        let mut code = Vec::new();
+        for word in image[12..data_start].chunks(8) {
+            code.push(Instruction::decode(u64::from_le_bytes(word.try_into().ok()?))?);
+        }
+        let data = image[data_start..].chunks(4)
+            .map(|word| i32::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        Some(Executable {
+            code,
+            data,
+            symbol_table: SymbolTable::new(),
+            data_table: SymbolTable::new(),
+            line_addresses: Vec::new(),
+            debug_info: DebugInfo::default(),
+            source_map: SourceMap { instr_locs: Vec::new() },
+        })
+    }
+}
+
+/// maps instructions to the C source lines they were compiled from
+/// built from the ".loc <file> <line>" directives the compiler emits before each statement
+pub struct SourceMap {
+    instr_locs: Vec<Option<(String, u32)>>, // instruction index -> (file, line)
+}
+
+impl SourceMap {
+    pub fn get_loc(&self, instr_i: u32) -> Option<&(String, u32)> {
+        self.instr_locs.get(instr_i as usize)?.as_ref()
+    }
+
+    /// returns the first instruction of the given source line
+    /// file can be given either as the full path or as a suffix of it (e.g just the file name)
+    pub fn get_instr(&self, file: &str, line: u32) -> Option<u32> {
+        self.instr_locs.iter().position(|loc| match loc {
+            Some((loc_file, loc_line)) => *loc_line == line && Path::new(loc_file).ends_with(file),
+            None => false,
+        }).map(|instr_i| instr_i as u32)
+    }
+}
+
+fn parse_loc_directive(line: &str) -> Option<(String, u32)> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 3 || parts[0] != ".loc" {
+        return None;
+    }
+    let src_line = parts[parts.len() - 1].parse().ok()?;
+    Some((parts[1..parts.len() - 1].join(" "), src_line))
+}
+
+/// assembles & links the programs into an executable whose code & data will be loaded at the given addresses
+pub fn assemble_and_link_at(programs: Vec<&str>, code_address: u32, data_address: u32) -> Executable {
+    let objects: Vec<RelocatableObject> = programs.iter().map(|program| assemble_object(program)).collect();
+    link_objects(&objects.iter().collect::<Vec<&RelocatableObject>>(), code_address, data_address)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -260,10 +725,10 @@ mod tests {
         ";
         let exec = assemble(program);
 
-        // println!("{:?}", symbol_table);
-        assert_eq!(*exec.symbol_table.get("L1").unwrap(), 0);
-        assert_eq!(*exec.symbol_table.get("L3").unwrap(), 2);
-        assert_eq!(*exec.symbol_table.get("L2").unwrap(), 4);
+        assert_eq!(exec.symbol_table.address_of("L1"), Some(0));
+        assert_eq!(exec.symbol_table.address_of("L3"), Some(2));
+        assert_eq!(exec.symbol_table.address_of("L2"), Some(4));
+        assert_eq!(exec.symbol_table.nearest_symbol(3).unwrap().name, "L3");
         if let Instruction::Flow { ref op, ref offset } = exec.code[1] {
             assert_eq!(*op, FlowOp::JUMP);
             assert_eq!(*offset, 3);
@@ -273,6 +738,25 @@ mod tests {
             assert_eq!(*offset, -3);
         }
     }
+    #[test]
+    fn test_source_map() {
+        let program = "
+        MAIN:
+        .loc dir/main.c 2
+        MOV R1 3
+        ADD R1 R1 1
+        .loc dir/main.c 3
+        HALT
+        ";
+        let exec = assemble(program);
+        assert_eq!(exec.source_map.get_loc(0), Some(&("dir/main.c".to_string(), 2)));
+        assert_eq!(exec.source_map.get_loc(1), Some(&("dir/main.c".to_string(), 2)));
+        assert_eq!(exec.source_map.get_loc(2), Some(&("dir/main.c".to_string(), 3)));
+        assert_eq!(exec.source_map.get_instr("main.c", 3), Some(2));
+        assert_eq!(exec.source_map.get_instr("dir/main.c", 2), Some(0));
+        assert_eq!(exec.source_map.get_instr("other.c", 2), None);
+    }
+
     #[test]
     fn test_data() {
         let program = "
@@ -287,11 +771,205 @@ mod tests {
         ";
         let exec = assemble(program);
         assert_eq!(exec.data.len(), 12);
-        assert_eq!(*exec.data_table.get("s1").unwrap(), 0);
-        assert_eq!(*exec.data_table.get("s2").unwrap(), 6);
+        assert_eq!(exec.data_table.address_of("s1"), Some(0));
+        assert_eq!(exec.data_table.address_of("s2"), Some(6));
         assert_eq!(exec.data[0] , 'h' as i32);
         assert_eq!(exec.data[5] , 0);
         assert_eq!(exec.data[6] , 'w' as i32);
         assert_eq!(exec.data[11] , 0);
     }
+
+    #[test]
+    fn test_string_escapes() {
+        let program = "
+        .stringz s1 a  b\\n\x41
+        .stringz s2
+        .stringz s3 x: %d
+        ";
+        let exec = assemble(program);
+        let s1: String = exec.data[..6].iter().map(|c| *c as u8 as char).collect();
+        assert_eq!(s1, "a  b\nA");
+        assert_eq!(exec.data[6], 0);
+        assert_eq!(exec.data_table.address_of("s2"), Some(7));
+        assert_eq!(exec.data.len(), 14);
+        assert!(exec.data_table.contains("s3"));
+        assert!(exec.symbol_table.iter().all(|symbol| !symbol.name.contains("stringz")));
+    }
+
+    #[test]
+    fn test_packed_string() {
+        let exec = assemble(".bytez s1 hello\n.bytez s2 abcd");
+        assert_eq!(exec.data, vec![
+            i32::from_le_bytes(*b"hell"), i32::from_le_bytes(*b"o\0\0\0"),
+            i32::from_le_bytes(*b"abcd"), 0,
+        ]);
+        assert_eq!(exec.data_table.address_of("s2"), Some(2));
+    }
+
+    #[test]
+    fn test_comments() {
+        let program = "
+        ; sets R1 to 3: the answer
+        MOV R1 3 ; x = 3
+        loop: ; a label
+        .stringz s a;b
+        ";
+        let exec = assemble(program);
+        assert_eq!(exec.code.len(), 1);
+        assert!(exec.symbol_table.contains("loop"));
+        assert!(exec.symbol_table.iter().all(|symbol| !symbol.name.contains(";")));
+        assert_eq!(exec.data.len(), 4);
+    }
+
+    #[test]
+    fn test_data_directives() {
+        let program = "
+        .define N 2
+        .stringz s ab
+        .word table 7 -1 N*3 s s+1 end
+        .space N
+        .align 4
+        .word aligned 5
+        LEA R1 table+2
+        end:
+        JUMP end-1
+        ";
+        let exec = link_objects(&[&assemble_object(program)], 100, 500);
+        // the string, the table, 2 words of space & 1 word of padding
+        assert_eq!(exec.data, vec!['a' as i32, 'b' as i32, 0, 7, -1, 6, 500, 501, 101, 0, 0, 0, 5]);
+        assert_eq!(exec.data_table.address_of("aligned"), Some(12));
+        assert_eq!(exec.code, vec![Instruction::from_str("LEA R1 505").unwrap(), Instruction::from_str("JUMP -1").unwrap()]);
+    }
+
+    #[test]
+    fn test_link_aligned_data() {
+        let lib = assemble_object(".global table\n.align 4\n.word table lib\n.global lib\nlib:\nRET");
+        let main = assemble_object(".stringz s a\nLEA R1 table\nCALL lib");
+        let exec = link_objects(&[&main, &lib], 100, 500);
+        // the lib's data starts at a multiple of 4, the table holds the address of lib's code
+        assert_eq!(exec.data, vec!['a' as i32, 0, 0, 0, 102]);
+        assert_eq!(exec.code[0], Instruction::from_str("LEA R1 504").unwrap());
+    }
+
+    #[test]
+    fn test_image() {
+        let program = "
+        .stringz s hi
+        LEA R1 s
+        loop:
+        ADD R1 R1 -1
+        JUMP loop
+        ";
+        let exec = assemble(program);
+        let image = exec.to_image();
+        assert_eq!(image.len(), 12 + 3 * 8 + 3 * 4);
+        let loaded = Executable::from_image(&image).unwrap();
+        assert_eq!(loaded.code, exec.code);
+        assert_eq!(loaded.data, exec.data);
+        assert!(Executable::from_image(&image[..image.len() - 1]).is_none());
+        assert!(Executable::from_image(b"ELF").is_none());
+    }
+
+    #[test]
+    fn test_constants() {
+        let program = "
+        .define STACK_TOP 9999
+        .define SIZE (STACK_TOP - 9989) * 2
+        MOV R1 STACK_TOP-4
+        ADD R2 R1 SIZE/4
+        SUB R3 R1 -(2+3)*2
+        TSTE R1 SIZE
+        MOV R4 -2147483648
+        MOV R4 -(2147483647+1)
+        ";
+        let exec = assemble(program);
+        assert!(exec.data.is_empty());
+        assert_eq!(exec.code, vec![
+            Instruction::from_str("MOV R1 9995").unwrap(),
+            Instruction::from_str("ADD R2 R1 5").unwrap(),
+            Instruction::from_str("SUB R3 R1 -10").unwrap(),
+            Instruction::from_str("TSTE R1 20").unwrap(),
+            Instruction::from_str("MOV R4 -2147483648").unwrap(),
+            Instruction::from_str("MOV R4 -2147483648").unwrap(),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid instruction: MOV R1 2147483648")]
+    fn test_constant_out_of_range() {
+        assemble("MOV R1 2147483648");
+    }
+
+    #[test]
+    fn test_relocatable_object() {
+        let main = assemble_object("
+        .stringz msg hi
+        LEA R1 msg
+        CALL print
+        LEA R2 print
+        HALT
+        ");
+        let lib = assemble_object("
+        .global print
+        print:
+        RET
+        ");
+        assert_eq!(main.relocations.len(), 3);
+        assert!(lib.relocations.is_empty());
+        // objects can be saved & loaded before linking
+        let main: RelocatableObject = serde_json::from_str(&serde_json::to_string(&main).unwrap()).unwrap();
+
+        let exec = link_objects(&[&main, &lib], 100, 500);
+        assert_eq!(exec.code[0], Instruction::from_str("LEA R1 500").unwrap());
+        assert_eq!(exec.code[1], Instruction::from_str("CALL 3").unwrap());
+        assert_eq!(exec.code[2], Instruction::from_str("LEA R2 104").unwrap());
+        assert_eq!(exec.symbol_table.address_of("print"), Some(4));
+        assert!(exec.symbol_table.is_exported("print"));
+
+        // the same objects can be linked to run from other addresses
+        let exec = link_objects(&[&main, &lib], 200, 800);
+        assert_eq!(exec.code[0], Instruction::from_str("LEA R1 800").unwrap());
+        assert_eq!(exec.code[1], Instruction::from_str("CALL 3").unwrap());
+        assert_eq!(exec.code[2], Instruction::from_str("LEA R2 204").unwrap());
+    }
+
+    #[test]
+    fn test_private_symbols() {
+        // both objects use the label loop, each object's jump goes to its own label
+        let main = assemble_object(".global main\nmain:\nloop:\nCALL lib\nJUMP loop");
+        let lib = assemble_object(".global lib\nlib:\nMOV R1 1\nloop:\nRET\nJUMP loop\nJUMP main");
+        let exec = link_objects(&[&main, &lib], 0, 0);
+        assert_eq!(exec.code[1], Instruction::from_str("JUMP -1").unwrap());
+        assert_eq!(exec.code[4], Instruction::from_str("JUMP -1").unwrap());
+        assert_eq!(exec.code[5], Instruction::from_str("JUMP -5").unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "label:loop does not exist in symbol table")]
+    fn test_private_symbol_not_exported() {
+        let main = assemble_object("JUMP loop");
+        let lib = assemble_object("loop:\nRET");
+        link_objects(&[&main, &lib], 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate symbols between programs: foo")]
+    fn test_duplicate_global() {
+        let first = assemble_object(".global foo\nfoo:\nRET");
+        let second = assemble_object(".global foo\n.block foo 1");
+        link_objects(&[&first, &second], 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "label:print does not exist in symbol table")]
+    fn test_undefined_label() {
+        let main = assemble_object("CALL print");
+        link_objects(&[&main], 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid instruction: MOV R1 SIZE+1")]
+    fn test_undefined_constant() {
+        assemble("MOV R1 SIZE+1");
+    }
 }
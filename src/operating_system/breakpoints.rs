@@ -0,0 +1,169 @@
+use crate::cpu::instructions::{register_from_str, Register};
+use crate::cpu::Registers;
+
+/// a comparison of a register with a value, e.g R1 == 5
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    reg: Register,
+    op: String,
+    value: i32,
+}
+
+impl Condition {
+    /// parses the condition from its args, e.g ["R1", ">=", "5"]
+    pub fn parse(args: &[&str]) -> Option<Condition> {
+        match args {
+            [reg, op, value] => {
+                if !["==", "!=", "<", "<=", ">", ">="].contains(op) {
+                    return None;
+                }
+                Some(Condition {
+                    reg: register_from_str(reg).ok()?,
+                    op: op.to_string(),
+                    value: value.parse().ok()?,
+                })
+            },
+            _ => None,
+        }
+    }
+
+    pub fn holds(&self, regs: &Registers) -> bool {
+        let reg_val = regs.get(&self.reg);
+        match self.op.as_str() {
+            "==" => reg_val == self.value,
+            "!=" => reg_val != self.value,
+            "<" => reg_val < self.value,
+            "<=" => reg_val <= self.value,
+            ">" => reg_val > self.value,
+            ">=" => reg_val >= self.value,
+            _ => panic!("invalid condition operator: {}", self.op),
+        }
+    }
+
+    pub fn to_str(&self) -> String {
+        format!("{} {} {}", self.reg, self.op, self.value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub id: u32,
+    pub instr_i: u32,
+    pub location: String, // as given to the break command, e.g foo.c:12
+    pub condition: Option<Condition>,
+    pub enabled: bool,
+}
+
+/// the breakpoints of a debugged program, numbered from 1 in the order they were set
+#[derive(Debug)]
+pub struct Breakpoints {
+    breakpoints: Vec<Breakpoint>,
+    next_id: u32,
+}
+
+impl Breakpoints {
+    pub fn new() -> Breakpoints {
+        Breakpoints {
+            breakpoints: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// returns the id of the new breakpoint
+    pub fn add(&mut self, instr_i: u32, location: &str, condition: Option<Condition>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push(Breakpoint {
+            id,
+            instr_i,
+            location: location.to_string(),
+            condition,
+            enabled: true,
+        });
+        id
+    }
+
+    /// returns false if there's no breakpoint with the id
+    pub fn delete(&mut self, id: u32) -> bool {
+        let prev_len = self.breakpoints.len();
+        self.breakpoints.retain(|bp| bp.id != id);
+        self.breakpoints.len() != prev_len
+    }
+
+    /// returns false if there's no breakpoint with the id
+    pub fn set_enabled(&mut self, id: u32, enabled: bool) -> bool {
+        match self.breakpoints.iter_mut().find(|bp| bp.id == id) {
+            Some(bp) => {
+                bp.enabled = enabled;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// returns the first enabled breakpoint at the instruction whose condition holds
+    pub fn hit(&self, instr_i: u32, regs: &Registers) -> Option<&Breakpoint> {
+        self.breakpoints.iter().find(|bp| {
+            bp.enabled && bp.instr_i == instr_i && bp.condition.as_ref().is_none_or(|cond| cond.holds(regs))
+        })
+    }
+
+    /// a line per breakpoint, e.g "2: foo.c:12 (instr 40) if R1 == 5 [disabled]"
+    pub fn describe(&self) -> Vec<String> {
+        self.breakpoints.iter().map(|bp| {
+            let mut desc = format!("{}: {} (instr {})", bp.id, bp.location, bp.instr_i);
+            if let Some(cond) = &bp.condition {
+                desc += &format!(" if {}", cond.to_str());
+            }
+            if !bp.enabled {
+                desc += " [disabled]";
+            }
+            desc
+        }).collect()
+    }
+}
+
+impl Default for Breakpoints {
+    fn default() -> Breakpoints {
+        Breakpoints::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_conditions() {
+        let mut regs = Registers::new();
+        regs.set(&Register::R1, 5);
+        assert!(Condition::parse(&["R1", "==", "5"]).unwrap().holds(&regs));
+        assert!(Condition::parse(&["R1", ">", "4"]).unwrap().holds(&regs));
+        assert!(!Condition::parse(&["R1", "!=", "5"]).unwrap().holds(&regs));
+        assert!(!Condition::parse(&["R2", ">=", "1"]).unwrap().holds(&regs));
+        assert_eq!(Condition::parse(&["R5", "==", "5"]), None);
+        assert_eq!(Condition::parse(&["R1", "=", "5"]), None);
+        assert_eq!(Condition::parse(&["R1", "=="]), None);
+    }
+    #[test]
+    fn test_breakpoints() {
+        let mut regs = Registers::new();
+        let mut breakpoints = Breakpoints::new();
+        assert_eq!(breakpoints.add(10, "3", None), 1);
+        assert_eq!(breakpoints.add(20, "a.c:5", Condition::parse(&["R1", "==", "2"])), 2);
+        assert_eq!(breakpoints.hit(10, &regs).unwrap().id, 1);
+        assert!(breakpoints.hit(20, &regs).is_none());
+        regs.set(&Register::R1, 2);
+        assert_eq!(breakpoints.hit(20, &regs).unwrap().id, 2);
+        assert!(breakpoints.set_enabled(1, false));
+        assert!(breakpoints.hit(10, &regs).is_none());
+        assert_eq!(breakpoints.describe(), vec!["1: 3 (instr 10) [disabled]", "2: a.c:5 (instr 20) if R1 == 2"]);
+        assert!(breakpoints.delete(2));
+        assert!(!breakpoints.delete(2));
+        assert!(!breakpoints.set_enabled(2, true));
+        assert_eq!(breakpoints.add(30, "4", None), 3);
+    }
+}
@@ -1,19 +1,7 @@
-extern crate serde_json;
-
 extern crate linked_hash_map;
 
 use linked_hash_map::LinkedHashMap;
-
-use std::collections::HashMap;
-
-use self::serde_json::Value as JsonNode;
-
-
-use std::process::Command;
-const PATH_TO_PY_EXEC: &str = "src/operating_system/compiler/parser/venv/bin/python";
-const PATH_TO_PARSER: &str = "src/operating_system/compiler/parser/to_ast_json.py";
-
-type AstError = ();
+use serde::Serialize;
 
 pub enum AstNode<'a> {
     RootAstNode(&'a RootAstNode),
@@ -26,133 +14,56 @@ pub enum AstNode<'a> {
     Constant(&'a Constant),
 }
 
+#[derive(Serialize)]
 pub struct RootAstNode {
     pub externals: Vec<External>,
 }
 
-impl RootAstNode {
-    fn from(node: &JsonNode) -> Result<RootAstNode, AstError> {
-        let mut externals = Vec::new();
-        for ext_node in node["ext"].as_array().unwrap().iter() {
-            externals.push(External::from(ext_node)?);
-        }
-        Ok(RootAstNode {
-            externals: externals,
-        })
-    }
-}
 
+#[derive(Serialize)]
 pub enum External {
     FuncDef(FuncDef),
     FuncDecl(FuncDecl),
     StructDecl(StructDecl),
     VarDecl(Decl),
+    ExternVarDecl(Decl), // a global variable that is defined in another file
+    Typedef(Typedef),
 }
 
-impl External {
-    fn from(node: &JsonNode) -> Result<External, AstError> {
-        match node["_nodetype"].as_str().unwrap() {
-            "FuncDef" => Ok(External::FuncDef(FuncDef::from(&node)?)),
-            "Decl" => match node["type"]["_nodetype"].as_str().unwrap(){
-                "FuncDecl" => Ok(External::FuncDecl(FuncDecl::from(&node)?)),
-                "Struct" => Ok(External::StructDecl(StructDecl::from(&node)?)),
-                "TypeDecl" | "PtrDecl"=> Ok(External::VarDecl(Decl::from(&node)?)),
-                _ => panic!(),
-                }
-            _ => {
-                panic!("Invalid external");
-            }
-        }
-    }
-}
 
+
+#[derive(Serialize)]
 pub struct FuncDef {
     pub body: Compound,
     pub decl: FuncDecl,
-}
-impl FuncDef {
-    fn from(node: &JsonNode) -> Result<FuncDef, AstError> {
-        Ok(FuncDef {
-            body: Compound::from(&node["body"])?,
-            decl: FuncDecl::from(&node["decl"])?,
-        })
-    }
+    pub loc: Option<SourceLoc>,
 }
 
+#[derive(Serialize)]
 pub struct FuncDecl {
     pub name: String,
     pub args: Vec<Decl>,
     pub ret_type: Type,
+    pub variadic: bool, // the args end with ..., e.g int printf(char* fmt, ...)
 }
-impl FuncDecl {
-    fn from(node: &JsonNode) -> Result<FuncDecl, AstError> {
-        let mut args = Vec::new();
-        match node["type"]["args"]{
-            JsonNode::Object(_) => {
-                for arg in node["type"]["args"]["params"].as_array().unwrap().iter(){
-                    args.push(
-                        Decl::from(arg).unwrap()
-                    );
-                }
-            },
-            _ => {},
-        }
-        Ok(FuncDecl {
-            name: node["name"].as_str().unwrap().to_string(),
-            args: args,
-            ret_type: Type::from(&node["type"]["type"]),
-        })
-    }
+
+/// location of a node in the C source, used for source-level debugging
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SourceLoc {
+    pub file: String,
+    pub line: u32,
 }
 
-#[derive(Clone, Debug)]
+
+#[derive(Clone, Debug, Serialize)]
 pub struct Compound {
     pub items: Vec<Statement>,
+    pub item_locs: Vec<Option<SourceLoc>>, // source location of each item
     pub code_loc: String, // needed for scope id
 }
 
-impl Compound {
-    fn from(node: &JsonNode) -> Result<Compound, AstError> {
-        let mut statements = Vec::new();
-        let node_type = node["_nodetype"].as_str().unwrap();
-        if node_type == "ExprList" {
-            for expr_node in node["exprs"].as_array().unwrap().iter() {
-                statements.push(Statement::Expression(Expression::from(expr_node)?));
-            }
-
-        }
-        else if node_type != "EmptyStatement"{
-            if node_type == "DeclList"{
-                // we treat DeclList as a compound, because a declaration is also a statement
-                for decl_node in node["decls"].as_array().unwrap().iter() {
-                    statements.push(Statement::from(&decl_node)?);
-                }
-            }
-            else{
-                match node["block_items"] {
-                    JsonNode::Null => {
-                        // to avoid infinite recursion
-                        if node_type != "Compound"{
-                            statements.push(Statement::from(&node)?);
-                        }
-                    }
-                    _ => {
-                        for statement_node in node["block_items"].as_array().unwrap().iter() {
-                            statements.push(Statement::from(&statement_node)?);
-                        }
-                    }
-                }
-            }
-        }
-        Ok(Compound {
-             items: statements,
-             code_loc: node["coord"].as_str().unwrap().to_string().replace(":", "-"),
-        })
-
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub enum Statement {
     Return(Return),
     Decl(Decl),
@@ -163,134 +74,62 @@ pub enum Statement {
     WhileLoop(WhileLoop),
     DoWhileLoop(DoWhileLoop),
     ForLoop(ForLoop),
+    Switch(Switch),
+    Label(Label),
+    Goto(String), // the name of the target label
+    ComputedGoto(Expression), // goto *expr, jumps to the label address that the expression evaluates to
     Break,
     Continue,
 }
 
 impl Statement {
-    fn from(node: &JsonNode) -> Result<Statement, AstError> {
-        match node["_nodetype"].as_str().unwrap() {
-            "Return" => Ok(Statement::Return(Return::from(&node)?)),
-            "Decl" => Ok(Statement::Decl(Decl::from(&node)?)),
-            "Assignment" => Ok(Statement::Assignment(Assignment::from(&node)?)),
-            "If" => Ok(Statement::If(If::from(&node)?)),
-            "Compound" | "EmptyStatement"=> Ok(Statement::Compound(Compound::from(&node)?)),
-            "While" => Ok(Statement::WhileLoop(WhileLoop::from(&node)?)),
-            "DoWhile" => Ok(Statement::DoWhileLoop(DoWhileLoop::from(&node)?)),
-            "For" => Ok(Statement::ForLoop(ForLoop::from(&node)?)),
-            "Break" => Ok(Statement::Break),
-            "Continue" => Ok(Statement::Continue),
-            _ => {
-                Ok(Statement::Expression(Expression::from(&node)?))
-            }
+
+    /// returns the statement without the labels before it
+    pub fn unlabeled(&self) -> &Statement {
+        match self {
+            Statement::Label(label) => label.stmt.unlabeled(),
+            statement => statement,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Return {
     pub expr: Option<Expression>,
 }
 
-impl Return {
-    fn from(node: &JsonNode) -> Result<Return, AstError> {
-        return Ok(Return {
-            expr: match &node["expr"] {
-                JsonNode::Null => None,
-                _ => Some(Expression::from(&node["expr"])?),
-            }
-        }
-        );
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum Type{
     Int,
     Char,
+    Float,
+    Long, // 64 bits, held in two words
     Void,
     _String,
     Ptr(Box<Type>),
     Struct(String),
+    FuncPtr{ret_type: Box<Type>, args: Vec<Type>},
+    Alias(String), // a typedef name, resolved by the compiler
 }
 
-impl Type{
-    fn from(node: &JsonNode) -> Type{
-        match node["_nodetype"].as_str().unwrap(){
-            "TypeDecl" => {
-                match node["type"]["_nodetype"].as_str().unwrap(){
-                    "IdentifierType" => {
-                        match node["type"]["names"].as_array().unwrap()[0].as_str().unwrap(){
-                            "int" => Type::Int,
-                            "char" => Type::Char,
-                            "void" => Type::Void,
-                            _ => panic!("unsupported type"),
-                        }
-                    },
-                    "Struct" => {
-                        Type::Struct(node["type"]["name"].as_str().unwrap().to_string())
-                    },
-                    _ => panic!()
-                }
-            },
-            "PtrDecl" => {
-                let boxed_type = Type::from(&node["type"]);
-                Type::Ptr(Box::new(boxed_type))
-            },
-            _ => panic!(),
-        }
-    }
-
-    fn from_name(name: &str) -> Type{
-        match name{
-            "int" => Type::Int,
-            "char" => Type::Char,
-            "string" => Type::_String,
-            _ => panic!("invalid name for type"),
-        }
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub enum Decl{
     VarDecl(VarDecl),
     ArrayDecl(ArrayDecl),
 }
 
-impl Decl {
-    fn from(node: &JsonNode) -> Result<Decl, AstError> {
-        match node["type"]["_nodetype"].as_str().unwrap(){
-            "ArrayDecl" => Ok(Decl::ArrayDecl(ArrayDecl::from(node)?)),
-            _ => Ok(Decl::VarDecl(VarDecl::from(node)?)),
-        }
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct VarDecl {
     pub name: String,
     pub _type: Type,
     pub init: Option<Expression>,
 }
 
-impl VarDecl {
-    fn from(node: &JsonNode) -> Result<VarDecl, AstError> {
-        let name = node["name"].as_str().unwrap().to_string();
-        let mut _type = get_decl_var_type(node);
-        let init = match node["init"] {
-            JsonNode::Object(_) => Some(Expression::from(&node["init"])?),
-            JsonNode::Null => None,
-            _ => panic!("Invalid decl init type"),
-        };
-        Ok(VarDecl {
-            name: name,
-            _type: _type,
-            init: init,
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ArrayDecl{
   pub name: String,
   pub _type: Type,
@@ -298,81 +137,36 @@ pub struct ArrayDecl{
   pub init: Option<Vec<Expression>>,
 }
 
-fn get_array_dimentions_and_type(node: &JsonNode) -> (Vec<u32>, Type){
-    let mut dimentions = Vec::new();
-    let mut cur_node = &node["type"];
-    while cur_node["type"]["_nodetype"] == "ArrayDecl"{
-        dimentions.push(cur_node["dim"]["value"].as_str().unwrap().to_string().parse::<u32>().unwrap());
-        cur_node = &cur_node["type"];
-    }
-    dimentions.push(cur_node["dim"]["value"].as_str().unwrap().to_string().parse::<u32>().unwrap());
-    (dimentions, get_decl_var_type(cur_node))
-}
 
-impl ArrayDecl {
-    fn from(node: &JsonNode) -> Result<ArrayDecl, AstError> {
-        let mut init_exprs = Vec::new();
-        let mut has_init = false;
-        if let Some(exprs) = &node["init"]["exprs"].as_array(){
-            has_init = true;
-            for exp in exprs.iter(){
-                init_exprs.push(Expression::from(exp)?);
-            }
-        }
-        let (dimentions, _type) = get_array_dimentions_and_type(node);
-        Ok(ArrayDecl{
-            name: node["name"].as_str().unwrap().to_string(),
-            _type: _type,
-            dimentions: dimentions,
-            init: if has_init {Some(init_exprs)} else {None},
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct StructDecl{
     pub name: String,
     pub items: LinkedHashMap<String, Decl>,
+    pub nested: Vec<StructDecl>, // structs that are defined in the declaration of an item, e.g struct A{struct B{int x;} b;};
 }
 
-impl StructDecl {
-    fn from(node: &JsonNode) -> Result<StructDecl, AstError> {
-        let mut items = LinkedHashMap::new();
-        for decl in node["type"]["decls"].as_array().unwrap().iter(){
-            items.insert(decl["name"].as_str().unwrap().to_string(), Decl::from(decl)?);
-        }
-        Ok(StructDecl{
-            name: node["type"]["name"].as_str().unwrap().to_string(),
-            items
-        })
-    }
-}
 
-fn get_decl_var_type(node: &JsonNode) -> Type{
-    Type::from(&node["type"])
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Typedef{
+    pub name: String,
+    pub _type: Type,
+    pub struct_decl: Option<StructDecl>, // e.g typedef struct {...} Foo;
 }
 
-#[derive(Clone, Debug)]
+
+
+#[derive(Clone, Debug, Serialize)]
 pub enum NameRef {
     ID(ID),
     ArrayRef(ArrayRef),
     StructRef(StructRef),
 }
 
-impl NameRef {
-    fn from(node: &JsonNode) -> Result<NameRef, AstError> {
-        println!("nameref from: {}", node);
-        match node["_nodetype"].as_str().unwrap() {
-            "ID" => Ok(NameRef::ID(ID::from(&node)?)),
-            "ArrayRef" => Ok(NameRef::ArrayRef(ArrayRef::from(&node)?)),
-            "StructRef" => Ok(NameRef::StructRef(StructRef::from(&node)?)),
-            _ => Err(()),
-        }
-    }
-}
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub enum Expression {
     Constant(Constant),
     BinaryOp(BinaryOp),
@@ -383,67 +177,27 @@ pub enum Expression {
     NameRef(NameRef),
     TypeName(TypeName), // used in sizeof()
     Cast(Cast),
+    Comma(Vec<Expression>), // e.g (a++, b), evaluated left to right, the value is the last expression's
+    LabelAddr(String), // &&label, the address of a label in the function, a void* for computed gotos
 }
 
-impl Expression {
-    fn from(node: &JsonNode) -> Result<Expression, AstError> {
-        match node["_nodetype"].as_str().unwrap() {
-            "Constant" => Ok(Expression::Constant(Constant::from(&node)?)),
-            "BinaryOp" => Ok(Expression::BinaryOp(BinaryOp::from(&node)?)),
-            "UnaryOp" => Ok(Expression::UnaryOp(UnaryOp::from(&node)?)),
-            "Assignment" => Ok(Expression::Assignment(Assignment::from(&node)?)),
-            "TernaryOp" => Ok(Expression::TernaryOp(TernaryOp::from(&node)?)),
-            "FuncCall" => Ok(Expression::FuncCall(FuncCall::from(&node)?)),
-            "ID" | "ArrayRef" | "StructRef" => Ok(Expression::NameRef(NameRef::from(&node)?)),
-            "Typename" => Ok(Expression::TypeName(TypeName::from(&node)?)),
-            "Cast" => Ok(Expression::Cast(Cast::from(&node)?)),
-            _ => {
-                panic!(format!(
-                    "Invalid expression type:{}",
-                    node["_nodetype"].as_str().unwrap())
-                );
-                Err(())
-            }
-        }
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Constant {
     pub _type: Type,
     pub val: String,
 }
 
-impl Constant {
-    fn from(node: &JsonNode) -> Result<Constant, AstError> {
-        Ok(Constant {
-            _type: Type::from_name(node["type"].as_str().unwrap()),
-            val: node["value"].as_str().unwrap().to_string(),
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct BinaryOp {
     pub op_type: BinaryopType,
     pub left: Box<Expression>,
     pub right: Box<Expression>,
 }
 
-impl BinaryOp {
-    fn from(node: &JsonNode) -> Result<BinaryOp, AstError> {
-        let left = Box::new(Expression::from(&node["left"])?);
-        let right = Box::new(Expression::from(&node["right"])?);
-        let op_type = BinaryopType::from(&node["op"])?;
-        Ok(BinaryOp {
-            op_type: op_type,
-            left: left,
-            right: right,
-        })
-    }
-}
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub enum BinaryopType {
     // arithmetical
     ADD,
@@ -468,36 +222,30 @@ pub enum BinaryopType {
 }
 
 impl BinaryopType {
-    fn _from(s: &str) -> Result<BinaryopType, AstError> {
-        println!("BinaryopType from:{}", s);
+    /// the operator of a binary expression, e.g "+", or of a compound assignment, e.g the "+" of "+="
+    pub fn from_op(s: &str) -> Option<BinaryopType> {
         match s {
-            "+" => Ok(BinaryopType::ADD),
-            "-" => Ok(BinaryopType::SUB),
-            "*" => Ok(BinaryopType::MUL),
-            "/" => Ok(BinaryopType::DIV),
-            "%" => Ok(BinaryopType::MOD),
-            "&" => Ok(BinaryopType::AND),
-            "|" => Ok(BinaryopType::OR),
-            "<<" => Ok(BinaryopType::SHL),
-            ">>" => Ok(BinaryopType::SHR),
-            "^" => Ok(BinaryopType::XOR),
-            "==" => Ok(BinaryopType::EQ),
-            "!=" => Ok(BinaryopType::NEQ),
-            "&&" => Ok(BinaryopType::LogicalAnd),
-            "||" => Ok(BinaryopType::LogicalOr),
-            "<" => Ok(BinaryopType::LT),
-            "<=" => Ok(BinaryopType::LTEQ),
-            ">" => Ok(BinaryopType::GT),
-            ">=" => Ok(BinaryopType::GTEQ),
-            _ => {
-                println!("BinaryopType from returning Err");
-                Err(())
-            }
+            "+" => Some(BinaryopType::ADD),
+            "-" => Some(BinaryopType::SUB),
+            "*" => Some(BinaryopType::MUL),
+            "/" => Some(BinaryopType::DIV),
+            "%" => Some(BinaryopType::MOD),
+            "&" => Some(BinaryopType::AND),
+            "|" => Some(BinaryopType::OR),
+            "<<" => Some(BinaryopType::SHL),
+            ">>" => Some(BinaryopType::SHR),
+            "^" => Some(BinaryopType::XOR),
+            "==" => Some(BinaryopType::EQ),
+            "!=" => Some(BinaryopType::NEQ),
+            "&&" => Some(BinaryopType::LogicalAnd),
+            "||" => Some(BinaryopType::LogicalOr),
+            "<" => Some(BinaryopType::LT),
+            "<=" => Some(BinaryopType::LTEQ),
+            ">" => Some(BinaryopType::GT),
+            ">=" => Some(BinaryopType::GTEQ),
+            _ => None,
         }
     }
-    fn from(node: &JsonNode) -> Result<BinaryopType, AstError> {
-        BinaryopType::_from(&node.as_str().unwrap())
-    }
     pub fn to_op(&self) -> Option<String> {
         match &self {
             BinaryopType::ADD => Some("ADD".to_string()),
@@ -515,30 +263,19 @@ impl BinaryopType {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct UnaryOp {
     pub op_type: UnaryopType,
     pub expr: Box<Expression>,
     pub id: Option<ID>, // will be Some for unary ops that operate on a variable, e.g x++
 }
 
-impl UnaryOp {
-    fn from(node: &JsonNode) -> Result<UnaryOp, AstError> {
-        let expr = Box::new(Expression::from(&node["expr"])?);
-        let op_type = UnaryopType::from(&node["op"])?;
-        let id = if node["expr"]["_nodetype"].as_str().unwrap() == "ID" {Some(ID::from(&node["expr"]).unwrap())} else {None};
-        Ok(UnaryOp {
-            op_type,
-            expr,
-            id
-        })
-    }
-}
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub enum UnaryopType {
     NEG,
     NOT,
+    BNOT, // ~
     XPP, // x++
     PPX, // ++x
     XMM, // x--
@@ -548,86 +285,28 @@ pub enum UnaryopType {
     SIZEOF,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ID {
     pub name: String,
 }
 
-impl ID {
-    fn from(node: &JsonNode) -> Result<ID, AstError> {
-        Ok(ID {
-            name: node["name"].as_str().unwrap().to_string(),
-        })
-    }
-}
 
-impl UnaryopType {
-    fn from(node: &JsonNode) -> Result<UnaryopType, AstError> {
-        println!("UnaryopType from:{}", node.as_str().unwrap());
-        match node.as_str().unwrap() {
-            "!" => Ok(UnaryopType::NOT),
-            "-" => Ok(UnaryopType::NEG),
-            "p++" => Ok(UnaryopType::XPP),
-            "++" => Ok(UnaryopType::PPX),
-            "p--" => Ok(UnaryopType::XMM),
-            "--" => Ok(UnaryopType::MMX),
-            "&" => Ok(UnaryopType::REF),
-            "*" => Ok(UnaryopType::DEREF),
-            "sizeof" => Ok(UnaryopType::SIZEOF),
-            _ => {
-                panic!("Unkown Unary type:{}", node.as_str().unwrap());
-            }
-        }
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Assignment {
     pub op: AssignmentOp,
     pub lvalue: Box<Expression>,
     pub rvalue: Box<Expression>,
 }
 
-impl Assignment {
-    fn from(node: &JsonNode) -> Result<Assignment, AstError> {
-        let op = AssignmentOp::from(&node)?;
-        let lvalue = Box::new(Expression::from(&node["lvalue"])?);
-        let rvalue = Box::new(Expression::from(&node["rvalue"])?);
-        Ok(Assignment {
-            op: op,
-            lvalue: lvalue,
-            rvalue: rvalue,
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct AssignmentOp {
     pub op: Option<BinaryopType>, // e.g for += assignment, this will be PLUS
 }
 
-impl AssignmentOp {
-    fn from(node: &JsonNode) -> Result<AssignmentOp, AstError> {
-        let op_str = node["op"].as_str().unwrap().to_string();
-        match op_str.len() {
-            1 => Ok(AssignmentOp { op: None }),
-            2 | 3 => {
-                // let op_char = op_str.chars().next().unwrap();
-                let eq_index = op_str
-                    .as_str()
-                    .find('=')
-                    .expect("no = sign in assignment with op");
-                let op_part: String = op_str.chars().take(eq_index).collect();
-                Ok(AssignmentOp {
-                    op: Some(BinaryopType::_from(&op_part.as_str())?),
-                })
-            }
-            _ => panic!("invalid assignment op string:{}", op_str),
-        }
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct If {
     pub cond: Expression,
     pub iftrue: Box<Compound>,
@@ -635,70 +314,32 @@ pub struct If {
     pub code_loc: String, // needed for scope id
 }
 
-impl If {
-    fn from(node: &JsonNode) -> Result<If, AstError> {
-        let iffalse = maybe_get_boxed_compound(node, "iffalse");
-        Ok(If {
-            cond: Expression::from(&node["cond"])?,
-            iftrue: Box::new(Compound::from(&node["iftrue"])?),
-            iffalse: iffalse,
-            code_loc: node["coord"].as_str().unwrap().to_string().replace(":","-"),
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct TernaryOp {
     pub cond: Box<Expression>,
     pub iftrue: Box<Expression>,
     pub iffalse: Box<Expression>,
 }
 
-impl TernaryOp {
-    fn from(node: &JsonNode) -> Result<TernaryOp, AstError> {
-        Ok(TernaryOp {
-            cond: Box::new(Expression::from(&node["cond"])?),
-            iftrue: Box::new(Expression::from(&node["iftrue"])?),
-            iffalse: Box::new(Expression::from(&node["iffalse"])?),
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct WhileLoop {
     pub cond: Expression,
     pub body: Box<Compound>,
     pub code_loc: String, // needed for scope id
 }
 
-impl WhileLoop {
-    fn from(node: &JsonNode) -> Result<WhileLoop, AstError> {
-        Ok(WhileLoop{
-            cond: Expression::from(&node["cond"])?,
-            body: Box::new(Compound::from(&node["stmt"])?),
-            code_loc: node["coord"].as_str().unwrap().to_string().replace(":","-"),
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct DoWhileLoop {
     pub cond: Expression,
     pub body: Box<Compound>,
     pub code_loc: String, // needed for scope id
 }
 
-impl DoWhileLoop {
-    fn from(node: &JsonNode) -> Result<DoWhileLoop, AstError> {
-        Ok(DoWhileLoop{
-            cond: Expression::from(&node["cond"])?,
-            body: Box::new(Compound::from(&node["stmt"])?),
-            code_loc: node["coord"].as_str().unwrap().to_string().replace(":","-"),
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ForLoop{
     pub cond: Option<Expression>,
     pub init: Option<Box<Compound>>,
@@ -707,154 +348,77 @@ pub struct ForLoop{
     pub code_loc: String, // needed for scope id
 }
 
-fn maybe_get_boxed_compound(node: &JsonNode, key: &str) -> Option<Box<Compound>>{
-    match &node[key] {
-        JsonNode::Object(_) => Some(Box::new(Compound::from(&node[key]).unwrap())),
-        JsonNode::Null => None,
-        _ => panic!("invalid type for optional compound"),
-    }
 
+
+/// a labeled statement, e.g "retry: x++;"
+#[derive(Clone, Debug, Serialize)]
+pub struct Label {
+    pub name: String,
+    pub stmt: Box<Statement>,
 }
 
-impl ForLoop {
-    fn from(node: &JsonNode) -> Result<ForLoop, AstError> {
-        println!("creating for loop");
-        Ok(ForLoop{
-            cond: 
-                match &node["cond"]{
-                    JsonNode::Object(_) => Some(Expression::from(&node["cond"]).unwrap()),
-                    JsonNode::Null => None,
-                    _ => panic!("unexpected JSON type for cond")
-                },
-            init: maybe_get_boxed_compound(node, "init"),
-            body: Box::new(Compound::from(&node["stmt"])?),
-            next: maybe_get_boxed_compound(node, "next"),
-            code_loc: node["coord"].as_str().unwrap().to_string().replace(":","-"),
-        })
-    }
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Switch {
+    pub cond: Expression,
+    pub body: Box<Compound>, // all statements of the switch, cases mark positions in it
+    pub cases: Vec<Case>,
+    pub code_loc: String, // needed for scope id
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Case {
+    pub value: Option<Expression>, // None for default
+    pub first_item: usize, // index of the first statement of the case in the switch's body
 }
 
-#[derive(Clone, Debug)]
+
+#[derive(Clone, Debug, Serialize)]
 pub struct FuncCall{
     pub name: String,
     pub args: Vec<Box<Expression>>,
 }
 
-impl FuncCall {
-    fn from(node: &JsonNode) -> Result<FuncCall, AstError> {
-        let mut args = Vec::new();
-        match &node["args"]{
-            JsonNode::Object(_) => {
-                for expr in node["args"]["exprs"].as_array().unwrap().iter(){
-                    args.push(Box::new(Expression::from(expr)?));
-                }
-            },
-            JsonNode::Null => {},
-            _ => panic!(),
-
-        }
-        Ok(FuncCall{
-            name: node["name"]["name"].as_str().unwrap().to_string(),
-            args: args,
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct ArrayRef{
     pub name: Box<NameRef>,
     pub indices: Vec<Box<Expression>>,
 }
 
-impl ArrayRef {
-    fn from(node: &JsonNode) -> Result<ArrayRef, AstError> {
-        let mut indices = Vec::new();
-        let mut cur_node = node;
-        while cur_node["_nodetype"].as_str().unwrap() == "ArrayRef"{
-            indices.push(Box::new(Expression::from(&cur_node["subscript"])?));
-            cur_node = &cur_node["name"];
-        }
-        let name = Box::new(NameRef::from(&cur_node)?);
-        indices.reverse();
-        Ok(ArrayRef{
-            name,
-            indices,
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub enum StructRefType{
     DOT,
     ARROW,
 }
 
-impl StructRefType {
-    fn from(node: &JsonNode) -> Result<StructRefType, AstError> {
-        match node.as_str().unwrap() {
-            "." => Ok(StructRefType::DOT),
-            "->" => Ok(StructRefType::ARROW),
-            _ => panic!(),
-        }
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct StructRef {
     pub name: Box<NameRef>,
     pub field: String,
     pub _type: StructRefType,
 }
 
-impl StructRef {
-    fn from(node: &JsonNode) -> Result<StructRef, AstError> {
-        Ok(StructRef{
-            name: Box::new(NameRef::from(&node["name"])?),
-            field: node["field"]["name"].as_str().unwrap().to_string(),
-            _type: StructRefType::from(&node["type"])?,
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct TypeName {
     pub _type: Type,
 }
-impl TypeName {
-    fn from(node: &JsonNode) -> Result<TypeName, AstError> {
-        Ok(TypeName {
-            _type: Type::from(&node["type"])
-        })
-    }
-}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Cast {
     pub expr: Box<Expression>,
     pub _type: Type,
 }
-impl Cast {
-    fn from(node: &JsonNode) -> Result<Cast, AstError> {
-        println!("CAST!");
-        Ok( Cast {
-            expr: Box::new(Expression::from(&node["expr"])?),
-            _type: Type::from(&node["to_type"]["type"]),
-        })
-    }
-}
 
+/// preprocesses & parses a C source file, the compiler parses the preprocessed program it already has instead
+#[cfg(test)]
 pub fn get_ast(path_to_c_source: &str) -> RootAstNode {
     assert!(path_to_c_source.ends_with(".c"));
-    let output = Command::new(PATH_TO_PY_EXEC)
-        .arg(PATH_TO_PARSER)
-        .arg(path_to_c_source)
-        .output()
-        .expect("Failed to execute c parser");
-
-    let json_str = String::from_utf8(output.stdout).expect("Error decoding ast json bytes");
-
-    let ast_json = serde_json::from_str(&json_str).expect("parser output is not JSON serializable");
-    return RootAstNode::from(&ast_json).unwrap();
+    let program = super::preprocessor::preprocess(path_to_c_source, &[], &[]).expect("failed to preprocess");
+    super::parser::parse(&program).expect("failed to parse")
 }
 
 #[cfg(test)]
@@ -1174,6 +738,30 @@ mod tests {
         }
     }
     #[test]
+    fn comma(){
+        let ast_root = get_ast("tests/compiler_test_data/arith_expressions/inputs/comma.c");
+        match &ast_root.externals[0] {
+            External::FuncDef(func_def) => {
+                // each declarator is a separate declaration
+                match &func_def.body.items[2] {
+                    Statement::Decl(Decl::VarDecl(var_decl)) => assert_eq!(var_decl.name, "c"),
+                    _ => panic!(),
+                }
+                match &func_def.body.items[3] {
+                    Statement::Decl(Decl::VarDecl(var_decl)) => {
+                        assert_eq!(var_decl.name, "x");
+                        match var_decl.init.as_ref().unwrap() {
+                            Expression::Comma(exprs) => assert_eq!(exprs.len(), 3),
+                            _ => panic!(),
+                        }
+                    },
+                    _ => panic!(),
+                }
+            },
+            _ => panic!(),
+        }
+    }
+    #[test]
     fn func_decl_args(){
         let ast_root = get_ast("tests/compiler_test_data/functions/inputs/multi_arg.c");
         match &ast_root.externals[0] {
@@ -1443,6 +1031,23 @@ mod tests {
         }
     }
     #[test]
+    fn nested_struct_definition(){
+        let ast_root = get_ast("tests/compiler_test_data/complex_struct/inputs/nested_definition.c");
+        match &ast_root.externals[1] {
+            External::StructDecl(struct_decl) => {
+                assert_eq!(struct_decl.name, "Outer");
+                assert_eq!(struct_decl.nested.len(), 1);
+                assert_eq!(struct_decl.nested[0].name, "Inner");
+                assert_eq!(struct_decl.nested[0].items.len(), 2);
+                match struct_decl.items.get("in").unwrap() {
+                    Decl::VarDecl(var_decl) => assert_eq!(var_decl._type, Type::Struct("Inner".to_string())),
+                    _ => panic!(),
+                }
+            },
+            _ => panic!(),
+        }
+    }
+    #[test]
     fn recursive_structs(){
         let ast_root = get_ast("tests/compiler_test_data/structs/inputs/recursive.c");
         match &ast_root.externals[0] {
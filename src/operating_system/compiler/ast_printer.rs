@@ -0,0 +1,303 @@
+// prints the AST as an indented tree, one node per line, for diagnosing the frontend
+
+use std::fmt;
+
+use super::type_checker::{op_name, type_name};
+use super::AST::*;
+
+fn line(f: &mut fmt::Formatter, depth: usize, text: &str) -> fmt::Result {
+    writeln!(f, "{}{}", "  ".repeat(depth), text)
+}
+
+fn unary_op_name(op: &UnaryopType) -> &'static str {
+    match op {
+        UnaryopType::NEG => "-",
+        UnaryopType::NOT => "!",
+        UnaryopType::BNOT => "~",
+        UnaryopType::XPP => "x++",
+        UnaryopType::PPX => "++x",
+        UnaryopType::XMM => "x--",
+        UnaryopType::MMX => "--x",
+        UnaryopType::REF => "&",
+        UnaryopType::DEREF => "*",
+        UnaryopType::SIZEOF => "sizeof",
+    }
+}
+
+fn write_decl(f: &mut fmt::Formatter, depth: usize, decl: &Decl) -> fmt::Result {
+    match decl {
+        Decl::VarDecl(var) => {
+            line(f, depth, &format!("Decl {}: {}", var.name, type_name(&var._type)))?;
+            if let Some(init) = &var.init {
+                write_expr(f, depth + 1, init)?;
+            }
+        },
+        Decl::ArrayDecl(array) => {
+            let dimentions: String = array.dimentions.iter().map(|dim| format!("[{}]", dim)).collect();
+            line(f, depth, &format!("ArrayDecl {}: {}{}", array.name, type_name(&array._type), dimentions))?;
+            for expr in array.init.iter().flatten() {
+                write_expr(f, depth + 1, expr)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+fn write_func_decl(f: &mut fmt::Formatter, depth: usize, kind: &str, func_decl: &FuncDecl) -> fmt::Result {
+    line(f, depth, &format!("{} {}: {}", kind, func_decl.name, type_name(&func_decl.ret_type)))?;
+    for arg in func_decl.args.iter() {
+        write_decl(f, depth + 1, arg)?;
+    }
+    if func_decl.variadic {
+        line(f, depth + 1, "...")?;
+    }
+    Ok(())
+}
+
+fn write_struct_decl(f: &mut fmt::Formatter, depth: usize, struct_decl: &StructDecl) -> fmt::Result {
+    line(f, depth, &format!("StructDecl {}", struct_decl.name))?;
+    for nested in struct_decl.nested.iter() {
+        write_struct_decl(f, depth + 1, nested)?;
+    }
+    for item in struct_decl.items.values() {
+        write_decl(f, depth + 1, item)?;
+    }
+    Ok(())
+}
+
+fn write_compound(f: &mut fmt::Formatter, depth: usize, kind: &str, compound: &Compound) -> fmt::Result {
+    line(f, depth, &format!("{} {}", kind, compound.code_loc))?;
+    for item in compound.items.iter() {
+        write_statement(f, depth + 1, item)?;
+    }
+    Ok(())
+}
+
+fn write_statement(f: &mut fmt::Formatter, depth: usize, statement: &Statement) -> fmt::Result {
+    match statement {
+        Statement::Return(ret) => {
+            line(f, depth, "Return")?;
+            if let Some(expr) = &ret.expr {
+                write_expr(f, depth + 1, expr)?;
+            }
+        },
+        Statement::Decl(decl) => write_decl(f, depth, decl)?,
+        Statement::Assignment(assignment) => write_assignment(f, depth, assignment)?,
+        Statement::Expression(expr) => write_expr(f, depth, expr)?,
+        Statement::If(if_stmt) => {
+            line(f, depth, &format!("If {}", if_stmt.code_loc))?;
+            write_expr(f, depth + 1, &if_stmt.cond)?;
+            write_compound(f, depth + 1, "Then", &if_stmt.iftrue)?;
+            if let Some(iffalse) = &if_stmt.iffalse {
+                write_compound(f, depth + 1, "Else", iffalse)?;
+            }
+        },
+        Statement::Compound(compound) => write_compound(f, depth, "Compound", compound)?,
+        Statement::WhileLoop(wl) => {
+            line(f, depth, &format!("While {}", wl.code_loc))?;
+            write_expr(f, depth + 1, &wl.cond)?;
+            write_compound(f, depth + 1, "Body", &wl.body)?;
+        },
+        Statement::DoWhileLoop(dwl) => {
+            line(f, depth, &format!("DoWhile {}", dwl.code_loc))?;
+            write_compound(f, depth + 1, "Body", &dwl.body)?;
+            write_expr(f, depth + 1, &dwl.cond)?;
+        },
+        Statement::ForLoop(fl) => {
+            line(f, depth, &format!("For {}", fl.code_loc))?;
+            if let Some(init) = &fl.init {
+                write_compound(f, depth + 1, "Init", init)?;
+            }
+            if let Some(cond) = &fl.cond {
+                write_expr(f, depth + 1, cond)?;
+            }
+            if let Some(next) = &fl.next {
+                write_compound(f, depth + 1, "Next", next)?;
+            }
+            write_compound(f, depth + 1, "Body", &fl.body)?;
+        },
+        Statement::Switch(sw) => {
+            line(f, depth, &format!("Switch {}", sw.code_loc))?;
+            write_expr(f, depth + 1, &sw.cond)?;
+            // the cases are printed before the first statement they run
+            for (item_i, item) in sw.body.items.iter().enumerate() {
+                for case in sw.cases.iter().filter(|case| case.first_item == item_i) {
+                    write_case(f, depth + 1, case)?;
+                }
+                write_statement(f, depth + 2, item)?;
+            }
+            for case in sw.cases.iter().filter(|case| case.first_item == sw.body.items.len()) {
+                write_case(f, depth + 1, case)?;
+            }
+        },
+        Statement::Label(label) => {
+            line(f, depth, &format!("Label {}", label.name))?;
+            write_statement(f, depth + 1, &label.stmt)?;
+        },
+        Statement::Goto(name) => line(f, depth, &format!("Goto {}", name))?,
+        Statement::ComputedGoto(expr) => {
+            line(f, depth, "ComputedGoto")?;
+            write_expr(f, depth + 1, expr)?;
+        },
+        Statement::Break => line(f, depth, "Break")?,
+        Statement::Continue => line(f, depth, "Continue")?,
+    }
+    Ok(())
+}
+
+fn write_case(f: &mut fmt::Formatter, depth: usize, case: &Case) -> fmt::Result {
+    match &case.value {
+        Some(value) => {
+            line(f, depth, "Case")?;
+            write_expr(f, depth + 1, value)
+        },
+        None => line(f, depth, "Default"),
+    }
+}
+
+fn write_assignment(f: &mut fmt::Formatter, depth: usize, assignment: &Assignment) -> fmt::Result {
+    let op = assignment.op.op.as_ref().map_or("", op_name);
+    line(f, depth, &format!("Assignment {}=", op))?;
+    write_expr(f, depth + 1, &assignment.lvalue)?;
+    write_expr(f, depth + 1, &assignment.rvalue)
+}
+
+fn write_name_ref(f: &mut fmt::Formatter, depth: usize, name_ref: &NameRef) -> fmt::Result {
+    match name_ref {
+        NameRef::ID(id) => line(f, depth, &format!("ID {}", id.name)),
+        NameRef::ArrayRef(array_ref) => {
+            line(f, depth, "ArrayRef")?;
+            write_name_ref(f, depth + 1, &array_ref.name)?;
+            for index in array_ref.indices.iter() {
+                write_expr(f, depth + 1, index)?;
+            }
+            Ok(())
+        },
+        NameRef::StructRef(struct_ref) => {
+            let op = match struct_ref._type {
+                StructRefType::DOT => ".",
+                StructRefType::ARROW => "->",
+            };
+            line(f, depth, &format!("StructRef {}{}", op, struct_ref.field))?;
+            write_name_ref(f, depth + 1, &struct_ref.name)
+        },
+    }
+}
+
+fn write_expr(f: &mut fmt::Formatter, depth: usize, expr: &Expression) -> fmt::Result {
+    match expr {
+        Expression::Constant(constant) => line(f, depth, &format!("Constant {}: {}", constant.val, type_name(&constant._type))),
+        Expression::BinaryOp(bop) => {
+            line(f, depth, &format!("BinaryOp {}", op_name(&bop.op_type)))?;
+            write_expr(f, depth + 1, &bop.left)?;
+            write_expr(f, depth + 1, &bop.right)
+        },
+        Expression::UnaryOp(uop) => {
+            line(f, depth, &format!("UnaryOp {}", unary_op_name(&uop.op_type)))?;
+            write_expr(f, depth + 1, &uop.expr)
+        },
+        Expression::Assignment(assignment) => write_assignment(f, depth, assignment),
+        Expression::TernaryOp(top) => {
+            line(f, depth, "TernaryOp")?;
+            write_expr(f, depth + 1, &top.cond)?;
+            write_expr(f, depth + 1, &top.iftrue)?;
+            write_expr(f, depth + 1, &top.iffalse)
+        },
+        Expression::FuncCall(call) => {
+            line(f, depth, &format!("FuncCall {}", call.name))?;
+            for arg in call.args.iter() {
+                write_expr(f, depth + 1, arg)?;
+            }
+            Ok(())
+        },
+        Expression::NameRef(name_ref) => write_name_ref(f, depth, name_ref),
+        Expression::TypeName(type_name_expr) => line(f, depth, &format!("TypeName {}", type_name(&type_name_expr._type))),
+        Expression::Cast(cast) => {
+            line(f, depth, &format!("Cast {}", type_name(&cast._type)))?;
+            write_expr(f, depth + 1, &cast.expr)
+        },
+        Expression::Comma(exprs) => {
+            line(f, depth, "Comma")?;
+            for expr in exprs.iter() {
+                write_expr(f, depth + 1, expr)?;
+            }
+            Ok(())
+        },
+        Expression::LabelAddr(name) => line(f, depth, &format!("LabelAddr {}", name)),
+    }
+}
+
+impl fmt::Display for RootAstNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for external in self.externals.iter() {
+            match external {
+                External::FuncDef(func_def) => {
+                    write_func_decl(f, 0, "FuncDef", &func_def.decl)?;
+                    write_compound(f, 1, "Compound", &func_def.body)?;
+                },
+                External::FuncDecl(func_decl) => write_func_decl(f, 0, "FuncDecl", func_decl)?,
+                External::StructDecl(struct_decl) => write_struct_decl(f, 0, struct_decl)?,
+                External::VarDecl(decl) => write_decl(f, 0, decl)?,
+                External::ExternVarDecl(decl) => {
+                    line(f, 0, "Extern")?;
+                    write_decl(f, 1, decl)?;
+                },
+                External::Typedef(typedef) => {
+                    line(f, 0, &format!("Typedef {}: {}", typedef.name, type_name(&typedef._type)))?;
+                    if let Some(struct_decl) = &typedef.struct_decl {
+                        write_struct_decl(f, 1, struct_decl)?;
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_statement(f, 0, self)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_expr(f, 0, self)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::super::parser::parse;
+    #[test]
+    fn test_print(){
+        let root = parse("#line 1 \"main.c\"\nint x;\nint main(){\n  if (x > 1) x = -x;\n  return f(x, 2) * 3;\n}").unwrap();
+        let target = "\
+Decl x: int
+FuncDef main: int
+  Compound main.c-2-11
+    If main.c-3-3
+      BinaryOp >
+        ID x
+        Constant 1: int
+      Then main.c-3-14
+        Assignment =
+          ID x
+          UnaryOp -
+            ID x
+    Return
+      BinaryOp *
+        FuncCall f
+          ID x
+          Constant 2: int
+        Constant 3: int
+";
+        assert_eq!(root.to_string(), target);
+    }
+    #[test]
+    fn test_json(){
+        let root = parse("char* s = \"hi\";").unwrap();
+        let target = r#"{"externals":[{"VarDecl":{"VarDecl":{"name":"s","_type":{"Ptr":"Char"},"init":{"Constant":{"_type":"_String","val":"\"hi\""}}}}}]}"#;
+        assert_eq!(serde_json::to_string(&root).unwrap(), target);
+    }
+}
@@ -0,0 +1,98 @@
+// compile time evaluation of constant expressions
+
+use super::AST::*;
+use super::literals::char_constant_value;
+use super::Compiler;
+
+fn eval_binary_op(op_type: &BinaryopType, x: i32, y: i32) -> Option<i32> {
+    match op_type {
+        BinaryopType::ADD => x.checked_add(y),
+        BinaryopType::SUB => x.checked_sub(y),
+        BinaryopType::MUL => x.checked_mul(y),
+        // division by zero is left for runtime
+        BinaryopType::DIV => x.checked_div(y),
+        BinaryopType::MOD => x.checked_rem(y),
+        BinaryopType::AND => Some(x & y),
+        BinaryopType::OR => Some(x | y),
+        BinaryopType::SHL => x.checked_shl(y as u32),
+        BinaryopType::SHR => x.checked_shr(y as u32),
+        BinaryopType::XOR => Some(x ^ y),
+        BinaryopType::EQ => Some((x == y) as i32),
+        BinaryopType::NEQ => Some((x != y) as i32),
+        BinaryopType::LogicalAnd => Some((x != 0 && y != 0) as i32),
+        BinaryopType::LogicalOr => Some((x != 0 || y != 0) as i32),
+        BinaryopType::LT => Some((x < y) as i32),
+        BinaryopType::LTEQ => Some((x <= y) as i32),
+        BinaryopType::GT => Some((x > y) as i32),
+        BinaryopType::GTEQ => Some((x >= y) as i32),
+    }
+}
+
+impl Compiler {
+    /// returns the value of the expression if it can be computed at compile time
+    pub(super) fn eval_const(&self, expr: &Expression) -> Option<i32> {
+        match expr {
+            Expression::Constant(c) => match &c._type {
+                Type::Int => c.val.parse::<i32>().ok(),
+                Type::Char => Some(char_constant_value(&c.val) as i32),
+                _ => None,
+            },
+            Expression::BinaryOp(op) => {
+                let left = self.eval_const(&op.left)?;
+                let right = self.eval_const(&op.right)?;
+                eval_binary_op(&op.op_type, left, right)
+            },
+            Expression::UnaryOp(op) => match &op.op_type {
+                UnaryopType::NEG => self.eval_const(&op.expr)?.checked_neg(),
+                UnaryopType::NOT => Some((self.eval_const(&op.expr)? == 0) as i32),
+                UnaryopType::BNOT => Some(!self.eval_const(&op.expr)?),
+                UnaryopType::SIZEOF => match &*op.expr {
+                    Expression::TypeName(t) => Some(self.get_type_size(&t._type) as i32),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Expression::Cast(cast) => match self.resolve_type(&cast._type) {
+                Type::Int => self.eval_const(&cast.expr),
+                Type::Char => Some(self.eval_const(&cast.expr)? as i8 as i32),
+                _ => None,
+            },
+            Expression::TernaryOp(top) => {
+                if self.eval_const(&top.cond)? != 0 {
+                    self.eval_const(&top.iftrue)
+                } else {
+                    self.eval_const(&top.iffalse)
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// the initial words of a global whose initializer can be computed at compile time, so it can be emitted with .word.
+    /// only ints & 1 dimensional arrays of ints (& chars, when they take a word) are supported, other globals are zeroed
+    pub(super) fn global_init_words(&self, decl: &Decl) -> Option<Vec<i32>> {
+        let is_word_type = |_type: &Type| match self.resolve_type(_type) {
+            Type::Int => true,
+            Type::Char => self.options.word_size() == 1,
+            _ => false,
+        };
+        // values are converted to the variable's type, like in an assignment
+        let eval = |expr: &Expression, _type: &Type| match self.resolve_type(_type) {
+            Type::Char => Some(self.eval_const(expr)? as i8 as i32),
+            _ => self.eval_const(expr),
+        };
+        match decl {
+            Decl::VarDecl(var_decl) if is_word_type(&var_decl._type) => {
+                Some(vec![eval(var_decl.init.as_ref()?, &var_decl._type)?])
+            },
+            Decl::ArrayDecl(arr_decl) if is_word_type(&arr_decl._type) && arr_decl.dimentions.len() == 1 => {
+                let init = arr_decl.init.as_ref()?;
+                if init.len() > arr_decl.dimentions[0] as usize {
+                    return None;
+                }
+                init.iter().map(|expr| eval(expr, &arr_decl._type)).collect()
+            },
+            _ => None,
+        }
+    }
+}
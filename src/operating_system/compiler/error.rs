@@ -0,0 +1,125 @@
+use std::fmt;
+
+use super::AST::SourceLoc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    IncompatibleTypes { context: String, expected: String, found: String }, // context is e.g "assignment", "return"
+    InvalidOperands { op: String, left: String, right: String },
+    InvalidOperand { op: String, operand: String },
+    InvalidCast { from: String, to: String },
+    WrongArgsCount { func: String, expected: usize, found: usize },
+    TooFewArgs { func: String, expected: usize, found: usize }, // a variadic function expects at least the typed args
+    WrongIndexCount { name: String, expected: usize, found: usize },
+    NotSubscriptable { found: String },
+    NestedArray { _type: String }, // an array whose items are arrays, multidimensional arrays have a single item type
+    NoSuchMember { _type: String, field: String },
+    UndeclaredVariable { name: String },
+    UndeclaredFunction { name: String },
+    NotCallable { name: String },
+    UnknownStruct { name: String },
+    InvalidLvalue,
+    InvalidSizeof,
+    InvalidSyscall,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    UndefinedLabel { name: String },
+    DuplicateLabel { name: String },
+    IncludeNotFound { name: String },
+    CircularInclude { name: String },
+    InvalidMacro,
+    WrongMacroArgsCount { name: String, expected: usize, found: usize },
+    InvalidCondition { condition: String },
+    UnmatchedDirective { directive: String }, // e.g an #endif without an #if
+    UnterminatedConditional,
+    SyntaxError { message: String },
+    ReadError { message: String }, // the source couldn't be read
+}
+
+/// an error in the C source, found by the preprocessor, the parser, the type checker or while generating code
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError {
+    pub kind: ErrorKind,
+    pub loc: Option<SourceLoc>,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::IncompatibleTypes { context, expected, found } =>
+                write!(f, "incompatible types in {}: expected '{}' but found '{}'", context, expected, found),
+            ErrorKind::InvalidOperands { op, left, right } =>
+                write!(f, "invalid operands to '{}': '{}' and '{}'", op, left, right),
+            ErrorKind::InvalidOperand { op, operand } =>
+                write!(f, "invalid operand to '{}': '{}'", op, operand),
+            ErrorKind::InvalidCast { from, to } =>
+                write!(f, "cannot cast '{}' to '{}'", from, to),
+            ErrorKind::WrongArgsCount { func, expected, found } =>
+                write!(f, "function '{}' expects {} arguments but {} were given", func, expected, found),
+            ErrorKind::TooFewArgs { func, expected, found } =>
+                write!(f, "function '{}' expects at least {} arguments but {} were given", func, expected, found),
+            ErrorKind::WrongIndexCount { name, expected, found } =>
+                write!(f, "array '{}' has {} dimensions but is indexed with {}", name, expected, found),
+            ErrorKind::NotSubscriptable { found } =>
+                write!(f, "subscripted value of type '{}' is not an array or a pointer", found),
+            ErrorKind::NestedArray { _type } =>
+                write!(f, "arrays cannot hold arrays as items: '{}'", _type),
+            ErrorKind::NoSuchMember { _type, field } =>
+                write!(f, "'{}' has no member named '{}'", _type, field),
+            ErrorKind::UndeclaredVariable { name } =>
+                write!(f, "'{}' undeclared", name),
+            ErrorKind::UndeclaredFunction { name } =>
+                write!(f, "call to undeclared function '{}'", name),
+            ErrorKind::NotCallable { name } =>
+                write!(f, "called object '{}' is not a function or a function pointer", name),
+            ErrorKind::UnknownStruct { name } =>
+                write!(f, "unknown struct '{}'", name),
+            ErrorKind::InvalidLvalue =>
+                write!(f, "expression is not assignable"),
+            ErrorKind::InvalidSizeof =>
+                write!(f, "sizeof() needs a type name or an expression of a known type"),
+            ErrorKind::InvalidSyscall =>
+                write!(f, "__syscall expects a syscall number and an optional argument"),
+            ErrorKind::BreakOutsideLoop =>
+                write!(f, "break statement not within loop or switch"),
+            ErrorKind::ContinueOutsideLoop =>
+                write!(f, "continue statement not within a loop"),
+            ErrorKind::UndefinedLabel { name } =>
+                write!(f, "label '{}' used but not defined", name),
+            ErrorKind::DuplicateLabel { name } =>
+                write!(f, "redefinition of label '{}'", name),
+            ErrorKind::IncludeNotFound { name } =>
+                write!(f, "'{}' file not found", name),
+            ErrorKind::CircularInclude { name } =>
+                write!(f, "circular include of '{}'", name),
+            ErrorKind::InvalidMacro =>
+                write!(f, "#define expects a macro name, with an optional parameter list"),
+            ErrorKind::WrongMacroArgsCount { name, expected, found } =>
+                write!(f, "macro '{}' expects {} arguments but {} were given", name, expected, found),
+            ErrorKind::InvalidCondition { condition } =>
+                write!(f, "invalid #if condition '{}'", condition),
+            ErrorKind::UnmatchedDirective { directive } =>
+                write!(f, "#{} without #if", directive),
+            ErrorKind::UnterminatedConditional =>
+                write!(f, "unterminated conditional directive"),
+            ErrorKind::SyntaxError { message } =>
+                write!(f, "{}", message),
+            ErrorKind::ReadError { message } =>
+                write!(f, "couldn't read the source: {}", message),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.loc {
+            Some(loc) => write!(f, "{}:{}: error: {}", loc.file, loc.line, self.kind),
+            None => write!(f, "error: {}", self.kind),
+        }
+    }
+}
+
+/// one error per line, in the order they were found
+pub fn format_errors(errors: &[CompileError]) -> String {
+    errors.iter().map(|error| error.to_string()).collect::<Vec<String>>().join("\n")
+}
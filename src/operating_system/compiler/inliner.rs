@@ -0,0 +1,147 @@
+// expansion of calls to small functions at their call site
+
+use super::AST::*;
+
+/// a function whose body is a single side effect free return expression, e.g int square(int x){return x * x;}
+/// its params & return value are ints, a char would be converted when it's passed or returned, e.g truncated
+#[derive(Debug)]
+pub struct InlineFunc {
+    params: Vec<String>,
+    expr: Expression,
+}
+
+fn is_scalar(_type: &Type) -> bool {
+    matches!(_type, Type::Int | Type::Char)
+}
+
+/// true if the expression has no side effects & only refers to the given params
+fn is_pure(expr: &Expression, params: &[String]) -> bool {
+    match expr {
+        Expression::Constant(c) => is_scalar(&c._type),
+        Expression::NameRef(NameRef::ID(id)) => params.contains(&id.name),
+        Expression::BinaryOp(op) => is_pure(&op.left, params) && is_pure(&op.right, params),
+        Expression::UnaryOp(op) => match op.op_type {
+            UnaryopType::NEG | UnaryopType::NOT | UnaryopType::BNOT => is_pure(&op.expr, params),
+            _ => false,
+        },
+        Expression::TernaryOp(top) => is_pure(&top.cond, params) && is_pure(&top.iftrue, params) && is_pure(&top.iffalse, params),
+        Expression::Cast(cast) => is_scalar(&cast._type) && is_pure(&cast.expr, params),
+        _ => false,
+    }
+}
+
+/// true if evaluating the expression may change the program's state, e.g x++ or f()
+fn has_side_effects(expr: &Expression) -> bool {
+    match expr {
+        Expression::Assignment(_) | Expression::FuncCall(_) => true,
+        Expression::BinaryOp(op) => has_side_effects(&op.left) || has_side_effects(&op.right),
+        Expression::UnaryOp(op) => match op.op_type {
+            UnaryopType::XPP | UnaryopType::PPX | UnaryopType::XMM | UnaryopType::MMX => true,
+            _ => has_side_effects(&op.expr),
+        },
+        Expression::TernaryOp(top) => has_side_effects(&top.cond) || has_side_effects(&top.iftrue) || has_side_effects(&top.iffalse),
+        Expression::NameRef(NameRef::ArrayRef(array_ref)) => array_ref.indices.iter().any(|index| has_side_effects(index)),
+        Expression::NameRef(NameRef::StructRef(struct_ref)) => has_side_effects(&Expression::NameRef((*struct_ref.name).clone())),
+        Expression::Cast(cast) => has_side_effects(&cast.expr),
+        Expression::Comma(exprs) => exprs.iter().any(has_side_effects),
+        Expression::Constant(_) | Expression::NameRef(NameRef::ID(_)) | Expression::TypeName(_) | Expression::LabelAddr(_) => false,
+    }
+}
+
+/// true if some part of the expression may not be evaluated, e.g the right side of &&
+fn has_conditional(expr: &Expression) -> bool {
+    match expr {
+        Expression::BinaryOp(op) => match op.op_type {
+            BinaryopType::LogicalAnd | BinaryopType::LogicalOr => true,
+            _ => has_conditional(&op.left) || has_conditional(&op.right),
+        },
+        Expression::UnaryOp(op) => has_conditional(&op.expr),
+        Expression::TernaryOp(_) => true,
+        Expression::Cast(cast) => has_conditional(&cast.expr),
+        _ => false,
+    }
+}
+
+fn count_uses(expr: &Expression, param: &str) -> usize {
+    match expr {
+        Expression::NameRef(NameRef::ID(id)) => (id.name == param) as usize,
+        Expression::BinaryOp(op) => count_uses(&op.left, param) + count_uses(&op.right, param),
+        Expression::UnaryOp(op) => count_uses(&op.expr, param),
+        Expression::TernaryOp(top) => count_uses(&top.cond, param) + count_uses(&top.iftrue, param) + count_uses(&top.iffalse, param),
+        Expression::Cast(cast) => count_uses(&cast.expr, param),
+        _ => 0,
+    }
+}
+
+/// replaces references to params with the matching args
+fn substitute(expr: &Expression, params: &[String], args: &[Box<Expression>]) -> Expression {
+    let sub = |expr: &Expression| Box::new(substitute(expr, params, args));
+    match expr {
+        Expression::NameRef(NameRef::ID(id)) => {
+            let param_i = params.iter().position(|param| *param == id.name).unwrap();
+            (*args[param_i]).clone()
+        },
+        Expression::BinaryOp(op) => Expression::BinaryOp(BinaryOp {
+            op_type: op.op_type.clone(),
+            left: sub(&op.left),
+            right: sub(&op.right),
+        }),
+        Expression::UnaryOp(op) => Expression::UnaryOp(UnaryOp {
+            op_type: op.op_type.clone(),
+            expr: sub(&op.expr),
+            id: op.id.clone(),
+        }),
+        Expression::TernaryOp(top) => Expression::TernaryOp(TernaryOp {
+            cond: sub(&top.cond),
+            iftrue: sub(&top.iftrue),
+            iffalse: sub(&top.iffalse),
+        }),
+        Expression::Cast(cast) => Expression::Cast(Cast {
+            expr: sub(&cast.expr),
+            _type: cast._type.clone(),
+        }),
+        expr => expr.clone(),
+    }
+}
+
+impl InlineFunc {
+    /// returns None if the function can't be inlined
+    pub fn from(func_def: &FuncDef) -> Option<InlineFunc> {
+        if func_def.decl.ret_type != Type::Int {
+            return None;
+        }
+        let mut params = Vec::new();
+        for arg in func_def.decl.args.iter() {
+            match arg {
+                Decl::VarDecl(var_decl) if var_decl._type == Type::Int => params.push(var_decl.name.clone()),
+                _ => return None,
+            }
+        }
+        match func_def.body.items.as_slice() {
+            [Statement::Return(Return { expr: Some(expr) })] if is_pure(expr, &params) => Some(InlineFunc {
+                params,
+                expr: expr.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// returns the function's expression with the args in place of the params
+    /// an arg with side effects is substituted only if it's evaluated exactly once, otherwise None is returned
+    pub fn expand(&self, args: &[Box<Expression>]) -> Option<Expression> {
+        if args.len() != self.params.len() {
+            return None;
+        }
+        for (param, arg) in self.params.iter().zip(args.iter()) {
+            if has_side_effects(arg) && (count_uses(&self.expr, param) != 1 || has_conditional(&self.expr)) {
+                return None;
+            }
+        }
+        Some(substitute(&self.expr, &self.params, args))
+    }
+}
+
+/// the number of instructions in the code, without labels, directives & comments
+pub fn count_instructions(code: &[String]) -> usize {
+    code.iter().filter(|line| !line.starts_with('.') && !line.starts_with(';') && !line.ends_with(':')).count()
+}
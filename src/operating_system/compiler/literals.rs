@@ -0,0 +1,150 @@
+// parsing of char and string literals, and their escape sequences
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// returns the value of the escape sequence whose backslash was just consumed
+fn unescape_sequence(chars: &mut Peekable<Chars>) -> Option<char> {
+    let c = chars.next()?;
+    let val = match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        'a' => '\x07',
+        'b' => '\x08',
+        'f' => '\x0c',
+        'v' => '\x0b',
+        '\\' | '\'' | '"' | '?' => c,
+        'x' => {
+            let mut val: u32 = 0;
+            let mut digits = 0;
+            while let Some(digit) = chars.peek().and_then(|d| d.to_digit(16)) {
+                val = val * 16 + digit;
+                digits += 1;
+                chars.next();
+            }
+            if digits == 0 || val > 0xff {
+                return None;
+            }
+            val as u8 as char
+        },
+        '0'..='7' => {
+            // up to 3 octal digits, e.g \0
+            let mut val = c.to_digit(8).unwrap();
+            for _ in 0..2 {
+                match chars.peek().and_then(|d| d.to_digit(8)) {
+                    Some(digit) => {
+                        val = val * 8 + digit;
+                        chars.next();
+                    },
+                    None => break,
+                }
+            }
+            if val > 0xff {
+                return None;
+            }
+            val as u8 as char
+        },
+        _ => return None,
+    };
+    Some(val)
+}
+
+/// replaces the escape sequences in s with the chars they stand for
+/// returns None if s contains an invalid escape sequence
+pub fn unescape(s: &str) -> Option<String> {
+    let mut res = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            res.push(unescape_sequence(&mut chars)?);
+        } else {
+            res.push(c);
+        }
+    }
+    Some(res)
+}
+
+/// the inverse of unescape, the result doesn't contain newlines or other control chars
+pub fn escape(s: &str) -> String {
+    let mut res = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\t' => res.push_str("\\t"),
+            '\r' => res.push_str("\\r"),
+            // octal escapes have at most 3 digits, so unlike \x they can't swallow the following chars
+            c if (c as u32) < 0x20 || c as u32 == 0x7f => res.push_str(&format!("\\{:03o}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// removes the quotes around a literal, e.g 'a' -> a
+fn strip_quotes(literal: &str, quote: char) -> &str {
+    assert!(literal.len() >= 2 && literal.starts_with(quote) && literal.ends_with(quote), "invalid literal: {}", literal);
+    &literal[1..literal.len() - 1]
+}
+
+/// returns the ascii value of a char constant such as 'a', '\n' or '\x41'
+pub fn char_constant_value(literal: &str) -> u8 {
+    let val = unescape(strip_quotes(literal, '\''))
+        .unwrap_or_else(|| panic!("invalid escape sequence in: {}", literal));
+    let chars: Vec<char> = val.chars().collect();
+    assert!(chars.len() == 1, "invalid char constant: {}", literal);
+    chars[0] as u8
+}
+
+/// returns the bits of a float constant such as 1.5 as a word
+pub fn float_constant_value(literal: &str) -> i32 {
+    let val: f32 = literal.parse().unwrap_or_else(|_| panic!("invalid float constant: {}", literal));
+    val.to_bits() as i32
+}
+
+/// returns the contents of a string constant such as "hello\n"
+pub fn string_constant_value(literal: &str) -> String {
+    unescape(strip_quotes(literal, '"'))
+        .unwrap_or_else(|| panic!("invalid escape sequence in: {}", literal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_constants() {
+        assert_eq!(char_constant_value("'a'"), b'a');
+        assert_eq!(char_constant_value("' '"), b' ');
+        assert_eq!(char_constant_value("'\\n'"), b'\n');
+        assert_eq!(char_constant_value("'\\0'"), 0);
+        assert_eq!(char_constant_value("'\\''"), b'\'');
+        assert_eq!(char_constant_value("'\\\\'"), b'\\');
+        assert_eq!(char_constant_value("'\\x41'"), b'A');
+        assert_eq!(char_constant_value("'\\101'"), b'A');
+    }
+
+    #[test]
+    fn test_string_constants() {
+        assert_eq!(string_constant_value("\"\""), "");
+        assert_eq!(string_constant_value("\"a  b\""), "a  b");
+        assert_eq!(string_constant_value("\"say \\\"hi\\\"\\r\\n\""), "say \"hi\"\r\n");
+        assert_eq!(string_constant_value("\"\\x7a\\\\\""), "z\\");
+    }
+
+    #[test]
+    fn test_invalid_escapes() {
+        assert_eq!(unescape("\\q"), None);
+        assert_eq!(unescape("\\x"), None);
+        assert_eq!(unescape("\\x100"), None);
+        assert_eq!(unescape("ends with \\"), None);
+    }
+
+    #[test]
+    fn test_escape() {
+        let s = "line 1\nline\t2 \\ \0b \x7f";
+        assert!(!escape(s).contains('\n'));
+        assert_eq!(unescape(&escape(s)).unwrap(), s);
+    }
+}
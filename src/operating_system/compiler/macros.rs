@@ -0,0 +1,483 @@
+// #define macros, expanded by the preprocessor token by token
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::error::ErrorKind;
+
+/// splits a line into tokens, whitespace & comments are kept as tokens so the expanded line keeps its layout
+/// only identifiers matter for expansion, so operators are single characters except for ##
+pub fn tokenize(line: &str) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let rest: String = chars[i..].iter().take(2).collect();
+        if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            // numbers are read like identifiers, so the letters of e.g 0x1f aren't expanded
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || (chars[start].is_ascii_digit() && chars[i] == '.')) {
+                i += 1;
+            }
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+        } else if rest == "//" {
+            i = chars.len();
+        } else if rest == "/*" {
+            i += 2;
+            while i < chars.len() && chars[i..].iter().take(2).collect::<String>() != "*/" {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+        } else if rest == "##" {
+            i += 2;
+        } else {
+            i += 1;
+        }
+        tokens.push(chars[start..i].iter().collect());
+    }
+    tokens
+}
+
+fn is_identifier(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_alphabetic() || c == '_')
+}
+
+fn is_whitespace(token: &str) -> bool {
+    token.starts_with(char::is_whitespace) || token.starts_with("//") || token.starts_with("/*")
+}
+
+/// the tokens without leading & trailing whitespace
+fn trim<T: AsRef<str>>(tokens: &[T]) -> &[T] {
+    let start = tokens.iter().position(|token| !is_whitespace(token.as_ref())).unwrap_or(tokens.len());
+    let end = tokens.iter().rposition(|token| !is_whitespace(token.as_ref())).map_or(start, |end| end + 1);
+    &tokens[start..end]
+}
+
+/// a token of a line that's being expanded
+/// the hide set holds the macros whose expansion the token came from, they aren't expanded again in it
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    hide_set: HashSet<String>,
+}
+
+impl From<String> for Token {
+    fn from(text: String) -> Self {
+        Token { text, hide_set: HashSet::new() }
+    }
+}
+
+impl AsRef<str> for Token {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    params: Option<Vec<String>>, // None for an object-like macro, a variadic macro's last param is "..."
+    body: Vec<String>,
+}
+
+impl Macro {
+    /// parses the rest of a #define line, e.g "MAX(a, b) ((a) > (b) ? (a) : (b))"
+    /// a function-like macro's params start right after its name, without a space
+    pub fn parse(definition: &str) -> Option<(String, Macro)> {
+        let tokens = tokenize(definition.trim());
+        let name = tokens.first().filter(|name| is_identifier(name))?.clone();
+        if tokens.get(1).map(String::as_str) != Some("(") {
+            return Some((name, Macro { params: None, body: trim(&tokens[1..]).to_vec() }));
+        }
+        let close = tokens.iter().position(|token| token == ")")?;
+        let mut params = Vec::new();
+        for param in tokens[2..close].split(|token| token == ",") {
+            match trim(param) {
+                [name] if is_identifier(name) => params.push(name.clone()),
+                [a, b, c] if [a, b, c] == ["." , ".", "."] => params.push("...".to_string()),
+                [] if params.is_empty() && close == 2 => {},
+                _ => return None,
+            }
+        }
+        Some((name, Macro { params: Some(params), body: trim(&tokens[close + 1..]).to_vec() }))
+    }
+}
+
+/// the defined macros, by name
+#[derive(Debug, Default)]
+pub struct Macros {
+    macros: HashMap<String, Macro>,
+}
+
+impl Macros {
+    pub fn define(&mut self, name: String, macro_def: Macro) {
+        self.macros.insert(name, macro_def);
+    }
+
+    pub fn undef(&mut self, name: &str) {
+        self.macros.remove(name);
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name)
+    }
+
+    /// evaluates the condition of an #if or an #elif, e.g "defined(DEBUG) && LEVEL > 2"
+    /// the macros are expanded first, & then the identifiers that are left are 0
+    pub fn eval_condition(&self, condition: &str) -> Result<bool, ErrorKind> {
+        let invalid = || ErrorKind::InvalidCondition { condition: condition.trim().to_string() };
+        let tokens: Vec<String> = tokenize(condition).into_iter().filter(|token| !is_whitespace(token)).collect();
+        // defined X & defined(X) are replaced before the expansion, so X isn't expanded
+        let mut replaced = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i] != "defined" {
+                replaced.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+            let name = match tokens.get(i + 1).map(String::as_str) {
+                Some("(") if tokens.get(i + 3).map(String::as_str) == Some(")") => {
+                    i += 4;
+                    &tokens[i - 2]
+                },
+                Some(_) => {
+                    i += 2;
+                    &tokens[i - 1]
+                },
+                None => return Err(invalid()),
+            };
+            replaced.push(if self.is_defined(name) { "1" } else { "0" }.to_string());
+        }
+        let expanded = self.expand(replaced.into_iter().map(Token::from).collect())?;
+        let mut parser = ConditionParser { tokens: Vec::new(), pos: 0 };
+        for token in expanded.into_iter().map(|token| token.text).filter(|token| !is_whitespace(token)) {
+            // the tokenizer splits operators into single characters
+            let op = format!("{}{}", parser.tokens.last().map_or("", String::as_str), token);
+            if ["==", "!=", "<=", ">=", "&&", "||", "<<", ">>"].contains(&op.as_str()) {
+                *parser.tokens.last_mut().unwrap() = op;
+            } else {
+                parser.tokens.push(if is_identifier(&token) { "0".to_string() } else { token });
+            }
+        }
+        match parser.parse_expr(0) {
+            Some(value) if parser.pos == parser.tokens.len() => Ok(value != 0),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// expands the macros in the line, a function-like macro's arguments must be on the same line as its name
+    pub fn expand_line(&self, line: &str) -> Result<String, ErrorKind> {
+        let tokens = tokenize(line).into_iter().map(Token::from).collect();
+        Ok(self.expand(tokens)?.into_iter().map(|token| token.text).collect())
+    }
+
+    /// a macro's expansion is put back in front of the tokens after it & rescanned with them,
+    /// so e.g an object-like macro can expand to the name of a function-like macro whose arguments follow it
+    fn expand(&self, mut tokens: Vec<Token>) -> Result<Vec<Token>, ErrorKind> {
+        let mut expanded = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let start = i;
+            let token = tokens[i].clone();
+            i += 1;
+            let macro_def = match self.macros.get(&token.text) {
+                Some(macro_def) if !token.hide_set.contains(&token.text) => macro_def,
+                _ => {
+                    expanded.push(token);
+                    continue;
+                },
+            };
+            let args = match &macro_def.params {
+                None => Vec::new(),
+                Some(params) => match self.parse_args(&tokens, i) {
+                    Some((args, next_i)) => {
+                        i = next_i;
+                        self.check_args(&token.text, params, args)?
+                    },
+                    // a function-like macro's name without arguments isn't expanded
+                    None => {
+                        expanded.push(token);
+                        continue;
+                    },
+                },
+            };
+            let mut hide_set = token.hide_set;
+            hide_set.insert(token.text);
+            let body = self.substitute(macro_def, &args, &hide_set)?;
+            tokens.splice(start..i, body);
+            i = start;
+        }
+        Ok(expanded)
+    }
+
+    /// the arguments of a macro call whose name is before tokens[start], split on the top level commas
+    /// returns the arguments & the index after the closing parenthesis, or None if there are no arguments
+    fn parse_args(&self, tokens: &[Token], start: usize) -> Option<(Vec<Vec<Token>>, usize)> {
+        let open = start + tokens[start..].iter().position(|token| !is_whitespace(&token.text))?;
+        if tokens[open].text != "(" {
+            return None;
+        }
+        let mut args = vec![Vec::new()];
+        let mut depth = 0;
+        for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+            match token.text.as_str() {
+                ")" if depth == 0 => return Some((args, i + 1)),
+                "," if depth == 0 => {
+                    args.push(Vec::new());
+                    continue;
+                },
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                _ => {},
+            }
+            args.last_mut().unwrap().push(token.clone());
+        }
+        None
+    }
+
+    /// returns the args of the macro's params, a variadic macro's extra args are joined into its last arg
+    fn check_args(&self, name: &str, params: &[String], mut args: Vec<Vec<Token>>) -> Result<Vec<Vec<Token>>, ErrorKind> {
+        let variadic = params.last().map(String::as_str) == Some("...");
+        if params.is_empty() && args.len() == 1 && trim(&args[0]).is_empty() {
+            return Ok(Vec::new());
+        }
+        if variadic && args.len() >= params.len() {
+            let rest = args.split_off(params.len() - 1);
+            args.push(rest.join(&Token::from(",".to_string())));
+        }
+        if args.len() != params.len() {
+            return Err(ErrorKind::WrongMacroArgsCount { name: name.to_string(), expected: params.len(), found: args.len() });
+        }
+        Ok(args.iter().map(|arg| trim(arg).to_vec()).collect())
+    }
+
+    /// replaces the params in the macro's body with their args, the hide set is added to the tokens of the result
+    /// an arg is expanded before it's substituted, unless it's an operand of # or ##
+    fn substitute(&self, macro_def: &Macro, args: &[Vec<Token>], hide_set: &HashSet<String>) -> Result<Vec<Token>, ErrorKind> {
+        let params = macro_def.params.clone().unwrap_or_default();
+        let param_i = |token: &str| params.iter().position(|param| param == token || (param == "..." && token == "__VA_ARGS__"));
+        let body = &macro_def.body;
+        let mut res: Vec<Token> = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            let token = &body[i];
+            let next = body[i + 1..].iter().position(|token| !is_whitespace(token)).map(|offset| i + 1 + offset);
+            if token == "#" && next.and_then(|next| param_i(&body[next])).is_some() {
+                // #param is the arg as a string literal
+                let arg = &args[param_i(&body[next.unwrap()]).unwrap()];
+                let text: String = arg.iter().map(|token| if is_whitespace(&token.text) { " " } else { &token.text }).collect();
+                res.push(Token::from(format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))));
+                i = next.unwrap() + 1;
+                continue;
+            }
+            if token == "##" {
+                // a ## b pastes the tokens before & after it into one token
+                while res.last().is_some_and(|token| is_whitespace(&token.text)) {
+                    res.pop();
+                }
+                let right = match next {
+                    Some(next) => match param_i(&body[next]) {
+                        Some(arg_i) => args[arg_i].iter().map(|token| token.text.as_str()).collect(),
+                        None => body[next].clone(),
+                    },
+                    None => String::new(),
+                };
+                let left = res.pop().map(|token| token.text).unwrap_or_default();
+                res.push(Token::from(left + &right));
+                i = next.map_or(body.len(), |next| next + 1);
+                continue;
+            }
+            match param_i(token) {
+                Some(arg_i) if next.is_some_and(|next| body[next] == "##") => res.extend(args[arg_i].iter().cloned()),
+                Some(arg_i) => res.extend(self.expand(args[arg_i].clone())?),
+                None => res.push(Token::from(token.clone())),
+            }
+            i += 1;
+        }
+        for token in res.iter_mut() {
+            token.hide_set.extend(hide_set.iter().cloned());
+        }
+        Ok(res)
+    }
+}
+
+/// the binary operators of #if conditions & their precedence, higher binds tighter
+const BINARY_OPS: [(&str, u32); 18] = [
+    ("||", 1), ("&&", 2), ("|", 3), ("^", 4), ("&", 5), ("==", 6), ("!=", 6),
+    ("<", 7), (">", 7), ("<=", 7), (">=", 7), ("<<", 8), (">>", 8),
+    ("+", 9), ("-", 9), ("*", 10), ("/", 10), ("%", 10),
+];
+
+/// a precedence climbing parser that evaluates a condition while parsing it, None if the condition is invalid
+struct ConditionParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl ConditionParser {
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn parse_expr(&mut self, min_precedence: u32) -> Option<i64> {
+        let mut left = self.parse_unary()?;
+        while let Some(&(op, precedence)) = BINARY_OPS.iter().find(|(op, _)| Some(*op) == self.peek()) {
+            if precedence <= min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let right = self.parse_expr(precedence)?;
+            left = match op {
+                "||" => (left != 0 || right != 0) as i64,
+                "&&" => (left != 0 && right != 0) as i64,
+                "|" => left | right,
+                "^" => left ^ right,
+                "&" => left & right,
+                "==" => (left == right) as i64,
+                "!=" => (left != right) as i64,
+                "<" => (left < right) as i64,
+                ">" => (left > right) as i64,
+                "<=" => (left <= right) as i64,
+                ">=" => (left >= right) as i64,
+                "<<" => left.checked_shl(right as u32)?,
+                ">>" => left.checked_shr(right as u32)?,
+                "+" => left.wrapping_add(right),
+                "-" => left.wrapping_sub(right),
+                "*" => left.wrapping_mul(right),
+                "/" => left.checked_div(right)?,
+                "%" => left.checked_rem(right)?,
+                _ => unreachable!(),
+            };
+        }
+        // the ternary operator has the lowest precedence
+        if min_precedence == 0 && self.peek() == Some("?") {
+            self.pos += 1;
+            let if_true = self.parse_expr(0)?;
+            (self.next()? == ":").then_some(())?;
+            let if_false = self.parse_expr(0)?;
+            return Some(if left != 0 { if_true } else { if_false });
+        }
+        Some(left)
+    }
+
+    fn parse_unary(&mut self) -> Option<i64> {
+        let token = self.next()?;
+        match token.as_str() {
+            "!" => Some((self.parse_unary()? == 0) as i64),
+            "-" => Some(self.parse_unary()?.wrapping_neg()),
+            "+" => self.parse_unary(),
+            "~" => Some(!self.parse_unary()?),
+            "(" => {
+                let value = self.parse_expr(0)?;
+                (self.next()? == ")").then_some(value)
+            },
+            _ => {
+                // integer suffixes such as 10L are ignored
+                let digits = token.trim_end_matches(['u', 'U', 'l', 'L']);
+                match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+                    Some(hex) => i64::from_str_radix(hex, 16).ok(),
+                    None if digits.len() > 1 && digits.starts_with('0') => i64::from_str_radix(&digits[1..], 8).ok(),
+                    None => digits.parse().ok(),
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn macros(definitions: &[&str]) -> Macros {
+        let mut macros = Macros::default();
+        for definition in definitions.iter() {
+            let (name, macro_def) = Macro::parse(definition).unwrap();
+            macros.define(name, macro_def);
+        }
+        macros
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("x1 = f(0x1f, \"a, b\");// c"), vec!["x1", " ", "=", " ", "f", "(", "0x1f", ",", " ", "\"a, b\"", ")", ";", "// c"]);
+        assert_eq!(tokenize("a##b 1.5e3 '\\''"), vec!["a", "##", "b", " ", "1.5e3", " ", "'\\''"]);
+    }
+
+    #[test]
+    fn test_expand() {
+        let macros = macros(&[
+            "SIZE 10",
+            "DOUBLE_SIZE (SIZE * 2)",
+            "MAX(a, b) ((a) > (b) ? (a) : (b))",
+            "SQUARE(x) ((x) * (x))",
+            "LOOP LOOP + 1",
+            "STR(x) #x",
+            "CONCAT(a, b) a ## b",
+            "LOG(fmt, ...) printf(fmt, __VA_ARGS__)",
+            "EMPTY()",
+            "ID(x) x",
+            "CALL_ID ID",
+            "SELF(x) x SELF",
+        ]);
+        let expand = |line: &str| macros.expand_line(line).unwrap();
+        assert_eq!(expand("int arr[SIZE];"), "int arr[10];");
+        assert_eq!(expand("x = DOUBLE_SIZE;"), "x = (10 * 2);");
+        assert_eq!(expand("MAX(f(1, 2), SIZE)"), "((f(1, 2)) > (10) ? (f(1, 2)) : (10))");
+        assert_eq!(expand("SQUARE(SQUARE(y))"), "((((y) * (y))) * (((y) * (y))))");
+        assert_eq!(expand("LOOP"), "LOOP + 1");
+        assert_eq!(expand("STR(a  + \"b\")"), "\"a + \\\"b\\\"\"");
+        assert_eq!(expand("CONCAT(SI, ZE) CONCAT(x, 1)"), "10 x1");
+        assert_eq!(expand("LOG(\"%d\", 1, 2)"), "printf(\"%d\", 1, 2)");
+        assert_eq!(expand("EMPTY() MAX // SIZE"), " MAX // SIZE");
+        assert_eq!(expand("\"SIZE\" SIZE_2"), "\"SIZE\" SIZE_2");
+        assert_eq!(expand("CALL_ID(4) CALL_ID"), "4 ID");
+        assert_eq!(expand("ID(LOOP) ID(ID)(1)"), "LOOP + 1 ID(1)");
+        assert_eq!(expand("SELF(1)(2)"), "1 SELF(2)");
+        assert_eq!(macros.expand_line("MAX(1)"), Err(ErrorKind::WrongMacroArgsCount { name: "MAX".to_string(), expected: 2, found: 1 }));
+    }
+
+    #[test]
+    fn test_eval_condition() {
+        let macros = macros(&["LEVEL 3", "DEBUG", "MAX(a, b) ((a) > (b) ? (a) : (b))"]);
+        let eval = |condition: &str| macros.eval_condition(condition).unwrap();
+        assert!(eval("1"));
+        assert!(!eval("0x0"));
+        assert!(eval("defined(DEBUG) && LEVEL >= 3"));
+        assert!(eval("defined LEVEL && !defined RELEASE"));
+        assert!(!eval("UNDEFINED_NAME"));
+        assert!(eval("MAX(LEVEL, 1) == 3 // comment"));
+        assert!(eval("1 + 2 * 3 == 7 && (1 + 2) * 3 == 9"));
+        assert!(eval("-1 < 0 && 1 << 4 == 16 && 17 % 5 == 2 && (6 & 3 | 8) == 10"));
+        assert!(eval("LEVEL > 5 ? 0 : LEVEL == 3 ? 1 : 0"));
+        assert!(eval("010 == 8 && 10L == 10"));
+        assert_eq!(macros.eval_condition("1 / 0"), Err(ErrorKind::InvalidCondition { condition: "1 / 0".to_string() }));
+        assert!(macros.eval_condition("(1").is_err());
+        assert!(macros.eval_condition("1 2").is_err());
+        assert!(macros.eval_condition("DEBUG").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Macro::parse("F (x) x").unwrap().1, Macro { params: None, body: tokenize("(x) x") });
+        assert_eq!(Macro::parse("F(x, ...) x").unwrap().1.params, Some(vec!["x".to_string(), "...".to_string()]));
+        assert_eq!(Macro::parse("F"), Some(("F".to_string(), Macro { params: None, body: Vec::new() })));
+        assert_eq!(Macro::parse("1 x"), None);
+        assert_eq!(Macro::parse("F(x, 1) x"), None);
+    }
+}
@@ -1,30 +1,47 @@
 
+use std::fs;
 use std::fs::File;
-use std::io::prelude::*;
-
-extern crate regex;
-use regex::Regex;
+use std::fmt;
+use std::io::Read;
 
 extern crate serde_json;
 
-extern crate tempfile;
-use tempfile::NamedTempFile;
-use std::io::{Write};
-
 extern crate linked_hash_map;
 use linked_hash_map::LinkedHashMap;
 
 mod AST;
+mod ast_printer;
+mod const_fold;
+mod error;
+mod inliner;
+pub(crate) mod literals;
+mod macros;
+mod optimizer;
+mod options;
+mod parser;
 mod preprocessor;
+mod type_checker;
+
+pub use self::error::{format_errors, CompileError, ErrorKind};
+pub use self::options::{CompilerOptions, OptLevel};
+pub use self::AST::RootAstNode;
 
 use self::AST::*;
-use crate::cpu::instructions::Register;
+use self::inliner::{count_instructions, InlineFunc};
+use self::literals::{char_constant_value, escape, float_constant_value, string_constant_value};
+use self::type_checker::{op_name, type_name};
+use super::assembler::is_instruction;
+use super::debug_info::{DebugInfo, FuncInfo, VarInfo};
+use super::linker::{get_referenced_labels, global_var_label, ObjectFile};
+use super::syscalls::SYS_EXIT;
+use super::{ABORT_EXIT_CODE, FAULT_EXIT_CODE};
+use crate::cpu::instructions::{Register, register_from_str};
+use crate::cpu::BYTES_PER_WORD;
+use crate::logging;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
-// typedef ast Node = JSON value
-use self::serde_json::Value as Node;
-
 #[derive(Debug)]
 enum VarStorageType{
     Local,
@@ -40,6 +57,17 @@ enum VariableType {
 }
 
 impl VariableType{
+    /// e.g "int", "struct point*" or "int[2][3]"
+    fn type_name(&self) -> String {
+        match self {
+            VariableType::Regular{_type} => type_name(_type),
+            VariableType::Array{_type, dimentions} => {
+                let dims: String = dimentions.iter().map(|dim| format!("[{}]", dim)).collect();
+                format!("{}{}", _type.type_name(), dims)
+            },
+        }
+    }
+
     fn from(decl: &Decl) -> VariableType{
         match decl{
             Decl::VarDecl(var_decl) => VariableType::Regular{
@@ -68,8 +96,13 @@ impl VariableData{
 #[derive(Debug)]
 struct FuncBodyData {
     name: String,
-    regs_used: Vec<Register>,
+    saved_regs: Vec<Register>, // the callee saved registers that the body uses
     local_vars_size: u32,
+    args_size: u32,
+    labels: HashSet<String>, // the targets of goto statements
+    uses_alloca: bool, // the body moves SP by a size that's only known at runtime
+    frame_escapes: bool, // the body may make a pointer into its stack frame, see frame_may_escape
+    tail_calls: Vec<String>, // the functions that the body tail calls, each gets a block after the epilogue that jumps to it
 }
 
 // this is the data that we get once we declare a function
@@ -89,7 +122,7 @@ struct ScopeData {
     name: String,
     parent_scope: String,
     parent_func: String,
-    variables: HashMap<String, VariableData>,
+    variables: LinkedHashMap<String, VariableData>,
     declared_variables: HashSet<String>,
     break_label: Option<String>,
     continue_label: Option<String>,
@@ -104,32 +137,423 @@ pub struct StructData{
 }
 
 pub struct Compiler {
-    scope_to_data: HashMap<String, ScopeData>,
-    func_to_data: HashMap<String, FuncData>,
-    struct_to_data: HashMap<String, StructData>,
-    data_val_to_label: HashMap<String, String>,
-    program_index: u32,  // hack to keep tmp labels from colliding accross different programs. OS is in charge of passing different indices
+    options: CompilerOptions,
+    inline_funcs: LinkedHashMap<String, InlineFunc>, // functions whose calls can be expanded in place
+    source_lines: LinkedHashMap<String, Vec<String>>, // lines of the source files, for comments in the generated code
+    scope_to_data: LinkedHashMap<String, ScopeData>,
+    func_to_data: LinkedHashMap<String, FuncData>,
+    struct_to_data: LinkedHashMap<String, StructData>,
+    typedefs: LinkedHashMap<String, Type>, // typedef name -> resolved type
+    data_val_to_label: LinkedHashMap<String, String>,
     cur_tmp_label: u32,
+    free_tmp_regs: Vec<Register>, // registers that can hold intermediate values instead of spilling them to the stack
+    cur_loc: Option<SourceLoc>, // location of the statement being generated, for error reporting
+}
+
+// registers used for holding intermediate values while evaluating expressions
+const TMP_REGS: [Register; 2] = [Register::R4, Register::R3];
+const GENERAL_REGS: [Register; 4] = [Register::R1, Register::R2, Register::R3, Register::R4];
+// the calling convention: R1 & R2 are caller saved, they're scratch registers that a call may change.
+// the tmp registers are callee saved, since they may hold intermediate values across a call, e.g of x + f()
+const CALLEE_SAVED_REGS: [Register; 2] = [Register::R3, Register::R4];
+
+// a switch with at least this many constant cases jumps to its case through a table of the case labels,
+// if the table has at most this many entries per case, see switch_jump_table
+const JUMP_TABLE_MIN_CASES: usize = 4;
+const JUMP_TABLE_MAX_SPARSENESS: usize = 3;
+
+// shifting a word left & then right by this leaves its low byte, sign extended
+const CHAR_SHIFT: u32 = 24;
+
+// builtin function that invokes the SYSCALL instruction
+const SYSCALL_BUILTIN: &str = "__syscall";
+const ALLOCA_BUILTIN: &str = "alloca";
+// exit(status) & abort() stop the program with the exit syscall, so they work without libc
+const EXIT_BUILTIN: &str = "exit";
+const ABORT_BUILTIN: &str = "abort";
+// __test_and_set(int* p) sets *p to 1 with the TSET instruction & returns its previous value, for locks
+const TEST_AND_SET_BUILTIN: &str = "__test_and_set";
+const BUILTINS: [&str; 5] = [SYSCALL_BUILTIN, ALLOCA_BUILTIN, EXIT_BUILTIN, ABORT_BUILTIN, TEST_AND_SET_BUILTIN];
+
+/// the assembly label of a C label, C labels are scoped to their function
+fn goto_label(func_name: &str, label_name: &str) -> String {
+    format!("_{}_LABEL_{}", func_name, label_name)
+}
+
+/// the types of the function's params, None for array params
+fn params_types(func_data: &FuncData) -> Vec<Option<Type>> {
+    func_data.decl_data.args_types.iter().map(|arg_type| match arg_type {
+        VariableType::Regular{_type} => Some(_type.clone()),
+        VariableType::Array{..} => None,
+    }).collect()
+}
+
+/// returns the functions that may run: main and the functions it calls or takes the address of, transitively
+/// a file without main is a library whose functions are all kept, since they may be called from other files
+fn reachable_funcs(funcs_code: &[(String, Vec<String>)]) -> HashSet<String> {
+    let func_to_code: HashMap<&String, &Vec<String>> = funcs_code.iter().map(|(name, code)| (name, code)).collect();
+    let main = "main".to_string();
+    if !func_to_code.contains_key(&main) {
+        return funcs_code.iter().map(|(name, _)| name.clone()).collect();
+    }
+    let mut reachable = HashSet::new();
+    let mut to_visit = vec![main];
+    while let Some(func_name) = to_visit.pop() {
+        if !reachable.insert(func_name.clone()) {
+            continue;
+        }
+        let referenced = get_referenced_labels(&func_to_code[&func_name].join("\n"));
+        to_visit.extend(referenced.into_iter().filter(|label| func_to_code.contains_key(label)));
+    }
+    reachable
+}
+
+/// returns the names of the labels in the statements, including labels in nested statements
+fn collect_labels(statements: &[Statement]) -> Vec<String> {
+    let mut labels = Vec::new();
+    for statement in statements.iter() {
+        match statement {
+            Statement::Label(label) => {
+                labels.push(label.name.clone());
+                labels.extend(collect_labels(std::slice::from_ref(&*label.stmt)));
+            },
+            Statement::Compound(comp) => labels.extend(collect_labels(&comp.items)),
+            Statement::If(if_stmt) => {
+                labels.extend(collect_labels(&if_stmt.iftrue.items));
+                if let Some(iffalse) = &if_stmt.iffalse {
+                    labels.extend(collect_labels(&iffalse.items));
+                }
+            },
+            Statement::WhileLoop(wl) => labels.extend(collect_labels(&wl.body.items)),
+            Statement::DoWhileLoop(dwl) => labels.extend(collect_labels(&dwl.body.items)),
+            Statement::ForLoop(fl) => labels.extend(collect_labels(&fl.body.items)),
+            Statement::Switch(sw) => labels.extend(collect_labels(&sw.body.items)),
+            _ => {},
+        }
+    }
+    labels
+}
+
+/// true if the statements may make a pointer into the function's stack frame, e.g &x, a local array or alloca.
+/// it's conservative: a struct member that's accessed with "." may be an array, which is its address
+fn frame_may_escape(statements: &[Statement]) -> bool {
+    let block_escapes = |comp: &Compound| frame_may_escape(&comp.items);
+    statements.iter().any(|statement| match statement {
+        Statement::Return(ret) => ret.expr.as_ref().is_some_and(expr_may_escape_frame),
+        Statement::Decl(Decl::VarDecl(var_decl)) => var_decl.init.as_ref().is_some_and(expr_may_escape_frame),
+        Statement::Decl(Decl::ArrayDecl(_)) => true,
+        Statement::Assignment(assignment) => expr_may_escape_frame(&assignment.lvalue) || expr_may_escape_frame(&assignment.rvalue),
+        Statement::Expression(expr) | Statement::ComputedGoto(expr) => expr_may_escape_frame(expr),
+        Statement::If(if_stmt) => expr_may_escape_frame(&if_stmt.cond) || block_escapes(&if_stmt.iftrue)
+            || if_stmt.iffalse.as_ref().is_some_and(|iffalse| block_escapes(iffalse)),
+        Statement::Compound(comp) => block_escapes(comp),
+        Statement::WhileLoop(wl) => expr_may_escape_frame(&wl.cond) || block_escapes(&wl.body),
+        Statement::DoWhileLoop(dwl) => expr_may_escape_frame(&dwl.cond) || block_escapes(&dwl.body),
+        Statement::ForLoop(fl) => fl.cond.as_ref().is_some_and(expr_may_escape_frame)
+            || fl.init.as_ref().is_some_and(|init| block_escapes(init))
+            || fl.next.as_ref().is_some_and(|next| block_escapes(next))
+            || block_escapes(&fl.body),
+        Statement::Switch(sw) => expr_may_escape_frame(&sw.cond) || block_escapes(&sw.body),
+        Statement::Label(label) => frame_may_escape(std::slice::from_ref(&*label.stmt)),
+        Statement::Goto(_) | Statement::Break | Statement::Continue => false,
+    })
+}
+
+fn expr_may_escape_frame(expr: &Expression) -> bool {
+    match expr {
+        Expression::UnaryOp(op) => op.op_type == UnaryopType::REF || expr_may_escape_frame(&op.expr),
+        Expression::FuncCall(func_call) => func_call.name == ALLOCA_BUILTIN || func_call.args.iter().any(|arg| expr_may_escape_frame(arg)),
+        Expression::BinaryOp(op) => expr_may_escape_frame(&op.left) || expr_may_escape_frame(&op.right),
+        Expression::Assignment(assignment) => expr_may_escape_frame(&assignment.lvalue) || expr_may_escape_frame(&assignment.rvalue),
+        Expression::TernaryOp(top) => expr_may_escape_frame(&top.cond) || expr_may_escape_frame(&top.iftrue) || expr_may_escape_frame(&top.iffalse),
+        Expression::NameRef(name) => name_may_escape_frame(name),
+        Expression::Cast(cast) => expr_may_escape_frame(&cast.expr),
+        Expression::Comma(exprs) => exprs.iter().any(expr_may_escape_frame),
+        Expression::Constant(_) | Expression::TypeName(_) | Expression::LabelAddr(_) => false,
+    }
+}
+
+fn name_may_escape_frame(name: &NameRef) -> bool {
+    match name {
+        NameRef::ID(_) => false,
+        NameRef::ArrayRef(array_ref) => name_may_escape_frame(&array_ref.name) || array_ref.indices.iter().any(|index| expr_may_escape_frame(index)),
+        NameRef::StructRef(struct_ref) => match struct_ref._type {
+            StructRefType::DOT => true,
+            StructRefType::ARROW => name_may_escape_frame(&struct_ref.name),
+        },
+    }
+}
+
+/// returns the general purpose registers that are referenced by the given code
+fn get_regs_used(code: &[String]) -> Vec<Register>{
+    let mut used = HashSet::new();
+    for line in code.iter(){
+        for token in line.split_whitespace(){
+            if let Ok(reg) = register_from_str(token){
+                used.insert(reg);
+            }
+        }
+    }
+    GENERAL_REGS.iter().filter(|reg| used.contains(reg)).cloned().collect()
 }
 
 impl Compiler {
-    pub fn new(program_i : u32) -> Compiler {
+    pub fn new() -> Compiler {
+        Compiler::with_options(CompilerOptions::default())
+    }
+
+    pub fn with_options(options: CompilerOptions) -> Compiler {
         Compiler {
-            scope_to_data: HashMap::new(),
-            func_to_data: HashMap::new(),
-            struct_to_data: HashMap::new(),
-            data_val_to_label: HashMap::new(),
-            program_index: program_i,
+            options,
+            inline_funcs: LinkedHashMap::new(),
+            source_lines: LinkedHashMap::new(),
+            scope_to_data: LinkedHashMap::new(),
+            func_to_data: LinkedHashMap::new(),
+            struct_to_data: LinkedHashMap::new(),
+            typedefs: LinkedHashMap::new(),
+            data_val_to_label: LinkedHashMap::new(),
             cur_tmp_label: 0,
+            free_tmp_regs: TMP_REGS.to_vec(),
+            cur_loc: None,
         }
     }
 
-    fn get_tmp_label(&self) -> String{
-        format!("{}_{}", self.program_index, self.cur_tmp_label)
+    fn error(&self, kind: ErrorKind) -> CompileError {
+        CompileError { kind, loc: self.cur_loc.clone() }
     }
 
-    fn get_global_label(&self) -> String{
-        format!("GLOBAL_{}", self.program_index)
+    /// logs the message if the compiler is verbose, see logging.rs
+    fn trace(&self, message: fmt::Arguments) {
+        if self.options.verbose {
+            log::trace!(target: logging::COMPILER, "{}", message);
+        }
+    }
+
+    /// saves the value of reg in a free tmp register, or on the stack if all tmp registers are taken.
+    /// the returned value must be passed to gen_restore_reg
+    fn gen_save_reg(&mut self, reg: &Register, code: &mut Vec<String>) -> Option<Register>{
+        let tmp_reg = self.free_tmp_regs.pop();
+        match &tmp_reg{
+            Some(tmp_reg) => code.push(format!("MOV {} {}", tmp_reg, reg)),
+            None => code.push(format!("PUSH {}", reg)),
+        }
+        tmp_reg
+    }
+
+    /// restores a value saved with gen_save_reg into dst
+    fn gen_restore_reg(&mut self, saved: Option<Register>, dst: &Register, code: &mut Vec<String>){
+        match saved{
+            Some(tmp_reg) => {
+                code.push(format!("MOV {} {}", dst, tmp_reg));
+                self.free_tmp_regs.push(tmp_reg);
+            },
+            None => code.push(format!("POP {}", dst)),
+        }
+    }
+
+    /// true if a value of the type is a single byte, which is accessed with LOADB & STRB
+    fn is_byte_sized(&self, _type: &Option<Type>) -> bool {
+        self.options.byte_addressing && matches!(_type.as_ref().map(|t| self.resolve_type(t)), Some(Type::Char))
+    }
+
+    fn is_long(&self, _type: &Option<Type>) -> bool {
+        matches!(_type.as_ref().map(|t| self.resolve_type(t)), Some(Type::Long))
+    }
+
+    /// loads the value at the address in addr into dst, a value of an unknown type is a whole word
+    /// a long is loaded from the address in R1 into R1 (low word) & R2 (high word)
+    fn gen_load(&self, _type: &Option<Type>, dst: &Register, addr: &Register, code: &mut Vec<String>) {
+        if self.is_long(_type) {
+            assert!(*dst == Register::R1 && *addr == Register::R1, "longs are loaded into R1 & R2");
+            if self.options.byte_addressing {
+                code.push(format!("DIV R1 R1 {}", BYTES_PER_WORD));
+            }
+            code.push("ADD R2 R1 1".to_string());
+            code.push("LOAD R2 R2".to_string());
+            code.push("LOAD R1 R1".to_string());
+        } else if self.is_byte_sized(_type) {
+            code.push(format!("LOADB {} {}", dst, addr));
+        } else if self.options.byte_addressing {
+            code.push(format!("DIV {} {} {}", dst, addr, BYTES_PER_WORD));
+            code.push(format!("LOAD {} {}", dst, dst));
+        } else {
+            code.push(format!("LOAD {} {}", dst, addr));
+        }
+    }
+
+    /// stores the value in val at the address in addr
+    /// with byte addressing, a word's address is converted in place so addr doesn't hold the address afterwards
+    fn gen_store(&self, _type: &Option<Type>, addr: &Register, val: &Register, code: &mut Vec<String>) {
+        if self.is_byte_sized(_type) {
+            code.push(format!("STRB {} {}", addr, val));
+            return;
+        }
+        if self.options.byte_addressing {
+            code.push(format!("DIV {} {} {}", addr, addr, BYTES_PER_WORD));
+        }
+        code.push(format!("STR {} {}", addr, val));
+    }
+
+    /// stores the long in R1 (low word) & R2 (high word) at the address on the top of the stack, which isn't popped
+    fn gen_store_long(&self, code: &mut Vec<String>) {
+        code.push("ADD ZR SP 1".to_string());
+        code.push("LOAD ZR ZR".to_string());
+        if self.options.byte_addressing {
+            code.push(format!("DIV ZR ZR {}", BYTES_PER_WORD));
+        }
+        code.push("STR ZR R1".to_string());
+        code.push("ADD ZR ZR 1".to_string());
+        code.push("STR ZR R2".to_string());
+    }
+
+    /// generates a binary op on longs, the left operand is on the stack (low word on top) & the right one is in R1 & R2
+    /// the left operand is popped, the result is in R1 & R2, or in R1 for comparisons
+    fn gen_long_op(&self, op: &BinaryopType, code: &mut Vec<String>) {
+        // R3 holds a word of the left operand at a time, ZR holds the carry of ADC & SBC
+        code.push("PUSH R3".to_string());
+        let load_left = |word_i: u32, code: &mut Vec<String>| {
+            code.push(format!("ADD R3 SP {}", 2 + word_i));
+            code.push("LOAD R3 R3".to_string());
+        };
+        match op {
+            BinaryopType::ADD | BinaryopType::SUB | BinaryopType::AND | BinaryopType::OR | BinaryopType::XOR => {
+                let opname = match op {
+                    BinaryopType::ADD => "ADC".to_string(),
+                    BinaryopType::SUB => "SBC".to_string(),
+                    _ => op.to_op().unwrap(),
+                };
+                code.push("MOV ZR 0".to_string());
+                load_left(0, code);
+                code.push(format!("{} R1 R3 R1", opname));
+                load_left(1, code);
+                code.push(format!("{} R2 R3 R2", opname));
+            },
+            BinaryopType::EQ | BinaryopType::NEQ => {
+                load_left(0, code);
+                code.push("XOR R1 R3 R1".to_string());
+                load_left(1, code);
+                code.push("XOR R2 R3 R2".to_string());
+                code.push("OR R1 R1 R2".to_string());
+                code.push(format!("{} R1 0", if *op == BinaryopType::EQ {"TSTE"} else {"TSTN"}));
+                code.push("MOV R1 ZR".to_string());
+            },
+            BinaryopType::LT | BinaryopType::GT | BinaryopType::LTEQ | BinaryopType::GTEQ => {
+                // left < right if its high word is less, or if the high words are equal & its low word is less as an unsigned word
+                // the borrow of subtracting the low words compares them as unsigned words
+                let is_less = matches!(op, BinaryopType::LT | BinaryopType::GTEQ);
+                code.push("MOV ZR 0".to_string());
+                load_left(0, code);
+                code.push(if is_less {"SBC R1 R3 R1"} else {"SBC R1 R1 R3"}.to_string());
+                code.push("MOV R1 ZR".to_string());
+                load_left(1, code);
+                code.push("TSTE R3 R2".to_string());
+                code.push("AND R1 R1 ZR".to_string());
+                code.push(format!("{} R3 R2", if is_less {"TSTL"} else {"TSTG"}));
+                code.push("OR R1 R1 ZR".to_string());
+                if matches!(op, BinaryopType::LTEQ | BinaryopType::GTEQ) {
+                    code.push("TSTE R1 0".to_string());
+                    code.push("MOV R1 ZR".to_string());
+                }
+            },
+            _ => panic!("invalid long binary op"),
+        }
+        code.push("POP R3".to_string());
+        code.push("POP ZR".to_string());
+        code.push("POP ZR".to_string());
+    }
+
+    /// generates code that evaluates the condition, at the end of the generated code ZR is 1 if the condition is true
+    fn gen_cond(&mut self, cond: &Expression, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        self.right_gen(cond, scope, code)?;
+        let _type = self.get_expr_type(cond, scope);
+        if self.is_long(&_type) {
+            code.push("OR R1 R1 R2".to_string());
+        } else if self.is_float(&_type) {
+            // -0.0 is also false
+            code.push("FCMP R1 R1 0".to_string());
+        }
+        code.push("TSTN R1 0".to_string());
+        Ok(())
+    }
+
+    /// converts the word address in reg (e.g of a stack slot or a data label) to the address a pointer holds
+    fn gen_word_to_addr(&self, reg: &Register, code: &mut Vec<String>) {
+        if self.options.byte_addressing {
+            code.push(format!("MUL {} {} {}", reg, reg, BYTES_PER_WORD));
+        }
+    }
+
+    fn is_float(&self, _type: &Option<Type>) -> bool {
+        matches!(_type.as_ref().map(|t| self.resolve_type(t)), Some(Type::Float))
+    }
+
+    /// converts the value in reg from one arithmetic type to another, e.g 3 to 3.0 when it's assigned to a float
+    /// a value that's converted to a long is sign extended into R2, & a long is converted by dropping its high word
+    fn gen_convert(&self, from: &Option<Type>, to: &Option<Type>, reg: &Register, code: &mut Vec<String>) {
+        match (self.is_float(from), self.is_float(to)) {
+            (false, true) => code.push(format!("ITOF {}", reg)),
+            (true, false) if to.is_some() => code.push(format!("FTOI {}", reg)),
+            _ => {},
+        }
+        if self.is_long(to) && !self.is_long(from) {
+            code.push(format!("MOV R2 {}", reg));
+            code.push("SHR R2 R2 31".to_string());
+        }
+    }
+
+    /// generates a binary op on floats, R1 holds the right operand & R2 holds the left one
+    /// at the end of the generated code, the result is in R1
+    fn gen_float_op(&self, op: &BinaryopType, code: &mut Vec<String>) {
+        if let BinaryopType::ADD | BinaryopType::SUB | BinaryopType::MUL | BinaryopType::DIV = op {
+            code.push(format!("F{} R1 R2 R1", op.to_op().unwrap()));
+            return;
+        }
+        // FCMP sets R1 to -1, 0 or 1 if left is less than, equal to or greater than right, & to 2 if they're unordered,
+        // which only != is true for
+        code.push("FCMP R1 R2 R1".to_string());
+        match op {
+            BinaryopType::EQ => code.push("TSTE R1 0".to_string()),
+            BinaryopType::NEQ => code.push("TSTN R1 0".to_string()),
+            BinaryopType::LT => code.push("TSTL R1 0".to_string()),
+            BinaryopType::GT => code.push("TSTE R1 1".to_string()),
+            BinaryopType::LTEQ => {
+                // -1 or 0
+                code.push("TSTG R1 0".to_string());
+                code.push("TSTN ZR 1".to_string());
+            },
+            BinaryopType::GTEQ => {
+                // 0 or 1
+                code.push("AND R1 R1 -2".to_string());
+                code.push("TSTE R1 0".to_string());
+            },
+            _ => panic!("invalid float binary op"),
+        }
+        code.push("MOV R1 ZR".to_string());
+    }
+
+    /// the number of words that hold a value of the given size, variables on the stack & globals take whole words
+    /// the stack grows down towards the heap, the program exits instead of overwriting it
+    /// sp holds the value SP has or is about to have
+    fn gen_stack_check(&self, sp: &Register, stack_ok_label: &str, code: &mut Vec<String>) {
+        code.push(format!("TSTL {} {}", sp, self.options.stack_end));
+        code.push(format!("FJMP {}", stack_ok_label));
+        code.push(format!("MOV R2 {}", FAULT_EXIT_CODE));
+        self.gen_exit(code);
+        code.push(format!("{}:", stack_ok_label));
+    }
+
+    /// exits the program with the status in R2, the os stops it after the syscall
+    fn gen_exit(&self, code: &mut Vec<String>) {
+        code.push(format!("MOV R1 {}", SYS_EXIT));
+        code.push("SYSCALL".to_string());
+    }
+
+    fn words(&self, size: u32) -> u32 {
+        size.div_ceil(self.options.word_size())
+    }
+
+    fn get_tmp_label(&self) -> String{
+        format!("{}", self.cur_tmp_label)
     }
 
     fn inc_tmp_label(&mut self){
@@ -148,13 +572,18 @@ impl Compiler {
         if !self.data_val_to_label.contains_key(s) {
             let label = format!("STR_{}", self.get_tmp_label());
             self.inc_tmp_label();
-            code.push(format!(".stringz {} {}", label, s));
+            let directive = if self.options.byte_addressing {".bytez"} else {".stringz"};
+            code.push(format!("{} {} {}", directive, label, escape(s)));
             self.data_val_to_label.insert(s.clone(), label);
         }
         self.data_val_to_label.get(s).unwrap()
     }
 
-    fn right_gen(&mut self, node: &Expression, scope: &String, code: &mut Vec<String>) {
+    fn right_gen(&mut self, node: &Expression, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        if let Some(val) = self.eval_const(node) {
+            code.push(format!("MOV R1 {}", val));
+            return Ok(());
+        }
         match node {
             Expression::Constant(c) => {
                 match &c._type{
@@ -163,44 +592,85 @@ impl Compiler {
                         code.push(format!("MOV R1 {}", const_val));
                     },
                     Type::Char => {
-                        // pasre char value & return ascii value
-                        let char_re = Regex::new(r"'(.+)'").unwrap();
-                        let c = &char_re.captures(&c.val).unwrap()[1];
-                        let chars = &c.chars().collect::<Vec<char>>(); 
-                        let val = match chars.len() {
-                            1 =>  {
-                                (chars[0] as u8)
-                            },
-                            2 => { // special chars
-                                assert_eq!(chars[0], '\\');
-                                match &chars[1] {
-                                    'n' => 10,
-                                    't' => 9,
-                                    _ => panic!("invalid special char"),
-                                }
-                            },
-                            _ => panic!(),
-                        };
-                        code.push(format!("MOV R1 {}", val));
+                        code.push(format!("MOV R1 {}", char_constant_value(&c.val)));
+                    },
+                    Type::Float => {
+                        code.push(format!("MOV R1 {}", float_constant_value(&c.val)));
+                    },
+                    Type::Long => {
+                        let val: i64 = c.val.parse().expect("invalid long constant");
+                        code.push(format!("MOV R1 {}", val as i32));
+                        code.push(format!("MOV R2 {}", (val >> 32) as i32));
                     },
                     Type::_String => {
-                        // regex to remove string's quotes
-                        println!("unwrapping string from: {}", &c.val);
-                        let str_re = Regex::new(r#""(.+)""#).unwrap();
-                        let s = &str_re.captures(&c.val).unwrap()[1];
-                        let string_label = self.maybe_add_string_data(&s.to_string(), code);
+                        let s = string_constant_value(&c.val);
+                        let string_label = self.maybe_add_string_data(&s, code);
                         code.push(format!("LEA R1 {}", string_label));
+                        self.gen_word_to_addr(&Register::R1, code);
                     }
                     _ => panic!("Invalid type for constant")
                 };
             }
+            Expression::BinaryOp(op) if op.op_type == BinaryopType::LogicalAnd || op.op_type == BinaryopType::LogicalOr => {
+                // the right operand is only evaluated if the left one doesn't determine the result
+                let end_label = format!("LOGICAL_{}_END", self.get_tmp_label());
+                self.inc_tmp_label();
+                self.gen_cond(&op.left, scope, code)?;
+                let short_circuit = if op.op_type == BinaryopType::LogicalAnd {"FJMP"} else {"TJMP"};
+                code.push(format!("{} {}", short_circuit, end_label));
+                self.gen_cond(&op.right, scope, code)?;
+                code.push(format!("{}:", end_label));
+                code.push("MOV R1 ZR".to_string());
+            },
+            Expression::BinaryOp(op) if self.is_float(&self.get_expr_type(&op.left, scope)) || self.is_float(&self.get_expr_type(&op.right, scope)) => {
+                // the int operand is converted to a float
+                let float_type = Some(Type::Float);
+                self.right_gen(&op.left, scope, code)?;
+                self.gen_convert(&self.get_expr_type(&op.left, scope), &float_type, &Register::R1, code);
+                let saved = self.gen_save_reg(&Register::R1, code);
+                self.right_gen(&op.right, scope, code)?;
+                self.gen_convert(&self.get_expr_type(&op.right, scope), &float_type, &Register::R1, code);
+                self.gen_restore_reg(saved, &Register::R2, code);
+                self.gen_float_op(&op.op_type, code);
+            },
+            Expression::BinaryOp(op) if self.is_long_op(op, scope) => {
+                // the left operand is kept on the stack, both operands are converted to longs
+                let long_type = Some(Type::Long);
+                self.right_gen(&op.left, scope, code)?;
+                self.gen_convert(&self.get_expr_type(&op.left, scope), &long_type, &Register::R1, code);
+                code.push("PUSH R2".to_string());
+                code.push("PUSH R1".to_string());
+                self.right_gen(&op.right, scope, code)?;
+                self.gen_convert(&self.get_expr_type(&op.right, scope), &long_type, &Register::R1, code);
+                self.gen_long_op(&op.op_type, code);
+            },
             Expression::BinaryOp(op) => {
-                self.right_gen(&op.left, &scope, code);
-                code.push("PUSH R1".to_string()); // save left result on stack
-                self.right_gen(&op.right, &scope, code);
-                code.push("POP R2".to_string());
+                self.right_gen(&op.left, &scope, code)?;
+                let saved = self.gen_save_reg(&Register::R1, code); // save left result
+                self.right_gen(&op.right, &scope, code)?;
+                self.gen_restore_reg(saved, &Register::R2, code);
+                // now R1 holds the right operand, R2 holds the left one
+                let ptr_sizes = match op.op_type {
+                    BinaryopType::ADD | BinaryopType::SUB => (
+                        self.get_expr_type(&op.left, scope).and_then(|t| self.get_pointee_size(&t)),
+                        self.get_expr_type(&op.right, scope).and_then(|t| self.get_pointee_size(&t)),
+                    ),
+                    _ => (None, None),
+                };
+                // pointer +- n moves n items, so n is scaled by the size of the pointed type
+                match ptr_sizes {
+                    (Some(size), None) if size != 1 => code.push(format!("MUL R1 R1 {}", size)),
+                    (None, Some(size)) if size != 1 => code.push(format!("MUL R2 R2 {}", size)),
+                    _ => {},
+                }
                 if let Some(opname) = op.op_type.to_op() {
                     code.push(format!("{} R1 R2 R1", opname));
+                    // the difference between pointers is in items
+                    if let (Some(size), Some(_)) = ptr_sizes {
+                        if size != 1 {
+                            code.push(format!("DIV R1 R1 {}", size));
+                        }
+                    }
                 } else {
                     // deal with blooean ops
                     match op.op_type {
@@ -214,20 +684,6 @@ impl Compiler {
                             code.push("MOV R1 ZR".to_string());
                         }
 
-                        BinaryopType::LogicalAnd => {
-                            code.push("TSTN R1 0".to_string());
-                            code.push("MOV R1 ZR".to_string());
-                            code.push("TSTN R2 0".to_string());
-                            code.push("AND R1 R1 ZR".to_string());
-                        }
-
-                        BinaryopType::LogicalOr => {
-                            code.push("TSTN R1 0".to_string());
-                            code.push("MOV R1 ZR".to_string());
-                            code.push("TSTN R2 0".to_string());
-                            code.push("OR R1 R1 ZR".to_string());
-                        }
-
                         BinaryopType::LT => {
                             code.push("TSTL R2 R1".to_string());
                             code.push("MOV R1 ZR".to_string());
@@ -257,32 +713,61 @@ impl Compiler {
             }
             Expression::UnaryOp(op) => {
                 match &op.op_type {
+                    UnaryopType::NEG if self.is_float(&self.get_expr_type(&op.expr, scope)) => {
+                        self.right_gen(&op.expr, scope, code)?;
+                        code.push(format!("FMUL R1 R1 {}", float_constant_value("-1")));
+                    }
+                    UnaryopType::NEG if self.is_long(&self.get_expr_type(&op.expr, scope)) => {
+                        // -x is ~x + 1
+                        self.right_gen(&op.expr, scope, code)?;
+                        code.push("NOT R1".to_string());
+                        code.push("NOT R2".to_string());
+                        code.push("MOV ZR 1".to_string());
+                        code.push("ADC R1 R1 0".to_string());
+                        code.push("ADC R2 R2 0".to_string());
+                    }
                     UnaryopType::NEG => {
-                        self.right_gen(&op.expr, &scope, code);
+                        self.right_gen(&op.expr, &scope, code)?;
                         code.push("NEG R1".to_string());
                     }
+                    UnaryopType::BNOT => {
+                        self.right_gen(&op.expr, scope, code)?;
+                        code.push("NOT R1".to_string());
+                        if self.is_long(&self.get_expr_type(&op.expr, scope)) {
+                            code.push("NOT R2".to_string());
+                        }
+                    }
+                    UnaryopType::PPX | UnaryopType::MMX | UnaryopType::XPP | UnaryopType::XMM if self.is_long(&self.get_expr_type(&op.expr, scope)) => {
+                        let is_inc = matches!(op.op_type, UnaryopType::PPX | UnaryopType::XPP);
+                        let (opname, undo_opname) = if is_inc {("ADC", "SBC")} else {("SBC", "ADC")};
+                        self.left_gen(&op.expr, scope, code)?;
+                        code.push("PUSH R1".to_string());
+                        self.gen_load(&Some(Type::Long), &Register::R1, &Register::R1, code);
+                        code.push("MOV ZR 0".to_string());
+                        code.push(format!("{} R1 R1 1", opname));
+                        code.push(format!("{} R2 R2 0", opname));
+                        self.gen_store_long(code);
+                        code.push("POP ZR".to_string());
+                        if matches!(op.op_type, UnaryopType::XPP | UnaryopType::XMM) {
+                            // the value before the change
+                            code.push("MOV ZR 0".to_string());
+                            code.push(format!("{} R1 R1 1", undo_opname));
+                            code.push(format!("{} R2 R2 0", undo_opname));
+                        }
+                    }
                     UnaryopType::NOT => {
-                        self.right_gen(&op.expr, &scope, code);
-                        code.push("TSTE R1 0".to_string());
+                        self.gen_cond(&op.expr, scope, code)?;
+                        code.push("TSTE ZR 0".to_string());
                         code.push("MOV R1 ZR".to_string());
                     }
                     UnaryopType::PPX | UnaryopType::MMX | UnaryopType::XPP | UnaryopType::XMM => {
-                        self.left_gen(&op.expr, &scope, code);
-                        let var_name = &op.id.as_ref().expect("op must be on a variable").name;
-                        let var = self.find_variable(var_name, scope).unwrap();
-                        let delta = match &var.var_type{
-                            VariableType::Regular {_type: t} => {
-                                if let Type::Ptr(ref pointed_t) = t{
-                                    self.get_type_size(pointed_t)
-                                }else{
-                                    1
-                                }
-                            },
-                            VariableType::Array {..} => 1,
-                        };
+                        self.left_gen(&op.expr, &scope, code)?;
+                        let _type = self.get_expr_type(&op.expr, scope);
+                        // a pointer moves by the size of the pointed type, e.g p++ on a struct*
+                        let delta = _type.as_ref().and_then(|t| self.get_pointee_size(t)).unwrap_or(1);
                         match &op.op_type{
                             UnaryopType::PPX | UnaryopType::MMX => {
-                                code.push("LOAD R2 R1".to_string());
+                                self.gen_load(&_type, &Register::R2, &Register::R1, code);
                                 code.push(format!(
                                     "{} R2 R2 {}",
                                     if op.op_type == UnaryopType::PPX {
@@ -292,11 +777,11 @@ impl Compiler {
                                     },
                                     delta,
                                 ));
-                                code.push("STR R1 R2".to_string());
+                                self.gen_store(&_type, &Register::R1, &Register::R2, code);
                                 code.push("MOV R1 R2".to_string());
                             },
                             UnaryopType::XPP | UnaryopType::XMM => {
-                                code.push("LOAD R2 R1".to_string());
+                                self.gen_load(&_type, &Register::R2, &Register::R1, code);
                                 code.push("PUSH R2".to_string());
                                 code.push(format!(
                                     "{} R2 R2 {}",
@@ -307,122 +792,269 @@ impl Compiler {
                                     },
                                     delta,
                                 ));
-                                code.push("STR R1 R2".to_string());
+                                self.gen_store(&_type, &Register::R1, &Register::R2, code);
                                 code.push("POP R1".to_string());
                             },
                             _ => panic!() // impossible execution path..
                         }
                     }
                     UnaryopType::REF => {
-                        self.left_gen(&op.expr, scope, code);
+                        self.left_gen(&op.expr, scope, code)?;
                     },
                     UnaryopType::DEREF => {
-                        self.right_gen(&op.expr, scope, code);
-                        code.push("LOAD R1 R1".to_string());
+                        self.right_gen(&op.expr, scope, code)?;
+                        self.gen_load(&self.get_expr_type(node, scope), &Register::R1, &Register::R1, code);
                     },
                     UnaryopType::SIZEOF => {
-                        if let Expression::TypeName(t) = &*op.expr {
-                            let size = self.get_type_size(&t._type);
-                            code.push(format!("MOV R1 {}", size));
-                        } else{
-                            panic!("expression inside sizeof() must be a type");
-                        }
+                        let size = match &*op.expr {
+                            Expression::TypeName(t) => self.get_type_size(&t._type),
+                            expr => self.get_expr_size(expr, scope)?,
+                        };
+                        code.push(format!("MOV R1 {}", size));
                     }
                 }
             }
             Expression::Assignment(ass) => {
-                self.gen_assignment_code(ass, &scope, code);
+                self.gen_assignment_code(ass, &scope, code)?;
             }
+            Expression::TernaryOp(top) if self.eval_const(&top.cond).is_some() => {
+                let taken = if self.eval_const(&top.cond).unwrap() != 0 {&top.iftrue} else {&top.iffalse};
+                self.right_gen(taken, scope, code)?;
+                self.gen_convert(&self.get_expr_type(taken, scope), &self.get_expr_type(node, scope), &Register::R1, code);
+            },
             Expression::TernaryOp(top) => {
                 let neg_label = format!("TERNARY_{}_NO", self.get_tmp_label());
                 let ternary_end_label = format!("TERNARY_{}_YES", self.get_tmp_label());
                 self.inc_tmp_label();
-                self.right_gen(&top.cond, &scope, code);
-                code.push("TSTN R1 0".to_string());
+                self.gen_cond(&top.cond, scope, code)?;
                 code.push(format!("FJMP {}", neg_label));
-                self.right_gen(&*top.iftrue, &scope, code);
+                let _type = self.get_expr_type(node, scope);
+                self.right_gen(&*top.iftrue, &scope, code)?;
+                self.gen_convert(&self.get_expr_type(&top.iftrue, scope), &_type, &Register::R1, code);
                 code.push(format!("JUMP {}", ternary_end_label));
                 code.push(format!("{}:", neg_label));
-                self.right_gen(&*top.iffalse, &scope, code);
+                self.right_gen(&*top.iffalse, &scope, code)?;
+                self.gen_convert(&self.get_expr_type(&top.iffalse, scope), &_type, &Register::R1, code);
                 code.push(format!("{}:", ternary_end_label));
             },
-            Expression::FuncCall(func_call) => {
-                let func_data = self.get_func_data(&func_call.name).expect(&format!("FuncCall to unknown function: {}", &func_call.name));
-                let rettype = func_data.decl_data.return_type.clone();
-                // push args
-                for arg in func_call.args.iter().rev(){
-                    self.right_gen(&*arg, scope, code);
-                    code.push("PUSH R1".to_string());
+            Expression::FuncCall(func_call) if func_call.name == SYSCALL_BUILTIN => {
+                // __syscall(num, arg): num goes to R1, arg to R2, result is returned in R1
+                if func_call.args.is_empty() || func_call.args.len() > 2 {
+                    return Err(self.error(ErrorKind::InvalidSyscall));
+                }
+                match func_call.args.get(1){
+                    Some(arg) => self.right_gen(arg, scope, code)?,
+                    None => code.push("MOV R1 0".to_string()),
+                }
+                let saved = self.gen_save_reg(&Register::R1, code);
+                self.right_gen(&func_call.args[0], scope, code)?;
+                self.gen_restore_reg(saved, &Register::R2, code);
+                code.push("SYSCALL".to_string());
+            },
+            Expression::FuncCall(func_call) if func_call.name == EXIT_BUILTIN || func_call.name == ABORT_BUILTIN => {
+                let expected = if func_call.name == EXIT_BUILTIN { 1 } else { 0 };
+                if func_call.args.len() != expected {
+                    return Err(self.error(ErrorKind::WrongArgsCount { func: func_call.name.clone(), expected, found: func_call.args.len() }));
                 }
-                // push space for func retval
-                for _ in 0..self.get_type_size(&rettype){
-                    code.push("PUSH ZR".to_string());
+                match func_call.args.first() {
+                    Some(status) => {
+                        self.right_gen(status, scope, code)?;
+                        self.gen_convert(&self.get_expr_type(status, scope), &Some(Type::Int), &Register::R1, code);
+                        code.push("MOV R2 R1".to_string());
+                    },
+                    None => code.push(format!("MOV R2 {}", ABORT_EXIT_CODE)),
+                }
+                self.gen_exit(code);
+            },
+            Expression::FuncCall(func_call) if func_call.name == TEST_AND_SET_BUILTIN => {
+                if func_call.args.len() != 1 {
+                    return Err(self.error(ErrorKind::WrongArgsCount { func: TEST_AND_SET_BUILTIN.to_string(), expected: 1, found: func_call.args.len() }));
                 }
-                code.push(format!("CALL {}", func_call.name));
-                if self.get_type_size(&rettype) > 0{
-                    // pop retval to R1
+                self.right_gen(&func_call.args[0], scope, code)?;
+                if self.options.byte_addressing {
+                    code.push(format!("DIV R1 R1 {}", BYTES_PER_WORD));
+                }
+                code.push("TSET R1 R1".to_string());
+            },
+            Expression::FuncCall(func_call) if func_call.name == ALLOCA_BUILTIN => {
+                // alloca(size): allocates size bytes in the caller's frame by moving SP, they're freed when it returns
+                // the allocated memory is below the values that were pushed so far, so it can't be called in a call's args
+                if func_call.args.len() != 1 {
+                    return Err(self.error(ErrorKind::WrongArgsCount { func: ALLOCA_BUILTIN.to_string(), expected: 1, found: func_call.args.len() }));
+                }
+                self.right_gen(&func_call.args[0], scope, code)?;
+                if self.options.byte_addressing {
+                    // rounded up to whole words
+                    code.push(format!("ADD R1 R1 {}", BYTES_PER_WORD - 1));
+                    code.push(format!("DIV R1 R1 {}", BYTES_PER_WORD));
+                }
+                let alloca_label = format!("ALLOCA_{}", self.get_tmp_label());
+                self.inc_tmp_label();
+                if self.options.stack_check {
+                    code.push("SUB R2 SP R1".to_string());
+                    self.gen_stack_check(&Register::R2, &format!("{}_STACK_OK", alloca_label), code);
+                }
+                // the words are pushed like the local variables are, so they're initialized
+                code.push(format!("{}:", alloca_label));
+                code.push("TSTG R1 0".to_string());
+                code.push(format!("FJMP {}_END", alloca_label));
+                code.push("PUSH ZR".to_string());
+                code.push("SUB R1 R1 1".to_string());
+                code.push(format!("JUMP {}", alloca_label));
+                code.push(format!("{}_END:", alloca_label));
+                let func_name = self.get_scope_data(scope).unwrap().parent_func.clone();
+                self.func_to_data.get_mut(&func_name).unwrap().body_data.as_mut().unwrap().uses_alloca = true;
+                // the allocated memory starts above the top of the stack
+                code.push("ADD R1 SP 1".to_string());
+                self.gen_word_to_addr(&Register::R1, code);
+            },
+            Expression::FuncCall(func_call) if self.inline_call(func_call, scope).is_some() => {
+                let inlined = self.inline_call(func_call, scope).unwrap();
+                self.right_gen(&inlined, scope, code)?;
+            },
+            Expression::FuncCall(func_call) => {
+                let (ret_size, args_size) = self.gen_func_call(func_call, scope, code)?;
+                let mut pop_size = ret_size + args_size;
+                if ret_size > 0{
+                    // pop (first word of) retval to R1
                     code.push("POP R1".to_string());
+                    pop_size -= 1;
+                }
+                if self.is_long(&self.get_expr_type(node, scope)) {
+                    code.push("POP R2".to_string());
+                    pop_size -= 1;
                 }
-                // pop args
-                for arg in func_call.args.iter().rev(){
+                // pop the rest of the retval & args
+                for _ in 0..pop_size{
                     code.push("POP ZR".to_string());
                 }
             },
             Expression::NameRef(name) => {
-                self.codegen_name(name, scope, code);
+                self.codegen_name(name, scope, code)?;
                 let mut deref = true;
 
-                // we do not want to deref rvalue in expressions like "ptr = arr"
-                if let NameRef::ID(_) = name{
-                    if let VariableType::Array{..} = self.get_type_of_name(name, scope){
+                // we do not want to deref rvalue in expressions like "ptr = arr" or "func_ptr = func"
+                if let NameRef::ID(id) = name{
+                    if self.is_func_name(&id.name, scope){
+                        deref = false;
+                    }
+                    else if let VariableType::Array{..} = self.get_type_of_name(name, scope)?{
                         deref = false;
                     }
                 }
                 if deref{
-                    code.push("LOAD R1 R1".to_string());
+                    self.gen_load(&self.get_expr_type(node, scope), &Register::R1, &Register::R1, code);
                 }
             },
             Expression::TypeName(_) => {
-                panic!("TypeName must be inside a sizeof() call");
+                // a type name is only valid inside a sizeof() call
+                return Err(self.error(ErrorKind::InvalidSizeof));
             },
             Expression::Cast(cast) => {
-                // NOTE: in the current implementation casting has no actual effect
-                self.right_gen(&*cast.expr, scope, code);
+                // casts between pointer types don't change the value, only the scaling of the pointer arithmetic on it,
+                // since the type of the cast expression is the pointer type it's cast to
+                self.right_gen(&*cast.expr, scope, code)?;
+                let from = self.get_expr_type(&cast.expr, scope);
+                let to = Some(self.resolve_type(&cast._type));
+                self.gen_convert(&from, &to, &Register::R1, code);
+                if to == Some(Type::Char) && from.map(|t| self.resolve_type(&t)) != Some(Type::Char) {
+                    // truncates to the low byte & sign extends it, like LOADB does
+                    code.push(format!("SHL R1 R1 {}", CHAR_SHIFT));
+                    code.push(format!("SHR R1 R1 {}", CHAR_SHIFT));
+                }
+            },
+            Expression::Comma(exprs) => {
+                // the value of the last expression is left in R1
+                for expr in exprs.iter() {
+                    self.right_gen(expr, scope, code)?;
+                }
+            }
+            Expression::LabelAddr(name) => {
+                // like a function's address, the address of the label's instruction
+                let label = self.goto_label_in_scope(name, scope)?;
+                code.push(format!("LEA R1 {}", label));
             }
         }
+        Ok(())
     }
 
     /// generates code for name reference
     /// returns type of the references name
-    fn codegen_name(&mut self, node: &NameRef, scope: &String, code: &mut Vec<String>) {
+    fn codegen_name(&mut self, node: &NameRef, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
         match node {
             NameRef::ID(id) => {
                 let var_name = &id.name;
-                self.codegen_load_addr_of_var(&var_name, &scope, code);
+                if self.is_func_name(var_name, scope){
+                    // a function's address is the address of its label
+                    code.push(format!("LEA R1 {}", var_name));
+                } else {
+                    self.codegen_load_addr_of_var(&var_name, &scope, code)?;
+                }
             }
             NameRef::ArrayRef(array_ref) => {
-                self.codegen_load_addr_of_array_indexing(array_ref, scope, code);
+                self.codegen_load_addr_of_array_indexing(array_ref, scope, code)?;
             },
             NameRef::StructRef(struct_ref) => {
-                self.codegen_load_addr_of_struct_ref(struct_ref, scope, code);
+                self.codegen_load_addr_of_struct_ref(struct_ref, scope, code)?;
             },
         }
+        Ok(())
+    }
+
+    /// a comment with the location & text of the source line, e.g "; foo.c:12: x = y + 1;"
+    /// None if the source file can't be read
+    fn source_comment(&mut self, loc: &SourceLoc) -> Option<String> {
+        let lines = self.source_lines.entry(loc.file.clone()).or_insert_with(|| {
+            fs::read_to_string(&loc.file)
+                .map(|src| src.split('\n').map(|line| line.to_string()).collect())
+                .unwrap_or_default()
+        });
+        let line = lines.get(loc.line as usize - 1)?;
+        Some(format!("; {}:{}: {}", loc.file, loc.line, line.trim()))
+    }
+
+    /// returns the expression that replaces the call if the called function can be inlined
+    fn inline_call(&self, func_call: &FuncCall, scope: &String) -> Option<Expression> {
+        if !self.is_func_name(&func_call.name, scope) {
+            return None;
+        }
+        // the params are ints, an arg of another type than int or char would have to be converted to int first,
+        // e.g a float, or a pointer, whose arithmetic in the function's expression would be scaled
+        if !func_call.args.iter().all(|arg| {
+            let arg_type = self.get_expr_type(arg, scope).map(|t| self.resolve_type(&t));
+            matches!(arg_type, Some(Type::Int | Type::Char))
+        }) {
+            return None;
+        }
+        self.inline_funcs.get(&func_call.name)?.expand(&func_call.args)
+    }
+
+    /// true if the binary op is done on longs, i.e an operand is a long & neither is a pointer
+    fn is_long_op(&self, op: &BinaryOp, scope: &String) -> bool {
+        let types = [self.get_expr_type(&op.left, scope), self.get_expr_type(&op.right, scope)];
+        let is_ptr = |t: &Option<Type>| matches!(t.as_ref().map(|t| self.resolve_type(t)), Some(Type::Ptr(_)));
+        types.iter().any(|t| self.is_long(t)) && !types.iter().any(is_ptr)
     }
 
-    fn get_type_of_name(&self, node: &NameRef, scope: &String) -> &VariableType {
+    /// true if name refers to a function rather than to a variable
+    fn is_func_name(&self, name: &String, scope: &String) -> bool {
+        self.find_variable(name, scope).is_none() && self.get_func_data(name).is_some()
+    }
+
+    fn get_type_of_name(&self, node: &NameRef, scope: &String) -> Result<&VariableType, CompileError> {
         match node {
             NameRef::ID(id) => {
                 let var_name = &id.name;
-                println!("get type of name found var_name: {}", var_name);
-                let var_data = self.find_variable(var_name, scope).unwrap();
-                println!("var data: {:?}", var_data);
-                &var_data.var_type
+                let var_data = self.find_variable(var_name, scope)
+                    .ok_or_else(|| self.error(ErrorKind::UndeclaredVariable { name: var_name.clone() }))?;
+                self.trace(format_args!("the type of {} is {:?}", var_name, var_data.var_type));
+                Ok(&var_data.var_type)
             }
             NameRef::ArrayRef(array_ref) => {
                 self.get_type_of_name(&array_ref.name, scope)
             },
             NameRef::StructRef(struct_ref) => {
-                let mut struct_vartype = self.get_type_of_name(&struct_ref.name, scope);
+                let mut struct_vartype = self.get_type_of_name(&struct_ref.name, scope)?;
                 if let VariableType::Array {_type: t, ..} = struct_vartype {
                     struct_vartype = t;
                 }
@@ -434,31 +1066,41 @@ impl Compiler {
                             struct_type = &*pointed_t;
                         }
                     }
-                    if let Type::Struct(struct_name) = struct_type {
-                        let struct_name = struct_name.clone(); // to please the borrow checker
-                        let struct_data = self.struct_to_data.get(&struct_name).expect("struct doesn't exist");
-                        let field_var = struct_data.items.get(&struct_ref.field).expect(&format!("field {} not found in struct {}", &struct_ref.field, &struct_data.name));
-                        &field_var.var_type
-                    } else {panic!()}
+                    Ok(&self.get_struct_field(struct_ref, struct_type)?.var_type)
                 } else{
-                    panic!();
+                    Err(self.error(ErrorKind::NestedArray { _type: struct_vartype.type_name() }))
                 }
             },
         }
     }
 
+    /// returns the data of the field that struct_ref refers to in a struct of type struct_type
+    fn get_struct_field(&self, struct_ref: &StructRef, struct_type: &Type) -> Result<&VariableData, CompileError> {
+        if let Type::Struct(struct_name) = struct_type {
+            let struct_data = self.struct_to_data.get(struct_name)
+                .ok_or_else(|| self.error(ErrorKind::UnknownStruct { name: struct_name.clone() }))?;
+            struct_data.items.get(&struct_ref.field)
+                .ok_or_else(|| self.error(ErrorKind::NoSuchMember { _type: type_name(struct_type), field: struct_ref.field.clone() }))
+        } else {
+            let op = match struct_ref._type {
+                StructRefType::DOT => ".",
+                StructRefType::ARROW => "->",
+            };
+            Err(self.error(ErrorKind::InvalidOperand { op: op.to_string(), operand: type_name(struct_type) }))
+        }
+    }
+
     fn get_struct_data_from_type(&self, _t: &Type) -> Option<&StructData> {
-        if let Type::Struct(struct_name) = _t {
-            Some(self.struct_to_data.get(struct_name)?)
+        if let Type::Struct(struct_name) = self.resolve_type(_t) {
+            Some(self.struct_to_data.get(&struct_name)?)
         } else {
             None
         }
     }
 
-    fn codegen_load_addr_of_struct_ref(&mut self, struct_ref: &StructRef, scope: &String, code: &mut Vec<String>){
-        println!("codegen load addr of struct ref: {:?}", struct_ref);
-        self.codegen_name(&struct_ref.name, scope, code);
-        let mut struct_vartype = self.get_type_of_name(&struct_ref.name, scope);
+    fn codegen_load_addr_of_struct_ref(&mut self, struct_ref: &StructRef, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        self.codegen_name(&struct_ref.name, scope, code)?;
+        let mut struct_vartype = self.get_type_of_name(&struct_ref.name, scope)?;
         if let VariableType::Array {_type: t, ..} = struct_vartype {
             struct_vartype = t;
         }
@@ -467,33 +1109,29 @@ impl Compiler {
             if let StructRefType::ARROW = struct_ref._type {
                 if let Type::Ptr(pointed_t) = t{
                     struct_type = &*pointed_t;
-                    code.push("LOAD R1 R1".to_string());
+                    self.gen_load(&None, &Register::R1, &Register::R1, code);
                 }
             }
-            if let Type::Struct(struct_name) = struct_type {
-                let struct_data = self.struct_to_data.get(struct_name).expect("struct doesn't exist");
-                let field_var = struct_data.items.get(&struct_ref.field).expect(&format!("field {} not found in struct {}", &struct_ref.field, &struct_data.name));
-                code.push(format!("ADD R1 R1 {}", field_var.offset));
-            } else {panic!()}
+            let field_var = self.get_struct_field(struct_ref, struct_type)?;
+            code.push(format!("ADD R1 R1 {}", field_var.offset));
+            Ok(())
         } else{
-            panic!();
+            Err(self.error(ErrorKind::NestedArray { _type: struct_vartype.type_name() }))
         }
     }
 
-    fn get_array_item_size(&self, arr_type: &VariableType) -> u32{
-        if let VariableType::Regular {_type} = arr_type {
-            self.get_type_size(_type)
+    fn get_array_item_size(&self, item_type: &VariableType) -> Result<u32, CompileError> {
+        if let VariableType::Regular {_type} = item_type {
+            Ok(self.get_type_size(_type))
         } else{
-            panic!("arrays cannot hold arrays as items")
+            Err(self.error(ErrorKind::NestedArray { _type: item_type.type_name() }))
         }
     }
 
     /// generates code for array indexing
-    fn codegen_load_addr_of_array_indexing(&mut self, array_ref: &ArrayRef, scope: &String, code: &mut Vec<String>){
-        self.codegen_name(&array_ref.name, scope, code);
-        println!("getting type of name {:?}", &array_ref.name);
-        let array_type = self.get_type_of_name(&array_ref.name, scope);
-        println!("type is: {:?}", &array_type);
+    fn codegen_load_addr_of_array_indexing(&mut self, array_ref: &ArrayRef, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        self.codegen_name(&array_ref.name, scope, code)?;
+        let array_type = self.get_type_of_name(&array_ref.name, scope)?;
         // let arr_var = self.find_variable(&*array_ref.name, scope).expect("array not found");
         match &array_type {
             VariableType::Array{_type, dimentions} => {
@@ -503,15 +1141,15 @@ impl Compiler {
                 // let mut offset = 0;                        
                 code.push("MOV R2 R1".to_string()); // R2 holds current item addr
                 let mut cur_dimentions_product = 1;
-                let item_size = self.get_array_item_size(item_type);
+                let item_size = self.get_array_item_size(&item_type)?;
 
                 // hiding from the borrow checker
                 let indices = array_ref.indices.clone();
                 assert_eq!(indices.len(), dimentions.len());
                 for (idx_expr, dimsize) in indices.iter().zip(dimentions).rev(){
-                    code.push("PUSH R2".to_string()); // save R2
-                    self.right_gen(idx_expr, scope, code);
-                    code.push("POP R2".to_string());
+                    let saved = self.gen_save_reg(&Register::R2, code);
+                    self.right_gen(idx_expr, scope, code)?;
+                    self.gen_restore_reg(saved, &Register::R2, code);
                     code.push(format!("MUL R1 R1 {}", cur_dimentions_product));
                     code.push(format!("MUL R1 R1 {}", item_size));
                     code.push("ADD R2 R2 R1".to_string());
@@ -519,86 +1157,501 @@ impl Compiler {
                 }
                 code.push("MOV R1 R2".to_string());
             },
-            _ => panic!(format!("not an array type")),
+            VariableType::Regular{_type} if array_ref.indices.len() == 1 && self.get_pointee_size(_type).is_some() => {
+                // indexing a pointer is like *(p + i), the index is scaled by the size of the pointed type
+                let ptr_type = Some(_type.clone());
+                let item_size = self.get_pointee_size(_type).unwrap();
+                self.gen_load(&ptr_type, &Register::R1, &Register::R1, code);
+                let saved = self.gen_save_reg(&Register::R1, code);
+                self.right_gen(&array_ref.indices[0], scope, code)?;
+                self.gen_restore_reg(saved, &Register::R2, code);
+                if item_size != 1 {
+                    code.push(format!("MUL R1 R1 {}", item_size));
+                }
+                code.push("ADD R1 R2 R1".to_string());
+            },
+            _ => return Err(self.error(ErrorKind::NotSubscriptable { found: array_type.type_name() })),
+        }
+        Ok(())
+    }
+
+    /// pushes the args & space for the return value, then calls the function
+    /// returns the sizes of the return value and of the args, which the caller must pop
+    fn gen_func_call(&mut self, func_call: &FuncCall, scope: &String, code: &mut Vec<String>) -> Result<(u32, u32), CompileError> {
+        // either a function, or a variable holding a function pointer
+        let func_ptr = match self.find_variable(&func_call.name, scope).map(|var| &var.var_type) {
+            Some(VariableType::Regular{_type: Type::FuncPtr{ret_type, args}}) => Some(((**ret_type).clone(), args.iter().cloned().map(Some).collect::<Vec<Option<Type>>>())),
+            Some(_) => return Err(self.error(ErrorKind::NotCallable { name: func_call.name.clone() })),
+            None => None,
+        };
+        let (rettype, params_types) = match func_ptr {
+            Some(ref func_ptr_type) => func_ptr_type.clone(),
+            None => {
+                let func_data = self.get_func_data(&func_call.name)
+                    .ok_or_else(|| self.error(ErrorKind::UndeclaredFunction { name: func_call.name.clone() }))?;
+                (func_data.decl_data.return_type.clone(), params_types(func_data))
+            },
+        };
+        let args_size = self.gen_push_args(func_call, &params_types, scope, code)?;
+        // push space for func retval
+        let ret_size = self.words(self.get_type_size(&rettype));
+        for _ in 0..ret_size{
+            code.push("PUSH ZR".to_string());
+        }
+        if func_ptr.is_some() {
+            // load the function's address
+            self.codegen_load_addr_of_var(&func_call.name, scope, code)?;
+            self.gen_load(&None, &Register::R1, &Register::R1, code);
+            code.push("CALLR R1".to_string());
+        } else {
+            code.push(format!("CALL {}", func_call.name));
+        }
+        Ok((ret_size, args_size))
+    }
+
+    /// pushes the args of the call in reverse order, returns their size in words
+    fn gen_push_args(&mut self, func_call: &FuncCall, params_types: &[Option<Type>], scope: &String, code: &mut Vec<String>) -> Result<u32, CompileError> {
+        let mut args_size = 0;
+        for (arg_i, arg) in func_call.args.iter().enumerate().rev(){
+            let param_type = params_types.get(arg_i).cloned().flatten();
+            args_size += self.arg_size(arg, &param_type, scope);
+            match self.get_struct_size(arg, scope) {
+                Some(size) => {
+                    // structs are passed by value, push them from the last word so the first word is at the lowest address
+                    self.left_gen(arg, scope, code)?;
+                    if self.options.byte_addressing {
+                        code.push(format!("DIV R1 R1 {}", BYTES_PER_WORD));
+                    }
+                    let size = self.words(size);
+                    code.push(format!("ADD R1 R1 {}", size - 1));
+                    for i in 0..size {
+                        if i > 0 {
+                            code.push("SUB R1 R1 1".to_string());
+                        }
+                        code.push("LOAD R2 R1".to_string());
+                        code.push("PUSH R2".to_string());
+                    }
+                },
+                None => {
+                    self.right_gen(&*arg, scope, code)?;
+                    // an arg that's passed to a float param is converted to a float, & vice versa
+                    let arg_type = self.get_expr_type(arg, scope);
+                    self.gen_convert(&arg_type, &param_type, &Register::R1, code);
+                    if self.arg_size(arg, &param_type, scope) == 2 {
+                        // the low word is at the lower address
+                        code.push("PUSH R2".to_string());
+                    }
+                    code.push("PUSH R1".to_string());
+                },
+            }
+        }
+        Ok(args_size)
+    }
+
+    /// the number of words an arg takes on the stack, param_type is None for variadic & array args
+    fn arg_size(&self, arg: &Expression, param_type: &Option<Type>, scope: &String) -> u32 {
+        match self.get_struct_size(arg, scope) {
+            Some(size) => self.words(size),
+            None if self.is_long(param_type) || (param_type.is_none() && self.is_long(&self.get_expr_type(arg, scope))) => 2,
+            None => 1,
+        }
+    }
+
+    /// true if "return func_call" can jump to the called function instead of calling it, see gen_tail_call
+    fn is_tail_call(&self, func_call: &FuncCall, scope: &String) -> bool {
+        if !self.options.tail_calls() || !self.is_func_name(&func_call.name, scope)
+            || BUILTINS.contains(&func_call.name.as_str())
+            || self.inline_call(func_call, scope).is_some() {
+            return false;
+        }
+        let func_data = self.get_func_data(&self.get_scope_data(scope).unwrap().parent_func).unwrap();
+        // the callee's frame replaces ours, so nothing may point into it, e.g g(&x)
+        if func_data.body_data.as_ref().unwrap().frame_escapes {
+            return false;
+        }
+        let callee_data = self.get_func_data(&func_call.name).unwrap();
+        let callee_params = params_types(callee_data);
+        let args_size: u32 = func_call.args.iter().enumerate()
+            .map(|(arg_i, arg)| self.arg_size(arg, &callee_params.get(arg_i).cloned().flatten(), scope))
+            .sum();
+        // the caller pops our ret val & args, so the callee's have to be at the same place
+        callee_data.decl_data.return_type == func_data.decl_data.return_type
+            && args_size <= func_data.body_data.as_ref().unwrap().args_size
+    }
+
+    /// "return f(x)" reuses the current stack frame: the args are copied over the current function's args,
+    /// and f is jumped to with the current ret_addr & prev_BP, so it returns straight to our caller
+    fn gen_tail_call(&mut self, func_call: &FuncCall, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        let func_name = self.get_scope_data(scope).unwrap().parent_func.clone();
+        let callee_params = params_types(self.get_func_data(&func_call.name).unwrap());
+        // the args are evaluated before any of them is overwritten, since they may use the current args
+        let args_size = self.gen_push_args(func_call, &callee_params, scope, code)?;
+        let ret_size = self.words(self.get_type_size(&self.get_func_data(&func_name).unwrap().decl_data.return_type));
+        for i in 0..args_size {
+            code.push(format!("ADD R1 SP {}", i + 1));
+            code.push("LOAD R1 R1".to_string());
+            code.push(format!("ADD R2 BP {}", 2 + ret_size + i));
+            code.push("STR R2 R1".to_string());
+        }
+        let tail_calls = &mut self.func_to_data.get_mut(&func_name).unwrap().body_data.as_mut().unwrap().tail_calls;
+        if !tail_calls.contains(&func_call.name) {
+            tail_calls.push(func_call.name.clone());
+        }
+        code.push(format!("JUMP _{}_TAIL_{}", func_name, func_call.name));
+        Ok(())
+    }
+
+    /// returns the type of the value of the expression, if it's known
+    fn get_expr_type(&self, expr: &Expression, scope: &String) -> Option<Type> {
+        match expr {
+            Expression::NameRef(name) => {
+                if let NameRef::ID(id) = name {
+                    if self.is_func_name(&id.name, scope) {
+                        return None;
+                    }
+                }
+                match (name, self.get_type_of_name(name, scope).ok()?) {
+                    (NameRef::ArrayRef(_), VariableType::Regular{_type}) => match self.resolve_type(_type) {
+                        Type::Ptr(pointed_t) => Some(*pointed_t),
+                        _ => None,
+                    },
+                    (_, VariableType::Regular{_type}) => Some(_type.clone()),
+                    (NameRef::ArrayRef(_), VariableType::Array{_type: item_type, ..}) => match &**item_type {
+                        VariableType::Regular{_type} => Some(_type.clone()),
+                        _ => None,
+                    },
+                    // an array is used as a pointer to its first item
+                    (_, VariableType::Array{_type: item_type, dimentions}) if dimentions.len() == 1 => match &**item_type {
+                        VariableType::Regular{_type} => Some(Type::Ptr(Box::new(_type.clone()))),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            },
+            Expression::UnaryOp(uop) if uop.op_type == UnaryopType::DEREF => {
+                match self.get_expr_type(&uop.expr, scope)? {
+                    Type::Ptr(pointed_t) => Some(*pointed_t),
+                    _ => None,
+                }
+            },
+            Expression::UnaryOp(uop) if uop.op_type == UnaryopType::REF => {
+                Some(Type::Ptr(Box::new(self.get_expr_type(&uop.expr, scope)?)))
+            },
+            Expression::UnaryOp(uop) => match uop.op_type {
+                UnaryopType::XPP | UnaryopType::PPX | UnaryopType::XMM | UnaryopType::MMX => self.get_expr_type(&uop.expr, scope),
+                UnaryopType::NEG if self.is_float(&self.get_expr_type(&uop.expr, scope)) => Some(Type::Float),
+                UnaryopType::NEG | UnaryopType::BNOT if self.is_long(&self.get_expr_type(&uop.expr, scope)) => Some(Type::Long),
+                _ => Some(Type::Int),
+            },
+            // an arithmetic op with a float operand is done on floats, comparisons are ints
+            Expression::BinaryOp(bop) if bop.op_type.to_op().is_some()
+                && (self.is_float(&self.get_expr_type(&bop.left, scope)) || self.is_float(&self.get_expr_type(&bop.right, scope))) => {
+                Some(Type::Float)
+            },
+            Expression::BinaryOp(bop) if bop.op_type.to_op().is_some() && self.is_long_op(bop, scope) => Some(Type::Long),
+            Expression::BinaryOp(bop) if bop.op_type == BinaryopType::ADD || bop.op_type == BinaryopType::SUB => {
+                let left = self.get_expr_type(&bop.left, scope);
+                let right = self.get_expr_type(&bop.right, scope);
+                match (left, right) {
+                    (Some(Type::Ptr(_)), Some(Type::Ptr(_))) => Some(Type::Int),
+                    (Some(t @ Type::Ptr(_)), _) => Some(t),
+                    (_, Some(t @ Type::Ptr(_))) => Some(t),
+                    _ => Some(Type::Int),
+                }
+            },
+            Expression::BinaryOp(_) => Some(Type::Int),
+            Expression::Constant(c) => match c._type {
+                Type::Int | Type::Char | Type::Float | Type::Long => Some(c._type.clone()),
+                _ => None,
+            },
+            Expression::FuncCall(func_call) => match self.find_variable(&func_call.name, scope) {
+                Some(VariableData{var_type: VariableType::Regular{_type: Type::FuncPtr{ret_type, ..}}, ..}) => Some((**ret_type).clone()),
+                Some(_) => None,
+                None => Some(self.get_func_data(&func_call.name)?.decl_data.return_type.clone()),
+            },
+            Expression::Cast(cast) => Some(self.resolve_type(&cast._type)),
+            Expression::TernaryOp(top) => {
+                let iftrue = self.get_expr_type(&top.iftrue, scope);
+                let iffalse = self.get_expr_type(&top.iffalse, scope);
+                if self.is_float(&iffalse) || (self.is_long(&iffalse) && !self.is_float(&iftrue)) {
+                    return iffalse;
+                }
+                iftrue
+            },
+            Expression::Assignment(ass) => self.get_expr_type(&ass.lvalue, scope),
+            Expression::Comma(exprs) => self.get_expr_type(exprs.last()?, scope),
+            Expression::LabelAddr(_) => Some(Type::Ptr(Box::new(Type::Void))),
+            _ => None,
+        }
+    }
+
+    /// returns the size of the value of the expression, for sizeof(expr)
+    /// the expression itself is not evaluated
+    fn get_expr_size(&self, expr: &Expression, scope: &String) -> Result<u32, CompileError> {
+        if let Expression::NameRef(name) = expr {
+            let indices = match name {
+                NameRef::ArrayRef(array_ref) => array_ref.indices.len(),
+                _ => 0,
+            };
+            match self.get_type_of_name(name, scope)? {
+                VariableType::Array{_type: item_type, dimentions} if indices < dimentions.len() => {
+                    // the size of the dimensions that are not indexed, e.g sizeof(arr[1]) of a 2d array is the size of a row
+                    if let VariableType::Regular{_type} = &**item_type {
+                        let rest: Vec<u32> = dimentions[indices..].to_vec();
+                        return Ok(self.get_array_size(_type, &rest));
+                    }
+                },
+                VariableType::Regular{_type} if indices > 0 => {
+                    if let Some(size) = self.get_pointee_size(_type) {
+                        return Ok(size);
+                    }
+                },
+                _ => {},
+            }
+        }
+        match self.get_expr_type(expr, scope) {
+            Some(_type) => Ok(self.get_type_size(&_type)),
+            None => Err(self.error(ErrorKind::InvalidSizeof)),
+        }
+    }
+
+    /// returns the size of the type a pointer points to, or None if the type isn't a pointer
+    fn get_pointee_size(&self, _type: &Type) -> Option<u32> {
+        match self.resolve_type(_type) {
+            // arithmetic on void* is done in words, like on char*
+            Type::Ptr(pointed_t) => Some(self.get_type_size(&pointed_t).max(1)),
+            _ => None,
+        }
+    }
+
+    /// returns the size of the struct if the expression evaluates to a struct
+    fn get_struct_size(&self, expr: &Expression, scope: &String) -> Option<u32> {
+        let _type = self.get_expr_type(expr, scope)?;
+        self.get_struct_data_from_type(&_type).map(|struct_data| struct_data.size)
+    }
+
+    /// copies a struct word by word from the address in R1 to the address in R2
+    /// at the end of the generated code, R1 holds the destination address
+    fn gen_struct_copy(&mut self, size: u32, code: &mut Vec<String>) {
+        if self.options.byte_addressing {
+            // structs are word aligned
+            code.push(format!("DIV R1 R1 {}", BYTES_PER_WORD));
+            code.push(format!("DIV R2 R2 {}", BYTES_PER_WORD));
+        }
+        let size = self.words(size);
+        for i in 0..size {
+            if i > 0 {
+                code.push("ADD R1 R1 1".to_string());
+                code.push("ADD R2 R2 1".to_string());
+            }
+            code.push("PUSH R1".to_string());
+            code.push("LOAD R1 R1".to_string());
+            code.push("STR R2 R1".to_string());
+            code.push("POP R1".to_string());
+        }
+        code.push(format!("SUB R1 R2 {}", size - 1));
+        self.gen_word_to_addr(&Register::R1, code);
+    }
+
+    /// generates a member-wise copy of the struct that rvalue evaluates to, into the address in R1
+    fn gen_struct_assignment(&mut self, rvalue: &Expression, size: u32, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        if let Expression::FuncCall(func_call) = rvalue {
+            // the returned struct is copied while it's still on the stack, above it are the args & the saved destination
+            code.push("PUSH R1".to_string());
+            let (ret_size, args_size) = self.gen_func_call(func_call, scope, code)?;
+            code.push("ADD R1 SP 1".to_string());
+            self.gen_word_to_addr(&Register::R1, code);
+            code.push(format!("ADD R2 SP {}", ret_size + args_size + 1));
+            code.push("LOAD R2 R2".to_string());
+            self.gen_struct_copy(size, code);
+            for _ in 0..(ret_size + args_size + 1){
+                code.push("POP ZR".to_string());
+            }
+            return Ok(());
         }
+        let saved = self.gen_save_reg(&Register::R1, code);
+        match rvalue {
+            // the struct is the last expression, e.g of va_arg(args, struct A)
+            Expression::Comma(exprs) => {
+                let (last, rest) = exprs.split_last().expect("empty comma expression");
+                for expr in rest.iter() {
+                    self.right_gen(expr, scope, code)?;
+                }
+                self.left_gen(last, scope, code)?;
+            },
+            _ => self.left_gen(rvalue, scope, code)?,
+        }
+        self.gen_restore_reg(saved, &Register::R2, code);
+        self.gen_struct_copy(size, code);
+        Ok(())
     }
 
     // generates code for assignment
-    // at the end of the generated code, value of assignment is in R1
-    fn gen_assignment_code(&mut self, ass: &Assignment, scope: &String, code: &mut Vec<String>) {
-        self.left_gen(&ass.lvalue, &scope, code);
-        code.push("PUSH R1".to_string());
-        self.right_gen(&ass.rvalue, &scope, code);
-        code.push("POP R2".to_string());
+    // at the end of the generated code, value of assignment is in R1 (& R2 for a long)
+    // (for struct assignment, R1 holds the address of the assigned struct)
+    fn gen_assignment_code(&mut self, ass: &Assignment, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        if let Some(size) = self.get_struct_size(&ass.lvalue, scope) {
+            if let Some(bop) = &ass.op.op {
+                let rvalue_type = self.get_expr_type(&ass.rvalue, scope);
+                return Err(self.error(ErrorKind::InvalidOperands {
+                    op: format!("{}=", op_name(bop)),
+                    left: type_name(&self.get_expr_type(&ass.lvalue, scope).unwrap()),
+                    right: rvalue_type.map(|t| type_name(&t)).unwrap_or_else(|| "?".to_string()),
+                }));
+            }
+            self.left_gen(&ass.lvalue, &scope, code)?;
+            return self.gen_struct_assignment(&ass.rvalue, size, scope, code);
+        }
+        let lvalue_type = self.get_expr_type(&ass.lvalue, scope);
+        let rvalue_type = self.get_expr_type(&ass.rvalue, scope);
+        if self.is_long(&lvalue_type) {
+            // the address is kept on the stack, for e.g += the old value is pushed above it
+            self.left_gen(&ass.lvalue, scope, code)?;
+            code.push("PUSH R1".to_string());
+            if ass.op.op.is_some() {
+                self.gen_load(&lvalue_type, &Register::R1, &Register::R1, code);
+                code.push("PUSH R2".to_string());
+                code.push("PUSH R1".to_string());
+            }
+            self.right_gen(&ass.rvalue, scope, code)?;
+            self.gen_convert(&rvalue_type, &lvalue_type, &Register::R1, code);
+            if let Some(bop) = &ass.op.op {
+                self.gen_long_op(bop, code);
+            }
+            self.gen_store_long(code);
+            code.push("POP ZR".to_string());
+            return Ok(());
+        }
+        self.left_gen(&ass.lvalue, &scope, code)?;
+        let saved = self.gen_save_reg(&Register::R1, code);
+        self.right_gen(&ass.rvalue, &scope, code)?;
+        self.gen_restore_reg(saved, &Register::R2, code);
         // now R1 holds rvalue, R2 holds lvalue
-        if let Some(bop) = &ass.op.op {
-            // if assignment is e.g +=, -=
-            code.push("PUSH R2".to_string());
-            code.push("LOAD R2 R2".to_string());
-            code.push(format!("{} R1 R2 R1", bop.to_op().unwrap()));
-            code.push("POP R2".to_string());
+        match &ass.op.op {
+            Some(bop) if self.is_float(&lvalue_type) || self.is_float(&rvalue_type) => {
+                // e.g i *= 1.5 is done on floats & the result is converted back to an int
+                let float_type = Some(Type::Float);
+                self.gen_convert(&rvalue_type, &float_type, &Register::R1, code);
+                code.push("PUSH R2".to_string());
+                self.gen_load(&lvalue_type, &Register::R2, &Register::R2, code);
+                self.gen_convert(&lvalue_type, &float_type, &Register::R2, code);
+                self.gen_float_op(bop, code);
+                self.gen_convert(&float_type, &lvalue_type, &Register::R1, code);
+                code.push("POP R2".to_string());
+            },
+            Some(bop) => {
+                // if assignment is e.g +=, -=
+                if *bop == BinaryopType::ADD || *bop == BinaryopType::SUB {
+                    // pointer += n moves n items
+                    match lvalue_type.as_ref().and_then(|t| self.get_pointee_size(t)) {
+                        Some(size) if size != 1 => code.push(format!("MUL R1 R1 {}", size)),
+                        _ => {},
+                    }
+                }
+                code.push("PUSH R2".to_string());
+                self.gen_load(&lvalue_type, &Register::R2, &Register::R2, code);
+                code.push(format!("{} R1 R2 R1", bop.to_op().unwrap()));
+                code.push("POP R2".to_string());
+            },
+            None => self.gen_convert(&rvalue_type, &lvalue_type, &Register::R1, code),
         }
-        code.push("STR R2 R1".to_string());
+        self.gen_store(&lvalue_type, &Register::R2, &Register::R1, code);
+        Ok(())
     }
 
 
-    fn codegen_load_addr_of_var(&mut self, var_name: &String, scope: &String, code: &mut Vec<String>) -> &VariableData{
-        let var_data = self.find_variable(var_name, scope).expect(&format!("Variable {} not found", var_name));
+    fn codegen_load_addr_of_var(&mut self, var_name: &String, scope: &String, code: &mut Vec<String>) -> Result<&VariableData, CompileError> {
+        let var_data = self.find_variable(var_name, scope)
+            .ok_or_else(|| self.error(ErrorKind::UndeclaredVariable { name: var_name.clone() }))?;
         let scope_data = self.get_scope_data(scope).expect("Scope doesn't exist");
         let func_data = self.get_func_data(& scope_data.parent_func).unwrap();
-        let func_body_data = &func_data.body_data.as_ref().expect("Function must be defined");
+        func_data.body_data.as_ref().expect("Function must be defined");
         match var_data.local_or_arg{
-            VarStorageType::Local => {
-                let bp_offset = -((1 + func_body_data.regs_used.len() as u32 + var_data.offset) as i32);
-                code.push(format!("ADD R1 BP {}", bp_offset));
-                },
-            VarStorageType::Arg => {
-                let func_retval_size = self.get_type_size(&func_data.decl_data.return_type);
-                let bp_offset = (2 + func_retval_size + var_data.offset) as i32;
-                code.push(format!("ADD R1 BP {}", bp_offset));
+            VarStorageType::Local | VarStorageType::Arg => {
+                code.push(format!("ADD R1 BP {}", self.bp_offset(var_data, func_data)));
             },
             VarStorageType::Global => {
-                code.push(format!("LEA R1 {}", self.get_global_label()));
-                code.push(format!("ADD R1 R1 {}", &var_data.offset));
+                // each global has its own label, so globals can be referenced from other files
+                code.push(format!("LEA R1 {}", global_var_label(var_name)));
             }
         };
-        var_data
+        self.gen_word_to_addr(&Register::R1, code);
+        Ok(var_data)
     }
 
     // after executing the generated code, evaluate daddress is stored in R1
-    fn left_gen(&mut self, node: &Expression, scope: &String, code: &mut Vec<String>) {
+    fn left_gen(&mut self, node: &Expression, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
         match node {
             Expression::UnaryOp(uop) => {
                 match uop.op_type{
                     UnaryopType::DEREF => {
-                        self.left_gen(&uop.expr, scope, code);
-                        code.push("LOAD R1 R1".to_string());
+                        // the address is the value of the dereferenced expression, e.g *(ptr + 1) = x
+                        self.right_gen(&uop.expr, scope, code)?;
                     },
-                    _ => panic!("only dereference unary op allowed as lvalue")
+                    // only dereference is allowed as an lvalue
+                    _ => return Err(self.error(ErrorKind::InvalidLvalue)),
                 }
             },
             Expression::NameRef(name) => {
-                self.codegen_name(name, scope, code);
+                self.codegen_name(name, scope, code)?;
             }
-            _ => panic!("not yet supported as an lvalue"),
+            _ => return Err(self.error(ErrorKind::InvalidLvalue)),
         }
+        Ok(())
     }
 
     // generates code, inserts generated code into the 'code' parameter
     // we want to get code as a paramter rather that having it as a member of Compiler,
     // so we can post-process the code generated for a specific object.
     // an example for usefulness of this is knowing which registers we need to save in a function.
-    fn code_gen(&mut self, node: AST::AstNode, scope: &String, code: &mut Vec<String>) {
+    fn gen_compound_item(&mut self, item: &Statement, item_loc: &Option<SourceLoc>, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        if let Some(loc) = item_loc {
+            if self.options.emit_comments {
+                code.extend(self.source_comment(loc));
+            }
+            if self.options.emit_line_labels {
+                // source map entry for the debugger
+                code.push(format!(".loc {} {}", loc.file, loc.line));
+            }
+            self.cur_loc = Some(loc.clone());
+        }
+        self.code_gen(AstNode::Statement(item), scope, code)
+    }
+
+    fn code_gen(&mut self, node: AST::AstNode, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
         match node {
             AstNode::RootAstNode(root_node) => {
-                let mut glob_vars = HashMap::new();
-                let mut next_var_offset : u32 = 0;
-                // register global variables
+                let mut glob_vars = LinkedHashMap::new();
+                let mut glob_blocks = Vec::new();
+                // register structs & global variables, structs are registered first since a global may be a struct
                 for ext in root_node.externals.iter(){
                     match ext{
+                        External::StructDecl(struct_decl) => {
+                            self.register_struct(struct_decl);
+                        },
+                        External::Typedef(typedef) => {
+                            self.register_typedef(typedef);
+                        },
                         External::VarDecl(decl) => {
-                            let var_data = self.variable_data_from_decl(decl, VarStorageType::Global, &next_var_offset.clone());
-                            next_var_offset += &var_data.size;
+                            let var_data = self.variable_data_from_decl(decl, VarStorageType::Global, &0);
+                            // labels are private to the object unless they're global
+                            glob_blocks.push(format!(".global {}", global_var_label(&var_data.name)));
+                            match self.global_init_words(decl).filter(|words| !words.is_empty()) {
+                                Some(words) => {
+                                    let values: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+                                    glob_blocks.push(format!(".word {} {}", global_var_label(&var_data.name), values.join(" ")));
+                                    // the rest of an array that isn't fully initialized is zeroed
+                                    if var_data.size > words.len() as u32 {
+                                        glob_blocks.push(format!(".space {}", var_data.size - words.len() as u32));
+                                    }
+                                },
+                                None => glob_blocks.push(format!(".block {} {}", global_var_label(&var_data.name), var_data.size)),
+                            }
+                            glob_vars.insert(var_data.name.clone(), var_data);
+                        },
+                        External::ExternVarDecl(decl) => {
+                            // storage is allocated by the file that defines the variable
+                            let var_data = self.variable_data_from_decl(decl, VarStorageType::Global, &0);
                             glob_vars.insert(var_data.name.clone(), var_data);
                         },
                         _ => {},
@@ -615,23 +1668,34 @@ impl Compiler {
                     break_label: None,
                     continue_label: None
                 });
-                let global_label = self.get_global_label();
-                code.push(format!(".block {} {}", global_label, next_var_offset));
+                code.append(&mut glob_blocks);
                 code.push("JUMP main".to_string());
+                let mut funcs_code = Vec::new();
                 for ext in root_node.externals.iter(){
                     match ext{
                         External::FuncDef(func_def) => {
-                            self.code_gen(AstNode::FuncDef(func_def), &"_GLOBAL".to_string(), code);
+                            let mut func_code = Vec::new();
+                            self.code_gen(AstNode::FuncDef(func_def), &"_GLOBAL".to_string(), &mut func_code)?;
+                            // the function is inlined in calls that follow its definition
+                            if self.options.inline() && count_instructions(&func_code) <= self.options.inline_threshold {
+                                if let Some(inline_func) = InlineFunc::from(func_def) {
+                                    self.inline_funcs.insert(func_def.decl.name.clone(), inline_func);
+                                }
+                            }
+                            funcs_code.push((func_def.decl.name.clone(), func_code));
                         },
                         External::FuncDecl(func_decl) => {
-                            self.code_gen(AstNode::FuncDecl(func_decl), &"_GLOBAL".to_string(), code);
+                            self.code_gen(AstNode::FuncDecl(func_decl), &"_GLOBAL".to_string(), code)?;
                         },
-                        External::StructDecl(struct_decl) => {
-                            self.register_struct(struct_decl);
-                        },
-                        External::VarDecl(_) => {},
+                        External::StructDecl(_) | External::Typedef(_) | External::VarDecl(_) | External::ExternVarDecl(_) => {},
                     };
                 }
+                let reachable = reachable_funcs(&funcs_code);
+                for (func_name, mut func_code) in funcs_code.into_iter() {
+                    if reachable.contains(&func_name) {
+                        code.append(&mut func_code);
+                    }
+                }
             },
             AstNode::FuncDecl(func_decl) => {
                 let func_name = &func_decl.name;
@@ -641,60 +1705,112 @@ impl Compiler {
             }
             AstNode::FuncDef(func_def) => {
                 let func_name = &func_def.decl.name;
+                code.push(format!(".global {}", func_name));
                 code.push(format!("{}:", func_name));
+                self.cur_loc = func_def.loc.clone();
+                // the prologue & epilogue are mapped to the function's definition line
+                let func_loc = func_def.loc.as_ref()
+                    .filter(|_| self.options.emit_line_labels)
+                    .map(|loc| format!(".loc {} {}", loc.file, loc.line));
+                code.extend(func_loc.clone());
                 self.register_func_decl(&func_def.decl);
                 self.register_func_body(&func_def.body, &func_def.decl, scope);
-                {
-                    // NLL workaround
-                    let func_data = self.get_func_data(func_name).unwrap();
-                    let func_data = &func_data.body_data.as_ref().unwrap();
-                    println!("regs used:{:?}", func_data.regs_used);
-                    // save registers
-                    for reg in func_data.regs_used.iter() {
-                        println!("saving reg:{}", reg);
-                        code.push(format!("PUSH {}", reg.to_str()));
-                    }
-                    // make space on stack for local variables
-                    let _scope_data = self.get_scope_data(func_name).unwrap();
-                    println!("local vars size:{}", func_data.local_vars_size);
-                    for _ in 0..func_data.local_vars_size {
-                            // ZR contains "garbage", but we're just making space
-                            code.push(String::from("PUSH ZR"));
+                let mut labels = HashSet::new();
+                for label in collect_labels(&func_def.body.items) {
+                    if !labels.insert(label.clone()) {
+                        return Err(self.error(ErrorKind::DuplicateLabel { name: label }));
                     }
                 }
+                let body_data = self.func_to_data.get_mut(func_name).unwrap().body_data.as_mut().unwrap();
+                body_data.labels = labels;
+                body_data.frame_escapes = frame_may_escape(&func_def.body.items);
+
+                // generate the body before the prologue, so we know which registers it uses
+                let mut body_code = Vec::new();
+                self.code_gen(AstNode::Compound(&func_def.body), &func_name, &mut body_code)?;
+                let regs_used = get_regs_used(&body_code);
+                self.trace(format_args!("{} uses {:?}", func_name, regs_used));
+                let saved_regs = regs_used.into_iter().filter(|reg| CALLEE_SAVED_REGS.contains(reg)).collect();
+                self.func_to_data.get_mut(func_name).unwrap().body_data.as_mut().unwrap().saved_regs = saved_regs;
+
+                let func_data = self.get_func_data(func_name).unwrap();
+                let func_data = &func_data.body_data.as_ref().unwrap();
+                // make space on stack for local variables
+                self.trace(format_args!("{} has {} words of locals & saves {:?}", func_name, func_data.local_vars_size, func_data.saved_regs));
+                for _ in 0..func_data.local_vars_size {
+                        // ZR contains "garbage", but we're just making space
+                        code.push(String::from("PUSH ZR"));
+                }
+                // save registers
+                for reg in func_data.saved_regs.iter() {
+                    code.push(format!("PUSH {}", reg.to_str()));
+                }
+                if self.options.stack_check {
+                    self.gen_stack_check(&Register::SP, &format!("_{}_STACK_OK", func_name), code);
+                }
 
-                self.code_gen(AstNode::Compound(&func_def.body), &func_name, code);
+                code.append(&mut body_code);
 
                 code.push(format!("_{}_END:", func_name));
+                code.extend(func_loc);
 
+                // SP is set to the saved registers from BP, instead of popping the local variables
+                // so it's correct even if the body moved SP, e.g with alloca. RET then sets SP from BP
+                let moves_sp = func_data.local_vars_size > 0 || func_data.uses_alloca;
+                if moves_sp && !func_data.saved_regs.is_empty() {
+                    code.push(format!("SUB SP BP {}", func_data.local_vars_size as usize + func_data.saved_regs.len() + 1));
+                }
                 // restore registers
-                let func_data = self.get_func_data(&func_name).unwrap();
-                let func_data = &func_data.body_data.as_ref().unwrap();
-                let _scope_data = self.get_scope_data(func_name).unwrap();
-                // dealocate stack space of local variables
-                    for _ in 0..func_data.local_vars_size {
-                        // ZR contains "garbage", but we're just making space
-                        code.push(String::from("POP ZR"));
-                    }
-
-                // save registers
-                for reg in func_data.regs_used.iter().rev() {
+                for reg in func_data.saved_regs.iter().rev() {
                     code.push(format!("POP {}", reg.to_str()));
                 }
                 code.push("RET".to_string());
+                // the callee of a tail call starts with SP & BP as if it was called by our caller
+                for callee in func_data.tail_calls.iter() {
+                    code.push(format!("_{}_TAIL_{}:", func_name, callee));
+                    if !func_data.saved_regs.is_empty() {
+                        code.push(format!("SUB SP BP {}", func_data.local_vars_size as usize + func_data.saved_regs.len() + 1));
+                    }
+                    for reg in func_data.saved_regs.iter().rev() {
+                        code.push(format!("POP {}", reg.to_str()));
+                    }
+                    code.push("SUB SP BP 1".to_string());
+                    code.push(format!("JUMP {}", callee));
+                }
             }
             AstNode::Compound(compound) => {
-                for item in compound.items.iter() {
-                    self.code_gen(AstNode::Statement(&item), &scope, code);
+                for (item, item_loc) in compound.items.iter().zip(compound.item_locs.iter()) {
+                    self.gen_compound_item(item, item_loc, scope, code)?;
                 }
             }
             AstNode::Statement(statement) => {
                 match statement {
+                    Statement::Return(Return{expr: Some(Expression::FuncCall(func_call))}) if self.is_tail_call(func_call, scope) => {
+                        self.gen_tail_call(func_call, scope, code)?;
+                    }
                     Statement::Return(ret) => {
-                        if let Some(ret_expr) = &ret.expr {
-                            self.right_gen(ret_expr, &scope, code);
-                            code.push("ADD R2 BP 2".to_string());
-                            code.push("STR R2 R1 ".to_string());
+                        let func_name = self.get_scope_data(scope).unwrap().parent_func.clone();
+                        let ret_type = self.get_func_data(&func_name).unwrap().decl_data.return_type.clone();
+                        match (&ret.expr, self.get_struct_data_from_type(&ret_type)) {
+                            (Some(ret_expr), Some(struct_data)) => {
+                                // the returned struct is copied to the ret_val slots
+                                let size = struct_data.size;
+                                code.push("ADD R1 BP 2".to_string());
+                                self.gen_word_to_addr(&Register::R1, code);
+                                self.gen_struct_assignment(ret_expr, size, scope, code)?;
+                            },
+                            (Some(ret_expr), None) => {
+                                self.right_gen(ret_expr, &scope, code)?;
+                                let ret_type = Some(ret_type);
+                                self.gen_convert(&self.get_expr_type(ret_expr, scope), &ret_type, &Register::R1, code);
+                                if self.is_long(&ret_type) {
+                                    code.push("ADD ZR BP 3".to_string());
+                                    code.push("STR ZR R2".to_string());
+                                }
+                                code.push("ADD R2 BP 2".to_string());
+                                code.push("STR R2 R1 ".to_string());
+                            },
+                            (None, _) => {},
                         }
                         code.push(format!("JUMP _{}_END", self.get_scope_data(scope).unwrap().parent_func));
                     }
@@ -704,17 +1820,30 @@ impl Compiler {
                                 self.update_var_declared(&var_decl.name, scope);
                                 if let Some(expr) = &var_decl.init {
                                     // if decleration is also initialization
-                                    self.codegen_load_addr_of_var(&var_decl.name, &scope, code);
-                                    code.push("PUSH R1".to_string());
-                                    self.right_gen(&expr, &scope, code);
-                                    code.push("POP R2".to_string());
-                                    code.push("STR R2 R1".to_string());
+                                    self.codegen_load_addr_of_var(&var_decl.name, &scope, code)?;
+                                    let var_type = Some(var_decl._type.clone());
+                                    if let Some(struct_data) = self.get_struct_data_from_type(&var_decl._type) {
+                                        let size = struct_data.size;
+                                        self.gen_struct_assignment(expr, size, scope, code)?;
+                                    } else if self.is_long(&var_type) {
+                                        code.push("PUSH R1".to_string());
+                                        self.right_gen(expr, scope, code)?;
+                                        self.gen_convert(&self.get_expr_type(expr, scope), &var_type, &Register::R1, code);
+                                        self.gen_store_long(code);
+                                        code.push("POP ZR".to_string());
+                                    } else {
+                                        let saved = self.gen_save_reg(&Register::R1, code);
+                                        self.right_gen(&expr, &scope, code)?;
+                                        self.gen_convert(&self.get_expr_type(expr, scope), &Some(var_decl._type.clone()), &Register::R1, code);
+                                        self.gen_restore_reg(saved, &Register::R2, code);
+                                        self.gen_store(&Some(var_decl._type.clone()), &Register::R2, &Register::R1, code);
+                                    }
                                 }
                             },
                             Decl::ArrayDecl(arr_decl) => {
                                 self.update_var_declared(&arr_decl.name, scope);
                                 if let Some(init) = &arr_decl.init{
-                                    self.gen_arr_init_code(&arr_decl.name, init, scope, code);
+                                    self.gen_arr_init_code(&arr_decl.name, init, scope, code)?;
                                 }
                                                         
                             }
@@ -722,31 +1851,38 @@ impl Compiler {
                         }
                     }
                     Statement::Assignment(ass) => {
-                        self.gen_assignment_code(ass, &scope, code);
+                        self.gen_assignment_code(ass, &scope, code)?;
                     }
                     Statement::Expression(exp) => {
-                        self.right_gen(&exp, &scope, code);
+                        self.right_gen(&exp, &scope, code)?;
                     }
+                    Statement::If(if_stmt) if self.eval_const(&if_stmt.cond).is_some() => {
+                        // only the taken branch is generated
+                        if self.eval_const(&if_stmt.cond).unwrap() != 0 {
+                            self.code_gen(AstNode::Compound(&*if_stmt.iftrue), &if_stmt.iftrue.code_loc, code)?;
+                        } else if let Some(iffalse) = &if_stmt.iffalse {
+                            self.code_gen(AstNode::Compound(iffalse), &iffalse.code_loc, code)?;
+                        }
+                    },
                     Statement::If(if_stmt) => {
                         let else_label = format!("IF_{}_ELSE", self.get_tmp_label());
                         let if_end_label = format!("IF_{}_END", self.get_tmp_label());
                         self.inc_tmp_label();
-                        self.right_gen(&if_stmt.cond, &scope, code);
-                        code.push("TSTN R1 0".to_string());
+                        self.gen_cond(&if_stmt.cond, scope, code)?;
                         code.push(format!("FJMP {}", else_label));
-                        self.code_gen(AstNode::Compound(&*if_stmt.iftrue), &if_stmt.iftrue.code_loc, code);
+                        self.code_gen(AstNode::Compound(&*if_stmt.iftrue), &if_stmt.iftrue.code_loc, code)?;
                         code.push(format!("JUMP {}", if_end_label));
                         code.push(format!("{}:", else_label));
                         match &if_stmt.iffalse.as_ref() {
                             Some(ref iffalse) => {
-                                self.code_gen(AstNode::Compound(&*(*iffalse)), &iffalse.code_loc, code);
+                                self.code_gen(AstNode::Compound(&*(*iffalse)), &iffalse.code_loc, code)?;
                             }
                             None => {}
                         }
                         code.push(format!("{}:", if_end_label));
                     },
                     Statement::Compound(comp) => {
-                        self.code_gen(AstNode::Compound(&comp), &comp.code_loc, code);
+                        self.code_gen(AstNode::Compound(&comp), &comp.code_loc, code)?;
                     },
                     Statement::WhileLoop(wl) => {
                         let while_start = format!("WHILE_{}_START", self.get_tmp_label());
@@ -754,10 +1890,9 @@ impl Compiler {
                         self.inc_tmp_label();
                         self.update_scope_break_continue_labels(&wl.code_loc, &while_end, &while_start);
                         code.push(format!("{}:", while_start));
-                        self.right_gen(&wl.cond, scope, code);
-                        code.push("TSTN R1 0".to_string());
+                        self.gen_cond(&wl.cond, scope, code)?;
                         code.push(format!("FJMP {}", while_end));
-                        self.code_gen(AstNode::Compound(&wl.body), &wl.code_loc, code);
+                        self.code_gen(AstNode::Compound(&wl.body), &wl.code_loc, code)?;
                         code.push(format!("JUMP {}", while_start));
                         code.push(format!("{}:", while_end));
                     },
@@ -769,11 +1904,10 @@ impl Compiler {
                         self.update_scope_break_continue_labels(&dwl.code_loc, &dowhile_end, &dowhile_cond);
                         code.push(format!("JUMP {}", dowhile_body));
                         code.push(format!("{}:", dowhile_cond));
-                        self.right_gen(&dwl.cond, scope, code);
-                        code.push("TSTN R1 0".to_string());
+                        self.gen_cond(&dwl.cond, scope, code)?;
                         code.push(format!("FJMP {}", dowhile_end));
                         code.push(format!("{}:", dowhile_body));
-                        self.code_gen(AstNode::Compound(&dwl.body), &dwl.code_loc, code);
+                        self.code_gen(AstNode::Compound(&dwl.body), &dwl.code_loc, code)?;
                         code.push(format!("JUMP {}", dowhile_cond));
                         code.push(format!("{}:", dowhile_end));
                     },
@@ -784,28 +1918,89 @@ impl Compiler {
                         self.inc_tmp_label();
                         self.update_scope_break_continue_labels(&fl.code_loc, &for_end, &for_next);
                         if let Some(init) = &fl.init{
-                            self.code_gen(AstNode::Compound(init), &fl.code_loc, code);
+                            self.code_gen(AstNode::Compound(init), &fl.code_loc, code)?;
                         }
                         code.push(format!("{}:", for_cond));
                         if let Some(cond) = &fl.cond{
-                            self.right_gen(cond, &fl.code_loc, code);
-                            code.push("TSTN R1 0".to_string());
+                            self.gen_cond(cond, &fl.code_loc, code)?;
                             code.push(format!("FJMP {}", for_end));
                         }
-                        self.code_gen(AstNode::Compound(&fl.body), &fl.code_loc, code);
+                        self.code_gen(AstNode::Compound(&fl.body), &fl.code_loc, code)?;
                         code.push(format!("{}:", for_next));  // we need the next label even if next part of empty for "continue"
                         if let Some(next) = &fl.next{
-                            self.code_gen(AstNode::Compound(next), &fl.code_loc, code);
+                            self.code_gen(AstNode::Compound(next), &fl.code_loc, code)?;
                         }
                         code.push(format!("JUMP {}", for_cond));
                         code.push(format!("{}:", for_end));
                     },
+                    Statement::Switch(sw) => {
+                        let switch_end = format!("SWITCH_{}_END", self.get_tmp_label());
+                        let case_labels: Vec<String> = (0..sw.cases.len()).map(|case_i| format!("SWITCH_{}_CASE_{}", self.get_tmp_label(), case_i)).collect();
+                        let table = format!("SWITCH_{}_TABLE", self.get_tmp_label());
+                        self.inc_tmp_label();
+                        self.update_scope_break_label(&sw.code_loc, &switch_end);
+                        // no case matched
+                        let default_label = match sw.cases.iter().position(|case| case.value.is_none()) {
+                            Some(default_i) => case_labels[default_i].clone(),
+                            None => switch_end.clone(),
+                        };
+                        if let Some((min_value, table_cases)) = self.switch_jump_table(sw, scope) {
+                            // the case to jump to is loaded from a table of the case labels, indexed by the value - min_value
+                            let targets: Vec<&str> = table_cases.iter()
+                                .map(|case_i| case_i.map_or(default_label.as_str(), |case_i| case_labels[case_i].as_str()))
+                                .collect();
+                            code.push(format!(".word {} {}", table, targets.join(" ")));
+                            self.right_gen(&sw.cond, scope, code)?;
+                            if min_value != 0 {
+                                code.push(format!("SUB R1 R1 {}", min_value));
+                            }
+                            code.push("TSTL R1 0".to_string());
+                            code.push(format!("TJMP {}", default_label));
+                            code.push(format!("TSTG R1 {}", table_cases.len() - 1));
+                            code.push(format!("TJMP {}", default_label));
+                            code.push(format!("LEA R2 {}", table));
+                            code.push("ADD R1 R1 R2".to_string());
+                            code.push("LOAD R1 R1".to_string());
+                            code.push("JMPR R1".to_string());
+                        } else {
+                            self.gen_switch_comparisons(sw, &case_labels, &default_label, scope, code)?;
+                        }
+                        // cases fall through to the next statements
+                        for (item_i, (item, item_loc)) in sw.body.items.iter().zip(sw.body.item_locs.iter()).enumerate() {
+                            for (case, case_label) in sw.cases.iter().zip(case_labels.iter()) {
+                                if case.first_item == item_i {
+                                    code.push(format!("{}:", case_label));
+                                }
+                            }
+                            self.gen_compound_item(item, item_loc, &sw.code_loc, code)?;
+                        }
+                        // cases with no statements after them
+                        for (case, case_label) in sw.cases.iter().zip(case_labels.iter()) {
+                            if case.first_item == sw.body.items.len() {
+                                code.push(format!("{}:", case_label));
+                            }
+                        }
+                        code.push(format!("{}:", switch_end));
+                    },
+                    Statement::Label(label) => {
+                        let func_name = &self.get_scope_data(scope).unwrap().parent_func;
+                        code.push(format!("{}:", goto_label(func_name, &label.name)));
+                        self.code_gen(AstNode::Statement(&label.stmt), scope, code)?;
+                    },
+                    Statement::Goto(name) => {
+                        let label = self.goto_label_in_scope(name, scope)?;
+                        code.push(format!("JUMP {}", label));
+                    },
+                    Statement::ComputedGoto(expr) => {
+                        self.right_gen(expr, scope, code)?;
+                        code.push("JMPR R1".to_string());
+                    },
                     Statement::Break => {
-                        let (break_label, _) = self.find_break_continue_labels(scope).unwrap();
+                        let break_label = self.find_break_label(scope).ok_or_else(|| self.error(ErrorKind::BreakOutsideLoop))?;
                         code.push(format!("JUMP {}", break_label));
                     },
                     Statement::Continue => {
-                        let (_, continue_label) = self.find_break_continue_labels(scope).unwrap();
+                        let continue_label = self.find_continue_label(scope).ok_or_else(|| self.error(ErrorKind::ContinueOutsideLoop))?;
                         code.push(format!("JUMP {}", continue_label));
                     }
                 }
@@ -814,34 +2009,152 @@ impl Compiler {
                 panic!("Unkown node type");
             }
         }
+        Ok(())
+    }
+
+    /// the assembly label of a C label in the function of the scope, for goto & &&label
+    fn goto_label_in_scope(&self, name: &String, scope: &String) -> Result<String, CompileError> {
+        let func_name = &self.get_scope_data(scope).unwrap().parent_func;
+        let func_data = self.get_func_data(func_name).unwrap().body_data.as_ref().unwrap();
+        if !func_data.labels.contains(name) {
+            return Err(self.error(ErrorKind::UndefinedLabel { name: name.clone() }));
+        }
+        Ok(goto_label(func_name, name))
     }
 
-    fn gen_arr_init_code(&mut self, arr_name: &String, arr_init: &Vec<Expression>, scope: &String, code: &mut Vec<String>){
+    /// compares the switch value with each case value, in order, & jumps to the first case that matches
+    fn gen_switch_comparisons(&mut self, sw: &Switch, case_labels: &[String], default_label: &str, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
+        self.right_gen(&sw.cond, scope, code)?;
+        let saved = self.gen_save_reg(&Register::R1, code);
+        for (case, case_label) in sw.cases.iter().zip(case_labels.iter()) {
+            if let Some(value) = &case.value {
+                let next_case = format!("{}_NEXT", case_label);
+                self.right_gen(value, scope, code)?;
+                match &saved {
+                    Some(tmp_reg) => code.push(format!("TSTE R1 {}", tmp_reg)),
+                    None => {
+                        // switch value is at the top of the stack
+                        code.push("POP R2".to_string());
+                        code.push("PUSH R2".to_string());
+                        code.push("TSTE R1 R2".to_string());
+                    },
+                }
+                code.push(format!("FJMP {}", next_case));
+                if saved.is_none() {
+                    code.push("POP ZR".to_string());
+                }
+                code.push(format!("JUMP {}", case_label));
+                code.push(format!("{}:", next_case));
+            }
+        }
+        self.gen_restore_reg(saved, &Register::R2, code);
+        code.push(format!("JUMP {}", default_label));
+        Ok(())
+    }
+
+    /// if the switch's case values are constants that are dense enough, returns the smallest value & the case
+    /// of each value from it to the largest one, None for values that go to the default, for a jump table
+    fn switch_jump_table(&self, sw: &Switch, scope: &String) -> Option<(i32, Vec<Option<usize>>)> {
+        if !matches!(self.get_expr_type(&sw.cond, scope).map(|t| self.resolve_type(&t)), Some(Type::Int) | Some(Type::Char)) {
+            return None;
+        }
+        let mut values = Vec::new();
+        for (case_i, case) in sw.cases.iter().enumerate() {
+            if let Some(value) = &case.value {
+                values.push((self.eval_const(value)?, case_i));
+            }
+        }
+        let min_value = values.iter().map(|(value, _)| *value).min()?;
+        let max_value = values.iter().map(|(value, _)| *value).max()?;
+        let table_size = max_value as i64 - min_value as i64 + 1;
+        if values.len() < JUMP_TABLE_MIN_CASES || table_size > (values.len() * JUMP_TABLE_MAX_SPARSENESS) as i64 {
+            return None;
+        }
+        let mut table = vec![None; table_size as usize];
+        // like the comparisons, the first of cases with the same value is taken
+        for (value, case_i) in values.into_iter().rev() {
+            table[(value as i64 - min_value as i64) as usize] = Some(case_i);
+        }
+        Some((min_value, table))
+    }
+
+    fn gen_arr_init_code(&mut self, arr_name: &String, arr_init: &Vec<Expression>, scope: &String, code: &mut Vec<String>) -> Result<(), CompileError> {
         let arr_var = self.find_variable(arr_name, scope).expect("array not found");
         match &arr_var.var_type{
             VariableType::Array{_type, dimentions} => {
-                let item_size = if let VariableType::Regular {_type} = &**_type { self.get_type_size(_type) } else{panic!("arrays cannot hold arrays as items")};
-                self.codegen_load_addr_of_var(arr_name, scope, code);
+                let item_type = match &**_type {
+                    VariableType::Regular {_type} => Some(_type.clone()),
+                    item_type => return Err(self.error(ErrorKind::NestedArray { _type: item_type.type_name() })),
+                };
+                let items_count: u32 = dimentions.iter().product();
+                let item_size = self.get_type_size(item_type.as_ref().unwrap());
+                self.codegen_load_addr_of_var(arr_name, scope, code)?;
                 code.push("MOV R2 R1".to_string());
                 for expr in arr_init.iter(){
-                    code.push("PUSH R2".to_string());
-                    self.right_gen(expr, scope, code);
-                    code.push("POP R2".to_string());
-                    code.push("STR R2 R1".to_string());
+                    if self.is_long(&item_type) {
+                        code.push("PUSH R2".to_string());
+                        self.right_gen(expr, scope, code)?;
+                        self.gen_convert(&self.get_expr_type(expr, scope), &item_type, &Register::R1, code);
+                        self.gen_store_long(code);
+                        code.push("POP R2".to_string());
+                        code.push(format!("ADD R2 R2 {}", item_size));
+                        continue;
+                    }
+                    let saved = self.gen_save_reg(&Register::R2, code);
+                    self.right_gen(expr, scope, code)?;
+                    self.gen_convert(&self.get_expr_type(expr, scope), &item_type, &Register::R1, code);
+                    self.gen_restore_reg(saved, &Register::R2, code);
+                    self.gen_store(&item_type, &Register::R2, &Register::R1, code);
+                    if !self.is_byte_sized(&item_type) {
+                        // the store converted the (word aligned) item address to a word address
+                        self.gen_word_to_addr(&Register::R2, code);
+                    }
                     code.push(format!("ADD R2 R2 {}", item_size));
                 }
+                let uninitialized = items_count - arr_init.len() as u32;
+                if uninitialized > 0 {
+                    self.gen_zero_fill(&item_type, uninitialized, code);
+                }
             },
             _ => panic!(),
         }
+        Ok(())
     }
-    fn find_break_continue_labels(&self, scope: &String) -> Option<(&String, &String)>{
+
+    /// zeroes count items of the given type from the address in R2, a byte or a word at a time
+    fn gen_zero_fill(&mut self, item_type: &Option<Type>, count: u32, code: &mut Vec<String>) {
+        let byte_sized = self.is_byte_sized(item_type);
+        let (fill_type, count) = if byte_sized {
+            (item_type.clone(), count)
+        } else {
+            (None, count * self.words(self.get_type_size(item_type.as_ref().unwrap())))
+        };
+        let step = if byte_sized { 1 } else { self.options.word_size() };
+        let loop_label = format!("ZERO_FILL_{}", self.get_tmp_label());
+        self.inc_tmp_label();
+        // R3 counts the remaining items
+        code.push("PUSH R3".to_string());
+        code.push(format!("MOV R3 {}", count));
+        code.push("MOV R1 0".to_string());
+        code.push(format!("{}:", loop_label));
+        self.gen_store(&fill_type, &Register::R2, &Register::R1, code);
+        if !byte_sized {
+            self.gen_word_to_addr(&Register::R2, code);
+        }
+        code.push(format!("ADD R2 R2 {}", step));
+        code.push("SUB R3 R3 1".to_string());
+        code.push("TSTG R3 0".to_string());
+        code.push(format!("TJMP {}", loop_label));
+        code.push("POP R3".to_string());
+    }
+    /// returns the label of the innermost scope that has one, using get_label to get a scope's label
+    fn find_scope_label<'a>(&'a self, scope: &'a String, get_label: fn(&ScopeData) -> &Option<String>) -> Option<&'a String>{
         let mut cur_scope_name = scope;
         loop{
             let scope_data = self.get_scope_data(cur_scope_name).expect(&format!("scope:{} doesn't exist", cur_scope_name));
-            if let Some(break_label) = &scope_data.break_label{
-                let continue_label = &scope_data.continue_label.as_ref().expect("scope has break label but not continue label");
-                return Some((break_label, continue_label))
-            }            
+            if let Some(label) = get_label(scope_data){
+                return Some(label)
+            }
             {
                 if cur_scope_name == "_GLOBAL"{
                     return None
@@ -850,22 +2163,47 @@ impl Compiler {
             }
         }
     }
+    fn find_break_label<'a>(&'a self, scope: &'a String) -> Option<&'a String>{
+        self.find_scope_label(scope, |scope_data| &scope_data.break_label)
+    }
+    /// unlike break, continue skips switch scopes
+    fn find_continue_label<'a>(&'a self, scope: &'a String) -> Option<&'a String>{
+        self.find_scope_label(scope, |scope_data| &scope_data.continue_label)
+    }
     fn update_scope_break_continue_labels(&mut self, scope: &String, break_label: &String, continue_label: &String){
         let scope_data = self.get_scope_data_mut(scope).expect("scope doesn't exist");
         scope_data.break_label = Some(break_label.clone());
         scope_data.continue_label = Some(continue_label.clone());
     }
+    /// the address of a local or an arg's first word relative to BP, see the stack frame layout in layout.rs
+    fn bp_offset(&self, var_data: &VariableData, func_data: &FuncData) -> i32 {
+        match var_data.local_or_arg {
+            VarStorageType::Local => -((1 + var_data.offset) as i32),
+            VarStorageType::Arg => {
+                let func_retval_size = self.words(self.get_type_size(&func_data.decl_data.return_type));
+                // args are pushed by the caller, so a multi-word arg starts at its lowest offset
+                (2 + func_retval_size + var_data.offset + 1 - var_data.size) as i32
+            },
+            VarStorageType::Global => panic!("global variable {} isn't on the stack", var_data.name),
+        }
+    }
+
+    /// for switch scopes, which can only be broken out of
+    fn update_scope_break_label(&mut self, scope: &String, break_label: &String){
+        let scope_data = self.get_scope_data_mut(scope).expect("scope doesn't exist");
+        scope_data.break_label = Some(break_label.clone());
+    }
 
     fn find_variable(&self, var_name: &String, scope: &String) -> Option<&VariableData>{
         let mut cur_scope_name = scope;
         loop{
-            println!("seraching for var {} inside scope {}", var_name, cur_scope_name);
+            self.trace(format_args!("searching for {} in scope {}", var_name, cur_scope_name));
             let scope_data = self.get_scope_data(cur_scope_name).expect(&format!("scope:{} doesn't exist", cur_scope_name));
             if let Some(x) = scope_data.variables.get(var_name.as_str()){
                 if scope_data.declared_variables.contains(var_name){
                     return Some(x);
                 }else{
-                    println!("found var {} in scope but it isn't declared yet", var_name);
+                    self.trace(format_args!("{} is in scope {} but isn't declared yet", var_name, cur_scope_name));
                 }
             }
             {
@@ -883,14 +2221,51 @@ impl Compiler {
         scope_data.declared_variables.insert(var_name.clone().to_string());
     }
 
+    /// replaces typedef names in the type with the types they stand for
+    fn resolve_type(&self, _type: &Type) -> Type {
+        match _type {
+            Type::Alias(name) => self.typedefs.get(name).expect(&format!("unknown type name: {}", name)).clone(),
+            Type::Ptr(pointed_t) => Type::Ptr(Box::new(self.resolve_type(pointed_t))),
+            Type::FuncPtr{ret_type, args} => Type::FuncPtr{
+                ret_type: Box::new(self.resolve_type(ret_type)),
+                args: args.iter().map(|arg| self.resolve_type(arg)).collect(),
+            },
+            _ => _type.clone(),
+        }
+    }
+
+    fn var_type_from_decl(&self, decl: &Decl) -> VariableType {
+        match VariableType::from(decl) {
+            VariableType::Regular{_type} => VariableType::Regular{_type: self.resolve_type(&_type)},
+            VariableType::Array{_type, dimentions} => match *_type {
+                VariableType::Regular{_type: item_type} => VariableType::Array{
+                    _type: Box::new(VariableType::Regular{_type: self.resolve_type(&item_type)}),
+                    dimentions,
+                },
+                item_type => VariableType::Array{_type: Box::new(item_type), dimentions},
+            },
+        }
+    }
+
+    fn register_typedef(&mut self, typedef: &Typedef) {
+        if let Some(struct_decl) = &typedef.struct_decl {
+            self.register_struct(struct_decl);
+        }
+        let resolved = self.resolve_type(&typedef._type);
+        self.typedefs.insert(typedef.name.clone(), resolved);
+    }
+
     fn get_type_size(&self, _type: &Type) -> u32 {
+        let _type = &self.resolve_type(_type);
         if let Some(struct_data) = self.get_struct_data_from_type(_type){
             return struct_data.size
         }
         match _type{
-            Type::Int => 1,
+            Type::Int | Type::Float => self.options.word_size(),
+            Type::Long => 2 * self.options.word_size(),
             Type::Char => 1,
-            Type::Ptr(_) => 1,
+            Type::Ptr(_) => self.options.word_size(),
+            Type::FuncPtr{..} => self.options.word_size(),
             Type::Void => 0,
             _ => panic!("invalid type")
         }
@@ -918,24 +2293,33 @@ impl Compiler {
         }
     }
 
+    /// chars are aligned to bytes & everything else to words, so a word is never split between two values
+    fn get_decl_alignment(&self, decl: &Decl) -> u32 {
+        let _type = match decl {
+            Decl::VarDecl(var_decl) => &var_decl._type,
+            Decl::ArrayDecl(arr_decl) => &arr_decl._type,
+        };
+        if self.resolve_type(_type) == Type::Char { 1 } else { self.options.word_size() }
+    }
+
     fn variable_data_from_decl(&self, decl: &Decl, local_or_arg: VarStorageType, offset: &u32) -> VariableData{
         match decl{
             Decl::VarDecl(var_decl) => {
-                let size = self.get_decl_size(decl);
+                let size = self.words(self.get_decl_size(decl));
                 VariableData{
                     name: var_decl.name.clone(),
                     local_or_arg: local_or_arg,
-                    var_type: VariableType::from(decl),
+                    var_type: self.var_type_from_decl(decl),
                     offset: *offset + size - 1,
                     size: size.clone(),
                 }
             },
             Decl::ArrayDecl(arr_decl) => {
-                let size = self.get_array_size(&arr_decl._type, &arr_decl.dimentions);
+                let size = self.words(self.get_array_size(&arr_decl._type, &arr_decl.dimentions));
                 VariableData{
                     name: arr_decl.name.clone(),
                     local_or_arg: local_or_arg,
-                    var_type: VariableType::from(decl),
+                    var_type: self.var_type_from_decl(decl),
                     offset: *offset + size - 1,
                     size: size,
                 }
@@ -945,8 +2329,8 @@ impl Compiler {
     fn register_scope(&mut self, scope_name: &String, statements: &Vec<Statement>, parent_scope_name: &String, parent_func_name: &String, current_var_offset: & mut u32){
         // collect variables
         let next_var_offset = current_var_offset;
-        let mut variables = HashMap::new();
-        for statement in statements.iter() {
+        let mut variables = LinkedHashMap::new();
+        for statement in statements.iter().map(Statement::unlabeled) {
             match statement{
                 Statement::Decl(decl) => {
                     let var_data = self.variable_data_from_decl(&decl, VarStorageType::Local, &next_var_offset.clone());
@@ -974,9 +2358,12 @@ impl Compiler {
                 Statement::DoWhileLoop(dwl) => {
                     self.register_scope(&dwl.code_loc, & dwl.body.items, scope_name, parent_func_name, next_var_offset)
                 },
+                Statement::Switch(sw) => {
+                    self.register_scope(&sw.code_loc, & sw.body.items, scope_name, parent_func_name, next_var_offset)
+                },
                 Statement::ForLoop(fl) => {
                     // we need to also collect variable declerations from initialization part of for loop
-                    let mut for_init_vars = HashMap::new();
+                    let mut for_init_vars = LinkedHashMap::new();
                     if let Some(init) = &fl.init{
                         for stmt in init.items.iter(){
                             match stmt{
@@ -1014,12 +2401,12 @@ impl Compiler {
     fn register_func_decl(&mut self, func_decl: &FuncDecl){
         let mut args_types = Vec::new();
         for arg in func_decl.args.iter(){
-            args_types.push(VariableType::from(arg));
+            args_types.push(self.var_type_from_decl(arg));
         }
         let func_data = FuncData{
             decl_data: FuncDeclData{
                 args_types: args_types,
-                return_type: func_decl.ret_type.clone(),
+                return_type: self.resolve_type(&func_decl.ret_type),
             },
             body_data: None,
         };
@@ -1031,11 +2418,11 @@ impl Compiler {
         let mut vars_size : u32 = 0;
         self.register_scope(func_name, &func_body.items, parent_scope, func_name, &mut vars_size);
 
-        let regs_used = vec![Register::R1, Register::R2];
+        let saved_regs = Vec::new(); // filled after the function's body is generated
         let funcret_type = func_decl.ret_type.clone();
         // insert local variables to scope's variables
         let mut cur_arg_offset : u32 = 0;
-        let mut args_variables = HashMap::new();
+        let mut args_variables = LinkedHashMap::new();
         for arg in func_decl.args.iter(){
             let var_data = self.variable_data_from_decl(arg, VarStorageType::Arg, &cur_arg_offset);
             cur_arg_offset += &var_data.size;
@@ -1052,20 +2439,29 @@ impl Compiler {
         let func_data = self.func_to_data.get_mut(&func_decl.name).expect("function not yet declared");
         func_data.body_data = Some(FuncBodyData{
             name: func_decl.name.clone(),
-            regs_used: regs_used,
+            saved_regs: saved_regs,
             local_vars_size: vars_size.clone(),
+            args_size: cur_arg_offset,
+            uses_alloca: false,
+            frame_escapes: false,
+            labels: HashSet::new(),
+            tail_calls: Vec::new(),
         });
     }
 
     fn register_struct(&mut self, struct_decl: &StructDecl){
+        for nested in struct_decl.nested.iter() {
+            self.register_struct(nested);
+        }
         let mut items = LinkedHashMap::new();
-        let mut cur_offset = 0;
+        let mut cur_offset: u32 = 0;
         for (name, decl) in &struct_decl.items{
             let size = self.get_decl_size(decl);
+            cur_offset = cur_offset.next_multiple_of(self.get_decl_alignment(decl));
             let var_data = VariableData {
                 name: name.clone(),
                 local_or_arg: VarStorageType::Local,
-                var_type: VariableType::from(decl),
+                var_type: self.var_type_from_decl(decl),
                 offset: cur_offset.clone(),
                 size: size,
             };
@@ -1074,7 +2470,8 @@ impl Compiler {
         }
         self.struct_to_data.insert(struct_decl.name.clone(), StructData{
             name: struct_decl.name.clone(),
-            size: cur_offset.clone(),
+            // structs are copied & passed word by word
+            size: cur_offset.next_multiple_of(self.options.word_size()),
             items,
         });
     }
@@ -1083,23 +2480,145 @@ impl Compiler {
         self.func_to_data.get(func_name)
     }
 
-    fn _compile(&mut self, path_to_c_source: &str) -> Vec<String> {
-        let program = preprocessor::preprocess(path_to_c_source);
+    fn _compile(&mut self, path_to_c_source: &str) -> Result<Vec<String>, Vec<CompileError>> {
+        let program = preprocessor::preprocess(path_to_c_source, &self.options.include_dirs, &self.options.defines).map_err(|error| vec![error])?;
+        self.compile_preprocessed(&program)
+    }
 
-        let mut tmpfile = tempfile::Builder::new().suffix(".c").tempfile().unwrap();
-        write!(tmpfile, "{}", &program.as_str()).unwrap();
+    fn compile_preprocessed(&mut self, program: &str) -> Result<Vec<String>, Vec<CompileError>> {
+        let ast = parser::parse(program).map_err(|error| vec![error])?;
 
         let mut code: Vec<String> = Vec::new();
-        let ast = AST::get_ast(tmpfile.path().to_str().unwrap());
-        self.code_gen(AstNode::RootAstNode(&ast), &"_GLOBAL".to_string(), &mut code);
+        let errors = type_checker::check(&ast);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        self.code_gen(AstNode::RootAstNode(&ast), &"_GLOBAL".to_string(), &mut code).map_err(|error| vec![error])?;
+
+        if self.options.optimize() {
+            code = optimizer::optimize(code);
+        }
+        Ok(code)
+    }
+
+    /// the params & locals of a function with their location on the stack
+    /// the instruction range is left empty, see debug_info
+    fn func_info(&self, func_name: &String) -> FuncInfo {
+        let func_data = self.get_func_data(func_name).unwrap();
+        let var_info = |var_data: &VariableData| VarInfo {
+            name: var_data.name.clone(),
+            bp_offset: self.bp_offset(var_data, func_data),
+            type_name: var_data.var_type.type_name(),
+            size: var_data.size,
+        };
+        let mut params = Vec::new();
+        let mut locals = Vec::new();
+        for scope_data in self.scope_to_data.values().filter(|scope_data| scope_data.parent_func == *func_name) {
+            for var_data in scope_data.variables.values() {
+                match var_data.local_or_arg {
+                    VarStorageType::Arg => params.push(var_info(var_data)),
+                    _ => locals.push(var_info(var_data)),
+                }
+            }
+        }
+        // in the order they were declared
+        params.sort_by_key(|var| var.bp_offset);
+        locals.sort_by_key(|var| (-var.bp_offset, var.name.clone()));
+        FuncInfo { name: func_name.clone(), start: 0, end: 0, params, locals }
+    }
+
+    /// the debug info of the functions in the generated code, their instructions are relative to the start of the code
+    fn debug_info(&self, code: &str) -> DebugInfo {
+        let mut functions: Vec<FuncInfo> = Vec::new();
+        let mut instr_i = 0;
+        for line in code.split('\n') {
+            let defined_func = line.strip_suffix(':')
+                .filter(|name| self.get_func_data(&name.to_string()).is_some_and(|func_data| func_data.body_data.is_some()));
+            if let Some(func_name) = defined_func {
+                if let Some(prev_func) = functions.last_mut() {
+                    prev_func.end = instr_i;
+                }
+                functions.push(FuncInfo { start: instr_i, ..self.func_info(&func_name.to_string()) });
+            } else if is_instruction(line) {
+                instr_i += 1;
+            }
+        }
+        if let Some(last_func) = functions.last_mut() {
+            last_func.end = instr_i;
+        }
+        DebugInfo { functions }
+    }
 
-        code
+    /// preprocesses & parses the file, without type checking it
+    pub fn parse(path_to_c_source: &str, options: &CompilerOptions) -> Result<RootAstNode, Vec<CompileError>> {
+        let program = preprocessor::preprocess(path_to_c_source, &options.include_dirs, &options.defines).map_err(|error| vec![error])?;
+        parser::parse(&program).map_err(|error| vec![error])
     }
 
-    pub fn compile(path_to_c_source: &str, program_index: u32) -> String {
-        let mut instance = Compiler::new(program_index);
-        let instructions = instance._compile(path_to_c_source);
-        instructions.join("\n")
+    pub fn compile(path_to_c_source: &str, options: &CompilerOptions) -> Result<String, Vec<CompileError>> {
+        let mut instance = Compiler::with_options(options.clone());
+        let instructions = instance._compile(path_to_c_source)?;
+        Ok(instructions.join("\n"))
+    }
+
+    /// like compile, with a source that's in memory rather than in a file
+    /// the name is the source's file name in errors & in the debug info, & "file.h" includes are searched next to it
+    pub fn compile_str(name: &str, source: &str, options: &CompilerOptions) -> Result<String, Vec<CompileError>> {
+        let program = preprocessor::preprocess_str(source, name, &options.include_dirs, &options.defines).map_err(|error| vec![error])?;
+        let mut instance = Compiler::with_options(options.clone());
+        let instructions = instance.compile_preprocessed(&program)?;
+        Ok(instructions.join("\n"))
+    }
+
+    /// compiles the file into an object file that can be linked with other object files
+    pub fn compile_object(path_to_c_source: &str, options: &CompilerOptions) -> Result<ObjectFile, Vec<CompileError>> {
+        let mut instance = Compiler::with_options(options.clone());
+        let code = instance._compile(path_to_c_source)?.join("\n");
+        Ok(instance.object_file(code))
+    }
+
+    /// like compile_object, with a source that's in memory rather than in a file, see compile_str
+    pub fn compile_object_str(name: &str, source: &str, options: &CompilerOptions) -> Result<ObjectFile, Vec<CompileError>> {
+        let program = preprocessor::preprocess_str(source, name, &options.include_dirs, &options.defines).map_err(|error| vec![error])?;
+        let mut instance = Compiler::with_options(options.clone());
+        let code = instance.compile_preprocessed(&program)?.join("\n");
+        Ok(instance.object_file(code))
+    }
+
+    /// like compile_object_str, with a source that's read until its end, e.g stdin
+    pub fn compile_object_reader(name: &str, mut reader: impl Read, options: &CompilerOptions) -> Result<ObjectFile, Vec<CompileError>> {
+        let mut source = String::new();
+        reader.read_to_string(&mut source)
+            .map_err(|err| vec![CompileError { kind: ErrorKind::ReadError { message: err.to_string() }, loc: None }])?;
+        Compiler::compile_object_str(name, &source, options)
+    }
+
+    /// the object file of the code that this instance generated
+    fn object_file(&self, code: String) -> ObjectFile {
+        let referenced_labels = get_referenced_labels(&code);
+        let mut exports = BTreeSet::new();
+        let mut imports = BTreeSet::new();
+        for (func_name, func_data) in self.func_to_data.iter() {
+            if func_data.body_data.is_some() {
+                // functions that were dropped as unreachable aren't exported
+                if !code.contains(&format!("\n{}:\n", func_name)) {
+                    continue;
+                }
+                exports.insert(func_name.clone());
+            } else if referenced_labels.contains(func_name) {
+                imports.insert(func_name.clone());
+            }
+        }
+        for var_name in self.get_scope_data(&"_GLOBAL".to_string()).unwrap().variables.keys() {
+            let label = global_var_label(var_name);
+            if code.contains(&format!(".block {} ", label)) || code.contains(&format!(".word {} ", label)) {
+                exports.insert(var_name.clone());
+            } else if referenced_labels.contains(&label) {
+                imports.insert(var_name.clone());
+            }
+        }
+        let debug_info = self.debug_info(&code);
+        ObjectFile { code, exports, imports, debug_info }
     }
 }
 
@@ -1108,16 +2627,52 @@ mod tests{
     use super::*;
     #[test]
     fn find_variable(){
-        let mut compiler = Compiler::new(0);
-        compiler._compile("tests/compiler_test_data/variables/inputs/assign.c");
+        let mut compiler = Compiler::new();
+        compiler._compile("tests/compiler_test_data/variables/inputs/assign.c").unwrap();
         let _a_var = compiler.find_variable(&"a".to_string(), &"main".to_string()).unwrap();
         let b_var = compiler.find_variable(&"b".to_string(), &"main".to_string());
         assert!(b_var.is_none());
     }
+    #[test]
+    fn deterministic_output(){
+        // the compiler's maps are new in each compilation, so hash ordering would show up as different outputs
+        let options = CompilerOptions::new().opt_level(OptLevel::O2);
+        let compile = || Compiler::compile_object("tests/compiler_test_data/complex_struct/inputs/nested_definition.c", &options).unwrap();
+        let object = compile();
+        for _ in 0..5 {
+            let other = compile();
+            assert_eq!((&other.code, &other.exports, &other.imports), (&object.code, &object.exports, &object.imports));
+            assert_eq!(other.debug_info, object.debug_info);
+        }
+    }
+    #[test]
+    fn compile_from_memory(){
+        let path = "tests/compiler_test_data/functions/inputs/fib.c";
+        let source = fs::read_to_string(path).unwrap();
+        let options = CompilerOptions::new();
+        assert_eq!(Compiler::compile_str(path, &source, &options), Compiler::compile(path, &options));
+        let object = Compiler::compile_object_reader(path, source.as_bytes(), &options).unwrap();
+        assert_eq!(object.code, Compiler::compile_object(path, &options).unwrap().code);
+        let errors = Compiler::compile_object_str("memory.c", "#include \"missing.h\"", &options).unwrap_err();
+        assert_eq!(format_errors(&errors), "memory.c:1: error: 'missing.h' file not found");
+    }
+    #[test]
+    fn debug_info(){
+        let object = Compiler::compile_object("tests/compiler_test_data/functions/inputs/fib.c", &CompilerOptions::new()).unwrap();
+        let funcs = &object.debug_info.functions;
+        assert_eq!(funcs.iter().map(|func| func.name.as_str()).collect::<Vec<&str>>(), vec!["fib", "main"]);
+        assert_eq!(funcs[0].end, funcs[1].start);
+        assert_eq!(funcs[1].end as usize, object.code.split('\n').filter(|line| is_instruction(line)).count());
+        let n = VarInfo { name: "n".to_string(), bp_offset: 3, type_name: "int".to_string(), size: 1 };
+        assert_eq!(funcs[0].params, vec![n.clone()]);
+        assert_eq!(funcs[1].locals, vec![VarInfo { bp_offset: -1, ..n }]);
+        let json = serde_json::to_string(&object.debug_info).unwrap();
+        assert_eq!(serde_json::from_str::<DebugInfo>(&json).unwrap(), object.debug_info);
+    }
     #[test] #[ignore]
     fn find_nested_scope(){
-        let mut compiler = Compiler::new(0);
-        compiler._compile("tests/compiler_test_data/scopes/inputs/declare_block.c");
+        let mut compiler = Compiler::new();
+        compiler._compile("tests/compiler_test_data/scopes/inputs/declare_block.c").unwrap();
         println!("{:?}", compiler.scope_to_data);
         assert_eq!(compiler.scope_to_data.len(), 3);
         let block_scope = compiler.scope_to_data.get("tests/compiler_test_data/scopes/inputs/declare_block.c-2-1").unwrap();
@@ -1128,22 +2683,18 @@ mod tests{
     #[test] #[ignore]
 
     fn find_break_continue_labels(){
-        let mut compiler = Compiler::new(0);
-        compiler._compile("tests/compiler_test_data/loops/inputs/while_multi_statement.c");
+        let mut compiler = Compiler::new();
+        compiler._compile("tests/compiler_test_data/loops/inputs/while_multi_statement.c").unwrap();
         println!("{:?}", compiler.scope_to_data);
         assert_eq!(compiler.scope_to_data.len(), 3);
-        match compiler.find_break_continue_labels(&"tests/compiler_test_data/loops/inputs/while_multi_statement.c-5-5".to_string()){
-            Some((break_label, continue_label)) => {
-                assert_eq!(break_label, "WHILE_0_END");
-                assert_eq!(continue_label, "WHILE_0_START");
-            },
-            _ => panic!()
-        }
+        let scope = "tests/compiler_test_data/loops/inputs/while_multi_statement.c-5-5".to_string();
+        assert_eq!(compiler.find_break_label(&scope).unwrap(), "WHILE_0_END");
+        assert_eq!(compiler.find_continue_label(&scope).unwrap(), "WHILE_0_START");
     }
     #[test]
     fn function_args(){
-        let mut compiler = Compiler::new(0);
-        compiler._compile("tests/compiler_test_data/functions/inputs/multi_arg.c");
+        let mut compiler = Compiler::new();
+        compiler._compile("tests/compiler_test_data/functions/inputs/multi_arg.c").unwrap();
         println!("{:?}", compiler.scope_to_data);
         let func_data = compiler.get_func_data(&"sub_3".to_string()).unwrap();
         let scope_data = compiler.get_scope_data(&"sub_3".to_string()).unwrap();
@@ -1177,8 +2728,8 @@ mod tests{
 
     #[test]
     fn struct_registration(){
-        let mut compiler = Compiler::new(0);
-        compiler._compile("tests/compiler_test_data/structs/inputs/1.c");
+        let mut compiler = Compiler::new();
+        compiler._compile("tests/compiler_test_data/structs/inputs/1.c").unwrap();
         let struct_data = compiler.struct_to_data.get("A").unwrap();
         assert_eq!(struct_data.name, "A");
         assert_eq!(struct_data.size, 3);
@@ -1195,5 +2746,102 @@ mod tests{
         assert_eq!(struct_data.items.get("z").unwrap().offset, 2);
     }
 
+    #[test]
+    fn constant_folding(){
+        let mut compiler = Compiler::new();
+        let code = compiler._compile("tests/compiler_test_data/const_folding/inputs/dead_if.c").unwrap();
+        assert!(!code.iter().any(|line| line.contains("IF_")));
+        assert!(code.contains(&"MOV R1 5".to_string()));
+        assert!(!code.contains(&"MOV R1 100".to_string()));
+        let mut compiler = Compiler::new();
+        let code = compiler._compile("tests/compiler_test_data/const_folding/inputs/sizeof.c").unwrap();
+        assert!(code.contains(&"MOV R1 9".to_string()));
+    }
+
+    #[test]
+    fn jump_tables(){
+        let code = Compiler::new()._compile("tests/compiler_test_data/switch/inputs/jump_table.c").unwrap();
+        // days_in_month & offset use a table, sparse compares its cases
+        assert_eq!(code.iter().filter(|line| *line == "JMPR R1").count(), 2);
+        let tables: Vec<&String> = code.iter().filter(|line| line.starts_with(".word SWITCH_")).collect();
+        assert_eq!(tables.len(), 2);
+        // offset's table is from -2 to 2, 0 goes to the default
+        let cases: Vec<&str> = tables[1].split_whitespace().skip(2).map(|label| label.rsplit('_').next().unwrap()).collect();
+        assert_eq!(cases, vec!["0", "1", "4", "2", "3"]);
+    }
+
+    #[test]
+    fn epilogue(){
+        let code = Compiler::new()._compile("tests/compiler_test_data/alloca/inputs/alloca.c").unwrap();
+        for func in ["main", "sum_squares", "depth_sum"].iter() {
+            let start = code.iter().position(|line| *line == format!("_{}_END:", func)).unwrap();
+            let end = start + code[start..].iter().position(|line| line == "RET").unwrap();
+            let epilogue = &code[start..end];
+            // the registers are restored from BP, the local variables aren't popped
+            assert!(epilogue.iter().any(|line| line.starts_with("SUB SP BP")));
+            assert!(!epilogue.contains(&"POP ZR".to_string()));
+        }
+    }
+
+    #[test]
+    fn unreachable_funcs(){
+        let path = "tests/compiler_test_data/dead_code/inputs/unused_funcs.c";
+        let code = Compiler::new()._compile(path).unwrap();
+        for func in ["main", "apply", "square", "add_one"].iter() {
+            assert!(code.contains(&format!("{}:", func)));
+        }
+        assert!(!code.contains(&"unused:".to_string()));
+        assert!(!code.contains(&"unused_helper:".to_string()));
+        let object = Compiler::compile_object(path, &CompilerOptions::default()).unwrap();
+        assert!(!object.exports.contains("unused"));
+        // without main, all the functions are kept
+        let object = Compiler::compile_object("tests/linker_test_data/counter.c", &CompilerOptions::default()).unwrap();
+        assert!(object.exports.contains("increment"));
+    }
+
+    #[test]
+    fn saved_regs(){
+        let mut compiler = Compiler::new();
+        compiler._compile("tests/compiler_test_data/functions/inputs/multi_arg.c").unwrap();
+        let sub_3 = compiler.get_func_data(&"sub_3".to_string()).unwrap();
+        // only the callee saved registers are saved
+        assert_eq!(sub_3.body_data.as_ref().unwrap().saved_regs, vec![Register::R3]);
+        let main = compiler.get_func_data(&"main".to_string()).unwrap();
+        assert!(main.body_data.as_ref().unwrap().saved_regs.is_empty());
+    }
+
+    #[test]
+    fn codegen_errors(){
+        let compile_error = |name: &str| {
+            let errors = Compiler::compile(&format!("tests/compile_error_test_data/{}.c", name), &CompilerOptions::default()).unwrap_err();
+            assert_eq!(errors.len(), 1);
+            (errors[0].loc.as_ref().unwrap().line, errors[0].kind.clone())
+        };
+        assert_eq!(compile_error("undeclared_var"), (3, ErrorKind::UndeclaredVariable { name: "b".to_string() }));
+        assert_eq!(compile_error("undeclared_func"), (3, ErrorKind::UndeclaredFunction { name: "foo".to_string() }));
+        assert_eq!(compile_error("not_callable"), (3, ErrorKind::NotCallable { name: "a".to_string() }));
+        assert_eq!(compile_error("break"), (4, ErrorKind::BreakOutsideLoop));
+        assert_eq!(compile_error("lvalue"), (3, ErrorKind::InvalidLvalue));
+        assert_eq!(compile_error("undefined_label"), (3, ErrorKind::UndefinedLabel { name: "end".to_string() }));
+        assert_eq!(compile_error("undefined_label_addr"), (2, ErrorKind::UndefinedLabel { name: "end".to_string() }));
+        assert_eq!(compile_error("duplicate_label"), (1, ErrorKind::DuplicateLabel { name: "end".to_string() }));
+    }
+
+    #[test]
+    fn unchecked_codegen_errors(){
+        // the type checker reports these first, but code generation doesn't rely on it
+        let codegen_error = |program: &str| {
+            let ast = parser::parse(program).unwrap();
+            let mut code = Vec::new();
+            Compiler::new().code_gen(AstNode::RootAstNode(&ast), &"_GLOBAL".to_string(), &mut code).unwrap_err().kind
+        };
+        assert_eq!(codegen_error("int main(){int* p; return p[0][1];}"), ErrorKind::NotSubscriptable { found: "int*".to_string() });
+        assert_eq!(codegen_error("int main(){int a; return (a + 1)++;}"), ErrorKind::InvalidLvalue);
+
+        let compiler = Compiler::new();
+        let row = VariableType::Array { _type: Box::new(VariableType::Regular { _type: Type::Int }), dimentions: vec![3] };
+        assert_eq!(compiler.get_array_item_size(&VariableType::Regular { _type: Type::Long }), Ok(2));
+        assert_eq!(compiler.get_array_item_size(&row), Err(compiler.error(ErrorKind::NestedArray { _type: "int[3]".to_string() })));
+    }
 
 }
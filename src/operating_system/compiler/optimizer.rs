@@ -0,0 +1,124 @@
+// peephole optimizations over the generated assembly
+
+/// true for directives & comments, which aren't instructions
+fn is_directive(line: &str) -> bool {
+    line.trim().starts_with(".") || line.trim().starts_with(";")
+}
+
+fn is_label(line: &str) -> bool {
+    !is_directive(line) && line.contains(":")
+}
+
+/// instructions after which execution never falls through to the next line
+fn is_unconditional_jump(line: &str) -> bool {
+    matches!(line.split_whitespace().next(), Some("JUMP") | Some("JMPR") | Some("RET") | Some("HALT"))
+}
+
+fn parts(line: &str) -> Vec<&str> {
+    line.split_whitespace().collect()
+}
+
+/// removes "MOV Rx Rx"
+fn remove_redundant_moves(code: Vec<String>) -> Vec<String> {
+    code.into_iter()
+        .filter(|line| !matches!(parts(line).as_slice(), ["MOV", dst, src] if dst == src))
+        .collect()
+}
+
+/// "PUSH Rx" followed by "POP Ry" becomes "MOV Ry Rx" (or nothing if x == y)
+fn combine_push_pop(code: Vec<String>) -> Vec<String> {
+    let mut res: Vec<String> = Vec::new();
+    for line in code.into_iter() {
+        let combined = match (res.last().map(|prev| parts(prev)), parts(&line).as_slice()) {
+            (Some(prev), ["POP", dst]) if prev.len() == 2 && prev[0] == "PUSH" => {
+                Some(if prev[1] == *dst { None } else { Some(format!("MOV {} {}", dst, prev[1])) })
+            }
+            _ => None,
+        };
+        match combined {
+            Some(mov) => {
+                res.pop();
+                res.extend(mov);
+            }
+            None => res.push(line),
+        }
+    }
+    res
+}
+
+/// removes instructions that follow an unconditional jump and aren't labeled
+/// also removes a jump to the label that immediately follows it
+fn remove_unreachable(code: Vec<String>) -> Vec<String> {
+    let mut res: Vec<String> = Vec::new();
+    let mut reachable = true;
+    for line in code.into_iter() {
+        if is_label(&line) {
+            reachable = true;
+            let label = line.trim().trim_end_matches(':');
+            let last_instr = res.iter().rposition(|prev| !is_directive(prev));
+            if let Some(last_i) = last_instr {
+                if parts(&res[last_i]).as_slice() == ["JUMP", label] {
+                    res.remove(last_i);
+                }
+            }
+        }
+        if reachable || is_directive(&line) || is_label(&line) {
+            if !is_directive(&line) && !is_label(&line) && is_unconditional_jump(&line) {
+                reachable = false;
+            }
+            res.push(line);
+        }
+    }
+    res
+}
+
+/// runs the peephole optimizations until the code doesn't change
+pub fn optimize(code: Vec<String>) -> Vec<String> {
+    let mut code = code;
+    loop {
+        let prev_len = code.len();
+        code = remove_unreachable(combine_push_pop(remove_redundant_moves(code)));
+        if code.len() == prev_len {
+            return code;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_code(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_redundant_moves() {
+        let code = to_code(&["MOV R1 R1", "MOV R1 R2", "MOV R2 5"]);
+        assert_eq!(optimize(code), to_code(&["MOV R1 R2", "MOV R2 5"]));
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let code = to_code(&["PUSH R1", "POP R2", "PUSH R3", "POP R3", "PUSH R1", "ADD R1 R1 1", "POP R2"]);
+        assert_eq!(optimize(code), to_code(&["MOV R2 R1", "PUSH R1", "ADD R1 R1 1", "POP R2"]));
+    }
+
+    #[test]
+    fn test_nested_push_pop() {
+        let code = to_code(&["PUSH R1", "PUSH R2", "POP R3", "POP R4"]);
+        assert_eq!(optimize(code), to_code(&["PUSH R1", "MOV R3 R2", "POP R4"]));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let code = to_code(&["JUMP end", "MOV R1 1", ".loc a.c 3", "MOV R1 2", "foo:", "RET", "ADD R1 R1 1", "end:", "HALT"]);
+        assert_eq!(optimize(code), to_code(&["JUMP end", ".loc a.c 3", "foo:", "RET", "end:", "HALT"]));
+    }
+
+    #[test]
+    fn test_jump_to_next() {
+        let code = to_code(&["TJMP else", "MOV R1 1", "JUMP end", ".loc a.c 3", "end:", "RET"]);
+        assert_eq!(optimize(code), to_code(&["TJMP else", "MOV R1 1", ".loc a.c 3", "end:", "RET"]));
+    }
+}
@@ -0,0 +1,123 @@
+// configuration of the generated code
+
+use crate::cpu::BYTES_PER_WORD;
+use crate::operating_system::layout::STACK_END_ADDRESS;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum OptLevel {
+    O0, // no optimizations
+    O1, // the peephole optimizer runs over the generated code
+    O2, // O1, calls to small functions are inlined & tail calls reuse the caller's stack frame
+}
+
+/// e.g CompilerOptions::new().opt_level(OptLevel::O2).stack_check(true)
+#[derive(Debug, Clone)]
+pub struct CompilerOptions {
+    pub(super) opt_level: OptLevel,
+    pub(super) inline_threshold: usize, // max number of instructions in a function that is inlined
+    pub(super) emit_comments: bool, // a comment with the source location & line before each statement's code, e.g "; foo.c:12: x = y + 1;"
+    pub(super) emit_line_labels: bool, // .loc directives that map the code to source lines, used by the debugger
+    pub(super) stack_check: bool, // functions exit the program if the stack overflows into the heap
+    pub(super) stack_end: u32, // the lowest address of the stack, used by the stack check
+    pub(super) include_dirs: Vec<String>, // searched for the included files, see preprocessor
+    pub(super) defines: Vec<(String, String)>, // (name, value) of the macros that are defined before the program
+    pub(super) byte_addressing: bool, // char is a byte & int is 4 bytes, pointers hold byte addresses that are accessed with LOADB & STRB
+    pub(super) verbose: bool, // the compiler logs what it does, see logging.rs
+}
+
+impl CompilerOptions {
+    pub fn new() -> CompilerOptions {
+        CompilerOptions {
+            opt_level: OptLevel::O0,
+            inline_threshold: 30,
+            emit_comments: false,
+            emit_line_labels: true,
+            stack_check: false,
+            stack_end: STACK_END_ADDRESS,
+            include_dirs: Vec::new(),
+            defines: Vec::new(),
+            byte_addressing: false,
+            verbose: false,
+        }
+    }
+
+    pub fn opt_level(mut self, opt_level: OptLevel) -> CompilerOptions {
+        self.opt_level = opt_level;
+        self
+    }
+
+    pub fn inline_threshold(mut self, inline_threshold: usize) -> CompilerOptions {
+        self.inline_threshold = inline_threshold;
+        self
+    }
+
+    pub fn emit_comments(mut self, emit_comments: bool) -> CompilerOptions {
+        self.emit_comments = emit_comments;
+        self
+    }
+
+    pub fn emit_line_labels(mut self, emit_line_labels: bool) -> CompilerOptions {
+        self.emit_line_labels = emit_line_labels;
+        self
+    }
+
+    pub fn stack_check(mut self, stack_check: bool) -> CompilerOptions {
+        self.stack_check = stack_check;
+        self
+    }
+
+    /// set by the os to the stack end of its memory layout
+    pub fn stack_end(mut self, stack_end: u32) -> CompilerOptions {
+        self.stack_end = stack_end;
+        self
+    }
+
+    /// can be given several times, the dirs are searched in order
+    pub fn include_dir(mut self, dir: &str) -> CompilerOptions {
+        self.include_dirs.push(dir.to_string());
+        self
+    }
+
+    /// like #define name value at the start of the program, e.g define("DEBUG", "1")
+    pub fn define(mut self, name: &str, value: &str) -> CompilerOptions {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn byte_addressing(mut self, byte_addressing: bool) -> CompilerOptions {
+        self.byte_addressing = byte_addressing;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> CompilerOptions {
+        self.verbose = verbose;
+        self
+    }
+
+    pub(crate) fn is_byte_addressing(&self) -> bool {
+        self.byte_addressing
+    }
+
+    /// the size of an int & of a pointer, sizes are in bytes if byte addressing & in words otherwise
+    pub(super) fn word_size(&self) -> u32 {
+        if self.byte_addressing { BYTES_PER_WORD } else { 1 }
+    }
+
+    pub(super) fn optimize(&self) -> bool {
+        self.opt_level >= OptLevel::O1
+    }
+
+    pub(super) fn inline(&self) -> bool {
+        self.opt_level >= OptLevel::O2
+    }
+
+    pub(super) fn tail_calls(&self) -> bool {
+        self.opt_level >= OptLevel::O2
+    }
+}
+
+impl Default for CompilerOptions {
+    fn default() -> CompilerOptions {
+        CompilerOptions::new()
+    }
+}
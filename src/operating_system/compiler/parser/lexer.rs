@@ -0,0 +1,181 @@
+// splits the preprocessed program into tokens
+
+use super::super::error::{CompileError, ErrorKind};
+use super::super::AST::SourceLoc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    Ident, // identifiers & keywords
+    Number,
+    Char,
+    Str,
+    Punct,
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub loc: SourceLoc,
+    pub column: u32,
+}
+
+// longest first, so e.g <<= isn't read as << & =
+const PUNCTUATORS: [&str; 46] = [
+    "...", "<<=", ">>=",
+    "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "+", "-", "*", "/", "%", "&", "|", "^", "~", "!", "<", ">", "=",
+    "?", ":", ";", ",", ".", "(", ")", "[", "]", "{", "}",
+];
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> char {
+        self.chars.get(self.pos + offset).copied().unwrap_or('\0')
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek(i) == c)
+    }
+
+    fn advance(&mut self) -> char {
+        let c = self.peek(0);
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        c
+    }
+
+    fn error(&self, message: String) -> CompileError {
+        let loc = SourceLoc { file: self.file.clone(), line: self.line };
+        CompileError { kind: ErrorKind::SyntaxError { message }, loc: Some(loc) }
+    }
+
+    /// skips whitespace, comments & the directives the preprocessor left, e.g #line
+    fn skip_whitespace(&mut self) -> Result<(), CompileError> {
+        let mut line_start = self.pos == 0;
+        loop {
+            let c = self.peek(0);
+            if c == '\n' {
+                line_start = true;
+                self.advance();
+            } else if c.is_whitespace() {
+                self.advance();
+            } else if self.starts_with("//") {
+                while self.pos < self.chars.len() && self.peek(0) != '\n' {
+                    self.advance();
+                }
+            } else if self.starts_with("/*") {
+                while self.pos < self.chars.len() && !self.starts_with("*/") {
+                    self.advance();
+                }
+                if self.pos >= self.chars.len() {
+                    return Err(self.error("unterminated comment".to_string()));
+                }
+                self.advance();
+                self.advance();
+            } else if c == '#' && line_start {
+                self.directive()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// #line 12 "file.c" (or cpp's # 12 "file.c") sets the location of the next line, #pragma is ignored
+    fn directive(&mut self) -> Result<(), CompileError> {
+        let start = self.pos;
+        while self.pos < self.chars.len() && self.peek(0) != '\n' {
+            self.advance();
+        }
+        let directive: String = self.chars[start + 1..self.pos].iter().collect();
+        let words: Vec<&str> = directive.split_whitespace().collect();
+        let line_words = match words.first() {
+            Some(&"line") => &words[1..],
+            Some(word) if word.parse::<u32>().is_ok() => &words[..],
+            Some(&"pragma") | None => return Ok(()),
+            Some(word) => return Err(self.error(format!("invalid preprocessing directive '#{}'", word))),
+        };
+        let line = line_words.first().and_then(|line| line.parse::<u32>().ok())
+            .ok_or_else(|| self.error(format!("invalid line directive '#{}'", directive.trim())))?;
+        if let Some(file) = line_words.get(1) {
+            self.file = file.trim_matches('"').to_string();
+        }
+        // the newline after the directive increments the line
+        self.line = line - 1;
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> Result<Token, CompileError> {
+        self.skip_whitespace()?;
+        let (start, loc, column) = (self.pos, SourceLoc { file: self.file.clone(), line: self.line }, self.column);
+        let c = self.peek(0);
+        let kind = if self.pos >= self.chars.len() {
+            TokenKind::Eof
+        } else if c.is_alphabetic() || c == '_' {
+            while self.peek(0).is_alphanumeric() || self.peek(0) == '_' {
+                self.advance();
+            }
+            TokenKind::Ident
+        } else if c.is_ascii_digit() || (c == '.' && self.peek(1).is_ascii_digit()) {
+            let is_hex = self.starts_with("0x") || self.starts_with("0X");
+            loop {
+                let c = self.peek(0);
+                // the sign of an exponent, e.g 1e-3
+                let is_exponent_sign = (c == '+' || c == '-') && !is_hex && matches!(self.chars[self.pos - 1], 'e' | 'E');
+                if !(c.is_alphanumeric() || c == '.' || c == '_' || is_exponent_sign) {
+                    break;
+                }
+                self.advance();
+            }
+            TokenKind::Number
+        } else if c == '"' || c == '\'' {
+            self.advance();
+            while self.peek(0) != c {
+                if self.peek(0) == '\n' || self.pos >= self.chars.len() {
+                    return Err(self.error(format!("missing terminating {} character", c)));
+                }
+                if self.advance() == '\\' {
+                    self.advance();
+                }
+            }
+            self.advance();
+            if c == '"' { TokenKind::Str } else { TokenKind::Char }
+        } else {
+            let punct = PUNCTUATORS.iter().find(|punct| self.starts_with(punct))
+                .ok_or_else(|| self.error(format!("unexpected character '{}'", c)))?;
+            for _ in 0..punct.len() {
+                self.advance();
+            }
+            TokenKind::Punct
+        };
+        Ok(Token { kind, text: self.chars[start..self.pos].iter().collect(), loc, column })
+    }
+}
+
+/// the tokens of the program, the last token is Eof
+pub fn tokenize(program: &str) -> Result<Vec<Token>, CompileError> {
+    let mut lexer = Lexer { chars: program.chars().collect(), pos: 0, file: String::new(), line: 1, column: 1 };
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        let eof = token.kind == TokenKind::Eof;
+        tokens.push(token);
+        if eof {
+            return Ok(tokens);
+        }
+    }
+}
@@ -0,0 +1,1139 @@
+// a recursive descent parser of the supported subset of C, builds the AST of a preprocessed program
+
+mod lexer;
+
+use linked_hash_map::LinkedHashMap;
+
+use std::collections::HashSet;
+
+use self::lexer::{tokenize, Token, TokenKind};
+use super::error::{CompileError, ErrorKind};
+use super::literals::{escape, string_constant_value};
+use super::AST::*;
+
+const KEYWORDS: [&str; 33] = [
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum", "extern",
+    "float", "for", "goto", "if", "inline", "int", "long", "register", "return", "short", "signed", "sizeof",
+    "static", "struct", "switch", "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+// storage classes & qualifiers, they're parsed but have no effect except for extern & typedef
+const SPECIFIER_KEYWORDS: [&str; 8] = ["auto", "const", "extern", "inline", "register", "static", "typedef", "volatile"];
+
+const UNSUPPORTED_TYPES: [&str; 6] = ["double", "enum", "short", "signed", "union", "unsigned"];
+
+const BASE_TYPES: [&str; 6] = ["int", "char", "float", "long", "void", "struct"];
+
+const ASSIGNMENT_OPS: [&str; 11] = ["=", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>="];
+
+/// the binary operators & their precedence, higher binds tighter
+const BINARY_OPS: [(&str, u32); 18] = [
+    ("||", 1), ("&&", 2), ("|", 3), ("^", 4), ("&", 5), ("==", 6), ("!=", 6),
+    ("<", 7), (">", 7), ("<=", 7), (">=", 7), ("<<", 8), (">>", 8),
+    ("+", 9), ("-", 9), ("*", 10), ("/", 10), ("%", 10),
+];
+
+/// the type of a declarator, before it's converted to the AST's Type or Decl
+/// e.g the type of funcs in void (*funcs[2])(void) is Array(Ptr(Func(Base(Void), [])), 2)
+#[derive(Clone, Debug)]
+enum DeclType {
+    Base(Type),
+    Ptr(Box<DeclType>),
+    Array(Box<DeclType>, Option<u32>),
+    Func(Box<DeclType>, Params, bool), // whether the params end with ...
+}
+
+/// the (name, type) of a function's params, the name is None for an unnamed param
+type Params = Vec<(Option<Token>, DeclType)>;
+
+/// the part of a declaration before the declarators, e.g "static struct A {int x;}"
+struct Specifiers {
+    _type: Type,
+    struct_decl: Option<StructDecl>, // the struct that's defined in the specifiers, an anonymous struct has an empty name
+    is_typedef: bool,
+    is_extern: bool,
+}
+
+enum Initializer {
+    Expr(Expression),
+    List(Vec<Initializer>), // e.g {1, 2, 3} or {{1, 2}, {3}}
+}
+
+/// the scope id of a node is the location of its first token, e.g "main.c-3-5"
+fn code_loc(token: &Token) -> String {
+    format!("{}:{}:{}", token.loc.file, token.loc.line, token.column).replace(":", "-")
+}
+
+fn unary_op(op_type: UnaryopType, expr: Expression) -> Expression {
+    let id = match &expr {
+        Expression::NameRef(NameRef::ID(id)) => Some(id.clone()),
+        _ => None,
+    };
+    Expression::UnaryOp(UnaryOp { op_type, expr: Box::new(expr), id })
+}
+
+/// an assignment statement is kept apart from the other expression statements
+fn expression_statement(expr: Expression) -> Statement {
+    match expr {
+        Expression::Assignment(assignment) => Statement::Assignment(assignment),
+        expr => Statement::Expression(expr),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    typedef_names: HashSet<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    /// the last token is Eof, so peeking beyond it returns it
+    fn peek_at(&self, offset: usize) -> &Token {
+        &self.tokens[(self.pos + offset).min(self.tokens.len() - 1)]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        self.pos = (self.pos + 1).min(self.tokens.len() - 1);
+        token
+    }
+
+    /// whether the token is the punctuator or the keyword
+    fn is(&self, text: &str) -> bool {
+        let token = self.peek();
+        matches!(token.kind, TokenKind::Punct | TokenKind::Ident) && token.text == text
+    }
+
+    fn accept(&mut self, text: &str) -> bool {
+        let is = self.is(text);
+        if is {
+            self.next();
+        }
+        is
+    }
+
+    fn error(&self, token: &Token, message: String) -> CompileError {
+        CompileError { kind: ErrorKind::SyntaxError { message }, loc: Some(token.loc.clone()) }
+    }
+
+    fn unexpected(&self, expected: &str) -> CompileError {
+        let token = self.peek();
+        let found = match token.kind {
+            TokenKind::Eof => "end of file".to_string(),
+            _ => format!("'{}'", token.text),
+        };
+        self.error(token, format!("expected {} but found {}", expected, found))
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Token, CompileError> {
+        if !self.is(text) {
+            return Err(self.unexpected(&format!("'{}'", text)));
+        }
+        Ok(self.next())
+    }
+
+    /// accepts the token that closes a list, e.g the } of a compound, the list can't reach the end of the file
+    fn accept_close(&mut self, text: &str) -> Result<bool, CompileError> {
+        if self.peek().kind == TokenKind::Eof {
+            return Err(self.unexpected(&format!("'{}'", text)));
+        }
+        Ok(self.accept(text))
+    }
+
+    fn expect_identifier(&mut self) -> Result<Token, CompileError> {
+        let token = self.peek();
+        if token.kind != TokenKind::Ident || KEYWORDS.contains(&token.text.as_str()) {
+            return Err(self.unexpected("an identifier"));
+        }
+        Ok(self.next())
+    }
+
+    /// whether the token starts a declaration or a type name
+    fn is_type_start(&self, token: &Token) -> bool {
+        let text = token.text.as_str();
+        token.kind == TokenKind::Ident && (
+            BASE_TYPES.contains(&text) || SPECIFIER_KEYWORDS.contains(&text)
+            || UNSUPPORTED_TYPES.contains(&text) || self.typedef_names.contains(text)
+        )
+    }
+
+    fn parse_program(&mut self) -> Result<RootAstNode, CompileError> {
+        let mut externals = Vec::new();
+        while self.peek().kind != TokenKind::Eof {
+            self.parse_external(&mut externals)?;
+        }
+        Ok(RootAstNode { externals })
+    }
+
+    /// a function definition or a declaration at file scope, a declaration can declare several externals
+    fn parse_external(&mut self, externals: &mut Vec<External>) -> Result<(), CompileError> {
+        let start = self.peek().clone();
+        let mut specifiers = self.parse_specifiers()?;
+        if specifiers.is_typedef {
+            return self.parse_typedef(specifiers, externals);
+        }
+        if let Some(struct_decl) = specifiers.struct_decl.take() {
+            if struct_decl.name.is_empty() {
+                return Err(self.error(&start, "anonymous structs are only supported in typedefs".to_string()));
+            }
+            externals.push(External::StructDecl(struct_decl));
+        }
+        if self.accept(";") {
+            return Ok(());
+        }
+        let mut first = true;
+        loop {
+            let (name, decl_type) = self.parse_named_declarator(&specifiers)?;
+            if let DeclType::Func(ret_type, params, variadic) = decl_type {
+                let decl = self.func_decl(&name, *ret_type, params, variadic)?;
+                if first && self.is("{") {
+                    let body = self.parse_compound()?;
+                    externals.push(External::FuncDef(FuncDef { body, decl, loc: Some(name.loc) }));
+                    return Ok(());
+                }
+                externals.push(External::FuncDecl(decl));
+            } else {
+                let init = if self.accept("=") { Some(self.parse_initializer()?) } else { None };
+                let decl = self.decl(&name, decl_type, init)?;
+                externals.push(if specifiers.is_extern { External::ExternVarDecl(decl) } else { External::VarDecl(decl) });
+            }
+            first = false;
+            if !self.accept(",") {
+                break;
+            }
+        }
+        self.expect(";")?;
+        Ok(())
+    }
+
+    /// e.g typedef struct {int x;} Foo, *FooPtr;
+    fn parse_typedef(&mut self, mut specifiers: Specifiers, externals: &mut Vec<External>) -> Result<(), CompileError> {
+        let mut struct_decl = specifiers.struct_decl.take();
+        loop {
+            let (name, decl_type) = self.parse_named_declarator(&specifiers)?;
+            self.typedef_names.insert(name.text.clone());
+            let typedef = match (decl_type, &specifiers._type) {
+                (DeclType::Base(_), Type::Struct(struct_name)) => {
+                    // an anonymous struct is named after the typedef
+                    let struct_name = if struct_name.is_empty() { name.text.clone() } else { struct_name.clone() };
+                    let struct_decl = struct_decl.take().map(|struct_decl| StructDecl { name: struct_name.clone(), ..struct_decl });
+                    specifiers._type = Type::Struct(struct_name.clone());
+                    Typedef { name: name.text, _type: Type::Struct(struct_name), struct_decl }
+                },
+                (decl_type, _) => Typedef { name: name.text.clone(), _type: self.to_type(&name, decl_type)?, struct_decl: None },
+            };
+            externals.push(External::Typedef(typedef));
+            if !self.accept(",") {
+                break;
+            }
+        }
+        self.expect(";")?;
+        Ok(())
+    }
+
+    fn parse_specifiers(&mut self) -> Result<Specifiers, CompileError> {
+        let start = self.peek().clone();
+        let mut specifiers = Specifiers { _type: Type::Void, struct_decl: None, is_typedef: false, is_extern: false };
+        let mut has_type = false;
+        loop {
+            let token = self.peek().clone();
+            if token.kind != TokenKind::Ident {
+                break;
+            }
+            let text = token.text.as_str();
+            if UNSUPPORTED_TYPES.contains(&text) {
+                return Err(self.error(&token, format!("type '{}' is not supported", text)));
+            }
+            if SPECIFIER_KEYWORDS.contains(&text) {
+                specifiers.is_typedef |= text == "typedef";
+                specifiers.is_extern |= text == "extern";
+                self.next();
+                continue;
+            }
+            let is_type = BASE_TYPES.contains(&text) || self.typedef_names.contains(text);
+            if !is_type || has_type {
+                break;
+            }
+            self.next();
+            specifiers._type = match text {
+                "int" => Type::Int,
+                "char" => Type::Char,
+                "float" => Type::Float,
+                "long" => {
+                    // long long & long int are the same type
+                    self.accept("long");
+                    self.accept("int");
+                    Type::Long
+                },
+                "void" => Type::Void,
+                "struct" => {
+                    let (_type, struct_decl) = self.parse_struct()?;
+                    specifiers.struct_decl = struct_decl;
+                    _type
+                },
+                name => Type::Alias(name.to_string()),
+            };
+            has_type = true;
+        }
+        if !has_type {
+            return Err(self.error(&start, format!("expected a type but found '{}'", start.text)));
+        }
+        Ok(specifiers)
+    }
+
+    /// the rest of a struct specifier after the struct keyword, returns the struct's definition if it has one
+    fn parse_struct(&mut self) -> Result<(Type, Option<StructDecl>), CompileError> {
+        let name = if self.is("{") { String::new() } else { self.expect_identifier()?.text };
+        if !self.accept("{") {
+            return Ok((Type::Struct(name), None));
+        }
+        let mut items = LinkedHashMap::new();
+        let mut nested = Vec::new();
+        while !self.accept_close("}")? {
+            let mut specifiers = self.parse_specifiers()?;
+            if let Some(struct_decl) = specifiers.struct_decl.take() {
+                nested.push(struct_decl);
+            }
+            loop {
+                let (item_name, decl_type) = self.parse_named_declarator(&specifiers)?;
+                items.insert(item_name.text.clone(), self.decl(&item_name, decl_type, None)?);
+                if !self.accept(",") {
+                    break;
+                }
+            }
+            self.expect(";")?;
+        }
+        Ok((Type::Struct(name.clone()), Some(StructDecl { name, items, nested })))
+    }
+
+    fn parse_named_declarator(&mut self, specifiers: &Specifiers) -> Result<(Token, DeclType), CompileError> {
+        let (name, decl_type) = self.parse_declarator(DeclType::Base(specifiers._type.clone()))?;
+        match name {
+            Some(name) => Ok((name, decl_type)),
+            None => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    /// parses a declarator, e.g *(*x)[3], the name is None for an abstract declarator, e.g the (*)(int) of a cast
+    /// the pointers apply to the base type, then the suffixes from the last one, & then the inner declarator
+    fn parse_declarator(&mut self, base: DeclType) -> Result<(Option<Token>, DeclType), CompileError> {
+        let mut decl_type = base;
+        while self.accept("*") {
+            while self.accept("const") || self.accept("volatile") {}
+            decl_type = DeclType::Ptr(Box::new(decl_type));
+        }
+        let mut name = None;
+        let mut inner_start = None;
+        if self.peek().kind == TokenKind::Ident && !self.is_type_start(self.peek()) {
+            name = Some(self.expect_identifier()?);
+        } else if self.is("(") && !self.is_type_start(self.peek_at(1)) && self.peek_at(1).text != ")" {
+            // the inner declarator is parsed after the suffixes, since it applies to the type they make
+            self.next();
+            inner_start = Some(self.pos);
+            self.skip_parens()?;
+        }
+        let mut suffixes = Vec::new();
+        loop {
+            if self.accept("[") {
+                let dim = if self.is("]") { None } else { Some(self.parse_array_dim()?) };
+                self.expect("]")?;
+                suffixes.push((true, dim, Vec::new(), false));
+            } else if self.is("(") {
+                let (params, variadic) = self.parse_params()?;
+                suffixes.push((false, None, params, variadic));
+            } else {
+                break;
+            }
+        }
+        for (is_array, dim, params, variadic) in suffixes.into_iter().rev() {
+            decl_type = if is_array { DeclType::Array(Box::new(decl_type), dim) } else { DeclType::Func(Box::new(decl_type), params, variadic) };
+        }
+        if let Some(inner_start) = inner_start {
+            let end = self.pos;
+            self.pos = inner_start;
+            let (inner_name, inner_type) = self.parse_declarator(decl_type)?;
+            self.expect(")")?;
+            self.pos = end;
+            return Ok((inner_name, inner_type));
+        }
+        Ok((name, decl_type))
+    }
+
+    /// skips to after the parenthesis that closes the one before the current token
+    fn skip_parens(&mut self) -> Result<(), CompileError> {
+        let mut depth = 1;
+        while depth > 0 {
+            let token = self.next();
+            match token.text.as_str() {
+                "(" if token.kind == TokenKind::Punct => depth += 1,
+                ")" if token.kind == TokenKind::Punct => depth -= 1,
+                _ if token.kind == TokenKind::Eof => return Err(self.unexpected("')'")),
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_array_dim(&mut self) -> Result<u32, CompileError> {
+        let token = self.peek().clone();
+        match self.parse_conditional()? {
+            Expression::Constant(Constant { _type: Type::Int, val }) if val.parse::<u32>().is_ok() => Ok(val.parse().unwrap()),
+            _ => Err(self.error(&token, "array size must be an integer constant".to_string())),
+        }
+    }
+
+    /// (int x, char* s), () & (void) have no params
+    /// returns the params & whether they end with ..., which must follow a named param, e.g (char* fmt, ...)
+    fn parse_params(&mut self) -> Result<(Params, bool), CompileError> {
+        self.expect("(")?;
+        let mut params = Vec::new();
+        if self.is("void") && self.peek_at(1).text == ")" {
+            self.next();
+        }
+        while !self.accept_close(")")? {
+            if !params.is_empty() {
+                self.expect(",")?;
+            }
+            if self.is("...") {
+                if params.is_empty() {
+                    return Err(self.error(self.peek(), "'...' must follow a named parameter".to_string()));
+                }
+                self.next();
+                self.expect(")")?;
+                return Ok((params, true));
+            }
+            let specifiers = self.parse_specifiers()?;
+            params.push(self.parse_declarator(DeclType::Base(specifiers._type))?);
+        }
+        Ok((params, false))
+    }
+
+    /// a type name, e.g the int* of sizeof(int*) or of a cast
+    fn parse_type_name(&mut self) -> Result<Type, CompileError> {
+        let start = self.peek().clone();
+        let specifiers = self.parse_specifiers()?;
+        let (name, decl_type) = self.parse_declarator(DeclType::Base(specifiers._type))?;
+        if let Some(name) = name {
+            return Err(self.error(&name, format!("unexpected identifier '{}' in type name", name.text)));
+        }
+        self.to_type(&start, decl_type)
+    }
+
+    fn to_type(&self, token: &Token, decl_type: DeclType) -> Result<Type, CompileError> {
+        match decl_type {
+            DeclType::Base(_type) => Ok(_type),
+            DeclType::Ptr(inner) => match *inner {
+                DeclType::Func(_, _, true) => Err(self.error(token, "pointers to variadic functions are not supported".to_string())),
+                DeclType::Func(ret_type, params, false) => {
+                    let mut args = Vec::new();
+                    for (_, param_type) in params {
+                        args.push(self.to_type(token, param_type)?);
+                    }
+                    Ok(Type::FuncPtr { ret_type: Box::new(self.to_type(token, *ret_type)?), args })
+                },
+                inner => Ok(Type::Ptr(Box::new(self.to_type(token, inner)?))),
+            },
+            DeclType::Array(..) => Err(self.error(token, "arrays are only supported as variables".to_string())),
+            DeclType::Func(..) => Err(self.error(token, "functions can only be declared at file scope".to_string())),
+        }
+    }
+
+    /// an array declarator is an ArrayDecl, e.g int arr[2][3] has dimentions [2, 3]
+    fn decl(&self, name: &Token, decl_type: DeclType, init: Option<Initializer>) -> Result<Decl, CompileError> {
+        if let DeclType::Array(..) = decl_type {
+            let mut dimentions = Vec::new();
+            let mut element_type = decl_type;
+            while let DeclType::Array(inner, dim) = element_type {
+                dimentions.push(dim);
+                element_type = *inner;
+            }
+            let _type = self.to_type(name, element_type)?;
+            // only the size of the first dimention may be left out, if it's given by the initializer
+            if dimentions[1..].iter().any(Option::is_none) || (dimentions[0].is_none() && init.is_none()) {
+                return Err(self.error(name, format!("array '{}' has no size", name.text)));
+            }
+            let init = match init {
+                Some(init) => {
+                    let (items, first_dim) = self.array_init(name, init, &dimentions, _type == Type::Char)?;
+                    dimentions[0] = Some(first_dim);
+                    Some(items)
+                },
+                None => None,
+            };
+            let dimentions = dimentions.into_iter().map(Option::unwrap).collect();
+            return Ok(Decl::ArrayDecl(ArrayDecl { name: name.text.clone(), _type, dimentions, init }));
+        }
+        let init = match init {
+            Some(Initializer::Expr(expr)) => Some(expr),
+            Some(Initializer::List(_)) => return Err(self.error(name, "initializer lists are only supported for arrays".to_string())),
+            None => None,
+        };
+        Ok(Decl::VarDecl(VarDecl { name: name.text.clone(), _type: self.to_type(name, decl_type)?, init }))
+    }
+
+    /// the items of an array's initializer in order, e.g {{1}, {2, 3}} of int arr[2][2] is 1, 0, 2, 3
+    /// the items after the last initialized one are left out, they're zero filled
+    /// also returns the size of the first dimention, if it's left out it's the size that fits the initializer,
+    /// e.g 3 for char s[] = "ab" & 2 for int arr[][2] = {1, 2, 3}
+    fn array_init(&self, name: &Token, init: Initializer, dimentions: &[Option<u32>], is_char: bool) -> Result<(Vec<Expression>, u32), CompileError> {
+        let inner: Vec<u32> = dimentions[1..].iter().map(|dim| dim.unwrap()).collect();
+        let first_dim = match (dimentions[0], &init) {
+            (Some(dim), _) => dim,
+            // with the terminating null
+            (None, Initializer::Expr(Expression::Constant(Constant { _type: Type::_String, val }))) if is_char && inner.is_empty() => {
+                string_constant_value(val).chars().count() as u32 + 1
+            },
+            // each item initializes at least one subarray, so there are at most as many subarrays as items
+            (None, Initializer::List(list)) => list.len() as u32,
+            (None, Initializer::Expr(_)) => return Err(self.error(name, format!("invalid initializer for array '{}'", name.text))),
+        };
+        let is_list = matches!(init, Initializer::List(_));
+        let mut items = Vec::new();
+        let initialized = self.flatten_init(name, init, &[&[first_dim], &inner[..]].concat(), is_char, 0, &mut items)?;
+        let first_dim = if dimentions[0].is_none() && is_list {
+            let sub_size = inner.iter().product::<u32>().max(1) as usize;
+            initialized.div_ceil(sub_size) as u32
+        } else {
+            first_dim
+        };
+        if first_dim == 0 {
+            return Err(self.error(name, format!("array '{}' has no size", name.text)));
+        }
+        let zero = || Expression::Constant(Constant { _type: Type::Int, val: "0".to_string() });
+        Ok((items.into_iter().map(|item| item.unwrap_or_else(zero)).collect(), first_dim))
+    }
+
+    /// sets the items of the (sub)array that starts at items[start] to its initializer
+    /// returns the number of items the initializer covers, from the start of the subarray
+    /// as in C, the braces of a subarray's initializer may be left out, e.g int arr[2][2] = {1, 2, 3}
+    /// & a char array may be initialized with a string, e.g char names[2][4] = {"ab", "cd"}
+    fn flatten_init(&self, name: &Token, init: Initializer, dimentions: &[u32], is_char: bool, start: usize, items: &mut Vec<Option<Expression>>) -> Result<usize, CompileError> {
+        let size = dimentions.iter().product::<u32>() as usize;
+        let sub_size = dimentions[1..].iter().product::<u32>() as usize;
+        let list = match init {
+            Initializer::Expr(Expression::Constant(Constant { _type: Type::_String, val })) if is_char && dimentions.len() == 1 => {
+                let s = string_constant_value(&val);
+                // the terminating null is left out if the array only fits the chars
+                if s.chars().count() > size {
+                    return Err(self.error(name, format!("initializer string for array '{}' is too long", name.text)));
+                }
+                s.chars().map(|c| Initializer::Expr(Expression::Constant(Constant { _type: Type::Char, val: format!("'{}'", escape(&c.to_string())) }))).collect()
+            },
+            Initializer::List(list) => list,
+            Initializer::Expr(_) => return Err(self.error(name, format!("invalid initializer for array '{}'", name.text))),
+        };
+        let excess = || self.error(name, format!("excess elements in initializer of array '{}'", name.text));
+        if size == 0 && !list.is_empty() {
+            return Err(excess());
+        }
+        let mut pos: usize = 0;
+        for item in list {
+            let is_subarray = dimentions.len() > 1 && match &item {
+                Initializer::List(_) => true,
+                Initializer::Expr(Expression::Constant(constant)) => is_char && dimentions.len() == 2 && constant._type == Type::_String,
+                Initializer::Expr(_) => false,
+            };
+            if is_subarray {
+                // starts at the next subarray, if the previous items were a part of one
+                pos = pos.div_ceil(sub_size) * sub_size;
+            }
+            if pos >= size {
+                return Err(excess());
+            }
+            if is_subarray {
+                self.flatten_init(name, item, &dimentions[1..], is_char, start + pos, items)?;
+                pos += sub_size;
+                continue;
+            }
+            match item {
+                Initializer::Expr(expr) => {
+                    if items.len() <= start + pos {
+                        items.resize(start + pos + 1, None);
+                    }
+                    items[start + pos] = Some(expr);
+                    pos += 1;
+                },
+                Initializer::List(_) => return Err(self.error(name, format!("braces around an item of array '{}'", name.text))),
+            }
+        }
+        Ok(pos)
+    }
+
+    fn func_decl(&self, name: &Token, ret_type: DeclType, params: Params, variadic: bool) -> Result<FuncDecl, CompileError> {
+        let mut args = Vec::new();
+        for (param_name, param_type) in params {
+            // the params of a prototype may be unnamed
+            let param_name = param_name.unwrap_or_else(|| Token { text: String::new(), ..name.clone() });
+            args.push(self.decl(&param_name, param_type, None)?);
+        }
+        Ok(FuncDecl { name: name.text.clone(), args, ret_type: self.to_type(name, ret_type)?, variadic })
+    }
+
+    fn parse_initializer(&mut self) -> Result<Initializer, CompileError> {
+        if !self.accept("{") {
+            return Ok(Initializer::Expr(self.parse_assignment()?));
+        }
+        let mut items = Vec::new();
+        while !self.accept_close("}")? {
+            items.push(self.parse_initializer()?);
+            if !self.accept(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+        Ok(Initializer::List(items))
+    }
+
+    /// the declarations of a local declaration statement, one for each declarator
+    fn parse_local_decls(&mut self) -> Result<Vec<(Decl, SourceLoc)>, CompileError> {
+        let start = self.peek().clone();
+        let specifiers = self.parse_specifiers()?;
+        if specifiers.is_typedef || specifiers.struct_decl.is_some() {
+            return Err(self.error(&start, "typedefs & struct definitions are only supported at file scope".to_string()));
+        }
+        let mut decls = Vec::new();
+        loop {
+            let (name, decl_type) = self.parse_named_declarator(&specifiers)?;
+            let init = if self.accept("=") { Some(self.parse_initializer()?) } else { None };
+            decls.push((self.decl(&name, decl_type, init)?, name.loc));
+            if !self.accept(",") {
+                break;
+            }
+        }
+        self.expect(";")?;
+        Ok(decls)
+    }
+
+    fn parse_compound(&mut self) -> Result<Compound, CompileError> {
+        let start = self.expect("{")?;
+        let mut compound = Compound { items: Vec::new(), item_locs: Vec::new(), code_loc: code_loc(&start) };
+        while !self.accept_close("}")? {
+            self.parse_block_item(&mut compound)?;
+        }
+        Ok(compound)
+    }
+
+    /// appends a statement or the declarations of a declaration statement to the compound
+    fn parse_block_item(&mut self, compound: &mut Compound) -> Result<(), CompileError> {
+        let start = self.peek().clone();
+        if self.is_type_start(&start) && self.peek_at(1).text != ":" {
+            for (decl, loc) in self.parse_local_decls()? {
+                compound.items.push(Statement::Decl(decl));
+                compound.item_locs.push(Some(loc));
+            }
+            return Ok(());
+        }
+        compound.items.push(self.parse_statement()?);
+        compound.item_locs.push(Some(start.loc));
+        Ok(())
+    }
+
+    /// the body of an if, a loop or a switch, a single statement is put in a compound
+    fn parse_body(&mut self) -> Result<Compound, CompileError> {
+        let start = self.peek().clone();
+        match self.parse_statement()? {
+            Statement::Compound(compound) => Ok(compound),
+            statement => Ok(Compound { items: vec![statement], item_locs: vec![Some(start.loc.clone())], code_loc: code_loc(&start) }),
+        }
+    }
+
+    /// the init or the next expression of a for loop, the expressions of a comma are separate statements
+    fn expression_compound(&mut self) -> Result<Compound, CompileError> {
+        let start = self.peek().clone();
+        let items = match self.parse_expr()? {
+            Expression::Comma(exprs) => exprs.into_iter().map(Statement::Expression).collect(),
+            expr => vec![expression_statement(expr)],
+        };
+        Ok(Compound { item_locs: vec![Some(start.loc.clone()); items.len()], items, code_loc: code_loc(&start) })
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement, CompileError> {
+        let start = self.peek().clone();
+        if start.kind == TokenKind::Ident && self.peek_at(1).text == ":" && !KEYWORDS.contains(&start.text.as_str()) {
+            self.next();
+            self.next();
+            return Ok(Statement::Label(Label { name: start.text, stmt: Box::new(self.parse_statement()?) }));
+        }
+        let is_keyword = start.kind == TokenKind::Ident;
+        match start.text.as_str() {
+            "{" => Ok(Statement::Compound(self.parse_compound()?)),
+            ";" => {
+                self.next();
+                Ok(Statement::Compound(Compound { items: Vec::new(), item_locs: Vec::new(), code_loc: code_loc(&start) }))
+            },
+            "if" if is_keyword => {
+                self.next();
+                self.expect("(")?;
+                let cond = self.parse_expr()?;
+                self.expect(")")?;
+                let iftrue = Box::new(self.parse_body()?);
+                let iffalse = if self.accept("else") { Some(Box::new(self.parse_body()?)) } else { None };
+                Ok(Statement::If(If { cond, iftrue, iffalse, code_loc: code_loc(&start) }))
+            },
+            "while" if is_keyword => {
+                self.next();
+                self.expect("(")?;
+                let cond = self.parse_expr()?;
+                self.expect(")")?;
+                Ok(Statement::WhileLoop(WhileLoop { cond, body: Box::new(self.parse_body()?), code_loc: code_loc(&start) }))
+            },
+            "do" if is_keyword => {
+                self.next();
+                let body = Box::new(self.parse_body()?);
+                self.expect("while")?;
+                self.expect("(")?;
+                let cond = self.parse_expr()?;
+                self.expect(")")?;
+                self.expect(";")?;
+                Ok(Statement::DoWhileLoop(DoWhileLoop { cond, body, code_loc: code_loc(&start) }))
+            },
+            "for" if is_keyword => self.parse_for(),
+            "switch" if is_keyword => self.parse_switch(),
+            "return" if is_keyword => {
+                self.next();
+                let expr = if self.is(";") { None } else { Some(self.parse_expr()?) };
+                self.expect(";")?;
+                Ok(Statement::Return(Return { expr }))
+            },
+            "break" | "continue" if is_keyword => {
+                self.next();
+                self.expect(";")?;
+                Ok(if start.text == "break" { Statement::Break } else { Statement::Continue })
+            },
+            "goto" if is_keyword => {
+                self.next();
+                // goto *expr, a computed goto (a gnu extension)
+                if self.accept("*") {
+                    let target = self.parse_expr()?;
+                    self.expect(";")?;
+                    return Ok(Statement::ComputedGoto(target));
+                }
+                let label = self.expect_identifier()?;
+                self.expect(";")?;
+                Ok(Statement::Goto(label.text))
+            },
+            "case" | "default" if is_keyword => Err(self.error(&start, format!("'{}' label not within a switch statement", start.text))),
+            _ if self.is_type_start(&start) => Err(self.error(&start, "a declaration is not a statement".to_string())),
+            _ => {
+                let expr = self.parse_expr()?;
+                self.expect(";")?;
+                Ok(expression_statement(expr))
+            },
+        }
+    }
+
+    fn parse_for(&mut self) -> Result<Statement, CompileError> {
+        let start = self.expect("for")?;
+        self.expect("(")?;
+        let init = if self.accept(";") {
+            None
+        } else if self.is_type_start(self.peek()) {
+            let decls = self.parse_local_decls()?;
+            let item_locs = decls.iter().map(|(_, loc)| Some(loc.clone())).collect();
+            let items = decls.into_iter().map(|(decl, _)| Statement::Decl(decl)).collect();
+            Some(Box::new(Compound { items, item_locs, code_loc: code_loc(&start) }))
+        } else {
+            let init = self.expression_compound()?;
+            self.expect(";")?;
+            Some(Box::new(init))
+        };
+        let cond = if self.is(";") { None } else { Some(self.parse_expr()?) };
+        self.expect(";")?;
+        let next = if self.is(")") { None } else { Some(Box::new(self.expression_compound()?)) };
+        self.expect(")")?;
+        let body = Box::new(self.parse_body()?);
+        Ok(Statement::ForLoop(ForLoop { cond, init, body, next, code_loc: code_loc(&start) }))
+    }
+
+    /// the statements of the cases are flattened into the switch's body, each case holds the index of its first statement
+    fn parse_switch(&mut self) -> Result<Statement, CompileError> {
+        let start = self.expect("switch")?;
+        self.expect("(")?;
+        let cond = self.parse_expr()?;
+        self.expect(")")?;
+        let mut body = Compound { items: Vec::new(), item_locs: Vec::new(), code_loc: code_loc(&start) };
+        let mut cases = Vec::new();
+        if self.accept("{") {
+            while !self.accept_close("}")? {
+                self.parse_switch_item(&mut body, &mut cases)?;
+            }
+        } else {
+            // a switch without braces has a single statement, which can be labeled with cases
+            while self.parse_switch_item(&mut body, &mut cases)? {}
+        }
+        Ok(Statement::Switch(Switch { cond, code_loc: body.code_loc.clone(), body: Box::new(body), cases }))
+    }
+
+    /// parses a case label or a block item, returns whether it was a case label
+    fn parse_switch_item(&mut self, body: &mut Compound, cases: &mut Vec<Case>) -> Result<bool, CompileError> {
+        if self.accept("case") {
+            let value = self.parse_conditional()?;
+            self.expect(":")?;
+            cases.push(Case { value: Some(value), first_item: body.items.len() });
+            return Ok(true);
+        }
+        if self.accept("default") {
+            self.expect(":")?;
+            cases.push(Case { value: None, first_item: body.items.len() });
+            return Ok(true);
+        }
+        self.parse_block_item(body)?;
+        Ok(false)
+    }
+
+    /// a comma separated list of expressions
+    fn parse_expr(&mut self) -> Result<Expression, CompileError> {
+        let expr = self.parse_assignment()?;
+        if !self.is(",") {
+            return Ok(expr);
+        }
+        let mut exprs = vec![expr];
+        while self.accept(",") {
+            exprs.push(self.parse_assignment()?);
+        }
+        Ok(Expression::Comma(exprs))
+    }
+
+    fn parse_assignment(&mut self) -> Result<Expression, CompileError> {
+        let lvalue = self.parse_conditional()?;
+        let token = self.peek().clone();
+        if token.kind != TokenKind::Punct || !ASSIGNMENT_OPS.contains(&token.text.as_str()) {
+            return Ok(lvalue);
+        }
+        self.next();
+        // e.g += is an assignment with ADD
+        let op = BinaryopType::from_op(&token.text[..token.text.len() - 1]);
+        let rvalue = self.parse_assignment()?;
+        Ok(Expression::Assignment(Assignment { op: AssignmentOp { op }, lvalue: Box::new(lvalue), rvalue: Box::new(rvalue) }))
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expression, CompileError> {
+        let cond = self.parse_binary(0)?;
+        if !self.accept("?") {
+            return Ok(cond);
+        }
+        let iftrue = self.parse_expr()?;
+        self.expect(":")?;
+        let iffalse = self.parse_conditional()?;
+        Ok(Expression::TernaryOp(TernaryOp { cond: Box::new(cond), iftrue: Box::new(iftrue), iffalse: Box::new(iffalse) }))
+    }
+
+    /// precedence climbing over the binary operators, all of them are left associative
+    fn parse_binary(&mut self, min_precedence: u32) -> Result<Expression, CompileError> {
+        let mut left = self.parse_cast()?;
+        loop {
+            let token = self.peek();
+            let op = BINARY_OPS.iter().find(|(op, _)| token.kind == TokenKind::Punct && token.text == *op);
+            let (op, precedence) = match op {
+                Some(&(op, precedence)) if precedence > min_precedence => (op, precedence),
+                _ => return Ok(left),
+            };
+            self.next();
+            let right = self.parse_binary(precedence)?;
+            left = Expression::BinaryOp(BinaryOp { op_type: BinaryopType::from_op(op).unwrap(), left: Box::new(left), right: Box::new(right) });
+        }
+    }
+
+    fn parse_cast(&mut self) -> Result<Expression, CompileError> {
+        if self.is("(") && self.is_type_start(self.peek_at(1)) {
+            self.next();
+            let _type = self.parse_type_name()?;
+            self.expect(")")?;
+            let expr = self.parse_cast()?;
+            return Ok(Expression::Cast(Cast { expr: Box::new(expr), _type }));
+        }
+        self.parse_unary()
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, CompileError> {
+        let token = self.peek().clone();
+        let op_type = match token.text.as_str() {
+            _ if token.kind == TokenKind::Ident && token.text == "sizeof" => {
+                self.next();
+                if self.is("(") && self.is_type_start(self.peek_at(1)) {
+                    self.next();
+                    let _type = self.parse_type_name()?;
+                    self.expect(")")?;
+                    return Ok(unary_op(UnaryopType::SIZEOF, Expression::TypeName(TypeName { _type })));
+                }
+                return Ok(unary_op(UnaryopType::SIZEOF, self.parse_unary()?));
+            },
+            _ if token.kind != TokenKind::Punct => return self.parse_postfix(),
+            "++" | "--" => {
+                self.next();
+                let op_type = if token.text == "++" { UnaryopType::PPX } else { UnaryopType::MMX };
+                return Ok(unary_op(op_type, self.parse_unary()?));
+            },
+            "+" => {
+                self.next();
+                return self.parse_cast();
+            },
+            "-" => UnaryopType::NEG,
+            "!" => UnaryopType::NOT,
+            "~" => UnaryopType::BNOT,
+            "&" => UnaryopType::REF,
+            "*" => UnaryopType::DEREF,
+            "&&" => {
+                // the address of a label, for computed gotos (a gnu extension)
+                self.next();
+                let label = self.expect_identifier()?;
+                return Ok(Expression::LabelAddr(label.text));
+            },
+            _ => return self.parse_postfix(),
+        };
+        self.next();
+        Ok(unary_op(op_type, self.parse_cast()?))
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expression, CompileError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            let token = self.peek().clone();
+            if token.kind != TokenKind::Punct {
+                return Ok(expr);
+            }
+            expr = match token.text.as_str() {
+                "[" => {
+                    self.next();
+                    let index = Box::new(self.parse_expr()?);
+                    self.expect("]")?;
+                    // a[1][2] is a single ArrayRef with 2 indices
+                    match expr {
+                        Expression::NameRef(NameRef::ArrayRef(mut array_ref)) => {
+                            array_ref.indices.push(index);
+                            Expression::NameRef(NameRef::ArrayRef(array_ref))
+                        },
+                        Expression::NameRef(name) => Expression::NameRef(NameRef::ArrayRef(ArrayRef { name: Box::new(name), indices: vec![index] })),
+                        _ => return Err(self.error(&token, "only a variable, an array element or a struct member can be subscripted".to_string())),
+                    }
+                },
+                "." | "->" => {
+                    self.next();
+                    let field = self.expect_identifier()?.text;
+                    let _type = if token.text == "." { StructRefType::DOT } else { StructRefType::ARROW };
+                    match expr {
+                        Expression::NameRef(name) => Expression::NameRef(NameRef::StructRef(StructRef { name: Box::new(name), field, _type })),
+                        _ => return Err(self.error(&token, format!("the left side of '{}' must be a variable, an array element or a struct member", token.text))),
+                    }
+                },
+                "(" => {
+                    self.next();
+                    let mut args = Vec::new();
+                    while !self.accept_close(")")? {
+                        if !args.is_empty() {
+                            self.expect(",")?;
+                        }
+                        args.push(Box::new(self.parse_assignment()?));
+                    }
+                    // calling through a dereferenced function pointer, e.g (*f)(x), is the same as f(x)
+                    let name = match expr {
+                        Expression::NameRef(NameRef::ID(id)) => id.name,
+                        Expression::UnaryOp(UnaryOp { op_type: UnaryopType::DEREF, id: Some(id), .. }) => id.name,
+                        _ => return Err(self.error(&token, "only a function or a function pointer variable can be called".to_string())),
+                    };
+                    Expression::FuncCall(FuncCall { name, args })
+                },
+                "++" => {
+                    self.next();
+                    unary_op(UnaryopType::XPP, expr)
+                },
+                "--" => {
+                    self.next();
+                    unary_op(UnaryopType::XMM, expr)
+                },
+                _ => return Ok(expr),
+            };
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, CompileError> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Ident if !KEYWORDS.contains(&token.text.as_str()) => {
+                self.next();
+                Ok(Expression::NameRef(NameRef::ID(ID { name: token.text })))
+            },
+            TokenKind::Number => {
+                self.next();
+                // the u suffix is ignored, e.g 10u is an int
+                let digits = token.text.trim_end_matches(['u', 'U', 'l', 'L']);
+                let int_val = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+                    Some(hex) => i64::from_str_radix(hex, 16).ok(),
+                    // e.g 017 is octal
+                    None if digits.len() > 1 && digits.starts_with('0') && digits.chars().all(|c| c.is_ascii_digit()) => {
+                        let octal = i64::from_str_radix(&digits[1..], 8)
+                            .map_err(|_| self.error(&token, format!("invalid octal constant '{}'", token.text)))?;
+                        Some(octal)
+                    },
+                    None => digits.parse().ok(),
+                };
+                if let Some(int_val) = int_val {
+                    // e.g 5L, or a constant that doesn't fit in an int
+                    if token.text[digits.len()..].contains(['l', 'L']) || int_val > i32::MAX as i64 {
+                        return Ok(Expression::Constant(Constant { _type: Type::Long, val: int_val.to_string() }));
+                    }
+                    return Ok(Expression::Constant(Constant { _type: Type::Int, val: int_val.to_string() }));
+                }
+                // e.g 1.5, .5f or 1e-3
+                let val = token.text.trim_end_matches(['f', 'F']);
+                if token.text.starts_with("0x") || val.parse::<f32>().is_err() {
+                    return Err(self.error(&token, format!("constant '{}' is not supported", token.text)));
+                }
+                Ok(Expression::Constant(Constant { _type: Type::Float, val: val.to_string() }))
+            },
+            TokenKind::Char => {
+                self.next();
+                Ok(Expression::Constant(Constant { _type: Type::Char, val: token.text }))
+            },
+            TokenKind::Str => {
+                // adjacent string literals are concatenated, e.g "ab" "cd" is "abcd"
+                let mut val = self.next().text;
+                while self.peek().kind == TokenKind::Str {
+                    val.pop();
+                    val.push_str(&self.next().text[1..]);
+                }
+                Ok(Expression::Constant(Constant { _type: Type::_String, val }))
+            },
+            TokenKind::Punct if token.text == "(" => {
+                self.next();
+                let expr = self.parse_expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            },
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+}
+
+/// parses a preprocessed program, the source locations follow its #line directives
+pub fn parse(program: &str) -> Result<RootAstNode, CompileError> {
+    let mut parser = Parser { tokens: tokenize(program)?, pos: 0, typedef_names: HashSet::new() };
+    parser.parse_program()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    fn parse_error(program: &str) -> String {
+        parse(&format!("#line 1 \"main.c\"\n{}", program)).err().unwrap().to_string()
+    }
+    #[test]
+    fn test_declarators(){
+        let root = parse("void (*funcs[2])(int, char*); typedef struct {int x;} Point, *PointPtr;").unwrap();
+        match &root.externals[0] {
+            External::VarDecl(Decl::ArrayDecl(array)) => {
+                assert_eq!(array.name, "funcs");
+                assert_eq!(array.dimentions, vec![2]);
+                assert_eq!(array._type, Type::FuncPtr{ret_type: Box::new(Type::Void), args: vec![Type::Int, Type::Ptr(Box::new(Type::Char))]});
+            },
+            _ => panic!(),
+        }
+        match (&root.externals[1], &root.externals[2]) {
+            (External::Typedef(point), External::Typedef(point_ptr)) => {
+                assert_eq!(point._type, Type::Struct("Point".to_string()));
+                assert_eq!(point.struct_decl.as_ref().unwrap().name, "Point");
+                assert_eq!(point_ptr._type, Type::Ptr(Box::new(Type::Struct("Point".to_string()))));
+            },
+            _ => panic!(),
+        }
+    }
+    #[test]
+    fn test_variadic(){
+        let root = parse("int printf(char* fmt, ...); int f(int x);").unwrap();
+        match (&root.externals[0], &root.externals[1]) {
+            (External::FuncDecl(printf), External::FuncDecl(f)) => {
+                assert_eq!(printf.args.len(), 1);
+                assert!(printf.variadic);
+                assert!(!f.variadic);
+            },
+            _ => panic!(),
+        }
+    }
+    #[test]
+    fn test_initializers(){
+        let root = parse("int a[3][2] = {{1}, 2, 3, {4}}; char s[2][3] = {\"ab\", \"c\"};").unwrap();
+        let vals = |external: &External| match external {
+            External::VarDecl(Decl::ArrayDecl(array)) => array.init.as_ref().unwrap().iter().map(|expr| match expr {
+                Expression::Constant(constant) => constant.val.clone(),
+                _ => panic!(),
+            }).collect::<Vec<String>>(),
+            _ => panic!(),
+        };
+        assert_eq!(vals(&root.externals[0]), vec!["1", "0", "2", "3", "4"]);
+        assert_eq!(vals(&root.externals[1]), vec!["'a'", "'b'", "0", "'c'"]);
+        // the size of the first dimention is given by the initializer
+        let root = parse("char s[] = \"ab\"; int a[][2] = {1, 2, 3};").unwrap();
+        for (external, dimentions) in root.externals.iter().zip([vec![3], vec![2, 2]]) {
+            match external {
+                External::VarDecl(Decl::ArrayDecl(array)) => assert_eq!(array.dimentions, dimentions),
+                _ => panic!(),
+            }
+        }
+    }
+    #[test]
+    fn test_expressions(){
+        let root = parse("int main(){ return 1 + 2 * 3 - (char*) \"ab\" \"cd\"; }").unwrap();
+        let expr = match &root.externals[0] {
+            External::FuncDef(func_def) => match &func_def.body.items[0] {
+                Statement::Return(ret) => ret.expr.clone().unwrap(),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        };
+        // (1 + (2 * 3)) - cast
+        match expr {
+            Expression::BinaryOp(sub) => {
+                assert_eq!(sub.op_type, BinaryopType::SUB);
+                assert!(matches!(*sub.left, Expression::BinaryOp(BinaryOp{op_type: BinaryopType::ADD, ..})));
+                match *sub.right {
+                    Expression::Cast(cast) => match *cast.expr {
+                        Expression::Constant(constant) => assert_eq!(constant.val, "\"abcd\""),
+                        _ => panic!(),
+                    },
+                    _ => panic!(),
+                }
+            },
+            _ => panic!(),
+        }
+    }
+    #[test]
+    fn test_computed_goto(){
+        let root = parse("int main(){ void *p = x && &&end; goto *p; end: return 0; }").unwrap();
+        let items = match &root.externals[0] {
+            External::FuncDef(func_def) => func_def.body.items.clone(),
+            _ => panic!(),
+        };
+        match &items[0] {
+            Statement::Decl(Decl::VarDecl(var_decl)) => match var_decl.init.as_ref().unwrap() {
+                Expression::BinaryOp(and) => {
+                    assert_eq!(and.op_type, BinaryopType::LogicalAnd);
+                    assert!(matches!(&*and.right, Expression::LabelAddr(name) if name == "end"));
+                },
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+        assert!(matches!(&items[1], Statement::ComputedGoto(Expression::NameRef(NameRef::ID(id))) if id.name == "p"));
+    }
+    #[test]
+    fn test_syntax_errors(){
+        assert_eq!(parse_error("int main(){\n  return 1\n}"), "main.c:3: error: expected ';' but found '}'");
+        assert_eq!(parse_error("int main(){\n  int x = ;\n}"), "main.c:2: error: expected an expression but found ';'");
+        assert_eq!(parse_error("int main(){\n  return 0;\n"), "main.c:3: error: expected '}' but found end of file");
+        assert_eq!(parse_error("unsigned int x;"), "main.c:1: error: type 'unsigned' is not supported");
+        assert_eq!(parse_error("int x = 1.5.2;"), "main.c:1: error: constant '1.5.2' is not supported");
+        assert_eq!(parse_error("int x = 019;"), "main.c:1: error: invalid octal constant '019'");
+        assert_eq!(parse_error("int x = 1 @ 2;"), "main.c:1: error: unexpected character '@'");
+        assert_eq!(parse_error("char* s = \"abc;"), "main.c:1: error: missing terminating \" character");
+        assert_eq!(parse_error("int a[2][2] = {{1, 2, 3}};"), "main.c:1: error: excess elements in initializer of array 'a'");
+        assert_eq!(parse_error("char s[2] = \"ab\\n\";"), "main.c:1: error: initializer string for array 's' is too long");
+        assert_eq!(parse_error("int a[][2];"), "main.c:1: error: array 'a' has no size");
+        assert_eq!(parse_error("int a[2] = 5;"), "main.c:1: error: invalid initializer for array 'a'");
+        assert_eq!(parse_error("int f(...);"), "main.c:1: error: '...' must follow a named parameter");
+        assert_eq!(parse_error("int (*f)(int, ...);"), "main.c:1: error: pointers to variadic functions are not supported");
+    }
+}
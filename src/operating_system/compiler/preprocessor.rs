@@ -2,42 +2,222 @@ extern crate regex;
 use regex::Regex;
 
 
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
-use std::ffi::OsStr;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::error::{CompileError, ErrorKind};
+use super::macros::{Macro, Macros};
+use super::AST::SourceLoc;
 
 
 static STD_DIR : &str = "./libc";
 
-pub fn expand_include(include_str: &str, program_dir: &Path) -> Vec<String> {
-    let include_program_path = program_dir.join(Path::new(include_str));
-    let mut include_file = File::open(include_program_path.to_str().unwrap()).unwrap();
-    let mut include_program = String::new(); 
-    include_file.read_to_string(&mut include_program);
-    include_program.split("\n").map(|s| s.to_string()).collect()
+/// a #line directive, so the parser's coordinates refer to the original source files
+fn line_marker(line: usize, path: &str) -> String {
+    format!("#line {} \"{}\"", line, path)
+}
+
+/// the name of the macro that guards the whole file, e.g FOO_H in:
+/// #ifndef FOO_H
+/// #define FOO_H
+/// ...
+/// #endif
+fn include_guard(lines: &[&str]) -> Option<String> {
+    let is_code = |line: &&str| !line.trim().is_empty() && !line.trim().starts_with("//");
+    let mut code_lines = lines.iter().filter(|line| is_code(line));
+    let ifndef = code_lines.next()?;
+    let define = code_lines.next()?;
+    let endif = code_lines.next_back()?;
+    let name = ifndef.trim().strip_prefix("#ifndef")?.trim();
+    if define.split_whitespace().collect::<Vec<&str>>() != ["#define", name] || endif.trim() != "#endif" {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+/// an #if, #ifdef or #ifndef block whose #endif wasn't reached yet
+struct Conditional {
+    line: u32, // the line of the #if, for errors
+    parent_active: bool, // if the block is inside an inactive branch, all of its branches are inactive
+    taken: bool, // whether one of the block's branches was active
+    active: bool, // whether the lines of the current branch are kept
+}
+
+/// joins the lines that end with a backslash with the lines after them
+/// the joined lines are left blank, to keep the line numbers
+fn join_continued_lines(lines: &[&str]) -> Vec<String> {
+    let mut joined: Vec<String> = Vec::new();
+    let mut continued_i = None; // the index of the line that's being continued
+    for line in lines.iter() {
+        match continued_i {
+            Some(i) => {
+                let continued: &mut String = &mut joined[i];
+                continued.pop();
+                continued.push_str(line);
+                joined.push(String::new());
+            },
+            None => joined.push(line.to_string()),
+        }
+        let last_i = continued_i.unwrap_or(joined.len() - 1);
+        continued_i = if joined[last_i].ends_with('\\') { Some(last_i) } else { None };
+    }
+    joined
+}
+
+struct Preprocessor<'a> {
+    include_dirs: &'a [String], // searched for included files, in order
+    once_files: HashSet<PathBuf>, // files with #pragma once that were included so far
+    include_stack: Vec<PathBuf>, // the file being preprocessed & the files that included it
+    macros: Macros,
 }
 
-pub fn preprocess(program_path: &str) -> String{
-    let program_dir = Path::new(program_path).parent().unwrap();
-    let mut file = File::open(program_path).unwrap();
-    let mut program = String::new();
-    file.read_to_string(&mut program).unwrap();
-    let src_lines: Vec<&str> = program.split("\n").collect();
-    let mut dst_lines : Vec<String> = Vec::new();
-    let include_re = Regex::new("^#include \"(.+)\"$").unwrap();
-    let std_include_re = Regex::new("^#include <(.+)>$").unwrap();
-    for line in src_lines.iter(){
-        if let Some(caps) = include_re.captures(&line){
-            dst_lines.append(&mut expand_include(&caps[1], program_dir));
-        } else if let Some(caps) = std_include_re.captures(&line){
-            dst_lines.append(&mut expand_include(&caps[1], Path::new(STD_DIR)));
+impl<'a> Preprocessor<'a> {
+    /// "file.h" is searched next to the including file & then in the include dirs,
+    /// <file.h> is searched in the include dirs & then in the std dir
+    fn find_include(&self, name: &str, quoted: bool, including_dir: &Path) -> Option<PathBuf> {
+        let mut dirs = Vec::new();
+        if quoted {
+            dirs.push(including_dir.to_path_buf());
+        }
+        dirs.extend(self.include_dirs.iter().map(PathBuf::from));
+        if !quoted {
+            dirs.push(PathBuf::from(STD_DIR));
+        }
+        dirs.into_iter().map(|dir| dir.join(name)).find(|path| path.is_file())
+    }
+
+    /// appends the preprocessed lines of the file to dst_lines, the included files are preprocessed recursively
+    fn preprocess_file(&mut self, path: &Path, dst_lines: &mut Vec<String>) -> Result<(), CompileError> {
+        let program = fs::read_to_string(path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+        self.preprocess_source(&program, path, dst_lines)
+    }
+
+    /// like preprocess_file, with the file's content
+    fn preprocess_source(&mut self, program: &str, path: &Path, dst_lines: &mut Vec<String>) -> Result<(), CompileError> {
+        let path_str = path.to_str().unwrap();
+        let src_lines: Vec<&str> = program.split("\n").collect();
+        // a guarded file that was already included would be all blank lines, so it's skipped
+        if include_guard(&src_lines).is_some_and(|guard| self.macros.is_defined(&guard)) {
+            return Ok(());
+        }
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if self.once_files.contains(&canonical) {
+            return Ok(());
+        }
+        self.include_stack.push(canonical.clone());
+        let include_re = Regex::new(r#"^\s*#\s*include\s*(?:"(.+)"|<(.+)>)\s*$"#).unwrap();
+        let pragma_once_re = Regex::new(r"^\s*#\s*pragma\s+once\s*$").unwrap();
+        let define_re = Regex::new(r"^\s*#\s*define\s+(.*)$").unwrap();
+        let undef_re = Regex::new(r"^\s*#\s*undef\s+(\w+)\s*$").unwrap();
+        let conditional_re = Regex::new(r"^\s*#\s*(ifdef|ifndef|if|elif|else|endif)\b(.*)$").unwrap();
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        dst_lines.push(line_marker(1, path_str));
+        for (line_i, line) in join_continued_lines(&src_lines).iter().enumerate(){
+            let loc = SourceLoc { file: path_str.to_string(), line: line_i as u32 + 1 };
+            let active = conditionals.last().is_none_or(|conditional| conditional.active);
+            if let Some(caps) = conditional_re.captures(line) {
+                self.conditional(&caps[1], &caps[2], &mut conditionals, &loc)?;
+                dst_lines.push(String::new());
+            } else if !active {
+                dst_lines.push(String::new());
+            } else if let Some(caps) = include_re.captures(line){
+                let (name, quoted) = match caps.get(1) {
+                    Some(name) => (name.as_str(), true),
+                    None => (&caps[2], false),
+                };
+                let include_path = self.find_include(name, quoted, dir)
+                    .ok_or_else(|| CompileError { kind: ErrorKind::IncludeNotFound { name: name.to_string() }, loc: Some(loc.clone()) })?;
+                let include_canonical = fs::canonicalize(&include_path).unwrap_or_else(|_| include_path.clone());
+                if self.include_stack.contains(&include_canonical) {
+                    return Err(CompileError { kind: ErrorKind::CircularInclude { name: name.to_string() }, loc: Some(loc) });
+                }
+                self.preprocess_file(&include_path, dst_lines)?;
+                dst_lines.push(line_marker(line_i + 2, path_str));
+            } else if pragma_once_re.is_match(line) {
+                self.once_files.insert(canonical.clone());
+                dst_lines.push(String::new());
+            } else if let Some(caps) = define_re.captures(line) {
+                let (name, macro_def) = Macro::parse(&caps[1]).ok_or(CompileError { kind: ErrorKind::InvalidMacro, loc: Some(loc) })?;
+                self.macros.define(name, macro_def);
+                dst_lines.push(String::new());
+            } else if let Some(caps) = undef_re.captures(line) {
+                self.macros.undef(&caps[1]);
+                dst_lines.push(String::new());
+            } else if line.trim_start().starts_with('#') {
+                dst_lines.push(line.to_string());
+            }
+            else{
+                let expanded = self.macros.expand_line(line).map_err(|kind| CompileError { kind, loc: Some(loc) })?;
+                dst_lines.push(expanded);
+            }
+        }
+        if let Some(conditional) = conditionals.last() {
+            let loc = SourceLoc { file: path_str.to_string(), line: conditional.line };
+            return Err(CompileError { kind: ErrorKind::UnterminatedConditional, loc: Some(loc) });
+        }
+        self.include_stack.pop();
+        Ok(())
+    }
+
+    /// updates the file's open conditional blocks with an #if, #ifdef, #ifndef, #elif, #else or #endif
+    /// conditions are evaluated only if their branch can be taken
+    fn conditional(&self, directive: &str, arg: &str, conditionals: &mut Vec<Conditional>, loc: &SourceLoc) -> Result<(), CompileError> {
+        let error = |kind| CompileError { kind, loc: Some(loc.clone()) };
+        let is_defined = || arg.split_whitespace().next().is_some_and(|name| self.macros.is_defined(name));
+        let parent_active = conditionals.last().is_none_or(|conditional| conditional.active);
+        if matches!(directive, "if" | "ifdef" | "ifndef") {
+            let active = parent_active && match directive {
+                "ifdef" => is_defined(),
+                "ifndef" => !is_defined(),
+                _ => self.macros.eval_condition(arg).map_err(error)?,
+            };
+            conditionals.push(Conditional { line: loc.line, parent_active, taken: active, active });
+            return Ok(());
         }
-        else{
-            dst_lines.push(line.clone().to_string());
+        let conditional = conditionals.last_mut().ok_or_else(|| error(ErrorKind::UnmatchedDirective { directive: directive.to_string() }))?;
+        match directive {
+            "elif" => {
+                conditional.active = conditional.parent_active && !conditional.taken && self.macros.eval_condition(arg).map_err(error)?;
+                conditional.taken |= conditional.active;
+            },
+            "else" => {
+                conditional.active = conditional.parent_active && !conditional.taken;
+                conditional.taken = true;
+            },
+            _ => {
+                conditionals.pop();
+            },
         }
-    } 
-    dst_lines.join("\n")
+        Ok(())
+    }
+}
+
+/// expands the #include directives & the #define macros of the program & removes the inactive #if branches
+/// included files may include other files, a file with an include guard or #pragma once is included only once
+/// defines are (name, value) pairs that are defined before the program, like the -D flag of gcc
+pub fn preprocess(program_path: &str, include_dirs: &[String], defines: &[(String, String)]) -> Result<String, CompileError> {
+    let program = fs::read_to_string(program_path).unwrap_or_else(|err| panic!("{}: {}", program_path, err));
+    preprocess_str(&program, program_path, include_dirs, defines)
+}
+
+/// like preprocess, with a program that's in memory rather than in a file
+/// the name is the program's file name in errors, & "file.h" includes are searched next to it
+pub fn preprocess_str(program: &str, name: &str, include_dirs: &[String], defines: &[(String, String)]) -> Result<String, CompileError> {
+    let mut preprocessor = Preprocessor {
+        include_dirs,
+        once_files: HashSet::new(),
+        include_stack: Vec::new(),
+        macros: Macros::default(),
+    };
+    for (name, value) in defines.iter() {
+        let (name, macro_def) = Macro::parse(&format!("{} {}", name, value)).ok_or(CompileError { kind: ErrorKind::InvalidMacro, loc: None })?;
+        preprocessor.macros.define(name, macro_def);
+    }
+    let mut dst_lines = Vec::new();
+    preprocessor.preprocess_source(program, Path::new(name), &mut dst_lines)?;
+    Ok(dst_lines.join("\n"))
 }
 
 #[cfg(test)]
@@ -46,10 +226,59 @@ mod tests{
     #[test]
     fn test_include(){
         let program_path = "tests/preprocessor_test_data/include/main1.c";
-        let result = preprocess(program_path);
-        let mut target = String::new();
-        let mut target_f = File::open("tests/preprocessor_test_data/include/tar.c").unwrap();
-        target_f.read_to_string(&mut target);
+        let result = preprocess(program_path, &[], &[]).unwrap();
+        let target = fs::read_to_string("tests/preprocessor_test_data/include/tar.c").unwrap();
+        assert_eq!(result, target);
+    }
+    #[test]
+    fn test_nested_includes(){
+        // shapes.h & point.h are found in the include dir, & both include the guarded vec.h
+        let include_dirs = vec!["tests/preprocessor_test_data/nested/include".to_string()];
+        let result = preprocess("tests/preprocessor_test_data/nested/main.c", &include_dirs, &[]).unwrap();
+        let target = fs::read_to_string("tests/preprocessor_test_data/nested/tar.c").unwrap();
+        assert_eq!(result, target);
+    }
+    #[test]
+    fn test_macros(){
+        let result = preprocess("tests/preprocessor_test_data/macros/main.c", &[], &[]).unwrap();
+        let target = fs::read_to_string("tests/preprocessor_test_data/macros/tar.c").unwrap();
         assert_eq!(result, target);
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_conditionals(){
+        let path = "tests/preprocessor_test_data/conditionals/main.c";
+        let result = preprocess(path, &[], &[]).unwrap();
+        let target = fs::read_to_string("tests/preprocessor_test_data/conditionals/tar.c").unwrap();
+        assert_eq!(result, target);
+        let defines = vec![("BIG".to_string(), "".to_string()), ("PLATFORM".to_string(), "3".to_string())];
+        let result = preprocess(path, &[], &defines).unwrap();
+        let target = fs::read_to_string("tests/preprocessor_test_data/conditionals/tar_defines.c").unwrap();
+        assert_eq!(result, target);
+    }
+    #[test]
+    fn test_conditional_errors(){
+        let error = preprocess("tests/preprocessor_test_data/conditionals/unterminated.c", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "tests/preprocessor_test_data/conditionals/unterminated.c:1: error: unterminated conditional directive");
+        let error = preprocess("tests/preprocessor_test_data/conditionals/unmatched.c", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "tests/preprocessor_test_data/conditionals/unmatched.c:2: error: #endif without #if");
+        let error = preprocess("tests/preprocessor_test_data/conditionals/invalid.c", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "tests/preprocessor_test_data/conditionals/invalid.c:1: error: invalid #if condition '1 +'");
+    }
+    #[test]
+    fn test_preprocess_str(){
+        let program = fs::read_to_string("tests/preprocessor_test_data/include/main1.c").unwrap();
+        let result = preprocess_str(&program, "tests/preprocessor_test_data/include/main1.c", &[], &[]).unwrap();
+        assert_eq!(result, fs::read_to_string("tests/preprocessor_test_data/include/tar.c").unwrap());
+        let error = preprocess_str("#define X\n#endif\n", "memory.c", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "memory.c:2: error: #endif without #if");
+    }
+    #[test]
+    fn test_include_errors(){
+        let error = preprocess("tests/preprocessor_test_data/nested/main.c", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "tests/preprocessor_test_data/nested/main.c:1: error: 'shapes.h' file not found");
+        let error = preprocess("tests/preprocessor_test_data/circular/a.h", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "tests/preprocessor_test_data/circular/b.h:1: error: circular include of 'a.h'");
+        let error = preprocess("tests/preprocessor_test_data/macros/bad_args.c", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "tests/preprocessor_test_data/macros/bad_args.c:3: error: macro 'MAX' expects 2 arguments but 3 were given");
+    }
+}
@@ -0,0 +1,728 @@
+// semantic analysis pass that runs before codegen, checks that types are used correctly
+
+use std::collections::HashMap;
+
+use super::error::{CompileError, ErrorKind};
+use super::AST::*;
+
+/// the type of a named object
+#[derive(Clone, Debug)]
+enum VarType {
+    Value(Type),
+    Array(Type, usize), // item type, number of dimensions
+}
+
+impl VarType {
+    /// arrays decay to pointers when used as values
+    fn value_type(&self) -> Type {
+        match self {
+            VarType::Value(t) => t.clone(),
+            VarType::Array(item_type, _) => Type::Ptr(Box::new(item_type.clone())),
+        }
+    }
+}
+
+struct FuncSignature {
+    ret_type: Type,
+    args: Option<Vec<Type>>, // None if the function is declared without a parameter list, e.g int f()
+    variadic: bool, // more args may follow the typed ones
+}
+
+pub fn type_name(t: &Type) -> String {
+    match t {
+        Type::Int => "int".to_string(),
+        Type::Char => "char".to_string(),
+        Type::Float => "float".to_string(),
+        Type::Long => "long".to_string(),
+        Type::Void => "void".to_string(),
+        Type::_String => "char*".to_string(),
+        Type::Ptr(pointed_t) => format!("{}*", type_name(pointed_t)),
+        Type::Struct(name) => format!("struct {}", name),
+        Type::FuncPtr { ret_type, args } => format!(
+            "{} (*)({})",
+            type_name(ret_type),
+            args.iter().map(type_name).collect::<Vec<String>>().join(", ")
+        ),
+        Type::Alias(name) => name.clone(),
+    }
+}
+
+fn is_integer(t: &Type) -> bool {
+    matches!(t, Type::Int | Type::Char | Type::Long)
+}
+
+fn is_arithmetic(t: &Type) -> bool {
+    is_integer(t) || matches!(t, Type::Float)
+}
+
+/// true if the type converts to & from float, longs don't
+fn is_float_convertible(t: &Type) -> bool {
+    matches!(t, Type::Int | Type::Char | Type::Float)
+}
+
+/// the type of an arithmetic operation's result, an int operand is converted to the type of the other one if it's a float or a long
+/// None if an operand isn't arithmetic, or if one is a float & the other a long
+fn arithmetic_type(left: &Type, right: &Type) -> Option<Type> {
+    if !is_arithmetic(left) || !is_arithmetic(right) {
+        return None;
+    }
+    match (left, right) {
+        (Type::Float, t) | (t, Type::Float) => if is_float_convertible(t) { Some(Type::Float) } else { None },
+        (Type::Long, _) | (_, Type::Long) => Some(Type::Long),
+        _ => Some(Type::Int),
+    }
+}
+
+fn is_pointer(t: &Type) -> bool {
+    matches!(t, Type::Ptr(_) | Type::_String)
+}
+
+fn is_scalar(t: &Type) -> bool {
+    is_arithmetic(t) || is_pointer(t) || matches!(t, Type::FuncPtr { .. })
+}
+
+/// true if a value of type 'from' can be assigned to an object of type 'to'
+/// integers & pointers convert to each other implicitly, as most C compilers allow with a warning
+/// ints & floats also convert to each other, but floats & pointers or longs don't
+fn is_assignable(to: &Type, from: &Type) -> bool {
+    match (to, from) {
+        (Type::Float, t) | (t, Type::Float) => is_float_convertible(t),
+        (Type::Struct(to_name), Type::Struct(from_name)) => to_name == from_name,
+        (Type::Struct(_), _) | (_, Type::Struct(_)) => false,
+        (Type::Void, _) | (_, Type::Void) => false,
+        (Type::Ptr(to_pointed), Type::Ptr(from_pointed)) => {
+            matches!(**to_pointed, Type::Void) || matches!(**from_pointed, Type::Void) || to_pointed == from_pointed
+        }
+        _ => is_scalar(to) && is_scalar(from),
+    }
+}
+
+/// true if a value of type 'from' can be explicitly cast to 'to', any value can be cast to void
+/// unlike assignment, casts between different pointer types are allowed
+fn is_castable(to: &Type, from: &Type) -> bool {
+    match (to, from) {
+        (Type::Void, _) => true,
+        (Type::Float, t) | (t, Type::Float) => is_float_convertible(t),
+        _ => is_scalar(to) && is_scalar(from),
+    }
+}
+
+pub(super) fn op_name(op: &BinaryopType) -> &'static str {
+    match op {
+        BinaryopType::ADD => "+",
+        BinaryopType::SUB => "-",
+        BinaryopType::MUL => "*",
+        BinaryopType::DIV => "/",
+        BinaryopType::MOD => "%",
+        BinaryopType::AND => "&",
+        BinaryopType::OR => "|",
+        BinaryopType::SHL => "<<",
+        BinaryopType::SHR => ">>",
+        BinaryopType::XOR => "^",
+        BinaryopType::EQ => "==",
+        BinaryopType::NEQ => "!=",
+        BinaryopType::LogicalAnd => "&&",
+        BinaryopType::LogicalOr => "||",
+        BinaryopType::LT => "<",
+        BinaryopType::LTEQ => "<=",
+        BinaryopType::GT => ">",
+        BinaryopType::GTEQ => ">=",
+    }
+}
+
+/// the type of the result of a binary operation, None if the operands are invalid
+fn binary_op_type(op: &BinaryopType, left: &Type, right: &Type) -> Option<Type> {
+    match op {
+        BinaryopType::ADD => match (is_pointer(left), is_pointer(right)) {
+            (false, false) => arithmetic_type(left, right),
+            (true, false) if is_integer(right) => Some(left.clone()),
+            (false, true) if is_integer(left) => Some(right.clone()),
+            _ => None,
+        },
+        BinaryopType::SUB => match (is_pointer(left), is_pointer(right)) {
+            (false, false) => arithmetic_type(left, right),
+            (true, false) if is_integer(right) => Some(left.clone()),
+            (true, true) => Some(Type::Int),
+            _ => None,
+        },
+        // multiplication, division & shifts of longs aren't supported
+        BinaryopType::MUL | BinaryopType::DIV => arithmetic_type(left, right).filter(|t| *t != Type::Long),
+        BinaryopType::MOD | BinaryopType::SHL | BinaryopType::SHR => {
+            if is_integer(left) && is_integer(right) { arithmetic_type(left, right).filter(|t| *t != Type::Long) } else { None }
+        }
+        BinaryopType::AND | BinaryopType::OR | BinaryopType::XOR => {
+            if is_integer(left) && is_integer(right) { arithmetic_type(left, right) } else { None }
+        }
+        _ if is_arithmetic(left) && is_arithmetic(right) => arithmetic_type(left, right).map(|_| Type::Int),
+        _ => {
+            if is_scalar(left) && is_scalar(right) { Some(Type::Int) } else { None }
+        }
+    }
+}
+
+struct TypeChecker {
+    typedefs: HashMap<String, Type>,
+    structs: HashMap<String, HashMap<String, VarType>>,
+    funcs: HashMap<String, FuncSignature>,
+    scopes: Vec<HashMap<String, VarType>>, // innermost scope is last, globals are first
+    cur_ret_type: Type,
+    cur_loc: Option<SourceLoc>,
+    errors: Vec<CompileError>,
+}
+
+impl TypeChecker {
+    fn error(&mut self, kind: ErrorKind) {
+        self.errors.push(CompileError { kind, loc: self.cur_loc.clone() });
+    }
+
+    fn resolve(&self, t: &Type) -> Type {
+        match t {
+            Type::Alias(name) => self.typedefs.get(name).cloned().unwrap_or_else(|| t.clone()),
+            Type::Ptr(pointed_t) => Type::Ptr(Box::new(self.resolve(pointed_t))),
+            Type::FuncPtr { ret_type, args } => Type::FuncPtr {
+                ret_type: Box::new(self.resolve(ret_type)),
+                args: args.iter().map(|arg| self.resolve(arg)).collect(),
+            },
+            _ => t.clone(),
+        }
+    }
+
+    fn decl_var_type(&self, decl: &Decl) -> (String, VarType) {
+        match decl {
+            Decl::VarDecl(var_decl) => (var_decl.name.clone(), VarType::Value(self.resolve(&var_decl._type))),
+            Decl::ArrayDecl(arr_decl) => (
+                arr_decl.name.clone(),
+                VarType::Array(self.resolve(&arr_decl._type), arr_decl.dimentions.len()),
+            ),
+        }
+    }
+
+    fn declare(&mut self, decl: &Decl) {
+        let (name, var_type) = self.decl_var_type(decl);
+        self.scopes.last_mut().unwrap().insert(name, var_type);
+    }
+
+    fn register_struct(&mut self, struct_decl: &StructDecl) {
+        for nested in struct_decl.nested.iter() {
+            self.register_struct(nested);
+        }
+        let fields = struct_decl.items.values().map(|decl| self.decl_var_type(decl)).collect();
+        self.structs.insert(struct_decl.name.clone(), fields);
+    }
+
+    fn register_func(&mut self, func_decl: &FuncDecl) {
+        let args: Vec<Type> = func_decl.args.iter().map(|arg| self.decl_var_type(arg).1.value_type()).collect();
+        let signature = FuncSignature {
+            ret_type: self.resolve(&func_decl.ret_type),
+            args: if args.is_empty() { None } else { Some(args) },
+            variadic: func_decl.variadic,
+        };
+        self.funcs.insert(func_decl.name.clone(), signature);
+    }
+
+    fn find_var(&self, name: &str) -> Option<&VarType> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn check_assignable(&mut self, context: &str, to: &Type, from: &Option<Type>) {
+        if let Some(from) = from {
+            if !is_assignable(to, from) {
+                self.error(ErrorKind::IncompatibleTypes {
+                    context: context.to_string(),
+                    expected: type_name(to),
+                    found: type_name(from),
+                });
+            }
+        }
+    }
+
+    fn name_type(&mut self, name: &NameRef) -> Option<VarType> {
+        match name {
+            NameRef::ID(id) => {
+                if let Some(var_type) = self.find_var(&id.name) {
+                    return Some(var_type.clone());
+                }
+                // a function used as a value is a function pointer
+                let signature = self.funcs.get(&id.name)?;
+                Some(VarType::Value(Type::FuncPtr {
+                    ret_type: Box::new(signature.ret_type.clone()),
+                    args: signature.args.clone().unwrap_or_default(),
+                }))
+            }
+            NameRef::ArrayRef(array_ref) => {
+                for index in array_ref.indices.iter() {
+                    if let Some(index_type) = self.expr_type(index) {
+                        if !is_integer(&index_type) {
+                            self.error(ErrorKind::InvalidOperand { op: "[]".to_string(), operand: type_name(&index_type) });
+                        }
+                    }
+                }
+                match self.name_type(&array_ref.name)? {
+                    VarType::Array(item_type, dimensions) => {
+                        if dimensions != array_ref.indices.len() {
+                            let array_name = match &*array_ref.name {
+                                NameRef::ID(id) => id.name.clone(),
+                                _ => "<array>".to_string(),
+                            };
+                            self.error(ErrorKind::WrongIndexCount {
+                                name: array_name,
+                                expected: dimensions,
+                                found: array_ref.indices.len(),
+                            });
+                            return None;
+                        }
+                        Some(VarType::Value(item_type))
+                    }
+                    VarType::Value(Type::Ptr(pointed_t)) if array_ref.indices.len() == 1 => Some(VarType::Value(*pointed_t)),
+                    VarType::Value(t) => {
+                        self.error(ErrorKind::NotSubscriptable { found: type_name(&t) });
+                        None
+                    }
+                }
+            }
+            NameRef::StructRef(struct_ref) => {
+                let base_type = self.name_type(&struct_ref.name)?.value_type();
+                let (struct_type, op) = match struct_ref._type {
+                    StructRefType::DOT => (Some(base_type.clone()), "."),
+                    StructRefType::ARROW => (
+                        match &base_type {
+                            Type::Ptr(pointed_t) => Some((**pointed_t).clone()),
+                            _ => None,
+                        },
+                        "->",
+                    ),
+                };
+                let struct_name = match struct_type {
+                    Some(Type::Struct(struct_name)) => struct_name,
+                    _ => {
+                        self.error(ErrorKind::InvalidOperand { op: op.to_string(), operand: type_name(&base_type) });
+                        return None;
+                    }
+                };
+                let field_type = self.structs.get(&struct_name)?.get(&struct_ref.field).cloned();
+                if field_type.is_none() {
+                    self.error(ErrorKind::NoSuchMember {
+                        _type: type_name(&Type::Struct(struct_name)),
+                        field: struct_ref.field.clone(),
+                    });
+                }
+                field_type
+            }
+        }
+    }
+
+    /// returns the type of the expression, or None if it's unknown or invalid
+    /// errors are reported where they're found, so an invalid sub expression doesn't cascade into more errors
+    fn expr_type(&mut self, expr: &Expression) -> Option<Type> {
+        match expr {
+            Expression::Constant(c) => match &c._type {
+                Type::_String => Some(Type::Ptr(Box::new(Type::Char))),
+                t => Some(t.clone()),
+            },
+            Expression::NameRef(name) => Some(self.name_type(name)?.value_type()),
+            Expression::BinaryOp(op) => {
+                let left = self.expr_type(&op.left);
+                let right = self.expr_type(&op.right);
+                let (left, right) = (left?, right?);
+                let res = binary_op_type(&op.op_type, &left, &right);
+                if res.is_none() {
+                    self.error(ErrorKind::InvalidOperands {
+                        op: op_name(&op.op_type).to_string(),
+                        left: type_name(&left),
+                        right: type_name(&right),
+                    });
+                }
+                res
+            }
+            Expression::UnaryOp(op) => self.unary_op_type(op),
+            Expression::Assignment(ass) => self.check_assignment(ass),
+            Expression::TernaryOp(top) => {
+                self.expr_type(&top.cond);
+                let iftrue = self.expr_type(&top.iftrue);
+                let iffalse = self.expr_type(&top.iffalse);
+                match (iftrue, iffalse) {
+                    (Some(iftrue), Some(iffalse)) if is_arithmetic(&iftrue) && is_arithmetic(&iffalse) => {
+                        let res = arithmetic_type(&iftrue, &iffalse);
+                        if res.is_none() {
+                            self.error(ErrorKind::InvalidOperands { op: "?:".to_string(), left: type_name(&iftrue), right: type_name(&iffalse) });
+                        }
+                        res
+                    }
+                    (iftrue, _) => iftrue,
+                }
+            }
+            Expression::FuncCall(func_call) => self.func_call_type(func_call),
+            Expression::TypeName(_) => None,
+            Expression::Cast(cast) => {
+                let to = self.resolve(&cast._type);
+                if let Some(from) = self.expr_type(&cast.expr) {
+                    if !is_castable(&to, &from) {
+                        self.error(ErrorKind::InvalidCast { from: type_name(&from), to: type_name(&to) });
+                    }
+                }
+                Some(to)
+            }
+            Expression::Comma(exprs) => {
+                let mut last = None;
+                for expr in exprs.iter() {
+                    last = self.expr_type(expr);
+                }
+                last
+            }
+            Expression::LabelAddr(_) => Some(Type::Ptr(Box::new(Type::Void))),
+        }
+    }
+
+    fn unary_op_type(&mut self, op: &UnaryOp) -> Option<Type> {
+        if op.op_type == UnaryopType::SIZEOF {
+            return Some(Type::Int);
+        }
+        if op.op_type == UnaryopType::REF {
+            return match &*op.expr {
+                // the address of an array is the address of its first item
+                Expression::NameRef(name) => match self.name_type(name)? {
+                    VarType::Array(item_type, _) => Some(Type::Ptr(Box::new(item_type))),
+                    VarType::Value(t @ Type::FuncPtr { .. }) => Some(t),
+                    VarType::Value(t) => Some(Type::Ptr(Box::new(t))),
+                },
+                expr => Some(Type::Ptr(Box::new(self.expr_type(expr)?))),
+            };
+        }
+        let operand = self.expr_type(&op.expr)?;
+        let (valid, op_str) = match op.op_type {
+            UnaryopType::NEG => (is_arithmetic(&operand), "-"),
+            UnaryopType::BNOT => (is_integer(&operand), "~"),
+            UnaryopType::NOT => (is_scalar(&operand), "!"),
+            UnaryopType::XPP | UnaryopType::PPX => (is_integer(&operand) || is_pointer(&operand), "++"),
+            UnaryopType::XMM | UnaryopType::MMX => (is_integer(&operand) || is_pointer(&operand), "--"),
+            UnaryopType::DEREF => (is_pointer(&operand) || matches!(operand, Type::FuncPtr { .. }), "*"),
+            UnaryopType::REF | UnaryopType::SIZEOF => (true, ""),
+        };
+        if !valid {
+            self.error(ErrorKind::InvalidOperand { op: op_str.to_string(), operand: type_name(&operand) });
+            return None;
+        }
+        match op.op_type {
+            UnaryopType::NEG | UnaryopType::BNOT => arithmetic_type(&operand, &Type::Int),
+            UnaryopType::NOT => Some(Type::Int),
+            UnaryopType::DEREF => match operand {
+                Type::Ptr(pointed_t) => Some(*pointed_t),
+                Type::_String => Some(Type::Char),
+                func_ptr => Some(func_ptr),
+            },
+            _ => Some(operand),
+        }
+    }
+
+    fn check_assignment(&mut self, ass: &Assignment) -> Option<Type> {
+        let lvalue = self.expr_type(&ass.lvalue);
+        let rvalue = self.expr_type(&ass.rvalue);
+        let lvalue = lvalue?;
+        match &ass.op.op {
+            Some(bop) => {
+                // e.g x += y is checked as x = x + y
+                let rvalue = rvalue?;
+                match binary_op_type(bop, &lvalue, &rvalue) {
+                    Some(res) => self.check_assignable("assignment", &lvalue, &Some(res)),
+                    None => self.error(ErrorKind::InvalidOperands {
+                        op: format!("{}=", op_name(bop)),
+                        left: type_name(&lvalue),
+                        right: type_name(&rvalue),
+                    }),
+                }
+            }
+            None => self.check_assignable("assignment", &lvalue, &rvalue),
+        }
+        Some(lvalue)
+    }
+
+    fn func_call_type(&mut self, func_call: &FuncCall) -> Option<Type> {
+        let arg_types: Vec<Option<Type>> = func_call.args.iter().map(|arg| self.expr_type(arg)).collect();
+        if func_call.name == "__syscall" {
+            return Some(Type::Int);
+        }
+        if func_call.name == "exit" || func_call.name == "abort" {
+            let expected = if func_call.name == "exit" { 1 } else { 0 };
+            if arg_types.len() != expected {
+                self.error(ErrorKind::WrongArgsCount { func: func_call.name.clone(), expected, found: arg_types.len() });
+            } else if let Some(status) = arg_types.first() {
+                self.check_assignable("argument 1 of 'exit'", &Type::Int, status);
+            }
+            return Some(Type::Void);
+        }
+        if func_call.name == "__test_and_set" {
+            if arg_types.len() != 1 {
+                self.error(ErrorKind::WrongArgsCount { func: func_call.name.clone(), expected: 1, found: arg_types.len() });
+            } else {
+                self.check_assignable("argument 1 of '__test_and_set'", &Type::Ptr(Box::new(Type::Int)), &arg_types[0]);
+            }
+            return Some(Type::Int);
+        }
+        if func_call.name == "alloca" {
+            if arg_types.len() != 1 {
+                self.error(ErrorKind::WrongArgsCount { func: func_call.name.clone(), expected: 1, found: arg_types.len() });
+            }
+            return Some(Type::Ptr(Box::new(Type::Void)));
+        }
+        let (ret_type, params, variadic) = match self.find_var(&func_call.name) {
+            Some(var_type) => match var_type.value_type() {
+                Type::FuncPtr { ret_type, args } => (*ret_type, if args.is_empty() { None } else { Some(args) }, false),
+                _ => return None,
+            },
+            None => {
+                let signature = self.funcs.get(&func_call.name)?;
+                (signature.ret_type.clone(), signature.args.clone(), signature.variadic)
+            }
+        };
+        if let Some(params) = params {
+            if variadic && arg_types.len() < params.len() {
+                self.error(ErrorKind::TooFewArgs {
+                    func: func_call.name.clone(),
+                    expected: params.len(),
+                    found: arg_types.len(),
+                });
+            } else if !variadic && params.len() != arg_types.len() {
+                self.error(ErrorKind::WrongArgsCount {
+                    func: func_call.name.clone(),
+                    expected: params.len(),
+                    found: arg_types.len(),
+                });
+            } else {
+                for (i, (param, arg)) in params.iter().zip(arg_types.iter()).enumerate() {
+                    self.check_assignable(&format!("argument {} of '{}'", i + 1, func_call.name), param, arg);
+                }
+            }
+        }
+        Some(ret_type)
+    }
+
+    fn check_compound(&mut self, compound: &Compound) {
+        self.scopes.push(HashMap::new());
+        for (item, loc) in compound.items.iter().zip(compound.item_locs.iter()) {
+            if loc.is_some() {
+                self.cur_loc = loc.clone();
+            }
+            self.check_statement(item);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Return(ret) => {
+                let ret_type = self.cur_ret_type.clone();
+                match (&ret.expr, &ret_type) {
+                    (Some(expr), Type::Void) => {
+                        if let Some(found) = self.expr_type(expr) {
+                            self.error(ErrorKind::IncompatibleTypes {
+                                context: "return".to_string(),
+                                expected: type_name(&ret_type),
+                                found: type_name(&found),
+                            });
+                        }
+                    }
+                    (Some(expr), _) => {
+                        let found = self.expr_type(expr);
+                        self.check_assignable("return", &ret_type, &found);
+                    }
+                    (None, _) => {}
+                }
+            }
+            Statement::Decl(decl) => {
+                self.declare(decl);
+                match decl {
+                    Decl::VarDecl(var_decl) => {
+                        if let Some(init) = &var_decl.init {
+                            let var_type = self.resolve(&var_decl._type);
+                            let init_type = self.expr_type(init);
+                            self.check_assignable("initialization", &var_type, &init_type);
+                        }
+                    }
+                    Decl::ArrayDecl(arr_decl) => {
+                        let item_type = self.resolve(&arr_decl._type);
+                        for init in arr_decl.init.iter().flatten() {
+                            let init_type = self.expr_type(init);
+                            self.check_assignable("initialization", &item_type, &init_type);
+                        }
+                    }
+                }
+            }
+            Statement::Assignment(ass) => {
+                self.check_assignment(ass);
+            }
+            Statement::Expression(expr) => {
+                self.expr_type(expr);
+            }
+            Statement::If(if_stmt) => {
+                self.expr_type(&if_stmt.cond);
+                self.check_compound(&if_stmt.iftrue);
+                if let Some(iffalse) = &if_stmt.iffalse {
+                    self.check_compound(iffalse);
+                }
+            }
+            Statement::Compound(compound) => self.check_compound(compound),
+            Statement::WhileLoop(wl) => {
+                self.expr_type(&wl.cond);
+                self.check_compound(&wl.body);
+            }
+            Statement::DoWhileLoop(dwl) => {
+                self.check_compound(&dwl.body);
+                self.expr_type(&dwl.cond);
+            }
+            Statement::ForLoop(fl) => {
+                // variables declared in the init statement are visible in the whole loop
+                self.scopes.push(HashMap::new());
+                if let Some(init) = &fl.init {
+                    for item in init.items.iter() {
+                        self.check_statement(item);
+                    }
+                }
+                if let Some(cond) = &fl.cond {
+                    self.expr_type(cond);
+                }
+                self.check_compound(&fl.body);
+                if let Some(next) = &fl.next {
+                    self.check_compound(next);
+                }
+                self.scopes.pop();
+            }
+            Statement::Switch(switch) => {
+                self.expr_type(&switch.cond);
+                for case in switch.cases.iter() {
+                    if let Some(value) = &case.value {
+                        self.expr_type(value);
+                    }
+                }
+                self.check_compound(&switch.body);
+            }
+            Statement::Label(label) => self.check_statement(&label.stmt),
+            Statement::ComputedGoto(expr) => {
+                let target_type = self.expr_type(expr);
+                self.check_assignable("goto", &Type::Ptr(Box::new(Type::Void)), &target_type);
+            }
+            Statement::Goto(_) | Statement::Break | Statement::Continue => {}
+        }
+    }
+
+    fn check_root(&mut self, root: &RootAstNode) {
+        for ext in root.externals.iter() {
+            match ext {
+                External::Typedef(typedef) => {
+                    if let Some(struct_decl) = &typedef.struct_decl {
+                        self.register_struct(struct_decl);
+                    }
+                    let resolved = self.resolve(&typedef._type);
+                    self.typedefs.insert(typedef.name.clone(), resolved);
+                }
+                External::StructDecl(struct_decl) => self.register_struct(struct_decl),
+                External::VarDecl(decl) | External::ExternVarDecl(decl) => self.declare(decl),
+                External::FuncDecl(func_decl) => self.register_func(func_decl),
+                External::FuncDef(func_def) => {
+                    self.register_func(&func_def.decl);
+                    self.cur_loc = func_def.loc.clone();
+                    self.cur_ret_type = self.resolve(&func_def.decl.ret_type);
+                    // args are in their own scope that encloses the body
+                    self.scopes.push(HashMap::new());
+                    for arg in func_def.decl.args.iter() {
+                        self.declare(arg);
+                    }
+                    self.check_compound(&func_def.body);
+                    self.scopes.pop();
+                }
+            }
+        }
+    }
+}
+
+/// returns the type errors in the program
+pub fn check(root: &RootAstNode) -> Vec<CompileError> {
+    let mut checker = TypeChecker {
+        typedefs: HashMap::new(),
+        structs: HashMap::new(),
+        funcs: HashMap::new(),
+        scopes: vec![HashMap::new()],
+        cur_ret_type: Type::Void,
+        cur_loc: None,
+        errors: Vec::new(),
+    };
+    checker.check_root(root);
+    checker.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_file(path: &str) -> Vec<(u32, ErrorKind)> {
+        check(&get_ast(path)).into_iter().map(|error| (error.loc.unwrap().line, error.kind)).collect()
+    }
+
+    fn incompatible(context: &str, expected: &str, found: &str) -> ErrorKind {
+        ErrorKind::IncompatibleTypes { context: context.to_string(), expected: expected.to_string(), found: found.to_string() }
+    }
+
+    fn invalid_operand(op: &str, operand: &str) -> ErrorKind {
+        ErrorKind::InvalidOperand { op: op.to_string(), operand: operand.to_string() }
+    }
+
+    #[test]
+    fn test_assignment() {
+        assert_eq!(check_file("tests/type_check_test_data/assignment.c"), vec![
+            (14, incompatible("assignment", "struct A", "struct B")),
+            (15, incompatible("assignment", "int*", "char*")),
+            (16, incompatible("initialization", "int", "struct A")),
+        ]);
+    }
+
+    #[test]
+    fn test_calls() {
+        assert_eq!(check_file("tests/type_check_test_data/calls.c"), vec![
+            (10, incompatible("return", "void", "int")),
+            (17, incompatible("return", "struct A", "int")),
+            (22, ErrorKind::WrongArgsCount { func: "foo".to_string(), expected: 2, found: 1 }),
+            (23, incompatible("argument 1 of 'foo'", "int", "struct A")),
+            (25, ErrorKind::WrongArgsCount { func: "f".to_string(), expected: 2, found: 3 }),
+            (27, ErrorKind::TooFewArgs { func: "log".to_string(), expected: 1, found: 0 }),
+            (28, incompatible("argument 1 of 'exit'", "int", "struct A")),
+            (29, ErrorKind::WrongArgsCount { func: "abort".to_string(), expected: 0, found: 1 }),
+        ]);
+    }
+
+    #[test]
+    fn test_operators() {
+        let operands = |op: &str, left: &str, right: &str| ErrorKind::InvalidOperands {
+            op: op.to_string(), left: left.to_string(), right: right.to_string(),
+        };
+        assert_eq!(check_file("tests/type_check_test_data/operators.c"), vec![
+            (12, operands("+", "int*", "int*")),
+            (13, operands("*", "int*", "int")),
+            (14, operands("+", "struct A", "int")),
+            (15, ErrorKind::WrongIndexCount { name: "arr".to_string(), expected: 2, found: 1 }),
+            (16, ErrorKind::NotSubscriptable { found: "struct A".to_string() }),
+            (17, ErrorKind::NoSuchMember { _type: "struct A".to_string(), field: "y".to_string() }),
+            (18, invalid_operand(".", "struct A*")),
+            (19, invalid_operand("->", "struct A")),
+            (20, invalid_operand("*", "struct A")),
+            (21, invalid_operand("~", "int*")),
+            (24, operands("%", "float", "int")),
+            (25, invalid_operand("~", "float")),
+            (26, operands("+", "int*", "float")),
+            (27, ErrorKind::InvalidCast { from: "struct A".to_string(), to: "int".to_string() }),
+            (28, ErrorKind::InvalidCast { from: "int*".to_string(), to: "float".to_string() }),
+            (32, operands("*", "long", "int")),
+            (33, operands("+", "long", "float")),
+            (34, operands("<<", "long", "int")),
+        ]);
+    }
+
+    #[test]
+    fn test_valid_program() {
+        assert!(check_file("tests/compiler_test_data/structs/inputs/return.c").is_empty());
+    }
+
+    #[test]
+    fn test_error_message() {
+        let errors = check(&get_ast("tests/type_check_test_data/assignment.c"));
+        assert_eq!(errors[0].to_string(), "tests/type_check_test_data/assignment.c:14: error: incompatible types in assignment: expected 'struct A' but found 'struct B'");
+    }
+}
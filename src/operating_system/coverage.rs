@@ -0,0 +1,134 @@
+// which source lines a program executed: the cpu's per address counts (see cpu/profile.rs) mapped to lines through the source map,
+// as an lcov tracefile (for genhtml & editors) or as a source annotated with each line's count, gcov style.
+// reports of several runs can be merged, e.g to see which lines of the std none of the compiler's tests execute
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use super::assembler::Executable;
+use crate::cpu::profile::Profile;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LineCoverage {
+    pub hits: u64, // executions of the line's most executed instruction
+    pub instructions: u32, // the line's instructions in the executable
+    pub executed_instructions: u32,
+}
+
+/// only lines that have code are covered, lines are known only for code that was compiled with line labels
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub files: BTreeMap<String, BTreeMap<u32, LineCoverage>>, // file -> line -> its coverage
+}
+
+impl CoverageReport {
+    pub fn new() -> CoverageReport {
+        CoverageReport::default()
+    }
+
+    /// code_start is where the executable's code was loaded, the profile's addresses are relative to it
+    pub fn from_profile(profile: &Profile, exec: &Executable, code_start: u32) -> CoverageReport {
+        let mut report = CoverageReport::new();
+        for instr_i in 0..exec.code.len() as u32 {
+            let (file, line) = match exec.source_map.get_loc(instr_i) {
+                Some(loc) => loc,
+                None => continue,
+            };
+            let coverage = report.files.entry(file.clone()).or_default().entry(*line).or_default();
+            coverage.instructions += 1;
+            if let Some(counts) = profile.counts.get(&(code_start + instr_i)) {
+                coverage.hits = coverage.hits.max(counts.executions);
+                coverage.executed_instructions += 1;
+            }
+        }
+        report
+    }
+
+    /// adds the hits of another run, the same lines of different runs should have the same code
+    pub fn merge(&mut self, other: &CoverageReport) {
+        for (file, lines) in other.files.iter() {
+            let file_lines = self.files.entry(file.clone()).or_default();
+            for (line, other) in lines.iter() {
+                let coverage = file_lines.entry(*line).or_default();
+                coverage.hits += other.hits;
+                coverage.instructions = coverage.instructions.max(other.instructions);
+                coverage.executed_instructions = coverage.executed_instructions.max(other.executed_instructions);
+            }
+        }
+    }
+
+    /// the number of lines with code & the number of them that were executed
+    pub fn lines_hit(&self) -> (usize, usize) {
+        let lines = self.files.values().flat_map(|lines| lines.values());
+        (lines.clone().count(), lines.filter(|coverage| coverage.hits > 0).count())
+    }
+
+    /// the lcov tracefile format, see geninfo(1)
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for (file, lines) in self.files.iter() {
+            writeln!(lcov, "SF:{}", file).unwrap();
+            for (line, coverage) in lines.iter() {
+                writeln!(lcov, "DA:{},{}", line, coverage.hits).unwrap();
+            }
+            writeln!(lcov, "LF:{}", lines.len()).unwrap();
+            writeln!(lcov, "LH:{}", lines.values().filter(|coverage| coverage.hits > 0).count()).unwrap();
+            writeln!(lcov, "end_of_record").unwrap();
+        }
+        lcov
+    }
+
+    /// the file's source with each line prefixed by its hits, gcov style: ##### for lines that weren't executed,
+    /// & - for lines without code. lines that were executed but have instructions that weren't (e.g a branch
+    /// that was never taken) are marked with a *, e.g
+    ///         1*:    2:    if (n == 0) {
+    ///          -:    3:        // never
+    ///      #####:    4:        return 1;
+    pub fn annotate(&self, file: &str, source: &str) -> String {
+        let lines = self.files.get(file);
+        let mut annotated = String::new();
+        for (line_i, source_line) in source.lines().enumerate() {
+            let line = line_i as u32 + 1;
+            let count = match lines.and_then(|lines| lines.get(&line)) {
+                None => "-".to_string(),
+                Some(coverage) if coverage.hits == 0 => "#####".to_string(),
+                Some(coverage) if coverage.executed_instructions < coverage.instructions => format!("{}*", coverage.hits),
+                Some(coverage) => coverage.hits.to_string(),
+            };
+            writeln!(annotated, "{:>10}:{:>5}:{}", count, line, source_line).unwrap();
+        }
+        annotated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(lines: &[(u32, u64, u32, u32)]) -> CoverageReport {
+        let lines = lines.iter()
+            .map(|(line, hits, instructions, executed_instructions)| (*line, LineCoverage { hits: *hits, instructions: *instructions, executed_instructions: *executed_instructions }))
+            .collect();
+        CoverageReport { files: BTreeMap::from([("a.c".to_string(), lines)]) }
+    }
+
+    #[test]
+    fn test_lcov_and_annotate() {
+        let report = report(&[(1, 3, 2, 2), (2, 1, 3, 1), (4, 0, 1, 0)]);
+        assert_eq!(report.lines_hit(), (3, 2));
+        assert_eq!(report.to_lcov(), "SF:a.c\nDA:1,3\nDA:2,1\nDA:4,0\nLF:3\nLH:2\nend_of_record\n");
+        let annotated = report.annotate("a.c", "one\ntwo\nthree\nfour\n");
+        let counts: Vec<&str> = annotated.lines().map(|line| line.split(':').next().unwrap().trim()).collect();
+        assert_eq!(counts, vec!["3", "1*", "-", "#####"]);
+        assert!(annotated.ends_with("    #####:    4:four\n"));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut merged = report(&[(1, 3, 2, 2), (4, 0, 1, 0)]);
+        merged.merge(&report(&[(1, 1, 2, 1), (4, 2, 1, 1)]));
+        assert_eq!(merged, report(&[(1, 4, 2, 2), (4, 2, 1, 1)]));
+    }
+}
@@ -0,0 +1,42 @@
+// the compiler's description of the generated code, which lets the debugger refer to functions & variables by name
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VarInfo {
+    pub name: String,
+    pub bp_offset: i32, // address of the variable's first word, relative to BP
+    pub type_name: String, // e.g "int", "char*", "struct point", "int[3]"
+    pub size: u32, // in words
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuncInfo {
+    pub name: String,
+    // the function's instructions are start..end
+    // in an object file they're relative to the object's code, & in an executable to the executable's code
+    pub start: u32,
+    pub end: u32,
+    pub params: Vec<VarInfo>,
+    pub locals: Vec<VarInfo>, // the variables of all the function's scopes, so names may repeat
+}
+
+impl FuncInfo {
+    /// a param or local with the given name, the first one if the name is declared in several scopes
+    pub fn find_var(&self, name: &str) -> Option<&VarInfo> {
+        self.params.iter().chain(self.locals.iter()).find(|var| var.name == name)
+    }
+}
+
+/// serialized as json, e.g by the compile command next to the assembly
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DebugInfo {
+    pub functions: Vec<FuncInfo>,
+}
+
+impl DebugInfo {
+    /// the function that contains the instruction
+    pub fn function_at(&self, instr_i: u32) -> Option<&FuncInfo> {
+        self.functions.iter().find(|func| func.start <= instr_i && instr_i < func.end)
+    }
+}
@@ -0,0 +1,583 @@
+// stepping through a program, breakpoints & inspection of the machine's state
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+use super::assembler::Executable;
+use super::breakpoints::{Breakpoints, Condition};
+use super::debug_info::{FuncInfo, VarInfo};
+use super::linker::global_var_label;
+use super::OS;
+use crate::cpu::instructions::{register_from_str, Instruction, Register, StackOp};
+use crate::cpu::{Access, MemEntry};
+
+/// returns an empty vector if the file can't be read
+fn read_source_lines(path: &str) -> Vec<String> {
+    match fs::read_to_string(path) {
+        Ok(src) => src.split("\n").map(|line| line.to_string()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// the number of executed instructions that can be undone
+const JOURNAL_SIZE: usize = 10000;
+
+fn parse_debug_addr(addr: &str) -> Option<u32> {
+    if let Some(hex) = addr.strip_prefix("0x") {
+        return u32::from_str_radix(hex, 16).ok();
+    }
+    addr.parse().ok()
+}
+
+/// why the debugger returned control to its caller
+#[derive(Debug, PartialEq)]
+pub enum Stop {
+    Step, // a single instruction was executed
+    Breakpoint(u32), // the id of the breakpoint
+    Watchpoint(u32, Access), // the address & the access to it
+    Exited(i32), // the exit value of the program
+    JournalStart, // the oldest instruction that can be undone was undone
+}
+
+/// a function call on the stack, see the stack frame layout in layout.rs
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub function: Option<String>,
+    pub instr_i: u32, // the next instruction in the innermost frame, the call instruction in the others
+    pub bp: u32,
+    pub args: Option<Vec<i32>>, // the words of the args, if their number is known
+}
+
+/// drives the execution of a program, e.g:
+/// let mut debugger = Debugger::new(&mut os, &exec);
+/// debugger.set_breakpoint("foo.c:12", None);
+/// debugger.run_until_break();
+/// debugger.read_reg(&Register::R1);
+pub struct Debugger<'a> {
+    os: &'a mut OS,
+    exec: &'a Executable,
+    pub breakpoints: Breakpoints,
+    source_files: HashMap<String, Vec<String>>, // cache of source lines, for showing the current line
+    exit_value: Option<i32>,
+}
+
+impl<'a> Debugger<'a> {
+    /// loads the program, it's stopped before its first instruction
+    pub fn new(os: &'a mut OS, exec: &'a Executable) -> Debugger<'a> {
+        os.load_executable(exec);
+        os.cpu.set_journal(JOURNAL_SIZE);
+        Debugger {
+            os,
+            exec,
+            breakpoints: Breakpoints::new(),
+            source_files: HashMap::new(),
+            exit_value: None,
+        }
+    }
+
+    /// the index of the next instruction in the program's code
+    /// negative for OS code that is outside the program (e.g the final HALT)
+    pub fn instr_i(&self) -> i32 {
+        self.os.cpu.regs.get(&Register::IR) - self.os.layout.code_start as i32
+    }
+
+    /// executes a single instruction
+    pub fn step(&mut self) -> Stop {
+        if let Some(exit_value) = self.exit_value {
+            return Stop::Exited(exit_value);
+        }
+        if !self.os.step() {
+            let exit_value = self.os.get_exit_value();
+            self.exit_value = Some(exit_value);
+            return Stop::Exited(exit_value);
+        }
+        match self.os.cpu.watch_hit.take() {
+            Some((address, access)) => Stop::Watchpoint(address, access),
+            None => Stop::Step,
+        }
+    }
+
+    /// runs until a breakpoint or a watchpoint is hit, or until the program exits
+    /// breakpoints are checked after stepping, so continuing from a breakpoint doesn't stop at it again
+    pub fn run_until_break(&mut self) -> Stop {
+        loop {
+            let stop = self.step();
+            if stop != Stop::Step {
+                return stop;
+            }
+            if let Some(bp) = self.breakpoints.hit(self.instr_i() as u32, &self.os.cpu.regs) {
+                return Stop::Breakpoint(bp.id);
+            }
+        }
+    }
+
+    /// undoes the last executed instruction, including the exit of the program
+    /// output that the program wrote isn't taken back
+    pub fn reverse_step(&mut self) -> Stop {
+        if !self.os.cpu.step_back() {
+            return Stop::JournalStart;
+        }
+        self.exit_value = None;
+        self.os.exit_code = None;
+        Stop::Step
+    }
+
+    /// undoes instructions until a breakpoint is reached, or until there are no more instructions to undo
+    pub fn reverse_continue(&mut self) -> Stop {
+        loop {
+            let stop = self.reverse_step();
+            if stop != Stop::Step {
+                return stop;
+            }
+            if let Some(bp) = self.breakpoints.hit(self.instr_i() as u32, &self.os.cpu.regs) {
+                return Stop::Breakpoint(bp.id);
+            }
+        }
+    }
+
+    pub fn read_reg(&self, reg: &Register) -> i32 {
+        self.os.cpu.regs.get(reg)
+    }
+
+    /// returns None if the address is uninitialized or holds an instruction
+    pub fn read_mem(&self, addr: u32) -> Option<i32> {
+        match self.os.cpu.mem.try_get(self.os.cpu.segment.physical(addr)) {
+            Some(MemEntry::Num(x)) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// location is either file.c:line for a source line, or an assembly line number
+    /// returns the id of the breakpoint, or None if there's no code at the location
+    pub fn set_breakpoint(&mut self, location: &str, condition: Option<Condition>) -> Option<u32> {
+        let instr_i = match location.rsplit_once(':') {
+            Some((file, src_line)) => self.exec.source_map.get_instr(file, src_line.parse().ok()?)?,
+            None => *self.exec.line_addresses.get(location.parse::<usize>().ok()?)?,
+        };
+        Some(self.breakpoints.add(instr_i, location, condition))
+    }
+
+    /// watch stops after the address is written, rwatch after it's read
+    pub fn watch(&mut self, addr: u32, access: Access) {
+        self.os.cpu.add_watchpoint(addr, access);
+    }
+
+    /// returns false if the address isn't watched
+    pub fn unwatch(&mut self, addr: u32) -> bool {
+        self.os.cpu.remove_watchpoints(addr)
+    }
+
+    /// the number of words of the return value & args of a call that returns to the instruction
+    /// the compiled code pops them right after the call, e.g "POP R1" & "POP ZR"s for int f(int x, int y)
+    fn call_words(&self, ret_instr_i: u32) -> Option<(u32, u32)> {
+        let pops: Vec<&Register> = self.exec.code.get(ret_instr_i as usize..)?.iter()
+            .map_while(|instr| match instr {
+                Instruction::Stack { op: StackOp::POP, dst } => Some(dst),
+                _ => None,
+            })
+            .collect();
+        match pops.split_first() {
+            Some((Register::R1, args)) if args.iter().all(|reg| **reg == Register::ZR) => Some((1, args.len() as u32)),
+            Some(_) if pops.iter().all(|reg| **reg == Register::ZR) => Some((0, pops.len() as u32)),
+            _ => None,
+        }
+    }
+
+    /// the frames of the called functions, innermost first
+    /// walks the chain of saved BPs, up to the frame that the OS sets up for main
+    pub fn backtrace(&self) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        let mut bp = self.read_reg(&Register::BP) as u32;
+        let mut instr_i = self.instr_i();
+        while instr_i >= 0 && (instr_i as usize) < self.exec.code.len() {
+            let (prev_bp, ret_addr) = match (self.read_mem(bp), self.read_mem(bp + 1)) {
+                (Some(prev_bp), Some(ret_addr)) => (prev_bp as u32, ret_addr),
+                _ => break,
+            };
+            let ret_instr_i = ret_addr - self.os.layout.code_start as i32;
+            let args = if ret_instr_i >= 0 { self.call_words(ret_instr_i as u32) } else { None };
+            let args = args.and_then(|(ret_words, arg_words)| {
+                (0..arg_words).map(|arg_i| self.read_mem(bp + 2 + ret_words + arg_i)).collect()
+            });
+            frames.push(Frame {
+                function: self.exec.function_at(instr_i as u32).cloned(),
+                instr_i: instr_i as u32,
+                bp,
+                args,
+            });
+            if prev_bp == bp {
+                break;
+            }
+            bp = prev_bp;
+            instr_i = ret_instr_i - 1;
+        }
+        frames
+    }
+
+    /// e.g "#1 fib(5) at fib.c:3", or "#1 print at instr 12 <loop+2>" for code without source lines
+    fn describe_frame(&self, frame_i: usize, frame: &Frame) -> String {
+        let mut desc = format!("#{} {}", frame_i, frame.function.as_deref().unwrap_or("??"));
+        if let Some(args) = &frame.args {
+            desc += &format!("({})", args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>().join(", "));
+        }
+        match self.exec.source_map.get_loc(frame.instr_i) {
+            Some((file, line)) => desc + &format!(" at {}:{}", file, line),
+            None => {
+                desc += &format!(" at instr {}", frame.instr_i);
+                match self.exec.symbol_table.nearest_symbol(frame.instr_i) {
+                    Some(symbol) => desc + &format!(" <{}+{}>", symbol.name, frame.instr_i - symbol.address),
+                    None => desc,
+                }
+            },
+        }
+    }
+
+    /// the debug info of the function that contains the next instruction
+    fn current_function(&self) -> Option<&FuncInfo> {
+        self.exec.debug_info.function_at(self.instr_i() as u32)
+    }
+
+    /// e.g "x = 5", "c = 97 'a'", "f = 1.5", "l = 4294967296" or "point = {1, 2}"
+    fn format_var(&self, var: &VarInfo) -> String {
+        let bp = self.read_reg(&Register::BP);
+        if var.type_name == "long" {
+            // the low word is first
+            let word = |word_i: i32| self.read_mem((bp + var.bp_offset + word_i) as u32);
+            if let (Some(low), Some(high)) = (word(0), word(1)) {
+                return format!("{} = {}", var.name, (high as i64) << 32 | low as u32 as i64);
+            }
+        }
+        let words: Vec<String> = (0..var.size as i32).map(|word_i| {
+            match self.read_mem((bp + var.bp_offset + word_i) as u32) {
+                Some(val) if var.type_name == "char" && (0x20..0x7f).contains(&val) => format!("{} '{}'", val, val as u8 as char),
+                Some(val) if var.type_name == "float" => format!("{:?}", f32::from_bits(val as u32)),
+                Some(val) => val.to_string(),
+                None => "<uninitialized>".to_string(),
+            }
+        }).collect();
+        match words.as_slice() {
+            [word] => format!("{} = {}", var.name, word),
+            _ => format!("{} = {{{}}}", var.name, words.join(", ")),
+        }
+    }
+
+    /// the value of a variable of the current function, or of a global variable
+    pub fn print_var(&self, name: &str) -> Option<String> {
+        if let Some(var) = self.current_function().and_then(|func| func.find_var(name)) {
+            return Some(self.format_var(var));
+        }
+        // the size & type of globals aren't known, so only their first word is shown
+        let addr = self.exec.data_table.address_of(&global_var_label(name))?;
+        let val = self.read_mem(self.os.layout.data_start + addr)?;
+        Some(format!("{} = {}", name, val))
+    }
+
+    /// a line for each of the current function's locals, or params if args is true
+    pub fn info_vars(&self, args: bool) -> Vec<String> {
+        match self.current_function() {
+            Some(func) => {
+                let vars = if args { &func.params } else { &func.locals };
+                vars.iter().map(|var| self.format_var(var)).collect()
+            },
+            None => vec!["no debug info for the current function".to_string()],
+        }
+    }
+
+    /// the source line of the next instruction, e.g "foo.c:12: x = y + 1;"
+    pub fn current_line(&mut self) -> Option<String> {
+        let (file, line) = self.exec.source_map.get_loc(self.instr_i() as u32)?;
+        let src_line = self.source_files.entry(file.clone())
+            .or_insert_with(|| read_source_lines(file))
+            .get(*line as usize - 1).cloned().unwrap_or_default();
+        Some(format!("{}:{}: {}", file, line, src_line.trim()))
+    }
+
+    fn describe_stop(&self, stop: &Stop) -> Option<String> {
+        match stop {
+            Stop::Breakpoint(id) => Some(format!("breakpoint {}", id)),
+            Stop::Watchpoint(address, access) => Some(self.os.describe_watch_hit(*address, *access)),
+            Stop::JournalStart => Some(format!("no more history, only the last {} instructions can be undone", JOURNAL_SIZE)),
+            _ => None,
+        }
+    }
+
+    /// reads debugger commands from the input until the program exits, returns its exit value
+    /// the location of the next instruction is written before each command, followed by the ">" prompt line
+    /// at the end of the input, the rest of the program runs without stopping at breakpoints
+    /// a program that crashed isn't exited, so its execution can be reversed
+    pub fn repl(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<i32> {
+        loop {
+            if let Some(exit_value) = self.exit_value {
+                if self.os.cpu.fault.is_none() {
+                    return Ok(exit_value);
+                }
+            }
+            if let Some(line) = self.current_line() {
+                writeln!(output, "{}", line)?;
+            }
+            let ir = self.os.cpu.segment.physical(self.read_reg(&Register::IR) as u32);
+            writeln!(output, "{}: {}", self.instr_i(), OS::format_mem_entry(self.os.cpu.mem.try_get(ir)))?;
+            writeln!(output, ">")?;
+            output.flush()?;
+            let mut cmd = String::new();
+            if input.read_line(&mut cmd)? == 0 {
+                if let Some(exit_value) = self.exit_value {
+                    return Ok(exit_value);
+                }
+                self.breakpoints.clear();
+                cmd = "continue".to_string();
+            }
+            for line in self.run_command(&cmd) {
+                writeln!(output, "{}", line)?;
+            }
+        }
+    }
+
+    /// runs the repl for the first client that connects to the listener, e.g with "nc localhost 1234"
+    /// when the client disconnects the rest of the program runs without stopping
+    pub fn serve(&mut self, listener: &TcpListener) -> io::Result<i32> {
+        let (stream, _) = listener.accept()?;
+        let input = BufReader::new(stream.try_clone()?);
+        match self.repl(input, &stream) {
+            // the client disconnected while the repl was writing
+            Err(err) if matches!(err.kind(), io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset) => {
+                self.repl(io::empty(), io::sink())
+            },
+            res => res,
+        }
+    }
+
+    /// returns the command's output lines
+    fn run_command(&mut self, cmd: &str) -> Vec<String> {
+        let args: Vec<&str> = cmd.split_whitespace().collect();
+        match args.as_slice() {
+            [] => vec![],
+            [cmd @ ("continue" | "step" | "reverse-continue" | "reverse-step")] => {
+                let stop = match *cmd {
+                    "continue" => self.run_until_break(),
+                    "step" => self.step(),
+                    "reverse-continue" => self.reverse_continue(),
+                    _ => self.reverse_step(),
+                };
+                self.describe_stop(&stop).into_iter().collect()
+            },
+            [cmd @ ("watch" | "rwatch"), addr] => match parse_debug_addr(addr) {
+                Some(addr) => {
+                    self.watch(addr, if *cmd == "watch" { Access::Write } else { Access::Read });
+                    vec![]
+                },
+                None => vec![format!("usage: {} <addr>", cmd)],
+            },
+            ["unwatch", addr] => match parse_debug_addr(addr) {
+                Some(addr) if self.unwatch(addr) => vec![],
+                Some(addr) => vec![format!("no watchpoint at {}", addr)],
+                None => vec!["usage: unwatch <addr>".to_string()],
+            },
+            ["reg", reg] => match register_from_str(reg) {
+                Ok(reg) => vec![self.read_reg(&reg).to_string()],
+                Err(_) => vec![format!("invalid register: {}", reg)],
+            },
+            ["regs"] => vec![self.os.dump_regs()],
+            ["mem", addr, count @ ..] if count.len() <= 1 => {
+                let count = match count {
+                    [count] => count.parse().ok(),
+                    _ => Some(1),
+                };
+                match (parse_debug_addr(addr), count) {
+                    (Some(addr), Some(count)) => self.os.dump_mem(addr, count),
+                    _ => vec!["usage: mem <addr> [count]".to_string()],
+                }
+            },
+            ["bt"] => self.backtrace().iter().enumerate()
+                .map(|(frame_i, frame)| self.describe_frame(frame_i, frame))
+                .collect(),
+            ["stack"] => self.os.dump_stack_frame(),
+            // e.g break 12 if R1 >= 5
+            ["break", location, condition @ ..] => {
+                let condition = match condition {
+                    [] => None,
+                    ["if", condition @ ..] => match Condition::parse(condition) {
+                        Some(condition) => Some(condition),
+                        None => return vec!["usage: break <line> if <reg> <op> <value>".to_string()],
+                    },
+                    _ => return vec!["usage: break <line> [if <reg> <op> <value>]".to_string()],
+                };
+                match self.set_breakpoint(location, condition) {
+                    Some(id) => vec![format!("breakpoint {}", id)],
+                    None => vec![format!("no code at: {}", location)],
+                }
+            },
+            [cmd @ ("delete" | "disable" | "enable"), id] => match id.parse() {
+                Ok(id) => {
+                    let found = match *cmd {
+                        "delete" => self.breakpoints.delete(id),
+                        cmd => self.breakpoints.set_enabled(id, cmd == "enable"),
+                    };
+                    if found { vec![] } else { vec![format!("no breakpoint number {}", id)] }
+                },
+                Err(_) => vec![format!("usage: {} <n>", cmd)],
+            },
+            ["print", name] => match self.print_var(name) {
+                Some(desc) => vec![desc],
+                None => vec![format!("no variable named {}", name)],
+            },
+            ["info", vars @ ("locals" | "args")] => self.info_vars(*vars == "args"),
+            ["info", "breakpoints"] => self.breakpoints.describe(),
+            _ => vec![format!("unknown command: {}", cmd.trim())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operating_system::assembler::assemble;
+    use crate::operating_system::linker::link;
+    use std::io::Read;
+    use std::net::{Shutdown, TcpStream};
+    use std::thread;
+
+    #[test]
+    fn test_breakpoints() {
+        let mut os = OS::new();
+        let exec = assemble(&os.compile("tests/compiler_test_data/loops/inputs/for.c"));
+        let mut debugger = Debugger::new(&mut os, &exec);
+        let id = debugger.set_breakpoint("for.c:5", None).unwrap();
+        assert_eq!(debugger.set_breakpoint("for.c:100", None), None);
+        assert_eq!(debugger.run_until_break(), Stop::Breakpoint(id));
+        let a_addr = debugger.read_reg(&Register::BP) as u32 - 1;
+        assert_eq!(debugger.read_mem(a_addr), Some(0));
+        assert_eq!(debugger.run_until_break(), Stop::Breakpoint(id));
+        assert_eq!(debugger.read_mem(a_addr), Some(1));
+        assert!(debugger.breakpoints.delete(id));
+        assert_eq!(debugger.run_until_break(), Stop::Exited(3));
+        assert_eq!(debugger.step(), Stop::Exited(3));
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut os = OS::new();
+        let exec = assemble(&os.compile("tests/compiler_test_data/loops/inputs/for.c"));
+        let mut debugger = Debugger::new(&mut os, &exec);
+        debugger.set_breakpoint("for.c:2", None).unwrap();
+        debugger.run_until_break();
+        let a_addr = debugger.read_reg(&Register::BP) as u32 - 1;
+        debugger.watch(a_addr, Access::Write);
+        assert_eq!(debugger.run_until_break(), Stop::Watchpoint(a_addr, Access::Write));
+        assert_eq!(debugger.read_mem(a_addr), Some(0));
+        assert!(debugger.unwatch(a_addr));
+        assert_eq!(debugger.run_until_break(), Stop::Exited(3));
+    }
+
+    #[test]
+    fn test_reverse_execution() {
+        let mut os = OS::new();
+        let exec = assemble(&os.compile("tests/compiler_test_data/loops/inputs/for.c"));
+        let mut debugger = Debugger::new(&mut os, &exec);
+        assert_eq!(debugger.reverse_step(), Stop::JournalStart);
+        let id = debugger.set_breakpoint("for.c:5", None).unwrap();
+        debugger.run_until_break();
+        let a_addr = debugger.read_reg(&Register::BP) as u32 - 1;
+        let instr_i = debugger.instr_i();
+        debugger.run_until_break();
+        assert_eq!(debugger.read_mem(a_addr), Some(1));
+        assert_eq!(debugger.reverse_continue(), Stop::Breakpoint(id));
+        assert_eq!(debugger.instr_i(), instr_i);
+        assert_eq!(debugger.read_mem(a_addr), Some(0));
+        assert!(debugger.breakpoints.delete(id));
+        assert_eq!(debugger.run_until_break(), Stop::Exited(3));
+        // undoing the exit lets the program run again
+        assert_eq!(debugger.reverse_step(), Stop::Step);
+        assert_eq!(debugger.run_until_break(), Stop::Exited(3));
+        assert_eq!(debugger.reverse_continue(), Stop::JournalStart);
+        assert_eq!(debugger.instr_i(), 0);
+        assert_eq!(debugger.run_until_break(), Stop::Exited(3));
+    }
+
+    #[test]
+    fn test_backtrace() {
+        let mut os = OS::new();
+        let exec = assemble(&os.compile("tests/compiler_test_data/functions/inputs/fib.c"));
+        let mut debugger = Debugger::new(&mut os, &exec);
+        debugger.set_breakpoint("fib.c:3", None).unwrap();
+        debugger.run_until_break();
+        let frames: Vec<String> = debugger.backtrace().iter().enumerate()
+            .map(|(frame_i, frame)| debugger.describe_frame(frame_i, frame))
+            .collect();
+        let file = "tests/compiler_test_data/functions/inputs/fib.c";
+        assert_eq!(frames, vec![
+            format!("#0 fib(1) at {}:3", file),
+            format!("#1 fib(2) at {}:5", file),
+            format!("#2 fib(3) at {}:5", file),
+            format!("#3 fib(4) at {}:5", file),
+            format!("#4 fib(5) at {}:5", file),
+            format!("#5 main at {}:11", file),
+        ]);
+    }
+
+    #[test]
+    fn test_backtrace_without_source() {
+        let mut os = OS::new();
+        let exec = assemble(".global main\nmain:\nCALL f\nHALT\n.global f\nf:\nMOV R1 1\nloop:\nADD R1 R1 1\nRET");
+        let mut debugger = Debugger::new(&mut os, &exec);
+        debugger.set_breakpoint("9", None).unwrap(); // the RET
+        debugger.run_until_break();
+        let frames: Vec<String> = debugger.backtrace().iter().enumerate()
+            .map(|(frame_i, frame)| debugger.describe_frame(frame_i, frame))
+            .collect();
+        assert_eq!(frames, vec!["#0 f at instr 4 <loop+1>", "#1 main at instr 0 <main+0>"]);
+    }
+
+    #[test]
+    fn test_print_vars() {
+        let mut os = OS::new();
+        let exec = link(&[&os.compile_object("tests/debugger_test_data/vars.c")], &os.layout);
+        let mut debugger = Debugger::new(&mut os, &exec);
+        debugger.set_breakpoint("vars.c:23", None).unwrap();
+        debugger.set_breakpoint("vars.c:10", None).unwrap();
+        debugger.run_until_break();
+        assert_eq!(debugger.info_vars(false), vec!["c = 97 'a'", "p = {2, 3}", "arr = {1, 2, 3}"]);
+        debugger.run_until_break();
+        assert_eq!(debugger.info_vars(true), vec!["p = {2, 3}", "scale = 3"]);
+        assert_eq!(debugger.info_vars(false), vec!["res = 6"]);
+        assert_eq!(debugger.print_var("counter"), Some("counter = 7".to_string()));
+        assert_eq!(debugger.print_var("c"), None);
+        assert_eq!(debugger.run_until_break(), Stop::Exited(18));
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut os = OS::new();
+        let exec = assemble(&os.compile("tests/compiler_test_data/variables/inputs/assign.c"));
+        let mut debugger = Debugger::new(&mut os, &exec);
+        let commands = "break assign.c:3 if R1 == 2\ninfo breakpoints\ncontinue\nstep\nreg R1\nbt\nbogus\n";
+        assert_eq!(debugger.repl(commands.as_bytes(), io::sink()).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_serve() {
+        let mut os = OS::new();
+        let exec = assemble(&os.compile("tests/compiler_test_data/loops/inputs/for.c"));
+        let mut debugger = Debugger::new(&mut os, &exec);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"break for.c:5\ncontinue\ncontinue\nreg R1\n").unwrap();
+            stream.shutdown(Shutdown::Write).unwrap();
+            let mut output = String::new();
+            stream.read_to_string(&mut output).unwrap();
+            output
+        });
+        assert_eq!(debugger.serve(&listener).unwrap(), 3);
+        // the responses are between the prompts
+        let output = client.join().unwrap();
+        let responses: Vec<&str> = output.split(">\n").skip(1)
+            .map(|response| response.lines().next().unwrap_or(""))
+            .collect();
+        assert_eq!(responses[..3], ["breakpoint 1", "breakpoint 1", "breakpoint 1"]);
+    }
+}
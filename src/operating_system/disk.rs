@@ -0,0 +1,109 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+pub const BLOCK_SIZE: usize = 512;
+
+/// the number of blocks of a new disk, 512KB
+pub const DEFAULT_DISK_BLOCKS: u32 = 1024;
+
+pub type Block = [u8; BLOCK_SIZE];
+
+/// where the blocks are stored, a host file or a buffer in memory
+trait Image: Read + Write + Seek {}
+impl<T: Read + Write + Seek> Image for T {}
+
+/// a block device, see filesystem.rs for the file system on it
+pub struct Disk {
+    image: Box<dyn Image>,
+    blocks: u32,
+}
+
+impl Disk {
+    /// a zeroed disk in memory, its blocks are lost when it's dropped
+    pub fn new(blocks: u32) -> Disk {
+        let image = Cursor::new(vec![0; blocks as usize * BLOCK_SIZE]);
+        Disk { image: Box::new(image), blocks }
+    }
+
+    /// a disk whose image is the host file, so its blocks persist across runs
+    /// a missing file is created as a zeroed disk with the given number of blocks
+    pub fn open(path: &str, blocks: u32) -> io::Result<Disk> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.set_len((blocks as usize * BLOCK_SIZE) as u64)?;
+        }
+        let blocks = (file.metadata()?.len() / BLOCK_SIZE as u64) as u32;
+        Ok(Disk { image: Box::new(file), blocks })
+    }
+
+    pub fn blocks(&self) -> u32 {
+        self.blocks
+    }
+
+    pub fn read_block(&mut self, block: u32) -> io::Result<Block> {
+        self.seek(block)?;
+        let mut data = [0; BLOCK_SIZE];
+        self.image.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    pub fn write_block(&mut self, block: u32, data: &Block) -> io::Result<()> {
+        self.seek(block)?;
+        self.image.write_all(data)
+    }
+
+    fn seek(&mut self, block: u32) -> io::Result<()> {
+        if block >= self.blocks {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("block {} is out of the disk", block)));
+        }
+        self.image.seek(SeekFrom::Start((block as usize * BLOCK_SIZE) as u64))?;
+        Ok(())
+    }
+
+    /// writes a copy of the disk's image to a host file, which Disk::open can use
+    pub fn save(&mut self, path: &str) -> io::Result<()> {
+        let mut image = Vec::with_capacity(self.blocks as usize * BLOCK_SIZE);
+        for block in 0..self.blocks {
+            image.extend_from_slice(&self.read_block(block)?);
+        }
+        fs::write(path, image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let mut disk = Disk::new(4);
+        let mut data = [0; BLOCK_SIZE];
+        data[0] = 1;
+        data[BLOCK_SIZE - 1] = 2;
+        disk.write_block(3, &data).unwrap();
+        assert_eq!(disk.read_block(3).unwrap()[..], data[..]);
+        assert_eq!(disk.read_block(2).unwrap()[..], [0; BLOCK_SIZE][..]);
+        assert!(disk.read_block(4).is_err());
+        assert!(disk.write_block(4, &data).is_err());
+    }
+
+    #[test]
+    fn test_image() {
+        let image_file = tempfile::NamedTempFile::new().unwrap();
+        let image_path = image_file.path().to_str().unwrap();
+        let mut data = [0; BLOCK_SIZE];
+        data[7] = 7;
+        let mut disk = Disk::open(image_path, 8).unwrap();
+        disk.write_block(5, &data).unwrap();
+        drop(disk);
+
+        let mut disk = Disk::open(image_path, 100).unwrap();
+        assert_eq!(disk.blocks(), 8);
+        assert_eq!(disk.read_block(5).unwrap()[..], data[..]);
+
+        let copy_file = tempfile::NamedTempFile::new().unwrap();
+        let copy_path = copy_file.path().to_str().unwrap();
+        disk.save(copy_path).unwrap();
+        assert_eq!(Disk::open(copy_path, 1).unwrap().read_block(5).unwrap()[..], data[..]);
+    }
+}
@@ -0,0 +1,72 @@
+use std::io::{self, Write};
+
+use super::layout::DISPLAY_COLUMNS;
+
+/// the terminal is redrawn at most once per this many instructions, since reading the display is slow
+const REFRESH_STEPS: u32 = 1000;
+
+/// the text of the display's cells (row by row), a cell that isn't a printable ascii char is blank
+/// trailing blanks are removed from each row
+pub fn render(cells: &[i32]) -> String {
+    cells.chunks(DISPLAY_COLUMNS as usize)
+        .map(|row| {
+            let line: String = row.iter()
+                .map(|cell| match *cell {
+                    32..=126 => *cell as u8 as char,
+                    _ => ' ',
+                })
+                .collect();
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// draws the display on the host's terminal when it changes
+#[derive(Default)]
+pub struct TerminalRenderer {
+    steps: u32, // since the display was last checked
+    last_frame: Option<String>,
+}
+
+impl TerminalRenderer {
+    pub fn new() -> TerminalRenderer {
+        TerminalRenderer::default()
+    }
+
+    /// called after each instruction, returns true when the display should be checked for changes
+    pub fn tick(&mut self) -> bool {
+        self.steps += 1;
+        if self.steps < REFRESH_STEPS {
+            return false;
+        }
+        self.steps = 0;
+        true
+    }
+
+    /// clears the terminal & draws the frame, unless it's the frame that was drawn last
+    pub fn draw(&mut self, frame: String) {
+        if self.last_frame.as_ref() == Some(&frame) {
+            return;
+        }
+        println!("\x1b[2J\x1b[H{}", frame);
+        io::stdout().flush().ok();
+        self.last_frame = Some(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut cells = vec![0; (DISPLAY_COLUMNS * 2) as usize];
+        cells[1] = 'h' as i32;
+        cells[2] = 'i' as i32;
+        cells[DISPLAY_COLUMNS as usize] = '#' as i32;
+        cells[DISPLAY_COLUMNS as usize + 1] = 7; // not printable
+        cells[DISPLAY_COLUMNS as usize + 2] = '#' as i32;
+        assert_eq!(render(&cells), " hi\n# #");
+    }
+}
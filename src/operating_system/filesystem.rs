@@ -0,0 +1,544 @@
+/*
+The file system is stored on a Disk, so its files persist if the disk's image is a host file
+Disk layout, in blocks of BLOCK_SIZE bytes:
+0       superblock: the magic "SVFS"
+1       bitmap of the used blocks, bit i of byte i / 8 is set if block i is used
+2-9     inode table, INODES inodes of INODE_SIZE bytes
+10-     data blocks
+An inode:
+    0       used, 1 if the inode is a file
+    1-27    name, padded with 0s. the directory is flat, a file is found by its name
+    28-31   size in bytes
+    32-59   DIRECT_BLOCKS block numbers
+    60-63   the number of a block of POINTERS_PER_BLOCK more block numbers, for large files
+numbers are u32 little endian, block number 0 means the block wasn't allocated (a hole reads as 0s)
+a file without a name was unlinked while it was open, it's freed when it's closed or when the disk is mounted
+*/
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::io;
+
+use super::disk::{Block, Disk, BLOCK_SIZE, DEFAULT_DISK_BLOCKS};
+
+// open flags, same values as in POSIX
+pub const O_RDONLY: i32 = 0;
+pub const O_WRONLY: i32 = 1;
+pub const O_RDWR: i32 = 2;
+pub const O_CREAT: i32 = 64;
+pub const O_TRUNC: i32 = 512;
+pub const O_APPEND: i32 = 1024;
+const O_ACCMODE: i32 = 3;
+
+/// file descriptors 0-2 are stdin, stdout & stderr
+const FIRST_FD: i32 = 3;
+
+const MAGIC: &[u8] = b"SVFS";
+const SUPERBLOCK: u32 = 0;
+const BITMAP_BLOCK: u32 = 1;
+const INODE_TABLE_BLOCK: u32 = 2;
+const INODE_SIZE: usize = 64;
+const INODES_PER_BLOCK: u32 = (BLOCK_SIZE / INODE_SIZE) as u32;
+pub const INODES: u32 = 64;
+const FIRST_DATA_BLOCK: u32 = INODE_TABLE_BLOCK + INODES / INODES_PER_BLOCK;
+/// the bitmap has a bit for each block
+pub const MAX_DISK_BLOCKS: u32 = (BLOCK_SIZE * 8) as u32;
+pub const MAX_NAME_LEN: usize = 27;
+const DIRECT_BLOCKS: usize = 7;
+const POINTERS_PER_BLOCK: usize = BLOCK_SIZE / 4;
+pub const MAX_FILE_SIZE: usize = (DIRECT_BLOCKS + POINTERS_PER_BLOCK) * BLOCK_SIZE;
+
+#[derive(Debug, Clone, Default)]
+struct Inode {
+    name: String, // empty if the file was unlinked
+    size: usize,
+    blocks: [u32; DIRECT_BLOCKS],
+    indirect: u32,
+}
+
+impl Inode {
+    fn from_bytes(bytes: &[u8]) -> Option<Inode> {
+        if bytes[0] == 0 {
+            return None;
+        }
+        let name_end = bytes[1..=MAX_NAME_LEN].iter().position(|byte| *byte == 0).unwrap_or(MAX_NAME_LEN);
+        let mut blocks = [0; DIRECT_BLOCKS];
+        for (i, block) in blocks.iter_mut().enumerate() {
+            *block = read_u32(bytes, 32 + i * 4);
+        }
+        Some(Inode {
+            name: String::from_utf8_lossy(&bytes[1..1 + name_end]).to_string(),
+            size: read_u32(bytes, 28) as usize,
+            blocks,
+            indirect: read_u32(bytes, 60),
+        })
+    }
+
+    fn to_bytes(&self) -> [u8; INODE_SIZE] {
+        let mut bytes = [0; INODE_SIZE];
+        bytes[0] = 1;
+        bytes[1..1 + self.name.len()].copy_from_slice(self.name.as_bytes());
+        write_u32(&mut bytes, 28, self.size as u32);
+        for (i, block) in self.blocks.iter().enumerate() {
+            write_u32(&mut bytes, 32 + i * 4, *block);
+        }
+        write_u32(&mut bytes, 60, self.indirect);
+        bytes
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+#[derive(Debug, Clone)]
+struct OpenFile {
+    inode: u32,
+    offset: usize,
+    readable: bool,
+    writable: bool,
+    append: bool,
+}
+
+/// a file system with a single flat directory, stored on a disk
+/// the files outlive the programs that use them, only the open file descriptors are kept in memory
+/// the disk is the vm's hardware, so the disk's io errors panic like memory errors would
+pub struct FileSystem {
+    disk: Disk,
+    open_files: BTreeMap<i32, OpenFile>, // fd -> open file
+}
+
+impl FileSystem {
+    /// an empty file system on a disk in memory
+    pub fn new() -> FileSystem {
+        FileSystem::format(Disk::new(DEFAULT_DISK_BLOCKS))
+    }
+
+    /// creates an empty file system on the disk, its blocks after MAX_DISK_BLOCKS aren't used
+    pub fn format(mut disk: Disk) -> FileSystem {
+        assert!(disk.blocks() > FIRST_DATA_BLOCK, "the disk is too small for a file system");
+        let mut superblock = [0; BLOCK_SIZE];
+        superblock[..MAGIC.len()].copy_from_slice(MAGIC);
+        disk.write_block(SUPERBLOCK, &superblock).unwrap();
+        let mut bitmap = [0; BLOCK_SIZE];
+        // the blocks before the data blocks & the blocks out of the disk are used
+        for block in (0..FIRST_DATA_BLOCK).chain(disk.blocks().min(MAX_DISK_BLOCKS)..MAX_DISK_BLOCKS) {
+            bitmap[block as usize / 8] |= 1 << (block % 8);
+        }
+        disk.write_block(BITMAP_BLOCK, &bitmap).unwrap();
+        for block in INODE_TABLE_BLOCK..FIRST_DATA_BLOCK {
+            disk.write_block(block, &[0; BLOCK_SIZE]).unwrap();
+        }
+        FileSystem { disk, open_files: BTreeMap::new() }
+    }
+
+    /// the file system that's on the disk, fails if the disk wasn't formatted
+    pub fn mount(disk: Disk) -> io::Result<FileSystem> {
+        let mut fs = FileSystem { disk, open_files: BTreeMap::new() };
+        if fs.disk.blocks() <= FIRST_DATA_BLOCK || fs.read_block(SUPERBLOCK)[..MAGIC.len()] != *MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the disk doesn't have a file system"));
+        }
+        // files that were unlinked while they were open
+        for inode in 0..INODES {
+            if fs.read_inode(inode).is_some_and(|file| file.name.is_empty()) {
+                fs.free_inode(inode);
+            }
+        }
+        Ok(fs)
+    }
+
+    /// the file system on the host's disk image, a missing image is created & formatted
+    pub fn open_image(path: &str) -> io::Result<FileSystem> {
+        let exists = std::path::Path::new(path).exists();
+        let disk = Disk::open(path, DEFAULT_DISK_BLOCKS)?;
+        if exists {
+            FileSystem::mount(disk)
+        } else {
+            Ok(FileSystem::format(disk))
+        }
+    }
+
+    /// for accessing the blocks directly, like programs do with the disk's registers
+    pub fn disk(&mut self) -> &mut Disk {
+        &mut self.disk
+    }
+
+    /// returns the new file descriptor, or None if the file doesn't exist & O_CREAT isn't given
+    /// or if it can't be created since its name is too long or there are INODES files
+    pub fn open(&mut self, name: &str, flags: i32) -> Option<i32> {
+        let inode = match self.find(name) {
+            Some(inode) => inode,
+            None if flags & O_CREAT != 0 && !name.is_empty() && name.len() <= MAX_NAME_LEN => {
+                let inode = (0..INODES).find(|inode| self.read_inode(*inode).is_none())?;
+                self.write_inode(inode, &Inode { name: name.to_string(), ..Inode::default() });
+                inode
+            },
+            None => return None,
+        };
+        let access = flags & O_ACCMODE;
+        let writable = access == O_WRONLY || access == O_RDWR;
+        if writable && flags & O_TRUNC != 0 {
+            self.truncate(inode);
+        }
+        // the lowest unused descriptor, like in POSIX
+        let fd = (FIRST_FD..).find(|fd| !self.open_files.contains_key(fd)).unwrap();
+        self.open_files.insert(fd, OpenFile {
+            inode,
+            offset: 0,
+            readable: access == O_RDONLY || access == O_RDWR,
+            writable,
+            append: flags & O_APPEND != 0,
+        });
+        Some(fd)
+    }
+
+    /// reads up to count bytes from the file's offset, returns None if the file isn't open for reading
+    pub fn read(&mut self, fd: i32, count: usize) -> Option<Vec<u8>> {
+        let file = self.open_files.get(&fd).filter(|file| file.readable)?.clone();
+        let mut inode = self.read_inode(file.inode).unwrap();
+        let start = file.offset.min(inode.size);
+        let end = (start + count).min(inode.size);
+        let mut bytes = Vec::with_capacity(end - start);
+        let mut offset = start;
+        while offset < end {
+            let chunk_end = end.min((offset / BLOCK_SIZE + 1) * BLOCK_SIZE);
+            let data = match self.file_block(&mut inode, offset / BLOCK_SIZE, false) {
+                Some(block) => self.read_block(block),
+                None => [0; BLOCK_SIZE],
+            };
+            bytes.extend_from_slice(&data[offset % BLOCK_SIZE..offset % BLOCK_SIZE + chunk_end - offset]);
+            offset = chunk_end;
+        }
+        self.open_files.get_mut(&fd).unwrap().offset = end;
+        Some(bytes)
+    }
+
+    /// returns the number of written bytes, which is less than the given bytes if the disk is full or the file reached MAX_FILE_SIZE
+    /// returns None if the file isn't open for writing, or if nothing could be written
+    pub fn write(&mut self, fd: i32, bytes: &[u8]) -> Option<usize> {
+        let file = self.open_files.get(&fd).filter(|file| file.writable)?.clone();
+        let mut inode = self.read_inode(file.inode).unwrap();
+        let start = if file.append { inode.size } else { file.offset };
+        let mut offset = start;
+        while offset < start + bytes.len() {
+            let block = match self.file_block(&mut inode, offset / BLOCK_SIZE, true) {
+                Some(block) => block,
+                None => break,
+            };
+            let chunk_end = (start + bytes.len()).min((offset / BLOCK_SIZE + 1) * BLOCK_SIZE);
+            let mut data = self.read_block(block);
+            data[offset % BLOCK_SIZE..offset % BLOCK_SIZE + chunk_end - offset].copy_from_slice(&bytes[offset - start..chunk_end - start]);
+            self.write_block(block, &data);
+            offset = chunk_end;
+        }
+        inode.size = inode.size.max(offset);
+        self.write_inode(file.inode, &inode);
+        self.open_files.get_mut(&fd).unwrap().offset = offset;
+        if offset == start && !bytes.is_empty() {
+            return None;
+        }
+        Some(offset - start)
+    }
+
+    pub fn close(&mut self, fd: i32) -> bool {
+        match self.open_files.remove(&fd) {
+            Some(file) => {
+                self.remove_unused_inode(file.inode);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// closes the files that the programs left open
+    pub fn close_all(&mut self) {
+        let fds: Vec<i32> = self.open_files.keys().cloned().collect();
+        for fd in fds {
+            self.close(fd);
+        }
+    }
+
+    /// removes the file from the directory, its data is kept until it's closed
+    pub fn unlink(&mut self, name: &str) -> bool {
+        match self.find(name) {
+            Some(inode) => {
+                let mut file = self.read_inode(inode).unwrap();
+                file.name.clear();
+                self.write_inode(inode, &file);
+                self.remove_unused_inode(inode);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn remove_unused_inode(&mut self, inode: u32) {
+        let linked = self.read_inode(inode).is_some_and(|file| !file.name.is_empty());
+        let open = self.open_files.values().any(|file| file.inode == inode);
+        if !linked && !open {
+            self.free_inode(inode);
+        }
+    }
+
+    /// the file's contents, for inspecting files from outside the vm
+    pub fn read_file(&mut self, name: &str) -> Option<Vec<u8>> {
+        let fd = self.open(name, O_RDONLY)?;
+        let data = self.read(fd, MAX_FILE_SIZE);
+        self.close(fd);
+        data
+    }
+
+    /// creates or replaces a file, for giving files to programs from outside the vm
+    pub fn write_file(&mut self, name: &str, data: &[u8]) {
+        let fd = self.open(name, O_WRONLY | O_CREAT | O_TRUNC).unwrap();
+        self.write(fd, data);
+        self.close(fd);
+    }
+
+    pub fn list_files(&mut self) -> Vec<String> {
+        let mut names: Vec<String> = (0..INODES)
+            .filter_map(|inode| self.read_inode(inode))
+            .map(|file| file.name)
+            .filter(|name| !name.is_empty())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// the number of data blocks that aren't used by files
+    pub fn free_blocks(&mut self) -> u32 {
+        let bitmap = self.read_block(BITMAP_BLOCK);
+        bitmap.iter().map(|byte| byte.count_zeros()).sum()
+    }
+
+    /// saves a copy of the disk's image to a host file, see open_image
+    pub fn save(&mut self, path: &str) -> io::Result<()> {
+        self.disk.save(path)
+    }
+
+    fn find(&mut self, name: &str) -> Option<u32> {
+        (0..INODES).find(|inode| self.read_inode(*inode).is_some_and(|file| !name.is_empty() && file.name == name))
+    }
+
+    /// the disk block of the file's index'th block
+    /// if allocate is set, missing blocks are allocated & the inode is updated (the caller writes it), else they're None
+    fn file_block(&mut self, inode: &mut Inode, index: usize, allocate: bool) -> Option<u32> {
+        if index < DIRECT_BLOCKS {
+            if inode.blocks[index] == 0 && allocate {
+                inode.blocks[index] = self.allocate_block()?;
+            }
+            return Some(inode.blocks[index]).filter(|block| *block != 0);
+        }
+        let index = index - DIRECT_BLOCKS;
+        if index >= POINTERS_PER_BLOCK {
+            return None;
+        }
+        if inode.indirect == 0 {
+            if !allocate {
+                return None;
+            }
+            inode.indirect = self.allocate_block()?;
+        }
+        let mut pointers = self.read_block(inode.indirect);
+        let mut block = read_u32(&pointers, index * 4);
+        if block == 0 && allocate {
+            block = self.allocate_block()?;
+            write_u32(&mut pointers, index * 4, block);
+            self.write_block(inode.indirect, &pointers);
+        }
+        Some(block).filter(|block| *block != 0)
+    }
+
+    /// frees the blocks of the file
+    fn truncate(&mut self, inode: u32) {
+        let mut file = self.read_inode(inode).unwrap();
+        let mut blocks: Vec<u32> = file.blocks.to_vec();
+        if file.indirect != 0 {
+            let pointers = self.read_block(file.indirect);
+            blocks.extend((0..POINTERS_PER_BLOCK).map(|i| read_u32(&pointers, i * 4)));
+            blocks.push(file.indirect);
+        }
+        for block in blocks.into_iter().filter(|block| *block != 0) {
+            self.set_block_used(block, false);
+        }
+        file.size = 0;
+        file.blocks = [0; DIRECT_BLOCKS];
+        file.indirect = 0;
+        self.write_inode(inode, &file);
+    }
+
+    fn free_inode(&mut self, inode: u32) {
+        self.truncate(inode);
+        self.write_inode_bytes(inode, &[0; INODE_SIZE]);
+    }
+
+    /// returns a zeroed unused block, or None if the disk is full
+    fn allocate_block(&mut self) -> Option<u32> {
+        let bitmap = self.read_block(BITMAP_BLOCK);
+        let block = (FIRST_DATA_BLOCK..MAX_DISK_BLOCKS).find(|block| bitmap[*block as usize / 8] & (1 << (block % 8)) == 0)?;
+        self.set_block_used(block, true);
+        self.write_block(block, &[0; BLOCK_SIZE]);
+        Some(block)
+    }
+
+    fn set_block_used(&mut self, block: u32, used: bool) {
+        let mut bitmap = self.read_block(BITMAP_BLOCK);
+        if used {
+            bitmap[block as usize / 8] |= 1 << (block % 8);
+        } else {
+            bitmap[block as usize / 8] &= !(1 << (block % 8));
+        }
+        self.write_block(BITMAP_BLOCK, &bitmap);
+    }
+
+    fn read_inode(&mut self, inode: u32) -> Option<Inode> {
+        let block = self.read_block(INODE_TABLE_BLOCK + inode / INODES_PER_BLOCK);
+        let offset = (inode % INODES_PER_BLOCK) as usize * INODE_SIZE;
+        Inode::from_bytes(&block[offset..offset + INODE_SIZE])
+    }
+
+    fn write_inode(&mut self, inode: u32, file: &Inode) {
+        self.write_inode_bytes(inode, &file.to_bytes());
+    }
+
+    fn write_inode_bytes(&mut self, inode: u32, bytes: &[u8; INODE_SIZE]) {
+        let block_num = INODE_TABLE_BLOCK + inode / INODES_PER_BLOCK;
+        let mut block = self.read_block(block_num);
+        let offset = (inode % INODES_PER_BLOCK) as usize * INODE_SIZE;
+        block[offset..offset + INODE_SIZE].copy_from_slice(bytes);
+        self.write_block(block_num, &block);
+    }
+
+    fn read_block(&mut self, block: u32) -> Block {
+        self.disk.read_block(block).unwrap_or_else(|err| panic!("disk error: {}", err))
+    }
+
+    fn write_block(&mut self, block: u32, data: &Block) {
+        self.disk.write_block(block, data).unwrap_or_else(|err| panic!("disk error: {}", err))
+    }
+}
+
+impl Default for FileSystem {
+    fn default() -> FileSystem {
+        FileSystem::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_write() {
+        let mut fs = FileSystem::new();
+        assert_eq!(fs.open("a", O_RDONLY), None);
+        let fd = fs.open("a", O_RDWR | O_CREAT).unwrap();
+        assert_eq!(fd, FIRST_FD);
+        assert_eq!(fs.write(fd, b"hello"), Some(5));
+        assert_eq!(fs.read(fd, 10), Some(vec![]));
+        assert!(fs.close(fd));
+        assert!(!fs.close(fd));
+
+        let fd = fs.open("a", O_RDONLY).unwrap();
+        assert_eq!(fs.read(fd, 3), Some(b"hel".to_vec()));
+        assert_eq!(fs.read(fd, 3), Some(b"lo".to_vec()));
+        assert_eq!(fs.write(fd, b"x"), None);
+
+        let append_fd = fs.open("a", O_WRONLY | O_APPEND).unwrap();
+        assert_eq!(append_fd, fd + 1);
+        fs.write(append_fd, b"!");
+        assert_eq!(fs.read_file("a"), Some(b"hello!".to_vec()));
+        fs.close_all();
+
+        let fd = fs.open("a", O_WRONLY | O_TRUNC).unwrap();
+        fs.write(fd, b"bye");
+        assert_eq!(fs.read_file("a"), Some(b"bye".to_vec()));
+    }
+
+    #[test]
+    fn test_unlink() {
+        let mut fs = FileSystem::new();
+        let free_blocks = fs.free_blocks();
+        fs.write_file("a", b"data");
+        let fd = fs.open("a", O_RDONLY).unwrap();
+        assert!(fs.unlink("a"));
+        assert!(!fs.unlink("a"));
+        assert!(fs.list_files().is_empty());
+        // the data can still be read through the open descriptor
+        assert_eq!(fs.read(fd, 4), Some(b"data".to_vec()));
+        fs.close(fd);
+        assert_eq!(fs.free_blocks(), free_blocks);
+        assert!((0..INODES).all(|inode| fs.read_inode(inode).is_none()));
+    }
+
+    #[test]
+    fn test_large_files() {
+        let mut fs = FileSystem::format(Disk::new(200));
+        let free_blocks = fs.free_blocks();
+        assert_eq!(free_blocks, 190);
+        let data: Vec<u8> = (0..MAX_FILE_SIZE + 1).map(|i| (i % 251) as u8).collect();
+        // the last byte doesn't fit, & the indirect block takes a block too
+        let fd = fs.open("big", O_RDWR | O_CREAT).unwrap();
+        assert_eq!(fs.write(fd, &data), Some(MAX_FILE_SIZE));
+        assert_eq!(fs.write(fd, b"x"), None);
+        assert_eq!(fs.free_blocks(), free_blocks - 136);
+        fs.close(fd);
+        assert_eq!(fs.read_file("big").unwrap(), &data[..MAX_FILE_SIZE]);
+
+        // the disk is full
+        let fd = fs.open("other", O_WRONLY | O_CREAT).unwrap();
+        assert_eq!(fs.write(fd, &data[..BLOCK_SIZE * 60]), Some(BLOCK_SIZE * 53));
+        fs.close(fd);
+        assert_eq!(fs.free_blocks(), 0);
+        assert!(fs.unlink("big"));
+        assert_eq!(fs.free_blocks(), 136);
+
+        // a hole reads as 0s
+        let fd = fs.open("other", O_RDWR | O_TRUNC).unwrap();
+        fs.open_files.get_mut(&fd).unwrap().offset = BLOCK_SIZE * 2;
+        fs.write(fd, b"end");
+        fs.close(fd);
+        let file = fs.read_file("other").unwrap();
+        assert_eq!(file.len(), BLOCK_SIZE * 2 + 3);
+        assert!(file[..BLOCK_SIZE * 2].iter().all(|byte| *byte == 0));
+        assert_eq!(fs.free_blocks(), 190 - 1);
+    }
+
+    #[test]
+    fn test_names() {
+        let mut fs = FileSystem::new();
+        let long_name = "a".repeat(MAX_NAME_LEN + 1);
+        assert_eq!(fs.open(&long_name, O_WRONLY | O_CREAT), None);
+        assert_eq!(fs.open("", O_WRONLY | O_CREAT), None);
+        for i in 0..INODES {
+            fs.write_file(&format!("{:027}", i), b"");
+        }
+        assert_eq!(fs.open("one more", O_WRONLY | O_CREAT), None);
+        assert_eq!(fs.list_files().len(), INODES as usize);
+        assert_eq!(fs.list_files()[1], format!("{:027}", 1));
+    }
+
+    #[test]
+    fn test_mount() {
+        let image_file = tempfile::NamedTempFile::new().unwrap();
+        let image_path = image_file.path().to_str().unwrap();
+        assert!(FileSystem::mount(Disk::open(image_path, 100).unwrap()).is_err());
+        let mut fs = FileSystem::format(Disk::open(image_path, 100).unwrap());
+        fs.write_file("kept", b"on disk");
+        fs.write_file("open", b"unlinked");
+        fs.open("open", O_RDONLY).unwrap();
+        fs.unlink("open");
+        let free_blocks = fs.free_blocks();
+        drop(fs);
+
+        // the unlinked file is freed when the disk is mounted
+        let mut fs = FileSystem::open_image(image_path).unwrap();
+        assert_eq!(fs.list_files(), vec!["kept"]);
+        assert_eq!(fs.read_file("kept"), Some(b"on disk".to_vec()));
+        assert_eq!(fs.free_blocks(), free_blocks + 1);
+    }
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// first-fit free list allocator that manages the heap region
+/// memory bellow the program break is owned by the program (see sbrk), the rest is managed by the allocator
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HeapAllocator {
+    free_blocks: Vec<(u32, u32)>, // (start, size), sorted by start address
+    allocated: HashMap<u32, u32>, // start -> size
+    start: u32, // the break can't move below it
+    brk: u32,
+}
+
+impl HeapAllocator {
+    pub fn new(start: u32, end: u32) -> HeapAllocator {
+        HeapAllocator {
+            free_blocks: vec![(start, end - start)],
+            allocated: HashMap::new(),
+            start,
+            brk: start,
+        }
+    }
+
+    /// moves the program break by increment words
+    /// returns the previous break, or None if the memory is not available or the break would move below the heap
+    pub fn sbrk(&mut self, increment: i32) -> Option<u32> {
+        let prev_brk = self.brk;
+        if increment > 0 {
+            let increment = increment as u32;
+            let (first_start, first_size) = *self.free_blocks.first()?;
+            if first_start != self.brk || first_size < increment {
+                return None;
+            }
+            if first_size == increment {
+                self.free_blocks.remove(0);
+            } else {
+                self.free_blocks[0] = (first_start + increment, first_size - increment);
+            }
+            self.brk += increment;
+        } else if increment < 0 {
+            let decrement = increment.unsigned_abs();
+            let heap_start = self.brk.checked_sub(decrement).filter(|heap_start| *heap_start >= self.start)?;
+            match self.free_blocks.first_mut() {
+                Some((first_start, first_size)) if *first_start == self.brk => {
+                    *first_start = heap_start;
+                    *first_size += decrement;
+                },
+                _ => self.free_blocks.insert(0, (heap_start, decrement)),
+            }
+            self.brk = heap_start;
+        }
+        Some(prev_brk)
+    }
+
+    /// returns the address of the allocated block, or None if there's no free block that is large enough
+    pub fn malloc(&mut self, size: u32) -> Option<u32> {
+        let size = if size == 0 { 1 } else { size };
+        let block_i = self.free_blocks.iter().position(|(_, block_size)| *block_size >= size)?;
+        let (start, block_size) = self.free_blocks[block_i];
+        if block_size == size {
+            self.free_blocks.remove(block_i);
+        } else {
+            self.free_blocks[block_i] = (start + size, block_size - size);
+        }
+        self.allocated.insert(start, size);
+        Some(start)
+    }
+
+    /// returns false if addr is not the start of an allocated block
+    pub fn free(&mut self, addr: u32) -> bool {
+        let size = match self.allocated.remove(&addr) {
+            Some(size) => size,
+            None => return false,
+        };
+        let block_i = self.free_blocks.iter().position(|(start, _)| *start > addr).unwrap_or(self.free_blocks.len());
+        self.free_blocks.insert(block_i, (addr, size));
+        // merge with next block
+        if block_i + 1 < self.free_blocks.len() && addr + size == self.free_blocks[block_i + 1].0 {
+            self.free_blocks[block_i].1 += self.free_blocks[block_i + 1].1;
+            self.free_blocks.remove(block_i + 1);
+        }
+        // merge with previous block
+        if block_i > 0 {
+            let (prev_start, prev_size) = self.free_blocks[block_i - 1];
+            if prev_start + prev_size == addr {
+                self.free_blocks[block_i - 1].1 += self.free_blocks[block_i].1;
+                self.free_blocks.remove(block_i);
+            }
+        }
+        true
+    }
+
+    pub fn allocated_size(&self, addr: u32) -> Option<u32> {
+        self.allocated.get(&addr).cloned()
+    }
+
+    /// the total size of the allocated blocks
+    pub fn total_allocated(&self) -> u32 {
+        self.allocated.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_malloc() {
+        let mut heap = HeapAllocator::new(100, 110);
+        assert_eq!(heap.malloc(4), Some(100));
+        assert_eq!(heap.malloc(4), Some(104));
+        assert_eq!(heap.malloc(4), None);
+        assert_eq!(heap.malloc(2), Some(108));
+        assert_eq!(heap.allocated_size(104), Some(4));
+    }
+    #[test]
+    fn test_free() {
+        let mut heap = HeapAllocator::new(100, 110);
+        let p1 = heap.malloc(5).unwrap();
+        let p2 = heap.malloc(5).unwrap();
+        assert!(heap.free(p1));
+        assert!(!heap.free(p1));
+        assert_eq!(heap.malloc(6), None);
+        assert!(heap.free(p2));
+        // freed blocks are merged
+        assert_eq!(heap.malloc(10), Some(100));
+    }
+    #[test]
+    fn test_sbrk() {
+        let mut heap = HeapAllocator::new(100, 110);
+        assert_eq!(heap.sbrk(0), Some(100));
+        assert_eq!(heap.sbrk(4), Some(100));
+        assert_eq!(heap.malloc(2), Some(104));
+        // the block after the break is taken
+        assert_eq!(heap.sbrk(1), None);
+        assert_eq!(heap.sbrk(-2), Some(104));
+        assert_eq!(heap.malloc(8), None);
+        assert_eq!(heap.malloc(2), Some(102));
+        // the break doesn't move below the heap's start
+        assert_eq!(heap.sbrk(-3), None);
+        assert_eq!(heap.sbrk(-100000), None);
+        assert_eq!(heap.sbrk(i32::MIN), None);
+        assert_eq!(heap.sbrk(-2), Some(102));
+        assert_eq!(heap.sbrk(0), Some(100));
+        assert_eq!(heap.malloc(2), Some(100));
+    }
+}
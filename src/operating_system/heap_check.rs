@@ -0,0 +1,112 @@
+// the bookkeeping of the heap checking mode (see OS::check_heap): the blocks the program allocated & the blocks it freed
+// recently. each block is surrounded by red zones, & freed blocks stay in a quarantine before they can be allocated again,
+// the os guards both (see Cpu::guard) so that accessing them faults rather than silently reading or corrupting another block
+
+use std::collections::{BTreeMap, VecDeque};
+
+pub const RED_ZONE_SIZE: u32 = 2; // words before & after each block
+pub const QUARANTINE_SIZE: u32 = 4096; // words of freed blocks that aren't reused yet, so accesses to them are caught
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapBlock {
+    pub address: u32, // of the block's first word, as malloc returned it
+    pub size: u32, // in words, not counting the red zones
+    pub allocated_at: u32, // the address of the call to malloc
+    pub freed_at: Option<u32>, // the address of the call to free
+}
+
+impl HeapBlock {
+    /// the first word of the block's red zone before it, which is where the allocator's block starts
+    pub fn start(&self) -> u32 {
+        self.address - RED_ZONE_SIZE
+    }
+
+    /// the end of the block's red zone after it
+    pub fn end(&self) -> u32 {
+        self.address + self.size + RED_ZONE_SIZE
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeapChecker {
+    allocated: BTreeMap<u32, HeapBlock>, // by address
+    quarantine: VecDeque<HeapBlock>, // freed blocks, oldest first
+    quarantined_size: u32,
+}
+
+impl HeapChecker {
+    pub fn new() -> HeapChecker {
+        HeapChecker::default()
+    }
+
+    pub fn allocated(&mut self, block: HeapBlock) {
+        self.allocated.insert(block.address, block);
+    }
+
+    /// the allocated block that starts at the address
+    pub fn get(&self, address: u32) -> Option<&HeapBlock> {
+        self.allocated.get(&address)
+    }
+
+    /// moves the block at the address to the quarantine, returns the blocks that left the quarantine to make room for it,
+    /// which can be allocated again, or None if the address isn't the start of an allocated block
+    pub fn free(&mut self, address: u32, freed_at: u32) -> Option<Vec<HeapBlock>> {
+        let mut block = self.allocated.remove(&address)?;
+        block.freed_at = Some(freed_at);
+        self.quarantined_size += block.size;
+        self.quarantine.push_back(block);
+        let mut released = Vec::new();
+        while self.quarantined_size > QUARANTINE_SIZE {
+            let block = self.quarantine.pop_front().unwrap();
+            self.quarantined_size -= block.size;
+            released.push(block);
+        }
+        Some(released)
+    }
+
+    /// the allocated or quarantined block whose words or red zones contain the address, the allocated one if there're both
+    pub fn find(&self, address: u32) -> Option<&HeapBlock> {
+        self.allocated.values().chain(self.quarantine.iter().rev())
+            .find(|block| block.start() <= address && address < block.end())
+    }
+
+    /// the blocks that were allocated & not freed, by address
+    pub fn leaks(&self) -> impl Iterator<Item = &HeapBlock> {
+        self.allocated.values()
+    }
+}
+
+/// e.g "1 word", "3 words"
+pub fn quantity(count: u32, unit: &str) -> String {
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(address: u32, size: u32) -> HeapBlock {
+        HeapBlock { address, size, allocated_at: 1000, freed_at: None }
+    }
+
+    #[test]
+    fn test_free() {
+        let mut checker = HeapChecker::new();
+        checker.allocated(block(102, 3));
+        checker.allocated(block(109, QUARANTINE_SIZE));
+        assert_eq!(checker.get(102).unwrap().size, 3);
+        assert_eq!(checker.get(103), None);
+        assert_eq!(checker.find(106).unwrap().address, 102);
+        assert_eq!(checker.find(107).unwrap().address, 109);
+        assert_eq!(checker.free(102, 1010), Some(vec![]));
+        assert_eq!(checker.free(102, 1020), None);
+        assert_eq!(checker.find(100).unwrap().freed_at, Some(1010));
+        assert_eq!(checker.leaks().map(|block| block.address).collect::<Vec<u32>>(), vec![109]);
+
+        // the oldest blocks leave the quarantine first
+        let released = checker.free(109, 1030).unwrap();
+        assert_eq!(released, vec![HeapBlock { freed_at: Some(1010), ..block(102, 3) }]);
+        assert_eq!(checker.find(102), None);
+        assert_eq!(checker.leaks().count(), 0);
+    }
+}
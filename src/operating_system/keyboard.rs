@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// queues key presses until the program reads them from the keyboard's registers, see layout.rs
+#[derive(Default)]
+pub struct Keyboard {
+    keys: VecDeque<u8>,
+    arrived: bool, // set when keys are queued, the os then raises the keyboard interrupt
+    host: Option<Receiver<u8>>, // keys pressed on the host, see connect
+}
+
+impl Keyboard {
+    pub fn new() -> Keyboard {
+        Keyboard::default()
+    }
+
+    pub fn press(&mut self, key: u8) {
+        self.keys.push_back(key);
+        self.arrived = true;
+    }
+
+    /// returns the key that was pressed first, if it wasn't read yet
+    pub fn read(&mut self) -> Option<u8> {
+        self.keys.pop_front()
+    }
+
+    pub fn has_keys(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// the keys sent to the channel are pressed while programs run
+    pub fn connect(&mut self, host: Receiver<u8>) {
+        self.host = Some(host);
+    }
+
+    /// presses each byte of the host's stdin, the terminal sends them once a line is entered
+    pub fn connect_stdin(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for byte in io::stdin().bytes() {
+                match byte {
+                    Ok(byte) if sender.send(byte).is_ok() => {},
+                    _ => break,
+                }
+            }
+        });
+        self.connect(receiver);
+    }
+
+    /// presses the keys that arrived from the host, returns whether keys were pressed since it was last called
+    pub fn poll(&mut self) -> bool {
+        let keys: Vec<u8> = match &self.host {
+            Some(host) => host.try_iter().collect(),
+            None => Vec::new(),
+        };
+        for key in keys {
+            self.press(key);
+        }
+        let arrived = self.arrived;
+        self.arrived = false;
+        arrived
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyboard() {
+        let mut keyboard = Keyboard::new();
+        assert!(!keyboard.poll());
+        let (sender, receiver) = mpsc::channel();
+        keyboard.connect(receiver);
+        keyboard.press(b'a');
+        sender.send(b'b').unwrap();
+        assert!(keyboard.poll());
+        assert!(!keyboard.poll());
+        assert_eq!(keyboard.read(), Some(b'a'));
+        assert_eq!(keyboard.read(), Some(b'b'));
+        assert_eq!(keyboard.read(), None);
+    }
+}
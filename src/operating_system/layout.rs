@@ -6,19 +6,57 @@ Memory layout:
     - 201 COD - char out data
     - 202 CIS - char in status
     - 203 CID - char in data
+    - 204 MAS - malloc status
+    - 205 MAD - malloc data
+    - 206 FRS - free status
+    - 207 FRD - free data
+    - 208 TMS - timer status
+    - 209 TMD - timer data
+    - 210 KBS - keyboard status
+    - 211 KBD - keyboard data
+    - 212 DKS - disk status
+    - 213 DKB - disk block
+    - 214 DKA - disk address
     
     to write a char, write its ascii value to COD & then set COS to 1
     to read a char, set CIS to 1 & read ascii value from CID
+    to allocate heap memory, write the size to MAD & then set MAS to 1, the allocated address (or 0) is then in MAD
+    to free heap memory, write the address to FRD & then set FRS to 1
+    to program the timer, write the interval in cycles to TMD (0 to stop it) & then set TMS to 1
+    to read a key press, set KBS to 1 & read its ascii value from KBD, which is 0 if no key was pressed
+    to read a disk block, write the block number to DKB & the address of a buffer of BLOCK_SIZE addresses to DKA,
+    then set DKS to 1 (2 to write the buffer to the block). each address of the buffer holds a byte of the block.
+    DKS is then 0, or -1 if the block is out of the disk or the program can't access the buffer. the os's file system is on the disk, see filesystem.rs
+    100-115 interrupt vector, see cpu/interrupts.rs
+    116 keyboard handler: the function that the keyboard interrupt calls, set with the keyboard syscall
+    300-499 os code: interrupt handlers
 500-999 data
 1000-3999 code
 4000-5999 heap
 6000-9999 stack
+10000-11999 display: 80x25 character cells, row by row, a cell holds the ascii value of its char (0 is blank)
+            the os draws it on the host's terminal if OS::show_display is set, see display.rs
+12000-     address spaces of spawned processes, 12000 addresses each
+           each has the layout of 0-11999, the cpu's segment registers translate the process's addresses to its region
+100000000-100004095 shared memory: every program sees the same memory at these addresses, see cpu::SHARED_MEMORY_START
+           its blocks are created & attached with the shm syscalls
+the memory ends at DEFAULT_MEMORY_SIZE (see OS::set_memory_size), accessing beyond it faults with an out of memory fault
+
+The data, code, heap & stack regions can be moved & resized with a MemoryLayout, see OS::new_with_layout,
+the addresses above are MemoryLayout::default()
+
+Permissions (see OS::protect_memory):
+    os stuff is read only, except for the memory mapped registers which are read-write,
+    and address 0 (the HALT that main returns to) & os code which are executable
+    code is read & execute
+    data, heap & stack are read-write
+    accessing memory without permission stops the program with a segmentation fault
 
 
 Stack frame:
-local vars...
------------------
 reg_save (callee save)
+-----------------
+local vars...
 ----------------
 prev_BP
 ret_addr
@@ -39,24 +77,106 @@ Calling the function:
                  jumps to function
     Callee:
         - 
+        - allocates local vars on the stack
         - saves all registers whose value would get destroyed
-        - can allocate local vars on the stack etc.
 Returning from the function:
     Callee:
         - pushes return value to the stack
+          (a struct is copied word by word into the ret_val slots, the caller copies it out before popping them)
         - restores values of saved registers
         - 
         - RET - SP = BP + 1
                 restores BP
                 jump to returna addr
+
+Tail calls (with OptLevel::O2):
+    "return f(x);" evaluates f's args & copies them over the current function's args,
+    restores the saved registers, sets SP = BP - 1 & jumps to f, which returns straight to our caller.
+    it's only done when f has the same return type & its args fit in the current function's args
 */
 
 pub const PROGRAM_INIT_ADDRESS: u32 = 1000;
 pub const DATA_INIT_ADDRESS: u32 = 500;
 pub const INIT_SP_ADDRESS: u32 = 9999;
+pub const HEAP_START_ADDRESS: u32 = 4000;
+pub const HEAP_END_ADDRESS: u32 = 6000;
+pub const STACK_END_ADDRESS: u32 = 6000; // the stack grows down from INIT_SP_ADDRESS to here
+pub const OS_CODE_ADDRESS: u32 = 300;
+
+// the display, right after the stack
+pub const DISPLAY_COLUMNS: u32 = 80;
+pub const DISPLAY_ROWS: u32 = 25;
+pub const DISPLAY_SIZE: u32 = DISPLAY_COLUMNS * DISPLAY_ROWS;
 
 // memory mapped registers for io
 pub const COS : u32 = 200; // char out status
 pub const COD : u32 = 201; // char out data
 pub const CIS : u32 = 202; // char in status
 pub const CID : u32 = 203; // char in data
+
+// memory mapped registers for heap management
+pub const MAS : u32 = 204; // malloc status
+pub const MAD : u32 = 205; // malloc data
+pub const FRS : u32 = 206; // free status
+pub const FRD : u32 = 207; // free data
+
+// memory mapped registers for the timer
+pub const TMS : u32 = 208; // timer status
+pub const TMD : u32 = 209; // timer data
+
+// memory mapped registers for the keyboard
+pub const KBS : u32 = 210; // keyboard status
+pub const KBD : u32 = 211; // keyboard data
+
+// memory mapped registers for the disk
+pub const DKS : u32 = 212; // disk status
+pub const DKB : u32 = 213; // disk block
+pub const DKA : u32 = 214; // disk address
+
+pub const KEYBOARD_HANDLER_ADDRESS: u32 = 116;
+
+/// where the program's regions are in its address space, os stuff is always at 0-499
+/// the regions are in the order data, code, heap, stack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryLayout {
+    pub data_start: u32,
+    pub code_start: u32, // the data region ends here
+    pub heap_start: u32, // the code region ends here
+    pub heap_end: u32,
+    pub stack_end: u32, // the stack grows down from stack_top to here
+    pub stack_top: u32, // the display is after it
+}
+
+impl MemoryLayout {
+    /// the number of addresses in the address space, each process started with OS::spawn gets its own address space of this size
+    pub fn size(&self) -> u32 {
+        self.display_start() + DISPLAY_SIZE
+    }
+
+    pub fn display_start(&self) -> u32 {
+        self.stack_top + 1
+    }
+
+    /// false if the regions overlap os stuff or each other, or are out of order
+    pub fn is_valid(&self) -> bool {
+        DATA_INIT_ADDRESS <= self.data_start
+            && self.data_start <= self.code_start
+            && self.code_start <= self.heap_start
+            && self.heap_start <= self.heap_end
+            && self.heap_end <= self.stack_end
+            && self.stack_end < self.stack_top
+    }
+}
+
+impl Default for MemoryLayout {
+    fn default() -> MemoryLayout {
+        MemoryLayout {
+            data_start: DATA_INIT_ADDRESS,
+            code_start: PROGRAM_INIT_ADDRESS,
+            heap_start: HEAP_START_ADDRESS,
+            heap_end: HEAP_END_ADDRESS,
+            stack_end: STACK_END_ADDRESS,
+            stack_top: INIT_SP_ADDRESS,
+        }
+    }
+}
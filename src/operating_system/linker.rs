@@ -0,0 +1,118 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::assembler::{assemble_and_link_at, referenced_labels, Executable};
+use super::layout::MemoryLayout;
+use super::debug_info::{DebugInfo, FuncInfo};
+
+/// the output of compiling a single C file
+/// symbols are C names of functions & global variables
+#[derive(Debug)]
+pub struct ObjectFile {
+    pub code: String,
+    pub exports: BTreeSet<String>, // symbols defined in this file, sorted so the linker's errors don't depend on the hash order
+    pub imports: BTreeSet<String>, // symbols this file uses but are defined in other files
+    pub debug_info: DebugInfo,
+}
+
+/// the data label of a global variable
+pub fn global_var_label(name: &str) -> String {
+    format!("_G_{}", name)
+}
+
+/// labels that are used by the program's instructions (called, jumped to or address taken) & by its .word data
+/// a jump is usually to a local label, but a tail call jumps to another function
+pub fn get_referenced_labels(code: &str) -> BTreeSet<String> {
+    code.split('\n')
+        .flat_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts.as_slice() {
+                ["CALL", label] | ["JUMP", label] | ["LEA", _, label] => referenced_labels(label),
+                [".word", _, values @ ..] => values.iter().flat_map(|value| referenced_labels(value)).collect(),
+                _ => Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// checks that every imported symbol is exported by exactly one object,
+/// then links the objects into a single executable
+/// execution starts from the first object, the executable is loaded into memory with the given layout
+pub fn link(objects: &[&ObjectFile], layout: &MemoryLayout) -> Executable {
+    let mut symbol_to_object = HashMap::new();
+    for (object_i, object) in objects.iter().enumerate() {
+        for symbol in object.exports.iter() {
+            if symbol_to_object.insert(symbol, object_i).is_some() {
+                panic!("multiple definition of {}", symbol);
+            }
+        }
+    }
+    for object in objects.iter() {
+        for symbol in object.imports.iter() {
+            if !symbol_to_object.contains_key(symbol) {
+                panic!("undefined reference to {}", symbol);
+            }
+        }
+    }
+    let programs = objects.iter().map(|object| object.code.as_str()).collect();
+    let mut exec = assemble_and_link_at(programs, layout.code_start, layout.data_start);
+    // the functions are moved to where they were linked
+    for object in objects.iter() {
+        for func in object.debug_info.functions.iter() {
+            let start = exec.symbol_table.address_of(&func.name).unwrap();
+            exec.debug_info.functions.push(FuncInfo { start, end: start + func.end - func.start, ..func.clone() });
+        }
+    }
+    exec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(code: &str, exports: &[&str], imports: &[&str]) -> ObjectFile {
+        ObjectFile {
+            code: code.to_string(),
+            exports: exports.iter().map(|s| s.to_string()).collect(),
+            imports: imports.iter().map(|s| s.to_string()).collect(),
+            debug_info: DebugInfo::default(),
+        }
+    }
+
+    #[test]
+    fn test_referenced_labels() {
+        let labels = get_referenced_labels("CALL foo\nLEA R1 _G_x\nJUMP bar\nfoo:\nRET");
+        assert_eq!(labels, ["foo", "_G_x", "bar"].iter().map(|s| s.to_string()).collect());
+    }
+
+    #[test]
+    fn test_link() {
+        let main = object("CALL foo\nHALT", &["main"], &["foo"]);
+        let foo = object(".global foo\nfoo:\nRET", &["foo"], &[]);
+        let exec = link(&[&main, &foo], &MemoryLayout::default());
+        assert_eq!(exec.code.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "undefined reference to foo")]
+    fn test_link_undefined() {
+        let main = object("CALL foo\nHALT", &["main"], &["foo"]);
+        link(&[&main], &MemoryLayout::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple definition of foo")]
+    fn test_link_multiple_definition() {
+        let foo1 = object("foo:\nRET", &["foo"], &[]);
+        let foo2 = object("RET", &["foo"], &[]);
+        link(&[&foo1, &foo2], &MemoryLayout::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple definition of bar")]
+    fn test_link_multiple_definitions() {
+        // the first symbol in sorted order is reported
+        let foo1 = object("foo:\nbar:\nRET", &["foo", "bar"], &[]);
+        let foo2 = object("RET", &["foo", "bar"], &[]);
+        link(&[&foo1, &foo2], &MemoryLayout::default());
+    }
+}
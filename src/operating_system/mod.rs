@@ -1,146 +1,804 @@
 pub mod assembler;
+pub mod breakpoints;
+pub mod debugger;
 pub mod compiler;
+pub mod coverage;
+pub mod debug_info;
+pub mod disk;
+pub mod display;
+pub mod filesystem;
+pub mod heap;
+pub mod heap_check;
+pub mod keyboard;
 pub mod layout;
+pub mod linker;
+pub mod process;
+pub mod profiler;
+pub mod random;
+pub mod shared_memory;
+pub mod shell;
+pub mod symbol_table;
+pub mod syscalls;
 
+use std::cell::OnceCell;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
 use std::io::Read;
+use std::net::TcpListener;
 
 use self::assembler::assemble;
-use self::assembler::assemble_and_link;
+use self::assembler::assemble_and_link_at;
 use self::assembler::Executable;
-use self::compiler::Compiler;
+use self::debugger::Debugger;
+use self::display::TerminalRenderer;
+use self::compiler::{format_errors, CompileError, Compiler, CompilerOptions, OptLevel};
+use self::coverage::CoverageReport;
+use self::debug_info::{DebugInfo, VarInfo};
+use self::disk::BLOCK_SIZE;
+use self::filesystem::FileSystem;
+use self::heap::HeapAllocator;
+use self::heap_check::{quantity, HeapBlock, HeapChecker, RED_ZONE_SIZE};
+use self::keyboard::Keyboard;
 use self::layout::*;
+use self::linker::{get_referenced_labels, link, ObjectFile};
+use self::process::{process_segment, Process, ProcessState, TIME_SLICE};
+use self::profiler::ProfileReport;
+use self::random::{Random, DEFAULT_RANDOM_SEED};
+use self::shared_memory::SharedMemory;
+use self::symbol_table::SymbolTable;
+use self::syscalls::SyscallHandler;
 use crate::cpu::instructions::*;
+use crate::cpu::interrupts::*;
+use crate::cpu::Access;
 use crate::cpu::Cpu;
+use crate::cpu::Fault;
+use crate::cpu::CpuSnapshot;
+use crate::cpu::stats::ExecutionStats;
+use crate::cpu::trace::TraceConfig;
 use crate::cpu::MemEntry;
+use crate::cpu::Permissions;
+use crate::cpu::Registers;
+use crate::cpu::RunOutcome;
+use crate::cpu::{SHARED_MEMORY_END, SHARED_MEMORY_START};
+use crate::cpu::BYTES_PER_WORD;
+use crate::cpu::DEFAULT_MEMORY_SIZE;
+use crate::logging;
+use log::Level;
 
 
 
+/// exit value of a program that was stopped by a fault, like a process killed by SIGSEGV
+pub const FAULT_EXIT_CODE: i32 = 139;
+
+/// exit value of a program that called abort(), like a process killed by SIGABRT
+pub const ABORT_EXIT_CODE: i32 = 134;
+
+/// the number of frames in the backtrace of a fault report, see OS::fault_report
+const FAULT_BACKTRACE_FRAMES: usize = 8;
+
+/// what happened when a program ran, see OS::run_program
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResult {
+    pub exit_code: i32, // the value main returned or the program exited with, FAULT_EXIT_CODE if it was stopped
+    pub outcome: RunOutcome, // why the program stopped
+    pub cycles: u64,
+    pub regs: Registers, // when the program stopped
+    pub stdout: String, // what the program wrote, without the output of previous programs
+}
+
+/// the state of a running program, written by OS::save_state
+#[derive(Serialize, Deserialize)]
+struct SavedState {
+    cpu: CpuSnapshot,
+    heap: HeapAllocator,
+    out_chars: Vec<char>,
+    exit_code: Option<i32>,
+}
+
 pub struct OS {
     pub cpu: Cpu,
     pub out_chars : Vec<char>,
-    pub inp_chars : Vec<char>,
-    std_programs: Vec<String>,
-    compiled_programs_count: u32, // hack to keep compiler tmp labels from colliding
+    input: Box<dyn Read>, // where the program's stdin is read from
+    pub heap: HeapAllocator,
+    pub fs: FileSystem, // on a disk that's kept between programs, open files are closed when a new program is loaded
+    pub keyboard: Keyboard, // keys that weren't read are kept between programs
+    pub exit_code: Option<i32>, // set if the program exited via the exit syscall
+    pub compiler_options: CompilerOptions, // used for compiling programs, std is always optimized
+    pub timer_ticks: u64, // number of handled timer interrupts
+    pub max_cycles: Option<u64>, // given to the cpu of each program, see Cpu::max_cycles
+    pub args: Vec<String>, // main's argv for each program, by convention args[0] is the program's name
+    pub verbose: bool, // the os logs what it & the cpu do, & programs are compiled verbosely, see logging.rs
+    pub check_uninit: bool, // reads of local variables that weren't initialized fault, see Cpu::enable_uninit_check
+    pub check_heap: bool, // invalid accesses to heap blocks & invalid frees fault, & leaks are reported, see heap_check.rs
+    random_seed: u64, // kept so each program's random numbers start from the seed, see set_random_seed
+    random: Random,
+    reschedule: bool, // set by the timer interrupt, the scheduler then switches to the next process
+    syscalls: HashMap<i32, SyscallHandler>,
+    processes: Vec<Process>, // indexed by pid
+    running_pid: Option<u32>, // None while the os's program runs
+    shared_memory: SharedMemory, // of all processes & the os's program, see shm_create
+    std_programs: Vec<ObjectFile>,
+    on_demand_std_programs: Vec<ObjectFile>, // linked only if one of their symbols is referenced
+    byte_addressing_std: OnceCell<(Vec<ObjectFile>, Vec<ObjectFile>)>, // std & on demand std with byte addressing, compiled on first use
+    trace_config: Option<TraceConfig>, // kept so tracing stays enabled when the cpu is reset
+    layout: MemoryLayout, // of the address space of every program, including spawned processes
+    memory_size: u32, // kept so the memory size stays the same when the cpu is reset
+    symbols: SymbolTable, // of the running program's code, by their address in memory, for fault reports
+    debug_info: DebugInfo, // of the os's program, for naming the variables in fault reports
+    heap_checker: Option<HeapChecker>, // of the os's program's heap, set if check_heap
+    display_renderer: Option<TerminalRenderer>, // set if the display is drawn on the host's terminal
 }
 
 impl OS {
     pub fn new() -> OS {
-        let mut std_programs = Vec::new();
-        let num_std_programs = 1;
-        std_programs.push(Compiler::compile("libc/libc.c", 0));
-        assert_eq!(std_programs.len() as u32, num_std_programs);
-        let mut instance = OS { cpu: Cpu::new() , out_chars: Vec::new(), inp_chars: Vec::new(),
-            std_programs, compiled_programs_count: num_std_programs};
+        Self::new_with_layout(MemoryLayout::default())
+    }
+
+    /// an os whose programs are linked & loaded into memory with the given layout, panics if the layout is invalid
+    pub fn new_with_layout(layout: MemoryLayout) -> OS {
+        assert!(layout.is_valid(), "invalid memory layout: {:?}", layout);
+        assert!(layout.size() <= DEFAULT_MEMORY_SIZE, "memory layout is larger than the memory: {:?}", layout);
+        let (std_programs, on_demand_std_programs) = Self::compile_std_programs(false);
+        let mut instance = OS { cpu: Cpu::new() , out_chars: Vec::new(), input: Box::new(io::stdin()),
+            heap: HeapAllocator::new(layout.heap_start, layout.heap_end), fs: FileSystem::new(), keyboard: Keyboard::new(),
+            exit_code: None, compiler_options: CompilerOptions::default(), timer_ticks: 0, max_cycles: None, args: Vec::new(), verbose: false, check_uninit: false, check_heap: false,
+            random_seed: DEFAULT_RANDOM_SEED, random: Random::new(DEFAULT_RANDOM_SEED), reschedule: false, syscalls: syscalls::default_syscalls().into_iter().collect(),
+            processes: Vec::new(), running_pid: None, shared_memory: SharedMemory::new(), std_programs, on_demand_std_programs, byte_addressing_std: OnceCell::new(),
+            trace_config: None, layout, memory_size: DEFAULT_MEMORY_SIZE, symbols: SymbolTable::new(), debug_info: DebugInfo::default(), heap_checker: None,
+            display_renderer: None};
         instance.initialize_memory();
         instance
     }
 
+    /// sets up the os region of the cpu's current segment
     fn initialize_memory(&mut self) {
-        self.cpu.mem.set(
-            0,
-            MemEntry::Instruction(Instruction::from_str("HALT").unwrap()),
-        );
-        self.cpu.mem.set(COS, MemEntry::Num(0));
-        self.cpu.mem.set(COD, MemEntry::Num(0));
-        self.cpu.mem.set(CIS, MemEntry::Num(0));
-        self.cpu.mem.set(CID, MemEntry::Num(0));
+        self.mem_set(0, MemEntry::Instruction(Instruction::from_str("HALT").unwrap()));
+        for register in [COS, COD, CIS, CID, MAS, MAD, FRS, FRD, TMS, TMD, KBS, KBD, DKS, DKB, DKA].iter() {
+            self.mem_set(*register, MemEntry::Num(0));
+        }
+        self.mem_set(KEYBOARD_HANDLER_ADDRESS, MemEntry::Num(0));
+        self.install_interrupt_handlers();
+        self.protect_memory();
+    }
+
+    /// loads the handlers to the os code region & points the interrupt vector at them
+    fn install_interrupt_handlers(&mut self) {
+        for num in 0..INTERRUPT_VECTOR_SIZE {
+            self.mem_set(INTERRUPT_VECTOR_ADDRESS + num, MemEntry::Num(0));
+        }
+        // R1 is restored because the interrupted program doesn't expect the SYSCALL
+        let timer_handler = [
+            "PUSH R1".to_string(),
+            format!("MOV R1 {}", syscalls::SYS_TIMER),
+            "SYSCALL".to_string(),
+            "POP R1".to_string(),
+            "IRET".to_string(),
+        ];
+        // calls the program's handler, with the registers that compiled code may change saved
+        let keyboard_handler = [
+            "PUSH R1".to_string(),
+            "PUSH R2".to_string(),
+            "PUSH ZR".to_string(),
+            format!("LOAD R1 {}", KEYBOARD_HANDLER_ADDRESS),
+            "TSTE R1 0".to_string(),
+            "TJMP 2".to_string(), // skip the call if there's no handler
+            "CALLR R1".to_string(),
+            "POP ZR".to_string(),
+            "POP R2".to_string(),
+            "POP R1".to_string(),
+            "IRET".to_string(),
+        ];
+        let mut address = OS_CODE_ADDRESS;
+        for (num, handler) in [(TIMER_INTERRUPT, &timer_handler[..]), (KEYBOARD_INTERRUPT, &keyboard_handler[..])].iter() {
+            self.mem_set(INTERRUPT_VECTOR_ADDRESS + num, MemEntry::Num(address as i32));
+            for instr in handler.iter() {
+                let instr = Instruction::from_str(instr).unwrap();
+                self.mem_set(address, MemEntry::Instruction(instr));
+                address += 1;
+            }
+        }
+    }
+
+    /// sets the permissions of the memory regions of the cpu's current segment, see layout.rs
+    fn protect_memory(&mut self) {
+        let layout = self.layout;
+        let regions = [
+            (0, layout.data_start, Permissions::READ_ONLY),
+            (0, 1, Permissions::READ_EXECUTE),
+            (COS, DKA + 1, Permissions::READ_WRITE),
+            (OS_CODE_ADDRESS, layout.data_start, Permissions::READ_EXECUTE),
+            (layout.data_start, layout.code_start, Permissions::READ_WRITE),
+            (layout.code_start, layout.heap_start, Permissions::READ_EXECUTE),
+            (layout.heap_start, layout.size(), Permissions::READ_WRITE),
+        ];
+        let segment = self.cpu.segment;
+        for (start, end, permissions) in regions.iter() {
+            self.cpu.mem.protect(segment.physical(*start), segment.physical(*end), *permissions);
+        }
+        // the shared memory isn't translated, it's the same region in every segment
+        self.cpu.mem.protect(SHARED_MEMORY_START, SHARED_MEMORY_END, Permissions::READ_WRITE);
+    }
+
+    /// reads from the address space of the running program, like the cpu does
+    fn mem_get_num(&self, address: u32) -> i32 {
+        self.cpu.mem.get_num(self.cpu.segment.physical(address))
+    }
+
+    /// the entry at the address of the running program, None if the address is invalid or was never written
+    fn mem_try_get(&self, address: u32) -> Option<&MemEntry> {
+        if address >= self.cpu.segment.limit {
+            return None;
+        }
+        self.cpu.mem.try_get(self.cpu.segment.physical(address))
+    }
+
+    /// like mem_get_num, but returns None if the address is invalid or doesn't hold a number
+    fn mem_try_get_num(&self, address: u32) -> Option<i32> {
+        match self.mem_try_get(address) {
+            Some(MemEntry::Num(val)) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// reads from the address space of the running program on its behalf, e.g a syscall's buffer
+    /// returns None if reading the address would fault the program
+    fn user_get_num(&self, address: u32) -> Option<i32> {
+        let physical = self.cpu.check_access(address, Access::Read).ok()?;
+        match self.cpu.mem.try_get(physical) {
+            Some(MemEntry::Num(val)) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// writes to the address space of the running program on its behalf, returns false if writing the address would fault it
+    fn user_set(&mut self, address: u32, val: i32) -> bool {
+        match self.cpu.check_access(address, Access::Write) {
+            Ok(physical) => {
+                self.cpu.mem.set(physical, MemEntry::Num(val));
+                self.cpu.mark_written(physical);
+                true
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// true if the running program can write to addresses [start, start + count)
+    fn user_can_write(&self, start: u32, count: u32) -> bool {
+        start.checked_add(count).is_some()
+            && (start..start + count).all(|address| self.cpu.check_access(address, Access::Write).is_ok())
+    }
+
+    /// reads a zero terminated string from the address space of the running program
+    /// returns None if the program can't read it
+    fn read_string(&self, address: u32) -> Option<String> {
+        let mut string = String::new();
+        for address in address..=u32::MAX {
+            match self.user_get_num(address)? {
+                0 => return Some(string),
+                c => string.push(c as u8 as char),
+            }
+        }
+        None
+    }
+
+    /// writes to the address space of the running program, permissions are not checked
+    fn mem_set(&mut self, address: u32, val: MemEntry) {
+        let physical = self.cpu.segment.physical(address);
+        self.cpu.mem.set(physical, val);
+        self.cpu.mark_written(physical);
     }
 
     fn reset_cpu_state(&mut self) {
-        self.cpu = Cpu::new();
+        self.cpu = Cpu::with_memory_size(self.memory_size);
+        self.cpu.max_cycles = self.max_cycles;
+        if let Some(config) = &self.trace_config {
+            self.cpu.set_trace(config.clone());
+        }
+        self.heap = HeapAllocator::new(self.layout.heap_start, self.layout.heap_end);
+        self.heap_checker = if self.check_heap { Some(HeapChecker::new()) } else { None };
+        self.fs.close_all();
+        self.exit_code = None;
+        self.timer_ticks = 0;
+        self.reschedule = false;
+        self.processes.clear();
+        self.running_pid = None;
+        self.shared_memory = SharedMemory::new();
+        self.random = Random::new(self.random_seed);
         self.initialize_memory();
     }
 
-    fn initialize_stackframe(&mut self) {
-        self.cpu
-            .regs
-            .set(&Register::SP, (INIT_SP_ADDRESS - 3) as i32);
-        self.cpu
-            .regs
-            .set(&Register::BP, (INIT_SP_ADDRESS - 2) as i32);
+    /// main's frame, as if it was called with argc & argv
+    fn initialize_stackframe(&mut self, argc: u32, argv: u32) {
+        let bp = self.layout.stack_top - 4;
+        self.cpu.regs.set(&Register::SP, (bp - 1) as i32);
+        self.cpu.regs.set(&Register::BP, bp as i32);
+        self.mem_set(bp, MemEntry::Num(bp as i32)); // no prev BP, BP points to itself
+        self.mem_set(bp + 1, MemEntry::Num(0)); // jump to HALT in the end
+        self.mem_set(bp + 2, MemEntry::Num(-1)); // deafult return value = -1
+        self.mem_set(bp + 3, MemEntry::Num(argc as i32));
+        self.mem_set(bp + 4, MemEntry::Num(argv as i32));
+    }
 
-        self.cpu.mem.set(INIT_SP_ADDRESS - 1, MemEntry::Num(0)); // jump to HALT in the end
-        self.cpu.mem.set(
-            INIT_SP_ADDRESS - 2,
-            MemEntry::Num((INIT_SP_ADDRESS - 2) as i32),
-        ); // no prev BP, BP points to itself
-        self.cpu.mem.set(INIT_SP_ADDRESS, MemEntry::Num(-1)); // deafult return value = -1
+    /// writes main's args to the data region after the program's data: the argv array, which ends with 0, & then the strings
+    /// returns the address of argv, a byte address if the programs are compiled with byte addressing
+    fn load_args(&mut self, data_end: u32, args: &[String]) -> u32 {
+        let byte_addressing = self.compiler_options.is_byte_addressing();
+        let pointer = |address: u32| if byte_addressing { address * BYTES_PER_WORD } else { address };
+        let mut argv = Vec::new();
+        let mut strings = Vec::new();
+        let strings_start = data_end + args.len() as u32 + 1;
+        for arg in args.iter() {
+            argv.push(pointer(strings_start + strings.len() as u32) as i32);
+            let mut bytes = arg.as_bytes().to_vec();
+            bytes.push(0);
+            if byte_addressing {
+                // packed like the strings of the program's data, see .bytez
+                strings.extend(bytes.chunks(BYTES_PER_WORD as usize).map(|word| {
+                    let mut word_bytes = [0; BYTES_PER_WORD as usize];
+                    word_bytes[..word.len()].copy_from_slice(word);
+                    i32::from_le_bytes(word_bytes)
+                }));
+            } else {
+                strings.extend(bytes.iter().map(|byte| *byte as i32));
+            }
+        }
+        argv.push(0);
+        assert!(strings_start + strings.len() as u32 <= self.layout.code_start, "the program's arguments don't fit in the data region");
+        for (i, word) in argv.into_iter().chain(strings).enumerate() {
+            self.mem_set(data_end + i as u32, MemEntry::Num(word));
+        }
+        pointer(data_end)
     }
 
     fn load_program(&mut self, instructions: &Vec<Instruction>, data: &Vec<i32>) {
         // load instructions
         for (instr_i, instr) in instructions.iter().enumerate() {
-            self.cpu.mem.set(
-                PROGRAM_INIT_ADDRESS + (instr_i as u32),
+            self.mem_set(
+                self.layout.code_start + (instr_i as u32),
                 MemEntry::Instruction(instr.clone()),
             );
         }
         // load data
         for (data_i, data) in data.iter().enumerate() {
-            self.cpu.mem.set(
-                DATA_INIT_ADDRESS + (data_i as u32),
+            self.mem_set(
+                self.layout.data_start + (data_i as u32),
                 MemEntry::Num(data.clone()),
             );
         }
     }
 
+    /// returns everything the program wrote to stdout so far
+    pub fn get_output(&self) -> String {
+        self.out_chars.iter().collect()
+    }
+
+    pub fn clear_output(&mut self){
+        self.out_chars.clear();
+    }
+
+    fn write_char(&mut self, c: char){
+        self.out_chars.push(c);
+        print!("{}", c);
+    }
+
+    /// replaces the program's stdin, e.g to give input to a program in tests
+    pub fn set_input(&mut self, input: Box<dyn Read>){
+        self.input = input;
+    }
+
+    /// reads a single byte from the input (stdin by default)
+    fn read_char(&mut self) -> Option<char>{
+        let mut buffer = [0];
+        match self.input.read(&mut buffer) {
+            Ok(1) => Some(buffer[0] as char),
+            _ => None,
+        }
+    }
+
     fn io_step(&mut self){
-        if self.cpu.mem.get_num(COS) != 0 {
-            let c = self.cpu.mem.get_num(COD);
+        if self.mem_get_num(COS) != 0 {
+            let c = self.mem_get_num(COD);
             let c = c as u8 as char;
-            self.out_chars.push(c);
-            print!("{}", c);
+            self.write_char(c);
             // reset status register
-            self.cpu.mem.set(COS, MemEntry::Num(0));
+            self.mem_set(COS, MemEntry::Num(0));
         }
-        if self.cpu.mem.get_num(CIS) != 0 {
-            // read a single byte fron stdin
-            let mut input_handle = std::io::stdin().take(1);
-            let mut buffer = [0];
-            input_handle.read(&mut buffer);
-            let c = buffer[0] as char;
-            self.cpu.mem.set(CID, MemEntry::Num(c as i32));
-            self.cpu.mem.set(CIS, MemEntry::Num(0));
+        if self.mem_get_num(CIS) != 0 {
+            let c = self.read_char().unwrap_or(0 as char);
+            self.mem_set(CID, MemEntry::Num(c as i32));
+            self.mem_set(CIS, MemEntry::Num(0));
         }
     }
 
+    /// registers a handler for the given syscall number, replacing the previous handler if there was one
+    pub fn register_syscall(&mut self, num: i32, handler: SyscallHandler){
+        self.syscalls.insert(num, handler);
+    }
+
+    fn syscall_step(&mut self){
+        if !self.cpu.pending_syscall {
+            return;
+        }
+        self.cpu.pending_syscall = false;
+        let num = self.cpu.regs.get(&Register::R1);
+        let arg = self.cpu.regs.get(&Register::R2);
+        // like an unknown syscall on unix, which fails with ENOSYS
+        let res = match self.syscalls.get(&num) {
+            Some(handler) => handler(self, arg),
+            None => -1,
+        };
+        self.log(logging::OS, Level::Trace, format_args!("syscall {}({}) returned {}", num, arg, res));
+        self.cpu.regs.set(&Register::R1, res);
+    }
+
+    fn heap_step(&mut self){
+        // the heaps of spawned processes aren't checked
+        let checked = self.heap_checker.is_some() && self.running_pid.is_none();
+        if self.mem_get_num(MAS) != 0 {
+            let size = self.mem_get_num(MAD) as u32;
+            let addr = if checked { self.checked_malloc(size) } else { self.heap.malloc(size).unwrap_or(0) };
+            self.mem_set(MAD, MemEntry::Num(addr as i32));
+            self.mem_set(MAS, MemEntry::Num(0));
+        }
+        if self.mem_get_num(FRS) != 0 {
+            let addr = self.mem_get_num(FRD) as u32;
+            if checked {
+                self.checked_free(addr);
+            } else {
+                self.heap.free(addr);
+            }
+            self.mem_set(FRS, MemEntry::Num(0));
+        }
+    }
+
+    /// allocates the block with guarded red zones around it, see heap_check.rs
+    fn checked_malloc(&mut self, size: u32) -> u32 {
+        // blocks are in words, so with byte addressing accesses past the end of a block's last word aren't caught
+        let size = if self.compiler_options.is_byte_addressing() { size.div_ceil(BYTES_PER_WORD) } else { size }.max(1);
+        let start = match size.checked_add(2 * RED_ZONE_SIZE).and_then(|size| self.heap.malloc(size)) {
+            Some(start) => start,
+            None => return 0,
+        };
+        let block = HeapBlock { address: start + RED_ZONE_SIZE, size, allocated_at: self.heap_call_site(), freed_at: None };
+        let segment = self.cpu.segment;
+        self.cpu.guard(segment.physical(block.start()), segment.physical(block.address));
+        self.cpu.guard(segment.physical(block.address + size), segment.physical(block.end()));
+        let address = block.address;
+        self.heap_checker.as_mut().unwrap().allocated(block);
+        address
+    }
+
+    /// guards the block until it leaves the quarantine, faults if the address isn't an allocated block, e.g a double free
+    fn checked_free(&mut self, address: u32) {
+        // like free(NULL)
+        if address == 0 {
+            return;
+        }
+        let freed_at = self.heap_call_site();
+        let checker = self.heap_checker.as_mut().unwrap();
+        let size = match checker.get(address) {
+            Some(block) => block.size,
+            None => {
+                self.cpu.fault = Some(Fault::InvalidFree { address });
+                return;
+            },
+        };
+        let released = checker.free(address, freed_at).unwrap();
+        let segment = self.cpu.segment;
+        self.cpu.guard(segment.physical(address), segment.physical(address + size));
+        for block in released {
+            self.cpu.unguard(segment.physical(block.start()), segment.physical(block.end()));
+            self.heap.free(block.start());
+        }
+    }
+
+    /// the address of the call to malloc or free, whose code requests heap blocks from the os, see libc.c
+    fn heap_call_site(&self) -> u32 {
+        let bp = self.cpu.regs.get(&Register::BP) as u32;
+        self.mem_try_get_num(bp + 1).map_or(0, |ret_addr| (ret_addr as u32).saturating_sub(1))
+    }
+
+    fn timer_step(&mut self){
+        if self.mem_get_num(TMS) != 0 {
+            let interval = self.mem_get_num(TMD);
+            self.cpu.set_timer(if interval > 0 { Some(interval as u32) } else { None });
+            self.mem_set(TMS, MemEntry::Num(0));
+        }
+    }
+
+    fn keyboard_step(&mut self){
+        if self.keyboard.poll() {
+            self.log(logging::CPU, Level::Trace, format_args!("keyboard interrupt at cycle {}", self.cpu.cycles));
+            self.cpu.raise_interrupt(KEYBOARD_INTERRUPT);
+        }
+        if self.mem_get_num(KBS) != 0 {
+            let key = self.keyboard.read().unwrap_or(0);
+            self.mem_set(KBD, MemEntry::Num(key as i32));
+            self.mem_set(KBS, MemEntry::Num(0));
+        }
+    }
+
+    fn disk_step(&mut self){
+        // the status stays -1 after a failed request, so only 1 & 2 are requests
+        let request = self.mem_get_num(DKS);
+        if request != 1 && request != 2 {
+            return;
+        }
+        let block = self.mem_get_num(DKB) as u32;
+        let address = self.mem_get_num(DKA) as u32;
+        // the buffer is accessed on behalf of the program, so the request fails if the program can't access all of it
+        let done = if request == 1 {
+            match self.fs.disk().read_block(block) {
+                Ok(data) if self.user_can_write(address, BLOCK_SIZE as u32) => {
+                    for (i, byte) in data.iter().enumerate() {
+                        self.user_set(address + i as u32, *byte as i32);
+                    }
+                    true
+                },
+                _ => false,
+            }
+        } else {
+            let mut data = [0; BLOCK_SIZE];
+            let mut readable = true;
+            for (i, byte) in data.iter_mut().enumerate() {
+                match address.checked_add(i as u32).and_then(|address| self.user_get_num(address)) {
+                    Some(val) => *byte = val as u8,
+                    None => {
+                        readable = false;
+                        break;
+                    },
+                }
+            }
+            readable && self.fs.disk().write_block(block, &data).is_ok()
+        };
+        self.mem_set(DKS, MemEntry::Num(if done { 0 } else { -1 }));
+    }
+
     fn step(&mut self) -> bool {
-        let keep_running = self.cpu.step();
+        let keep_running = match self.cpu.step() {
+            Ok(keep_running) => keep_running,
+            Err(_) => return self.terminate_faulted(),
+        };
+        if self.display_renderer.as_mut().is_some_and(|renderer| renderer.tick()) {
+            self.draw_display();
+        }
+        if self.cpu.timed_out {
+            eprintln!("the program ran out of cycles ({})", self.cpu.cycles);
+            self.exit_code = Some(FAULT_EXIT_CODE);
+            return false;
+        }
         self.io_step();
-        keep_running
+        self.heap_step();
+        // an invalid free, see check_heap
+        if self.cpu.fault.is_some() {
+            return self.terminate_faulted();
+        }
+        self.timer_step();
+        self.keyboard_step();
+        self.disk_step();
+        self.syscall_step();
+        keep_running && self.exit_code.is_none()
+    }
+
+    /// the faulting program is terminated, like a process that gets a signal
+    fn terminate_faulted(&mut self) -> bool {
+        let fault = self.cpu.fault.as_ref().unwrap();
+        self.log(logging::CPU, Level::Debug, format_args!("{} at cycle {}", fault, self.cpu.cycles));
+        eprintln!("{}", self.fault_report().unwrap());
+        self.exit_code = Some(FAULT_EXIT_CODE);
+        false
     }
 
-    fn run(&mut self){
+    fn run(&mut self) -> RunOutcome {
         loop{
             let keep_running = self.step();
             if !keep_running {
                 break;
             }
         }
+        self.draw_display();
+        let outcome = self.cpu.outcome();
+        if outcome == RunOutcome::Halted {
+            if let Some(report) = self.leak_report() {
+                eprintln!("{}", report);
+            }
+        }
+        outcome
     }
 
     // runs given program
     // returns program's exit value
     pub fn load_and_run(&mut self, exec: &Executable) -> i32 {
+        self.run_program(exec).exit_code
+    }
+
+    /// runs the program, a program that runs out of cycles (see max_cycles) stops with RunOutcome::Timeout & FAULT_EXIT_CODE
+    pub fn run_program(&mut self, exec: &Executable) -> RunResult {
+        let output_start = self.out_chars.len();
+        self.load_executable(exec);
+        let outcome = self.run();
+        RunResult {
+            exit_code: self.get_exit_value(),
+            outcome,
+            cycles: self.cpu.cycles,
+            regs: self.cpu.regs.clone(),
+            stdout: self.out_chars[output_start..].iter().collect(),
+        }
+    }
+
+    /// like load_and_run, but also returns the performance stats of the execution
+    pub fn run_program_with_stats(&mut self, exec: &Executable) -> (i32, ExecutionStats) {
+        self.load_executable(exec);
+        self.cpu.enable_stats();
+        self.run();
+        (self.get_exit_value(), self.cpu.get_stats().unwrap().clone())
+    }
+
+    /// runs the program & reports the time it spent in each function & source line
+    pub fn profile_program(&mut self, exec: &Executable) -> ProfileReport {
+        self.load_executable(exec);
+        self.cpu.enable_profile();
+        self.run();
+        ProfileReport::new(self.get_exit_value(), self.cpu.get_profile().unwrap(), exec, self.layout.code_start)
+    }
+
+    /// links the objects with std & profiles them, see profile_program
+    pub fn link_and_profile(&mut self, objects: Vec<&ObjectFile>) -> ProfileReport {
+        let exec = self.link_executable(objects);
+        self.profile_program(&exec)
+    }
+
+    /// like load_and_run, but also returns which source lines the program executed
+    pub fn run_program_with_coverage(&mut self, exec: &Executable) -> (i32, CoverageReport) {
+        self.load_executable(exec);
+        self.cpu.enable_profile();
+        self.run();
+        (self.get_exit_value(), CoverageReport::from_profile(self.cpu.get_profile().unwrap(), exec, self.layout.code_start))
+    }
+
+    /// links the objects with std & runs them with coverage, see run_program_with_coverage
+    pub fn link_and_run_with_coverage(&mut self, objects: Vec<&ObjectFile>) -> (i32, CoverageReport) {
+        let exec = self.link_executable(objects);
+        self.run_program_with_coverage(&exec)
+    }
+
+    /// loads the program & sets up the cpu to start executing it
+    fn load_executable(&mut self, exec: &Executable) {
         self.reset_cpu_state();
+        self.log(logging::OS, Level::Debug, format_args!("loading a program of {} instructions & {} words of data", exec.code.len(), exec.data.len()));
+        self.symbols = self.symbols_in_memory(exec);
+        self.debug_info = exec.debug_info.clone();
         self.load_program(&exec.code, &exec.data);
+        if self.check_uninit {
+            // the compiler allocates local variables & return values by pushing ZR
+            let allocations = exec.code.iter().enumerate()
+                .filter(|(_, instr)| **instr == Instruction::Stack { op: StackOp::PUSH, dst: Register::ZR })
+                .map(|(instr_i, _)| self.cpu.segment.physical(self.layout.code_start + instr_i as u32))
+                .collect();
+            self.cpu.enable_uninit_check(allocations);
+        }
         self.cpu
             .regs
-            .set(&Register::IR, PROGRAM_INIT_ADDRESS as i32);
-        self.initialize_stackframe();
+            .set(&Register::IR, self.layout.code_start as i32);
+        let args = self.args.clone();
+        let argv = self.load_args(self.layout.data_start + exec.data.len() as u32, &args);
+        self.initialize_stackframe(args.len() as u32, argv);
+    }
+
+    /// saves the state of the current program to a file, so it can later be resumed with load_state
+    /// processes created with spawn are not saved
+    pub fn save_state(&self, path: &str) -> io::Result<()> {
+        let state = SavedState {
+            cpu: self.cpu.snapshot(),
+            heap: self.heap.clone(),
+            out_chars: self.out_chars.clone(),
+            exit_code: self.exit_code,
+        };
+        fs::write(path, serde_json::to_string(&state)?)
+    }
+
+    /// restores a state saved with save_state, call resume to continue running the program
+    pub fn load_state(&mut self, path: &str) -> io::Result<()> {
+        let state: SavedState = serde_json::from_str(&fs::read_to_string(path)?)?;
+        self.reset_cpu_state();
+        self.cpu.restore(state.cpu);
+        self.heap = state.heap;
+        self.out_chars = state.out_chars;
+        self.exit_code = state.exit_code;
+        Ok(())
+    }
+
+    /// runs the current program from where it stopped
+    /// returns program's exit value
+    pub fn resume(&mut self) -> i32 {
         self.run();
+        self.get_exit_value()
+    }
 
+    /// returns the exit status if the program called exit, otherwise main's return value
+    fn get_exit_value(&self) -> i32 {
+        if let Some(exit_code) = self.exit_code {
+            return exit_code;
+        }
         let bp = self.cpu.regs.get(&Register::BP);
-        self.cpu.mem.get_num((bp + 2) as u32)
+        self.mem_get_num((bp + 2) as u32)
+    }
+
+    /// links the programs for this os's memory layout
+    fn assemble_and_link(&self, programs: Vec<&str>) -> Executable {
+        assemble_and_link_at(programs, self.layout.code_start, self.layout.data_start)
+    }
+
+    /// the std objects & the on demand std objects, compiled like the programs
+    fn std_objects(&self) -> (&Vec<ObjectFile>, &Vec<ObjectFile>) {
+        if !self.compiler_options.is_byte_addressing() {
+            return (&self.std_programs, &self.on_demand_std_programs);
+        }
+        let (std_programs, on_demand_std_programs) = self.byte_addressing_std.get_or_init(|| Self::compile_std_programs(true));
+        (std_programs, on_demand_std_programs)
+    }
+
+    /// appends the std programs to the given programs
+    /// on demand std programs are added only if the programs call (or take the address of) one of their functions
+    fn with_std_programs<'a>(&'a self, programs: Vec<&'a str>) -> Vec<&'a str> {
+        let referenced_labels: BTreeSet<String> = programs.iter()
+            .flat_map(|program| get_referenced_labels(program))
+            .collect();
+        let (std_programs, on_demand_std_programs) = self.std_objects();
+        let mut programs_with_std = programs.clone();
+        programs_with_std.extend(std_programs.iter().map(|object| object.code.as_str()));
+        for object in on_demand_std_programs.iter() {
+            if !object.exports.is_disjoint(&referenced_labels) {
+                programs_with_std.push(&object.code);
+            }
+        }
+        programs_with_std
+    }
+
+    /// appends the std objects to the given objects
+    /// on demand std objects are added only if one of the objects imports one of their symbols
+    fn with_std_objects<'a>(&'a self, objects: Vec<&'a ObjectFile>) -> Vec<&'a ObjectFile> {
+        let (std_programs, on_demand_std_programs) = self.std_objects();
+        let mut objects_with_std = objects.clone();
+        objects_with_std.extend(std_programs.iter());
+        for std_object in on_demand_std_programs.iter() {
+            if objects.iter().any(|object| !object.imports.is_disjoint(&std_object.exports)) {
+                objects_with_std.push(std_object);
+            }
+        }
+        objects_with_std
+    }
+
+    /// links the objects with std and runs them, execution starts from the first object
+    pub fn link_and_run(&mut self, objects: Vec<&ObjectFile>) -> i32 {
+        let exec = self.link_executable(objects);
+        self.load_and_run(&exec)
+    }
+
+    /// links the objects with std for this os's memory layout, execution starts from the first object
+    pub fn link_executable(&self, objects: Vec<&ObjectFile>) -> Executable {
+        link(&self.with_std_objects(objects), &self.layout)
+    }
+
+    /// links the objects with std into a binary image, see Executable::to_image
+    pub fn link_image(&self, objects: Vec<&ObjectFile>) -> Vec<u8> {
+        self.link_executable(objects).to_image()
+    }
+
+    /// loads & runs a binary image created with link_image
+    pub fn run_image_file(&mut self, path: &str) -> io::Result<i32> {
+        let image = fs::read(path)?;
+        let exec = Executable::from_image(&image)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid executable image"))?;
+        Ok(self.load_and_run(&exec))
     }
 
     pub fn assemble_link_and_run(&mut self, programs: Vec<&str>) -> i32 {
-        let mut programs_with_std = programs;
-        let mut std_programs_clone = self.std_programs.iter().map(|s| s.as_str()).collect();
-        programs_with_std.append(&mut std_programs_clone);
-        let exec = assemble_and_link(programs_with_std);
+        let exec = self.assemble_and_link(self.with_std_programs(programs));
         self.load_and_run(&exec)
     }
 
@@ -149,79 +807,1200 @@ impl OS {
     }
 
     pub fn assemble_and_run_no_std(&mut self, program: &str) -> i32{
-        let exec = assemble_and_link(vec![program]);
+        let exec = self.assemble_and_link(vec![program]);
         self.load_and_run(&exec)
     }
 
-    pub fn debug_program(&mut self, exec: &Executable) -> i32{
-        self.reset_cpu_state();
+    /// creates a process that runs the program (linked with std) in its own address space
+    /// the program is linked at the usual addresses, the cpu's segment registers translate them to the process's memory
+    /// the process will run when run_all is called
+    /// returns the pid of the new process
+    pub fn spawn(&mut self, program: &str) -> u32 {
+        let exec = self.assemble_and_link(self.with_std_programs(vec![program]));
+        self.spawn_executable(&exec, None, &[])
+    }
+
+    /// creates a child of the running process (or of the os's program) that runs the binary image at the path in os.fs
+    /// the image is created with link_image, its argv is the path. returns the pid of the child
+    pub fn spawn_file(&mut self, path: &str) -> Option<u32> {
+        let exec = Executable::from_image(&self.fs.read_file(path)?)?;
+        Some(self.spawn_executable(&exec, self.running_pid, &[path.to_string()]))
+    }
+
+    fn spawn_executable(&mut self, exec: &Executable, parent: Option<u32>, args: &[String]) -> u32 {
+        let pid = self.processes.len() as u32;
+        let segment = process_segment(pid, &self.layout);
+
+        // the process's memory is initialized through the cpu's segment & registers, so save & restore the current ones
+        let cur_segment = std::mem::replace(&mut self.cpu.segment, segment);
+        let cur_regs = std::mem::replace(&mut self.cpu.regs, Registers::new());
+        self.initialize_memory();
         self.load_program(&exec.code, &exec.data);
-        self.cpu
-            .regs
-            .set(&Register::IR, PROGRAM_INIT_ADDRESS as i32);
-        self.initialize_stackframe();
-        let mut breakpoints : HashSet<u32> = HashSet::new();
-        let mut running = false;
+        self.cpu.regs.set(&Register::IR, self.layout.code_start as i32);
+        let argv = self.load_args(self.layout.data_start + exec.data.len() as u32, args);
+        self.initialize_stackframe(args.len() as u32, argv);
+        let regs = std::mem::replace(&mut self.cpu.regs, cur_regs);
+        self.cpu.segment = cur_segment;
+
+        let heap = HeapAllocator::new(self.layout.heap_start, self.layout.heap_end);
+        let symbols = self.symbols_in_memory(exec);
+        self.processes.push(Process {
+            pid, regs, interrupts_enabled: true, segment, heap, symbols, state: ProcessState::Ready, parent, reaped: false,
+        });
+        self.log(logging::OS, Level::Debug, format_args!("spawned process {} with parent {:?}", pid, parent));
+        pid
+    }
+
+    /// returns the first ready process, starting the search from the given pid & wrapping around
+    fn next_ready_pid(&self, start_pid: u32) -> Option<u32> {
+        let count = self.processes.len() as u32;
+        (0..count)
+            .map(|i| (start_pid + i) % count)
+            .find(|pid| self.processes[*pid as usize].state == ProcessState::Ready)
+    }
+
+    /// restores the process's context & runs it until it finishes or the timer interrupt preempts it
+    fn run_until_preempted(&mut self, pid: u32) {
+        self.log(logging::OS, Level::Trace, format_args!("switching to process {} at cycle {}", pid, self.cpu.cycles));
+        self.running_pid = Some(pid);
+        let process = &mut self.processes[pid as usize];
+        self.cpu.regs = process.regs.clone();
+        self.cpu.interrupts_enabled = process.interrupts_enabled;
+        self.cpu.segment = process.segment;
+        std::mem::swap(&mut self.heap, &mut process.heap);
+        std::mem::swap(&mut self.symbols, &mut process.symbols);
+        self.reschedule = false;
         let mut keep_running = true;
-        while keep_running{
-            let cur_instr_addr = self.cpu.regs.get(&Register::IR);
-            // println!("{}: {}", cur_instr_addr - PROGRAM_INIT_ADDRESS as i32, self.cpu.fetch().to_str());
-            if breakpoints.contains(&(cur_instr_addr as u32 - PROGRAM_INIT_ADDRESS)){
-                running = false;
+        while keep_running && !self.reschedule {
+            keep_running = self.step();
+        }
+        if !keep_running {
+            let exit_value = self.get_exit_value();
+            self.exit_code = None;
+            self.cpu.fault = None; // a fault only kills the faulting process
+            self.processes[pid as usize].state = ProcessState::Exited(exit_value);
+            self.log(logging::OS, Level::Debug, format_args!("process {} exited with {}", pid, exit_value));
+        }
+        let process = &mut self.processes[pid as usize];
+        process.regs = self.cpu.regs.clone();
+        process.interrupts_enabled = self.cpu.interrupts_enabled;
+        std::mem::swap(&mut self.heap, &mut process.heap);
+        std::mem::swap(&mut self.symbols, &mut process.symbols);
+        self.running_pid = None;
+        if !keep_running {
+            self.on_exit(pid);
+        }
+    }
+
+    /// wakes the parent if it's waiting for the exited process, the parent's waitpid then returns the exit value
+    /// the process's children are adopted by the os's program
+    fn on_exit(&mut self, pid: u32) {
+        let exit_value = self.processes[pid as usize].state.exit_value().unwrap();
+        if let Some(parent) = self.processes[pid as usize].parent {
+            let parent = &mut self.processes[parent as usize];
+            if parent.state == ProcessState::Waiting(pid) {
+                parent.regs.set(&Register::R1, exit_value);
+                parent.state = ProcessState::Ready;
+                self.reap(pid);
             }
-            if running{
-                keep_running = self.step();
-                continue;
+        }
+        for child in self.processes.iter_mut().filter(|child| child.parent == Some(pid)) {
+            child.parent = None;
+        }
+    }
+
+    /// frees the memory of the exited process, returns its exit value
+    fn reap(&mut self, pid: u32) -> i32 {
+        let process = &mut self.processes[pid as usize];
+        process.reaped = true;
+        self.cpu.mem.clear(process.segment.base, process.segment.base + process.segment.limit);
+        process.state.exit_value().unwrap()
+    }
+
+    /// waits for the child of the running process (or of the os's program) to exit, see sys_waitpid
+    /// returns None if it's not a child that wasn't reaped yet
+    fn waitpid(&mut self, pid: u32) -> Option<i32> {
+        let child = self.processes.get(pid as usize)?;
+        if child.parent != self.running_pid || child.reaped {
+            return None;
+        }
+        if child.state.exit_value().is_none() {
+            match self.running_pid {
+                Some(running_pid) => {
+                    // blocks the process, on_exit sets the return value when the child exits
+                    self.processes[running_pid as usize].state = ProcessState::Waiting(pid);
+                    self.reschedule = true;
+                    return Some(0);
+                },
+                None => self.run_processes_until(|os| os.processes[pid as usize].state.exit_value().is_some()),
             }
-            let next_instr = self.cpu.fetch();
-            println!("{}: {}", self.cpu.regs.get(&Register::IR) - PROGRAM_INIT_ADDRESS as i32, next_instr.to_str());
-            use std::io::{stdin,stdout,Write};
-            let mut cmd = String::new();
-            if let Some('\n')=cmd.chars().next_back() {
-                cmd.pop();
+        }
+        Some(self.reap(pid))
+    }
+
+    /// runs the ready processes until stop returns true or there are no ready processes
+    /// sleeping processes are woken once the cpu's cycle counter reaches their time, the cpu idles if they're all asleep
+    /// processes that are blocked on mutexes when no process is ready are deadlocked, so they're killed
+    /// the timer interrupt preempts the running process every TIME_SLICE cycles, & the next ready process runs (round robin)
+    fn schedule(&mut self, stop: impl Fn(&OS) -> bool) {
+        let cur_segment = self.cpu.segment;
+        self.cpu.set_timer(Some(TIME_SLICE));
+        let mut start_pid = 0;
+        loop {
+            self.wake_sleepers();
+            if stop(self) {
+                break;
             }
-            stdin().read_line(&mut cmd).expect("");
-            let args: Vec<&str> = cmd.split_whitespace().collect();
-            if args.len() == 0{
-                continue;
+            match self.next_ready_pid(start_pid) {
+                Some(pid) => {
+                    self.run_until_preempted(pid);
+                    start_pid = pid + 1;
+                },
+                // the cpu idles until a sleeping process wakes, & killing deadlocked processes may wake their parents
+                None if self.idle() || self.kill_deadlocked() => {},
+                None => break,
             }
-            if args[0] == "continue"{
-                running = true;
+        }
+        self.cpu.set_timer(None);
+        self.cpu.segment = cur_segment;
+    }
+
+    /// called by the os's program when it blocks in a syscall, it isn't a process so it runs the processes meanwhile
+    fn run_processes_until(&mut self, stop: impl Fn(&OS) -> bool) {
+        let (regs, interrupts_enabled) = (self.cpu.regs.clone(), self.cpu.interrupts_enabled);
+        self.schedule(stop);
+        self.cpu.regs = regs;
+        self.cpu.interrupts_enabled = interrupts_enabled;
+        self.reschedule = false;
+    }
+
+    /// takes the mutex at the address of the running program (see sys_mutex_lock), blocking until it's unlocked
+    /// returns false if the os's program would wait forever, since no process can unlock it
+    fn mutex_lock(&mut self, address: u32) -> bool {
+        let mutex = self.cpu.segment.physical(address);
+        let locked = |os: &OS| os.cpu.mem.try_get(mutex).is_some_and(|val| !matches!(val, MemEntry::Num(0)));
+        if locked(self) {
+            match self.running_pid {
+                Some(running_pid) => {
+                    // mutex_unlock hands the mutex over to the process & wakes it
+                    self.processes[running_pid as usize].state = ProcessState::Locking(mutex);
+                    self.reschedule = true;
+                    return true;
+                },
+                None => {
+                    self.run_processes_until(|os| !locked(os));
+                    if locked(self) {
+                        return false;
+                    }
+                },
             }
-            if args[0] == "step"{
-                keep_running = self.cpu.step();
+        }
+        self.cpu.mem.set(mutex, MemEntry::Num(1));
+        true
+    }
+
+    /// hands the mutex over to a process that's blocked on it, or unlocks it if there's none
+    fn mutex_unlock(&mut self, address: u32) {
+        let mutex = self.cpu.segment.physical(address);
+        match self.processes.iter_mut().find(|process| process.state == ProcessState::Locking(mutex)) {
+            Some(process) => process.state = ProcessState::Ready,
+            None => self.cpu.mem.set(mutex, MemEntry::Num(0)),
+        }
+    }
+
+    /// the running program sleeps for at least the given number of cycles, see sys_sleep
+    fn sleep(&mut self, cycles: u32) {
+        let wake = self.cpu.cycles + cycles as u64;
+        match self.running_pid {
+            Some(running_pid) => {
+                // the scheduler wakes the process once the cycle counter reaches wake
+                self.processes[running_pid as usize].state = ProcessState::Sleeping(wake);
+                self.reschedule = true;
+            },
+            None => {
+                self.run_processes_until(|os| os.cpu.cycles >= wake);
+                self.cpu.cycles = self.cpu.cycles.max(wake);
+            },
+        }
+    }
+
+    /// makes the sleeping processes whose time has come ready
+    fn wake_sleepers(&mut self) {
+        let cycles = self.cpu.cycles;
+        for process in self.processes.iter_mut() {
+            if matches!(process.state, ProcessState::Sleeping(wake) if wake <= cycles) {
+                process.state = ProcessState::Ready;
             }
-            if args[0] == "reg"{
-                let reg = register_from_str(args[1]).unwrap();
-                let reg_val = self.cpu.regs.get(&reg);
-                println!("{}", reg_val);
+        }
+    }
+
+    /// when no process is ready, the cpu idles until the first sleeping process wakes
+    /// returns false if no process is sleeping
+    fn idle(&mut self) -> bool {
+        let first_wake = self.processes.iter()
+            .filter_map(|process| match process.state {
+                ProcessState::Sleeping(wake) => Some(wake),
+                _ => None,
+            })
+            .min();
+        match first_wake {
+            Some(wake) => {
+                self.cpu.cycles = self.cpu.cycles.max(wake);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// kills the processes that are blocked on mutexes when no process is ready, since no one can unlock them
+    /// returns false if there are none
+    fn kill_deadlocked(&mut self) -> bool {
+        let deadlocked: Vec<u32> = self.processes.iter()
+            .filter(|process| matches!(process.state, ProcessState::Locking(_)))
+            .map(|process| process.pid)
+            .collect();
+        for pid in deadlocked.iter() {
+            eprintln!("process {} is deadlocked", pid);
+            self.kill(*pid);
+        }
+        !deadlocked.is_empty()
+    }
+
+    /// runs all spawned processes until they finish
+    /// returns the exit value of each process, indexed by pid
+    pub fn run_all(&mut self) -> Vec<i32> {
+        self.schedule(|_| false);
+        self.processes.iter().map(|process| process.state.exit_value().unwrap()).collect()
+    }
+
+    /// runs the spawned processes until the given process finishes, returns its exit value
+    pub fn wait_for(&mut self, pid: u32) -> i32 {
+        self.schedule(|os| os.processes[pid as usize].state.exit_value().is_some());
+        self.processes[pid as usize].state.exit_value().unwrap()
+    }
+
+    /// stops the process, returns false if there's no such running process
+    pub fn kill(&mut self, pid: u32) -> bool {
+        match self.processes.get_mut(pid as usize) {
+            Some(process) if process.state.exit_value().is_none() => {
+                process.state = ProcessState::Killed;
+                self.on_exit(pid);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn format_mem_entry(entry: Option<&MemEntry>) -> String {
+        match entry {
+            Some(MemEntry::Num(x)) => format!("{} (0x{:x})", x, x),
+            Some(MemEntry::Instruction(instr)) => instr.to_str(),
+            None => "<uninitialized>".to_string(),
+        }
+    }
+
+    /// returns a line for each of the count addresses starting at addr
+    pub fn dump_mem(&self, addr: u32, count: u32) -> Vec<String> {
+        (addr..=u32::MAX).take(count as usize).map(|cur_addr|
+            format!("{}: {}", cur_addr, OS::format_mem_entry(self.mem_try_get(cur_addr)))
+        ).collect()
+    }
+
+    /// the text of the running program's display, see layout.rs
+    /// cells that were never written are blank
+    pub fn display(&self) -> String {
+        let start = self.layout.display_start();
+        let cells: Vec<i32> = (start..start + DISPLAY_SIZE)
+            .map(|address| self.mem_try_get_num(address).unwrap_or(0))
+            .collect();
+        display::render(&cells)
+    }
+
+    /// if show is set, the display is drawn on the host's terminal while programs run
+    pub fn show_display(&mut self, show: bool) {
+        self.display_renderer = if show { Some(TerminalRenderer::new()) } else { None };
+    }
+
+    /// draws the display if it's shown & it changed since it was last drawn
+    fn draw_display(&mut self) {
+        if self.display_renderer.is_none() {
+            return;
+        }
+        let frame = self.display();
+        if let Some(renderer) = self.display_renderer.as_mut() {
+            renderer.draw(frame);
+        }
+    }
+
+    /// the code symbols of the executable by their address in memory
+    fn symbols_in_memory(&self, exec: &Executable) -> SymbolTable {
+        exec.symbol_table.relocated(self.layout.code_start)
+    }
+
+    /// describes the fault that stopped the program, followed by the innermost frames of the stack, e.g
+    ///     segmentation fault: invalid write to address 1000 (IR=1010)
+    ///     #0 1010 in foo
+    ///     #1 1003 in main
+    /// returns None if the program didn't fault
+    pub fn fault_report(&self) -> Option<String> {
+        let fault = self.cpu.fault.as_ref()?;
+        let mut address = self.cpu.regs.get(&Register::IR) as u32;
+        let mut lines = vec![format!("{} (IR={})", fault, address)];
+        match fault {
+            Fault::UninitializedStackRead { address: var_address } => {
+                if let Some((func_name, var)) = self.find_stack_var(*var_address) {
+                    lines.push(format!("'{}' ({}) in {} was read before it was initialized", var.name, var.type_name, func_name));
+                }
+            },
+            Fault::InvalidHeapAccess { address: heap_address, .. } | Fault::InvalidFree { address: heap_address } => {
+                if let Some(block) = self.heap_checker.as_ref().and_then(|checker| checker.find(*heap_address)) {
+                    lines.push(self.describe_heap_address(*heap_address, block));
+                }
+            },
+            _ => {},
+        }
+        let mut bp = self.cpu.regs.get(&Register::BP) as u32;
+        for frame_i in 0..FAULT_BACKTRACE_FRAMES {
+            lines.push(format!("#{} {}", frame_i, self.describe_code_address(address)));
+            // walk the chain of saved BPs, up to the frame that the OS sets up for main
+            let (prev_bp, ret_addr) = match (self.mem_try_get_num(bp), self.mem_try_get_num(bp + 1)) {
+                (Some(prev_bp), Some(ret_addr)) => (prev_bp as u32, ret_addr as u32),
+                _ => break,
+            };
+            if prev_bp == bp || ret_addr == 0 {
+                break;
             }
-            if args[0] == "break"{
-                let line = args[1];
-                let instr_i = exec.symbol_table.get(&format!("_LINE_{}", line)).expect("invalid breakpoint line");
-                println!("break instr: {:?}", &exec.code[*instr_i as usize]);
-                breakpoints.insert(*instr_i);
+            bp = prev_bp;
+            address = ret_addr - 1; // the call
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// the address & the function it's in, e.g "1010 in foo"
+    fn describe_code_address(&self, address: u32) -> String {
+        let function = self.symbols.nearest_exported(address).map(|symbol| symbol.name.as_str());
+        format!("{} in {}", address, function.unwrap_or("??"))
+    }
+
+    /// where the address is relative to the heap block, e.g
+    ///     address 5005 is 0 words after a block of 3 words allocated at 1030 in main
+    fn describe_heap_address(&self, address: u32, block: &HeapBlock) -> String {
+        let end = block.address + block.size;
+        let position = if address < block.address {
+            format!("{} before", quantity(block.address - address, "word"))
+        } else if address >= end {
+            format!("{} after", quantity(address - end, "word"))
+        } else {
+            format!("{} inside", quantity(address - block.address, "word"))
+        };
+        let mut description = format!("address {} is {} a block of {} allocated at {}",
+            address, position, quantity(block.size, "word"), self.describe_code_address(block.allocated_at));
+        if let Some(freed_at) = block.freed_at {
+            description += &format!(", & freed at {}", self.describe_code_address(freed_at));
+        }
+        description
+    }
+
+    /// the heap blocks that the os's program didn't free, e.g
+    ///     leak summary: 4 words in 2 blocks weren't freed
+    ///     3 words allocated at 1030 in main
+    ///     1 word allocated at 1042 in foo
+    /// returns None if the heap isn't checked, see check_heap
+    pub fn leak_report(&self) -> Option<String> {
+        let leaks: Vec<&HeapBlock> = self.heap_checker.as_ref()?.leaks().collect();
+        if leaks.is_empty() {
+            return Some("leak summary: all heap blocks were freed".to_string());
+        }
+        let size = leaks.iter().map(|block| block.size).sum();
+        let mut lines = vec![format!("leak summary: {} in {} weren't freed", quantity(size, "word"), quantity(leaks.len() as u32, "block"))];
+        for block in leaks {
+            lines.push(format!("{} allocated at {}", quantity(block.size, "word"), self.describe_code_address(block.allocated_at)));
+        }
+        Some(lines.join("\n"))
+    }
 
+    /// the local variable or param (of the function that faulted or of one of its callers) that holds the address, with its function's name
+    fn find_stack_var(&self, address: u32) -> Option<(&str, &VarInfo)> {
+        // the debug info is only known for the os's program
+        if self.running_pid.is_some() {
+            return None;
+        }
+        let mut instr_address = self.cpu.regs.get(&Register::IR) as u32;
+        let mut bp = self.cpu.regs.get(&Register::BP) as u32;
+        loop {
+            let func = self.debug_info.function_at(instr_address.checked_sub(self.layout.code_start)?)?;
+            let offset = address as i32 - bp as i32;
+            let var = func.params.iter().chain(func.locals.iter())
+                .find(|var| var.bp_offset <= offset && offset < var.bp_offset + var.size as i32);
+            if let Some(var) = var {
+                return Some((&func.name, var));
             }
-            
+            let (prev_bp, ret_addr) = (self.mem_try_get_num(bp)? as u32, self.mem_try_get_num(bp + 1)? as u32);
+            if prev_bp == bp || ret_addr == 0 {
+                return None;
+            }
+            bp = prev_bp;
+            instr_address = ret_addr - 1;
         }
+    }
+
+    /// describes the access that stopped the debugger at a watchpoint, with the accessed value
+    fn describe_watch_hit(&self, address: u32, access: Access) -> String {
+        let access = match access {
+            Access::Write => "write to",
+            _ => "read from",
+        };
+        let value = OS::format_mem_entry(self.mem_try_get(address));
+        format!("watchpoint: {} {}, value: {}", access, address, value)
+    }
 
+    /// returns a line for each address of the current stack frame, from the top of the stack down to the return address
+    pub fn dump_stack_frame(&self) -> Vec<String> {
+        let sp = self.cpu.regs.get(&Register::SP);
         let bp = self.cpu.regs.get(&Register::BP);
-        self.cpu.mem.get_num((bp + 2) as u32)
+        (sp.saturating_add(1)..=bp.saturating_add(1)).map(|addr| {
+            let annotation = match addr - bp {
+                0 => " <- prev_BP".to_string(),
+                1 => " <- ret_addr".to_string(),
+                offset => format!(" (BP{})", offset),
+            };
+            format!("{}: {}{}", addr, OS::format_mem_entry(self.mem_try_get(addr as u32)), annotation)
+        }).collect()
+    }
+
+    /// sets the number of words in the memory, accesses beyond it fault with Fault::OutOfMemory
+    /// e.g spawned processes whose address space doesn't fit in the memory
+    /// panics if the memory is smaller than the address space of a program
+    pub fn set_memory_size(&mut self, size: u32) {
+        assert!(self.layout.size() <= size, "memory size {} is smaller than the memory layout", size);
+        self.memory_size = size;
+        self.cpu.mem.size = size;
+    }
+
+    /// the random numbers of the programs (see sys_rand) start from the seed, they're the same in each run
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random_seed = seed;
+        self.random = Random::new(seed);
+    }
+
+    /// records the instructions executed by programs that are run from now on, see dump_trace
+    pub fn set_trace(&mut self, config: TraceConfig) {
+        self.cpu.set_trace(config.clone());
+        self.trace_config = Some(config);
+    }
+
+    /// returns a line for each of the last traced instructions, oldest first
+    /// empty if tracing is not enabled
+    pub fn dump_trace(&self) -> Vec<String> {
+        match self.cpu.get_trace() {
+            Some(trace) => trace.entries().map(|entry| entry.to_string()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn dump_regs(&self) -> String {
+        ALL_REGISTERS.iter()
+            .map(|reg| format!("{}={}", reg, self.cpu.regs.get(reg)))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    pub fn debug_program(&mut self, exec: &Executable) -> i32{
+        Debugger::new(self, exec).repl(io::stdin().lock(), io::stdout()).expect("debugger i/o failed")
     }
 
     pub fn assemble_and_debug(&mut self, programs: Vec<&str>) -> i32 {
-        let mut programs_with_std = programs;
-        let mut std_programs_clone = self.std_programs.iter().map(|s| s.as_str()).collect();
-        programs_with_std.append(&mut std_programs_clone);
-        let exec = assemble_and_link(programs_with_std);
+        let exec = self.assemble_and_link(self.with_std_programs(programs));
         self.debug_program(&exec)
     }
 
-    pub fn compile(&mut self, path_to_c_source: &str) -> String{
-        let res = Compiler::compile(path_to_c_source, self.compiled_programs_count);
-        self.compiled_programs_count += 1;
-        res
+    pub fn link_and_debug(&mut self, objects: Vec<&ObjectFile>) -> i32 {
+        let exec = link(&self.with_std_objects(objects), &self.layout);
+        self.debug_program(&exec)
+    }
+
+    /// like link_and_debug, with the debugger's commands read from a client that connects to the address over TCP
+    pub fn link_and_debug_remote(&mut self, objects: Vec<&ObjectFile>, addr: &str) -> io::Result<i32> {
+        let exec = link(&self.with_std_objects(objects), &self.layout);
+        let listener = TcpListener::bind(addr)?;
+        println!("waiting for a debugger to connect to {}", listener.local_addr()?);
+        Debugger::new(self, &exec).serve(&listener)
+    }
+
+    /// returns the std objects & the on demand std objects
+    fn compile_std_programs(byte_addressing: bool) -> (Vec<ObjectFile>, Vec<ObjectFile>) {
+        let compile = |path_to_c_source: &str| {
+            let options = CompilerOptions::new().opt_level(OptLevel::O1).byte_addressing(byte_addressing);
+            Compiler::compile_object(path_to_c_source, &options)
+                .unwrap_or_else(|errors| panic!("{}", format_errors(&errors)))
+        };
+        (vec![compile("libc/libc.c")], vec![compile("libc/string.c"), compile("libc/display.c"), compile("libc/keyboard.c"), compile("libc/disk.c")])
+    }
+
+    pub fn compile(&self, path_to_c_source: &str) -> String{
+        Compiler::compile(path_to_c_source, &self.layout_compiler_options())
+            .unwrap_or_else(|errors| panic!("{}", format_errors(&errors)))
+    }
+
+    pub fn compile_object(&self, path_to_c_source: &str) -> ObjectFile{
+        self.try_compile_object(path_to_c_source)
+            .unwrap_or_else(|errors| panic!("{}", format_errors(&errors)))
+    }
+
+    /// like compile_object, but returns the compile errors instead of panicking
+    pub fn try_compile_object(&self, path_to_c_source: &str) -> Result<ObjectFile, Vec<CompileError>>{
+        Compiler::compile_object(path_to_c_source, &self.layout_compiler_options())
+    }
+
+    /// like try_compile_object, with a source that's in memory rather than in a file, see Compiler::compile_str
+    pub fn try_compile_object_str(&self, name: &str, source: &str) -> Result<ObjectFile, Vec<CompileError>>{
+        Compiler::compile_object_str(name, source, &self.layout_compiler_options())
+    }
+
+    /// the compiler options, with the generated code adjusted to the memory layout
+    fn layout_compiler_options(&self) -> CompilerOptions {
+        let options = self.compiler_options.clone().stack_end(self.layout.stack_end);
+        if self.verbose { options.verbose(true) } else { options }
+    }
+
+    /// logs the message to the target if the os is verbose, see logging.rs
+    fn log(&self, target: &str, level: Level, message: fmt::Arguments) {
+        if self.verbose {
+            log::log!(target: target, level, "{}", message);
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::{Access, Fault, Segment};
+    use super::process::KILLED_EXIT_CODE;
+
+    #[test]
+    fn test_dump_mem() {
+        let mut os = OS::new();
+        os.cpu.mem.set(600, MemEntry::Num(26));
+        assert_eq!(os.dump_mem(600, 2), vec!["600: 26 (0x1a)", "601: <uninitialized>"]);
+        assert_eq!(os.dump_mem(u32::MAX, 2), vec![format!("{}: <uninitialized>", u32::MAX)]);
+        // the addresses are in the address space of the running process
+        os.cpu.segment = Segment { base: 10000, limit: 5000 };
+        os.cpu.mem.set(10600, MemEntry::Num(27));
+        assert_eq!(os.dump_mem(600, 1), vec!["600: 27 (0x1b)"]);
+        assert_eq!(os.dump_mem(4999, 2), vec!["4999: <uninitialized>", "5000: <uninitialized>"]);
+    }
+
+    #[test]
+    fn test_dump_stack_frame() {
+        let mut os = OS::new();
+        os.cpu.regs.set(&Register::BP, 100);
+        os.cpu.regs.set(&Register::SP, 98);
+        os.cpu.mem.set(99, MemEntry::Num(5));
+        os.cpu.mem.set(100, MemEntry::Num(200));
+        os.cpu.mem.set(101, MemEntry::Num(1003));
+        assert_eq!(os.dump_stack_frame(), vec![
+            "99: 5 (0x5) (BP-1)",
+            "100: 200 (0xc8) <- prev_BP",
+            "101: 1003 (0x3eb) <- ret_addr",
+        ]);
+        os.cpu.segment = Segment { base: 10000, limit: 5000 };
+        os.cpu.mem.set(10101, MemEntry::Num(1004));
+        assert_eq!(os.dump_stack_frame()[2], "101: 1004 (0x3ec) <- ret_addr");
+        os.cpu.regs.set(&Register::BP, i32::MAX);
+        os.cpu.regs.set(&Register::SP, i32::MAX - 1);
+        assert_eq!(os.dump_stack_frame(), vec![format!("{}: <uninitialized> <- prev_BP", i32::MAX)]);
+    }
+
+    #[test]
+    fn test_source_map() {
+        let mut os = OS::new();
+        let program = os.compile("tests/compiler_test_data/variables/inputs/assign.c");
+        let exec = assemble(&program);
+        let instr_i = exec.source_map.get_instr("assign.c", 3).unwrap();
+        let (file, line) = exec.source_map.get_loc(instr_i).unwrap();
+        assert_eq!(file, "tests/compiler_test_data/variables/inputs/assign.c");
+        assert_eq!(*line, 3);
+        assert!(exec.source_map.get_instr("assign.c", 100).is_none());
+    }
+
+    #[test]
+    fn test_string_lib_linked_on_demand() {
+        let os = OS::new();
+        assert_eq!(os.with_std_programs(vec!["CALL main"]).len(), 2);
+        assert_eq!(os.with_std_programs(vec!["CALL strlen"]).len(), 3);
+        assert_eq!(os.with_std_programs(vec!["LEA R1 strcmp"]).len(), 3);
     }
 
+    #[test]
+    fn test_optimize() {
+        let path = "tests/compiler_test_data/func_ptrs/inputs/callback.c";
+        let mut os = OS::new();
+        let program = os.compile(path);
+        let res = os.assemble_and_run(&program);
+        let mut optimized_os = OS::new();
+        optimized_os.compiler_options = CompilerOptions::new().opt_level(OptLevel::O1);
+        let optimized_program = optimized_os.compile(path);
+        assert!(assemble(&optimized_program).code.len() < assemble(&program).code.len());
+        assert_eq!(optimized_os.assemble_and_run(&optimized_program), res);
+    }
+
+    #[test]
+    fn test_inline() {
+        let run = |path: &str, inline_threshold: usize| {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().opt_level(OptLevel::O2).inline_threshold(inline_threshold);
+            let object = os.compile_object(path);
+            let exec = link(&os.with_std_objects(vec![&object]), &os.layout);
+            let (res, stats) = os.run_program_with_stats(&exec);
+            (object.code, res, stats.cycles)
+        };
+        let path = "tests/compiler_test_data/functions/inputs/inline.c";
+        // no function is small enough to be inlined
+        let (code, res, cycles) = run(path, 0);
+        let (inlined_code, inlined_res, inlined_cycles) = run(path, 30);
+        assert_eq!(res, inlined_res);
+        assert!(inlined_cycles < cycles);
+        assert!(!inlined_code.contains("CALL square"));
+        // args with side effects aren't inlined if they'd be evaluated more than once, or conditionally
+        assert_eq!(inlined_code.matches("CALL max").count(), 1);
+        assert_eq!(inlined_code.matches("CALL is_digit").count(), 1);
+        // all the calls to square were inlined
+        assert!(!inlined_code.contains("square:"));
+        assert!(code.contains("CALL square"));
+        // an arg that isn't an int or a char isn't substituted for the param
+        let (inlined_code, inlined_res, _) = run("tests/compiler_options_test_data/inline_pointer_arg.c", 30);
+        assert_eq!(inlined_res, 1);
+        assert!(inlined_code.contains("CALL plus_one"));
+
+        // a char param or return value converts the value, so functions with them aren't inlined
+        for byte_addressing in [false, true] {
+            let run = |opt_level: OptLevel| {
+                let mut os = OS::new();
+                os.compiler_options = CompilerOptions::new().opt_level(opt_level).byte_addressing(byte_addressing);
+                let object = os.compile_object("tests/compiler_options_test_data/inline_char.c");
+                os.link_and_run(vec![&object])
+            };
+            assert_eq!(run(OptLevel::O2), run(OptLevel::O0));
+        }
+    }
+
+    #[test]
+    fn test_stack_check() {
+        let run = |path: &str, stack_check: bool| {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().stack_check(stack_check);
+            let object = os.compile_object(path);
+            os.link_and_run(vec![&object])
+        };
+        // without the check, the stack silently overwrites the heap, with deep recursion or with a large alloca
+        for path in ["tests/compiler_options_test_data/stack_overflow.c", "tests/compiler_options_test_data/alloca_overflow.c"] {
+            assert_eq!(run(path, false), 0);
+            assert_eq!(run(path, true), FAULT_EXIT_CODE);
+        }
+    }
+
+    #[test]
+    fn test_tail_calls() {
+        let run = |path: &str, opt_level: OptLevel| {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().opt_level(opt_level).stack_check(true);
+            let object = os.compile_object(path);
+            os.link_and_run(vec![&object])
+        };
+        assert_eq!(run("tests/compiler_options_test_data/tail_calls.c", OptLevel::O1), FAULT_EXIT_CODE);
+        assert_eq!(run("tests/compiler_options_test_data/tail_calls.c", OptLevel::O2), 4501500 + 1);
+        // a call that's passed a pointer into the caller's frame isn't a tail call
+        assert_eq!(run("tests/compiler_options_test_data/tail_call_address.c", OptLevel::O2), 110);
+    }
+
+    #[test]
+    fn test_byte_addressing() {
+        let mut os = OS::new();
+        os.compiler_options = CompilerOptions::new().byte_addressing(true);
+        let object = os.compile_object("tests/compiler_options_test_data/byte_addressing.c");
+        assert!(object.code.contains("LOADB") && object.code.contains("STRB"));
+        assert_eq!(os.link_and_run(vec![&object]), 'o' as i32);
+        assert_eq!(os.out_chars.iter().collect::<String>(), "1 4 16 1 4 b 5 It 43 xy ok Hello ");
+    }
+
+    #[test]
+    fn test_defines() {
+        let programs = [
+            (CompilerOptions::new(), 4),
+            (CompilerOptions::new().define("DEBUG", "1"), 5),
+            (CompilerOptions::new().define("DEBUG", "").define("SIZE", "10"), 20),
+        ];
+        for (options, expected) in programs {
+            let mut os = OS::new();
+            os.compiler_options = options;
+            let object = os.compile_object("tests/compiler_options_test_data/defines.c");
+            assert_eq!(os.link_and_run(vec![&object]), expected);
+        }
+    }
+
+    #[test]
+    fn test_memory_layout() {
+        // the stack is large enough for the recursion that overflows the default stack
+        let layout = MemoryLayout {
+            data_start: 600,
+            code_start: 2000,
+            heap_start: 5000,
+            heap_end: 7000,
+            stack_end: 7000,
+            stack_top: 19999,
+        };
+        let mut os = OS::new_with_layout(layout);
+        os.compiler_options = CompilerOptions::new().stack_check(true);
+        let object = os.compile_object("tests/compiler_options_test_data/stack_overflow.c");
+        assert_eq!(os.link_and_run(vec![&object]), 7);
+        assert_eq!(os.cpu.regs.get(&Register::BP), 19995);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid memory layout")]
+    fn test_invalid_memory_layout() {
+        OS::new_with_layout(MemoryLayout { heap_start: 500, ..MemoryLayout::default() });
+    }
+
+    #[test]
+    fn test_comments() {
+        let path = "tests/compiler_test_data/functions/inputs/inline.c";
+        let mut os = OS::new();
+        os.compiler_options = CompilerOptions::new().emit_comments(true).emit_line_labels(false);
+        let object = os.compile_object(path);
+        assert!(object.code.contains(&format!("\n; {}:2: return x * x;\n", path)));
+        assert!(!object.code.contains(".loc"));
+        assert_eq!(os.link_and_run(vec![&object]), 394);
+    }
+
+    #[test]
+    fn test_image_file() {
+        let os = OS::new();
+        let object = os.compile_object("tests/compiler_test_data/functions/inputs/inline.c");
+        let image_file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(image_file.path(), os.link_image(vec![&object])).unwrap();
+        let mut new_os = OS::new();
+        assert_eq!(new_os.run_image_file(image_file.path().to_str().unwrap()).unwrap(), 394);
+        fs::write(image_file.path(), "MOV R1 3").unwrap();
+        assert!(new_os.run_image_file(image_file.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_link_c_files() {
+        let mut os = OS::new();
+        let main = os.compile_object("tests/linker_test_data/main.c");
+        let counter = os.compile_object("tests/linker_test_data/counter.c");
+        assert!(main.imports.contains("counter") && main.imports.contains("increment"));
+        assert!(counter.exports.contains("counter") && counter.exports.contains("increment"));
+        assert_eq!(os.link_and_run(vec![&main, &counter]), 12);
+        assert_eq!(os.get_output(), "5");
+    }
+
+    #[test]
+    #[should_panic(expected = "undefined reference to counter")]
+    fn test_link_c_files_undefined() {
+        let mut os = OS::new();
+        let main = os.compile_object("tests/linker_test_data/main.c");
+        os.link_and_run(vec![&main]);
+    }
+
+    #[test]
+    fn test_try_compile_object() {
+        let mut os = OS::new();
+        let errors = os.try_compile_object("tests/compile_error_test_data/undeclared_var.c").unwrap_err();
+        assert_eq!(format_errors(&errors), "tests/compile_error_test_data/undeclared_var.c:3: error: 'b' undeclared");
+        assert!(os.try_compile_object("tests/linker_test_data/counter.c").is_ok());
+    }
+
+    #[test]
+    fn test_save_and_load_state() {
+        let mut os = OS::new();
+        let main = os.compile_object("tests/linker_test_data/main.c");
+        let counter = os.compile_object("tests/linker_test_data/counter.c");
+        let exec = link(&os.with_std_objects(vec![&main, &counter]), &os.layout);
+        os.load_executable(&exec);
+        for _ in 0..50 {
+            assert!(os.step());
+        }
+        let state_file = tempfile::NamedTempFile::new().unwrap();
+        let state_path = state_file.path().to_str().unwrap();
+        os.save_state(state_path).unwrap();
+
+        let mut resumed = OS::new();
+        resumed.load_state(state_path).unwrap();
+        assert_eq!(resumed.resume(), 12);
+        assert_eq!(resumed.get_output(), "5");
+        // the saved program can be resumed more than once
+        os.load_state(state_path).unwrap();
+        assert_eq!(os.resume(), 12);
+    }
+
+    #[test]
+    fn test_files_persist_between_programs() {
+        let mut os = OS::new();
+        let writer = os.compile("tests/compiler_test_data/files/inputs/read_write.c");
+        let reader = os.compile("tests/compiler_test_data/files/inputs/_read_notes.c");
+        assert_eq!(os.assemble_and_run(&reader), -1);
+        assert_eq!(os.assemble_and_run(&writer), 11);
+        assert_eq!(os.fs.read_file("notes.txt"), Some(b"hello files".to_vec()));
+        assert_eq!(os.assemble_and_run(&reader), 11);
+
+        // the disk's image can be saved to the host & used by another OS
+        let image_file = tempfile::NamedTempFile::new().unwrap();
+        let image_path = image_file.path().to_str().unwrap();
+        os.fs.save(image_path).unwrap();
+        let mut other = OS::new();
+        other.fs = FileSystem::open_image(image_path).unwrap();
+        assert_eq!(other.assemble_and_run(&reader), 11);
+    }
+
+    #[test]
+    fn test_profile() {
+        let mut os = OS::new();
+        let object = os.compile_object("tests/compiler_test_data/functions/inputs/fib.c");
+        let report = os.link_and_profile(vec![&object]);
+        assert_eq!(report.exit_code, 5);
+        assert_eq!(report.cycles, os.cpu.cycles);
+        assert_eq!(report.functions[0].name, "fib");
+        assert_eq!(report.functions[0].calls, 15);
+        let main = report.functions.iter().find(|function| function.name == "main").unwrap();
+        assert_eq!(main.total_cycles, main.cycles + report.functions[0].total_cycles);
+        // recursive calls are counted in main's call of fib
+        let calls: Vec<(&str, &str, u64)> = report.calls.iter().map(|call| (call.caller.as_str(), call.callee.as_str(), call.calls)).collect();
+        assert_eq!(calls, vec![("main", "fib", 1), ("fib", "fib", 14)]);
+        assert_eq!(report.calls[0].cycles, report.functions[0].cycles);
+        assert_eq!(report.functions.iter().map(|function| function.instructions).sum::<u64>(), report.instructions);
+        // the condition runs in each of fib's 15 calls
+        let hottest_line = &report.lines[0];
+        assert_eq!((hottest_line.file.as_str(), hottest_line.line), ("tests/compiler_test_data/functions/inputs/fib.c", 2));
+        assert!(report.to_string().contains("tests/compiler_test_data/functions/inputs/fib.c:5"));
+        assert_eq!(serde_json::from_str::<ProfileReport>(&report.to_json()).unwrap(), report);
+    }
+
+    #[test]
+    fn test_coverage() {
+        let mut os = OS::new();
+        let object = os.try_compile_object_str("a.c", "int f(int n) {\n    if (n) {\n        return 1;\n    }\n    return 2;\n}\nint main() { return f(0) == 3 && f(1); }").unwrap();
+        let (exit_code, report) = os.link_and_run_with_coverage(vec![&object]);
+        assert_eq!(exit_code, 0);
+        let lines = &report.files["a.c"];
+        assert_eq!(lines.keys().copied().collect::<Vec<u32>>(), vec![1, 2, 3, 5, 7]);
+        assert_eq!(lines[&3].hits, 0);
+        assert_eq!(lines[&5].hits, 1);
+        assert_eq!(lines[&5].executed_instructions, lines[&5].instructions);
+        // the && short circuits, so f(1) isn't called
+        assert!(lines[&7].executed_instructions < lines[&7].instructions);
+        // the std's lines are covered too
+        assert!(report.files.len() > 1);
+    }
+
+    #[test]
+    fn test_execution_stats() {
+        let mut os = OS::new();
+        let exec = assemble("MOV R1 2\nPUSH R1\nPOP R2\nMUL R1 R1 R1\nTSTE R1 4\nTJMP end\nHALT\nend:\nHALT");
+        let (res, stats) = os.run_program_with_stats(&exec);
+        assert_eq!(res, -1);
+        assert_eq!(stats.instructions, 7);
+        assert_eq!(stats.cycles, 1 + 2 + 2 + 3 + 1 + 1 + 1);
+        assert_eq!(stats.opcode_counts.get("HALT"), Some(&1));
+        assert_eq!(stats.opcode_counts.get("MUL"), Some(&1));
+        assert_eq!((stats.branches, stats.branches_taken), (1, 1));
+    }
+
+    #[test]
+    fn test_run_program() {
+        let mut os = OS::new();
+        os.max_cycles = Some(100);
+        let print = |c: char| assemble(&format!("MOV R1 2\nMOV R2 {}\nSYSCALL\nMOV R3 7\nHALT", c as i32));
+        let result = os.run_program(&print('a'));
+        assert_eq!((result.exit_code, result.outcome, result.stdout.as_str()), (-1, RunOutcome::Halted, "a"));
+        assert_eq!(result.regs.get(&Register::R3), 7);
+        assert_eq!(result.cycles, 5);
+        // only the output of this run
+        assert_eq!(os.run_program(&print('b')).stdout, "b");
+
+        let result = os.run_program(&assemble("MOV R1 5\nMOV R2 1000\nSTR R2 R1\nHALT"));
+        assert_eq!((result.exit_code, result.outcome), (FAULT_EXIT_CODE, RunOutcome::Faulted));
+        assert_eq!(result.regs.get(&Register::IR), (PROGRAM_INIT_ADDRESS + 2) as i32);
+
+        let result = os.run_program(&assemble("loop:\nJUMP loop"));
+        assert_eq!((result.exit_code, result.outcome, result.cycles), (FAULT_EXIT_CODE, RunOutcome::Timeout, 100));
+    }
+
+    #[test]
+    fn test_optimizer_stats() {
+        let run = |optimize: bool| {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().opt_level(if optimize { OptLevel::O1 } else { OptLevel::O0 });
+            let object = os.compile_object("tests/compiler_test_data/structs/inputs/return.c");
+            let exec = link(&os.with_std_objects(vec![&object]), &os.layout);
+            os.run_program_with_stats(&exec)
+        };
+        let (res, stats) = run(false);
+        let (optimized_res, optimized_stats) = run(true);
+        assert_eq!(res, optimized_res);
+        assert!(optimized_stats.cycles < stats.cycles);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut os = OS::new();
+        os.set_trace(TraceConfig::new(3));
+        assert_eq!(os.assemble_and_run_no_std("MOV R1 5\nADD R2 R1 2\nPUSH R2\nHALT"), -1);
+        assert_eq!(os.dump_trace(), vec![
+            format!("{}: ADD R2 R1 Val(2) | R2: 0 -> 7", PROGRAM_INIT_ADDRESS + 1),
+            format!("{}: PUSH R2 | SP: {} -> {}", PROGRAM_INIT_ADDRESS + 2, INIT_SP_ADDRESS - 5, INIT_SP_ADDRESS - 6),
+            format!("{}: HALT", PROGRAM_INIT_ADDRESS + 3),
+        ]);
+    }
+
+    #[test]
+    fn test_out_of_memory() {
+        let mut os = OS::new();
+        let store = |address: u32| format!("MOV R1 5\nMOV R2 {}\nSTR R2 R1\nHALT", address);
+        assert_eq!(os.assemble_and_run_no_std(&store(DEFAULT_MEMORY_SIZE - 1)), -1);
+        assert_eq!(os.assemble_and_run_no_std(&store(DEFAULT_MEMORY_SIZE)), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::OutOfMemory { address: DEFAULT_MEMORY_SIZE }));
+
+        // only the first process's address space fits in the memory
+        let mut os = OS::new();
+        os.set_memory_size(MemoryLayout::default().size() * 2);
+        let program = ".global main\nmain:\nADD R1 BP 2\nSTR R1 5\nRET";
+        os.spawn(program);
+        os.spawn(program);
+        assert_eq!(os.run_all(), vec![5, FAULT_EXIT_CODE]);
+    }
+
+    #[test]
+    fn test_reap_children() {
+        let mut os = OS::new();
+        let child = os.compile_object("tests/scheduler_test_data/child.c");
+        let image = os.link_image(vec![&child]);
+        os.fs.write_file("x", &image);
+        os.fs.write_file("y", &image);
+        let parent = os.compile("tests/scheduler_test_data/parent.c");
+        os.spawn(&parent);
+        os.schedule(|os| os.processes[0].state == ProcessState::Waiting(1));
+        assert_eq!(os.processes[2].parent, Some(0));
+
+        // a killed child wakes its parent too
+        assert!(os.kill(1));
+        assert_eq!(os.run_all(), vec![KILLED_EXIT_CODE + 7, KILLED_EXIT_CODE, 7]);
+        for child in &os.processes[1..] {
+            assert!(child.reaped);
+            assert!(os.cpu.mem.try_get(child.segment.physical(PROGRAM_INIT_ADDRESS)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_segmentation_fault() {
+        let mut os = OS::new();
+        assert_eq!(os.assemble_and_run_no_std("MOV R1 5\nMOV R2 1000\nSTR R2 R1\nHALT"), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::SegmentationFault { address: 1000, access: Access::Write }));
+        assert_eq!(os.cpu.regs.get(&Register::IR), (PROGRAM_INIT_ADDRESS + 2) as i32);
+        assert!(matches!(os.cpu.mem.get(PROGRAM_INIT_ADDRESS), MemEntry::Instruction(_)));
+
+        assert_eq!(os.assemble_and_run_no_std(&format!("MOV R1 {}\nSTR R1 1\nHALT", MAS - 100)), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::SegmentationFault { address: MAS - 100, access: Access::Write }));
+
+        // the memory mapped registers & the stack are still writable
+        assert_eq!(os.assemble_and_run_no_std(&format!("MOV R1 {}\nSTR R1 0\nPUSH R1\nHALT", COS)), -1);
+        assert_eq!(os.cpu.fault, None);
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut os = OS::new();
+        let object = os.compile_object("tests/fault_test_data/uninitialized_read.c");
+        assert_eq!(os.link_and_run(vec![&object]), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::UninitializedRead { address: 5000 }));
+        let report = os.fault_report().unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("invalid memory access: read from address 5000 which was never written (IR="));
+        assert!(lines[1].starts_with("#0 ") && lines[1].ends_with(" in load"));
+        assert!(lines[2].starts_with("#1 ") && lines[2].ends_with(" in main"));
+
+        assert_eq!(os.assemble_and_run_no_std(&format!("MOV R1 {}\nCALLR R1", PROGRAM_INIT_ADDRESS + 100)), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::IllegalInstruction { address: PROGRAM_INIT_ADDRESS + 100 }));
+        assert_eq!(os.assemble_and_run_no_std(&format!("MOV R1 {}\nLOAD R2 R1\nHALT", PROGRAM_INIT_ADDRESS)), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::InstructionRead { address: PROGRAM_INIT_ADDRESS }));
+        assert_eq!(os.fault_report().unwrap().lines().count(), 2);
+        assert_eq!(os.assemble_and_run_no_std("HALT"), -1);
+        assert_eq!(os.fault_report(), None);
+    }
+
+    #[test]
+    fn test_uninitialized_local() {
+        let mut os = OS::new();
+        let object = os.compile_object("tests/fault_test_data/uninitialized_local.c");
+        // without the check the variable has the garbage its slot was allocated with
+        assert_eq!(os.link_and_run(vec![&object]), 0);
+        os.check_uninit = true;
+        assert_eq!(os.link_and_run(vec![&object]), FAULT_EXIT_CODE);
+        assert!(matches!(os.cpu.fault, Some(Fault::UninitializedStackRead { .. })));
+        let report = os.fault_report().unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("uninitialized read: read from stack address "));
+        assert_eq!(lines[1], "'value' (int) in get was read before it was initialized");
+        assert!(lines[2].starts_with("#0 ") && lines[2].ends_with(" in get"));
+
+        // initialized variables, params & return values don't fault
+        let object = os.compile_object("tests/fault_test_data/division_by_zero.c");
+        assert_eq!(os.link_and_run(vec![&object]), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::DivisionByZero));
+    }
+
+    #[test]
+    fn test_heap_check() {
+        for byte_addressing in [false, true] {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().byte_addressing(byte_addressing);
+            let object = os.compile_object("tests/fault_test_data/heap_overflow.c");
+            assert_eq!(os.link_and_run(vec![&object]), 5);
+            os.check_heap = true;
+            assert_eq!(os.link_and_run(vec![&object]), FAULT_EXIT_CODE);
+            assert!(matches!(os.cpu.fault, Some(Fault::InvalidHeapAccess { access: Access::Write, .. })));
+            let report = os.fault_report().unwrap();
+            let lines: Vec<&str> = report.lines().collect();
+            assert!(lines[0].starts_with("invalid heap access: invalid write to address "));
+            assert!(lines[1].contains(" is 0 words after a block of 3 words allocated at ") && lines[1].ends_with(" in main"));
+        }
+
+        let mut os = OS::new();
+        os.check_heap = true;
+        let object = os.compile_object("tests/fault_test_data/use_after_free.c");
+        assert_eq!(os.link_and_run(vec![&object]), FAULT_EXIT_CODE);
+        assert!(matches!(os.cpu.fault, Some(Fault::InvalidHeapAccess { access: Access::Read, .. })));
+        let report = os.fault_report().unwrap();
+        let description = report.lines().nth(1).unwrap();
+        assert!(description.contains(" is 0 words inside a block of 1 word allocated at ") && description.contains(" in make_counter, & freed at "));
+
+        let object = os.compile_object("tests/fault_test_data/double_free.c");
+        assert_eq!(os.link_and_run(vec![&object]), FAULT_EXIT_CODE);
+        assert!(matches!(os.cpu.fault, Some(Fault::InvalidFree { .. })));
+        let report = os.fault_report().unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("invalid free: address "));
+        assert!(lines[1].ends_with(" in release"));
+        assert!(lines[2].ends_with(" in free") && lines[3].ends_with(" in release") && lines[4].ends_with(" in main"));
+
+        let object = os.compile_object("tests/fault_test_data/heap_leak.c");
+        assert_eq!(os.link_and_run(vec![&object]), 0);
+        let report = os.leak_report().unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "leak summary: 4 words in 2 blocks weren't freed");
+        assert!(lines[1].starts_with("3 words allocated at ") && lines[1].ends_with(" in make_array"));
+        assert!(lines[2].starts_with("1 word allocated at ") && lines[2].ends_with(" in main"));
+        assert_eq!(os.assemble_and_run_no_std("HALT"), -1);
+        assert_eq!(os.leak_report().unwrap(), "leak summary: all heap blocks were freed");
+        os.check_heap = false;
+        assert_eq!(os.assemble_and_run_no_std("HALT"), -1);
+        assert_eq!(os.leak_report(), None);
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let mut os = OS::new();
+        let object = os.compile_object("tests/fault_test_data/division_by_zero.c");
+        assert_eq!(os.link_and_run(vec![&object]), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::DivisionByZero));
+        let report = os.fault_report().unwrap();
+        assert!(report.starts_with("division by zero (IR="));
+        assert!(report.lines().nth(1).unwrap().ends_with(" in average"));
+
+        assert_eq!(os.assemble_and_run_no_std("MOV R1 7\nMOD R2 R1 0\nHALT"), FAULT_EXIT_CODE);
+        assert_eq!(os.cpu.fault, Some(Fault::DivisionByZero));
+        assert_eq!(os.cpu.regs.get(&Register::IR), (PROGRAM_INIT_ADDRESS + 1) as i32);
+        assert_eq!(os.assemble_and_run_no_std("MOV R1 7\nMOD R2 R1 2\nDIV R3 R1 -1\nHALT"), -1);
+        assert_eq!((os.cpu.regs.get(&Register::R2), os.cpu.regs.get(&Register::R3)), (1, -7));
+    }
+
+    #[test]
+    fn test_display() {
+        for byte_addressing in [false, true] {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().byte_addressing(byte_addressing);
+            let object = os.compile_object("tests/device_test_data/display.c");
+            assert_eq!(os.link_and_run(vec![&object]), 0);
+            let display = os.display();
+            let lines: Vec<&str> = display.lines().collect();
+            assert_eq!(lines.len(), DISPLAY_ROWS as usize);
+            assert_eq!(lines[0], "score: 42");
+            assert_eq!(lines[2], format!("{}[]", " ".repeat(78)));
+            assert_eq!(lines[24], "=".repeat(80));
+        }
+    }
+
+    #[test]
+    fn test_keyboard() {
+        let mut os = OS::new();
+        let object = os.compile_object("tests/device_test_data/read_key.c");
+        for key in b"123" {
+            os.keyboard.press(*key);
+        }
+        assert_eq!(os.link_and_run(vec![&object]), 123);
+        assert_eq!(os.link_and_run(vec![&object]), 0);
+
+        // the keys arrive while the program waits for them, each arrival calls its handler
+        for byte_addressing in [false, true] {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().byte_addressing(byte_addressing);
+            os.max_cycles = Some(100_000_000);
+            let object = os.compile_object("tests/device_test_data/keyboard.c");
+            os.keyboard.press(b'a');
+            let (sender, receiver) = std::sync::mpsc::channel();
+            os.keyboard.connect(receiver);
+            let host = std::thread::spawn(move || {
+                for key in b"bc" {
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    sender.send(*key).unwrap();
+                }
+            });
+            assert_eq!(os.link_and_run(vec![&object]), 97 * 10000 + 98 * 100 + 99);
+            host.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_disk() {
+        let expected: i32 = (0..512).map(|i| i % 100).sum();
+        for byte_addressing in [false, true] {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().byte_addressing(byte_addressing);
+            let object = os.compile_object("tests/device_test_data/disk.c");
+            assert_eq!(os.link_and_run(vec![&object]), expected);
+            assert_eq!(os.fs.disk().read_block(1023).unwrap()[99], 99);
+        }
+
+        // the request fails if the program can't access the whole buffer
+        let mut os = OS::new();
+        let program = |request: i32, address: u32| format!("MOV R1 {}\nMOV R2 {}\nSTR R2 R1\nMOV R1 {}\nMOV R2 {}\nSTR R2 R1\nHALT", address, DKA, request, DKS);
+        for (request, address, status) in [(1, 4000, 0), (1, 1000, -1), (1, DEFAULT_MEMORY_SIZE - 100, -1), (2, 50000, -1)] {
+            os.assemble_and_run_no_std(&program(request, address));
+            assert_eq!(os.mem_get_num(DKS), status);
+        }
+    }
+
+    #[test]
+    fn test_main_args() {
+        for byte_addressing in [false, true] {
+            let mut os = OS::new();
+            os.compiler_options = CompilerOptions::new().byte_addressing(byte_addressing);
+            let object = os.compile_object("tests/args_test_data/args.c");
+            os.args = vec!["args".to_string(), "hello".to_string(), "vm".to_string()];
+            let exec = os.link_executable(vec![&object]);
+            let result = os.run_program(&exec);
+            assert_eq!((result.exit_code, result.stdout.as_str()), (7, "hello\nvm\n"));
+            os.args.clear();
+            assert_eq!(os.run_program(&exec).exit_code, 0);
+        }
+    }
+
+    #[test]
+    fn test_dump_regs() {
+        let mut os = OS::new();
+        os.cpu.regs.set(&Register::R2, 7);
+        assert_eq!(os.dump_regs(), "R1=0 R2=7 R3=0 R4=0 SP=0 BP=0 IR=0 ZR=0");
+    }
 }
@@ -0,0 +1,67 @@
+use crate::cpu::{Registers, Segment};
+use super::heap::HeapAllocator;
+use super::layout::MemoryLayout;
+use super::symbol_table::SymbolTable;
+
+/// number of cycles a process runs before the timer interrupt preempts it & the scheduler switches to the next process
+pub const TIME_SLICE: u32 = 50;
+
+/// exit value of a killed process, like a process killed by SIGKILL
+pub const KILLED_EXIT_CODE: i32 = 137;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ProcessState {
+    Ready,
+    Waiting(u32), // blocked in waitpid until the child with this pid exits
+    Locking(u32), // blocked in mutex_lock until the mutex at this physical address is unlocked
+    Sleeping(u64), // in sleep until the cpu's cycle counter reaches this
+    Exited(i32), // exit value
+    Killed,
+}
+
+impl ProcessState {
+    pub fn exit_value(&self) -> Option<i32> {
+        match self {
+            ProcessState::Ready | ProcessState::Waiting(_) | ProcessState::Locking(_) | ProcessState::Sleeping(_) => None,
+            ProcessState::Exited(exit_value) => Some(*exit_value),
+            ProcessState::Killed => Some(KILLED_EXIT_CODE),
+        }
+    }
+}
+
+/// the address space of the process with the given pid, see layout.rs
+/// the address spaces of the processes are after the address space of the os's program
+pub fn process_segment(pid: u32, layout: &MemoryLayout) -> Segment {
+    Segment {
+        base: (pid + 1) * layout.size(),
+        limit: layout.size(),
+    }
+}
+
+/// process control block
+pub struct Process {
+    pub pid: u32,
+    pub regs: Registers, // saved while the process is not running
+    pub interrupts_enabled: bool, // saved with the registers, disabled if the process was preempted inside the timer handler
+    pub segment: Segment,
+    pub heap: HeapAllocator, // swapped with the OS's heap while the process is running
+    pub symbols: SymbolTable, // swapped with the OS's symbols while the process is running
+    pub state: ProcessState,
+    pub parent: Option<u32>, // the process that spawned it, None if the host or the os's program spawned it, or if the parent exited
+    pub reaped: bool, // set when the parent waits for it after it exits, its memory is freed. until then it's a zombie
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_segments_do_not_overlap() {
+        let layout = MemoryLayout::default();
+        let first = process_segment(0, &layout);
+        let second = process_segment(1, &layout);
+        assert!(layout.size() <= first.base);
+        assert!(first.physical(layout.stack_top) < first.base + first.limit);
+        assert!(first.base + first.limit <= second.base);
+    }
+}
@@ -0,0 +1,159 @@
+// where a program spends its time: the cpu's per address counts (see cpu/profile.rs) summed per function & per source line,
+// & its call graph, gprof style: the cycles of each function's children are attributed to it through its calls.
+// like in gprof, the children of mutually recursive functions are counted in each of them
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::assembler::Executable;
+use crate::cpu::profile::{AddressCounts, CallCounts, Profile};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionProfile {
+    pub name: String, // "?" for instructions outside of functions
+    pub instructions: u64, // executed instructions
+    pub cycles: u64, // spent in the function itself
+    pub total_cycles: u64, // spent in the function & its children
+    pub calls: u64, // times the function was called
+}
+
+/// the calls from caller to callee
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallProfile {
+    pub caller: String,
+    pub callee: String,
+    pub calls: u64,
+    pub cycles: u64, // spent in the callee & its children during these calls, recursive calls are counted in the outermost one
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineProfile {
+    pub file: String,
+    pub line: u32,
+    pub instructions: u64,
+    pub cycles: u64,
+}
+
+/// the functions, lines & calls are sorted by their cycles, the hottest first
+/// lines are known only for code that was compiled with line labels, see CompilerOptions::emit_line_labels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileReport {
+    pub exit_code: i32,
+    pub instructions: u64,
+    pub cycles: u64,
+    pub functions: Vec<FunctionProfile>,
+    pub lines: Vec<LineProfile>,
+    pub calls: Vec<CallProfile>,
+}
+
+impl ProfileReport {
+    /// code_start is where the executable's code was loaded, the profile's addresses are relative to it
+    pub fn new(exit_code: i32, profile: &Profile, exec: &Executable, code_start: u32) -> ProfileReport {
+        let profile = profile.finished();
+        let function_at = |address: &u32| {
+            let instr_i = address.wrapping_sub(code_start);
+            exec.function_at(instr_i).filter(|_| (instr_i as usize) < exec.code.len()).map_or("?", |name| name.as_str())
+        };
+        let mut functions: HashMap<&str, AddressCounts> = HashMap::new();
+        let mut lines: HashMap<(&str, u32), AddressCounts> = HashMap::new();
+        let add = |total: &mut AddressCounts, counts: &AddressCounts| {
+            total.executions += counts.executions;
+            total.cycles += counts.cycles;
+        };
+        for (address, counts) in profile.counts.iter() {
+            add(functions.entry(function_at(address)).or_default(), counts);
+            if let Some((file, line)) = exec.source_map.get_loc(address.wrapping_sub(code_start)) {
+                add(lines.entry((file.as_str(), *line)).or_default(), counts);
+            }
+        }
+        let mut calls: HashMap<(&str, &str), CallCounts> = HashMap::new();
+        let mut function_calls: HashMap<&str, u64> = HashMap::new();
+        let mut children_cycles: HashMap<&str, u64> = HashMap::new();
+        for ((call_site, callee), counts) in profile.calls.iter() {
+            let (caller, callee) = (function_at(call_site), function_at(callee));
+            let call = calls.entry((caller, callee)).or_default();
+            call.calls += counts.calls;
+            call.cycles += counts.cycles;
+            *function_calls.entry(callee).or_default() += counts.calls;
+            if caller != callee {
+                *children_cycles.entry(caller).or_default() += counts.cycles;
+            }
+        }
+        let mut functions: Vec<FunctionProfile> = functions.into_iter()
+            .map(|(name, counts)| FunctionProfile {
+                name: name.to_string(),
+                instructions: counts.executions,
+                cycles: counts.cycles,
+                total_cycles: counts.cycles + children_cycles.get(name).copied().unwrap_or(0),
+                calls: function_calls.get(name).copied().unwrap_or(0),
+            })
+            .collect();
+        functions.sort_by(|a, b| b.cycles.cmp(&a.cycles).then_with(|| a.name.cmp(&b.name)));
+        let mut lines: Vec<LineProfile> = lines.into_iter()
+            .map(|((file, line), counts)| LineProfile { file: file.to_string(), line, instructions: counts.executions, cycles: counts.cycles })
+            .collect();
+        lines.sort_by(|a, b| b.cycles.cmp(&a.cycles).then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line))));
+        let mut calls: Vec<CallProfile> = calls.into_iter()
+            .map(|((caller, callee), counts)| CallProfile { caller: caller.to_string(), callee: callee.to_string(), calls: counts.calls, cycles: counts.cycles })
+            .collect();
+        calls.sort_by(|a, b| b.cycles.cmp(&a.cycles).then_with(|| (&a.caller, &a.callee).cmp(&(&b.caller, &b.callee))));
+        let counts = profile.counts.values();
+        ProfileReport {
+            exit_code,
+            instructions: counts.clone().map(|counts| counts.executions).sum(),
+            cycles: counts.map(|counts| counts.cycles).sum(),
+            functions,
+            lines,
+            calls,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    fn percent(&self, cycles: u64) -> f64 {
+        if self.cycles == 0 { 0.0 } else { cycles as f64 * 100.0 / self.cycles as f64 }
+    }
+}
+
+/// a flat profile, the lines & the call graph, in which each function is followed by its callers (<-) & callees (->), e.g
+///     exit code: 0, instructions: 1200, cycles: 1500
+///     functions:    (self cycles, %, total cycles, calls, instructions)
+///        1100  73.3%      1100      15       900  fib
+///     lines:
+///         600  40.0%   500  fib.c:3
+///     call graph:   (calls, cycles)
+///     fib: 15 calls, 1100 cycles, 1100 with its children
+///              1      1100  <- main
+///             14         0  <- fib
+///             14         0  -> fib
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "exit code: {}, instructions: {}, cycles: {}", self.exit_code, self.instructions, self.cycles)?;
+        writeln!(f, "functions:    (self cycles, %, total cycles, calls, instructions)")?;
+        for function in self.functions.iter() {
+            writeln!(f, "{:>10} {:>5.1}% {:>9} {:>7} {:>9}  {}",
+                function.cycles, self.percent(function.cycles), function.total_cycles, function.calls, function.instructions, function.name)?;
+        }
+        writeln!(f, "lines:")?;
+        for line in self.lines.iter() {
+            writeln!(f, "{:>10} {:>5.1}% {:>9}  {}:{}", line.cycles, self.percent(line.cycles), line.instructions, line.file, line.line)?;
+        }
+        writeln!(f, "call graph:   (calls, cycles)")?;
+        let mut functions: Vec<&FunctionProfile> = self.functions.iter().collect();
+        functions.sort_by(|a, b| b.total_cycles.cmp(&a.total_cycles).then_with(|| a.name.cmp(&b.name)));
+        for function in functions {
+            writeln!(f, "{}: {} calls, {} cycles, {} with its children", function.name, function.calls, function.cycles, function.total_cycles)?;
+            for call in self.calls.iter().filter(|call| call.callee == function.name) {
+                writeln!(f, "{:>10} {:>9}  <- {}", call.calls, call.cycles, call.caller)?;
+            }
+            for call in self.calls.iter().filter(|call| call.caller == function.name) {
+                writeln!(f, "{:>10} {:>9}  -> {}", call.calls, call.cycles, call.callee)?;
+            }
+        }
+        Ok(())
+    }
+}
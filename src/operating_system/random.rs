@@ -0,0 +1,44 @@
+/// the seed of every program's random numbers unless OS::random_seed is set, so runs are reproducible
+pub const DEFAULT_RANDOM_SEED: u64 = 1;
+
+/// the largest number that the generator returns, like C's RAND_MAX
+pub const RAND_MAX: i32 = i32::MAX;
+
+/// a pseudo random number generator (splitmix64), the same seed always gives the same numbers
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    /// returns a number in [0, RAND_MAX]
+    pub fn next_number(&mut self) -> i32 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut x = self.state;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+        (x >> 32) as i32 & RAND_MAX
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeds() {
+        let numbers = |seed: u64| -> Vec<i32> {
+            let mut random = Random::new(seed);
+            (0..100).map(|_| random.next_number()).collect()
+        };
+        assert_eq!(numbers(5), numbers(5));
+        assert_ne!(numbers(5), numbers(6));
+        assert!(numbers(0).iter().all(|x| (0..=RAND_MAX).contains(x)));
+        // the numbers are spread over the range
+        assert!(numbers(7).iter().any(|x| *x < RAND_MAX / 4) && numbers(7).iter().any(|x| *x > RAND_MAX / 4 * 3));
+    }
+}
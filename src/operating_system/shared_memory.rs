@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use crate::cpu::{SHARED_MEMORY_END, SHARED_MEMORY_START};
+
+/// the blocks of the shared memory, which every process can access at the same addresses, see Segment::physical
+/// a block is created with a key, & the processes that know the key attach it to get its address
+pub struct SharedMemory {
+    blocks: BTreeMap<i32, (u32, u32)>, // key -> (address, size)
+    next: u32, // blocks are never freed, so the free memory starts here
+}
+
+impl SharedMemory {
+    pub fn new() -> SharedMemory {
+        SharedMemory { blocks: BTreeMap::new(), next: SHARED_MEMORY_START }
+    }
+
+    /// returns the address of the new block, or None if the key is taken or there's not enough shared memory
+    pub fn create(&mut self, key: i32, size: u32) -> Option<u32> {
+        if self.blocks.contains_key(&key) || SHARED_MEMORY_END - self.next < size {
+            return None;
+        }
+        let address = self.next;
+        self.blocks.insert(key, (address, size));
+        self.next += size;
+        Some(address)
+    }
+
+    /// returns the address of the block with the key
+    pub fn attach(&self, key: i32) -> Option<u32> {
+        self.blocks.get(&key).map(|(address, _)| *address)
+    }
+}
+
+impl Default for SharedMemory {
+    fn default() -> SharedMemory {
+        SharedMemory::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let mut shm = SharedMemory::new();
+        assert_eq!(shm.create(7, 10), Some(SHARED_MEMORY_START));
+        assert_eq!(shm.create(7, 1), None);
+        assert_eq!(shm.create(8, 1), Some(SHARED_MEMORY_START + 10));
+        assert_eq!(shm.create(9, SHARED_MEMORY_END - SHARED_MEMORY_START), None);
+        assert_eq!(shm.attach(7), Some(SHARED_MEMORY_START));
+        assert_eq!(shm.attach(9), None);
+    }
+}
@@ -0,0 +1,151 @@
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use super::compiler::format_errors;
+use super::process::ProcessState;
+use super::OS;
+
+const PROMPT: &str = "$ ";
+const HELP: &str = "commands:
+    run <file.c> [&]  compile & run a program, in the background if & is given
+    ps                list the processes
+    kill <pid>        stop a process
+    wait              run the background processes until they finish
+    mem               show the memory usage of the processes
+    exit              exit the shell";
+
+impl OS {
+    /// an interactive shell that reads commands from stdin
+    pub fn run_shell(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        self.run_shell_from(&mut stdin.lock(), &mut io::stdout())
+    }
+
+    /// runs commands from input until it ends or the exit command, the shell's messages are written to out
+    /// the programs' output is written to stdout, see get_output
+    pub fn run_shell_from(&mut self, input: &mut dyn BufRead, out: &mut dyn Write) -> io::Result<()> {
+        loop {
+            write!(out, "{}", PROMPT)?;
+            out.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let args: Vec<&str> = line.split_whitespace().collect();
+            match args.as_slice() {
+                [] => {},
+                ["exit"] => return Ok(()),
+                ["run", path] => self.shell_run(path, false, out)?,
+                ["run", path, "&"] => self.shell_run(path, true, out)?,
+                ["ps"] => self.shell_ps(out)?,
+                ["kill", pid] => match pid.parse() {
+                    Ok(pid) if self.kill(pid) => writeln!(out, "killed {}", pid)?,
+                    _ => writeln!(out, "no such process: {}", pid)?,
+                },
+                ["wait"] => {
+                    self.run_all();
+                },
+                ["mem"] => self.shell_mem(out)?,
+                _ => writeln!(out, "{}", HELP)?,
+            }
+        }
+    }
+
+    /// a foreground program runs (along with the background processes) until it finishes
+    fn shell_run(&mut self, path: &str, background: bool, out: &mut dyn Write) -> io::Result<()> {
+        if !Path::new(path).is_file() {
+            return writeln!(out, "no such file: {}", path);
+        }
+        let object = match self.try_compile_object(path) {
+            Ok(object) => object,
+            Err(errors) => return writeln!(out, "{}", format_errors(&errors)),
+        };
+        let pid = self.spawn(&object.code);
+        if background {
+            return writeln!(out, "[{}]", pid);
+        }
+        let exit_value = self.wait_for(pid);
+        writeln!(out, "process {} exited with {}", pid, exit_value)
+    }
+
+    fn shell_ps(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{:<6}{}", "PID", "STATE")?;
+        for process in self.processes.iter() {
+            let state = match process.state {
+                ProcessState::Ready => "ready".to_string(),
+                ProcessState::Waiting(child) => format!("waiting for {}", child),
+                ProcessState::Locking(mutex) => format!("locking {}", mutex),
+                ProcessState::Sleeping(until) => format!("sleeping until cycle {}", until),
+                ProcessState::Exited(exit_value) => format!("exited ({})", exit_value),
+                ProcessState::Killed => "killed".to_string(),
+            };
+            writeln!(out, "{:<6}{}", process.pid, state)?;
+        }
+        Ok(())
+    }
+
+    fn shell_mem(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{:<6}{:<16}{}", "PID", "SEGMENT", "HEAP")?;
+        for process in self.processes.iter() {
+            let segment = format!("{}-{}", process.segment.base, process.segment.base + process.segment.limit - 1);
+            writeln!(out, "{:<6}{:<16}{}", process.pid, segment, process.heap.total_allocated())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn run_shell(commands: &str) -> String {
+        let mut os = OS::new();
+        let mut out = Vec::new();
+        os.run_shell_from(&mut Cursor::new(commands), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_shell() {
+        let out = run_shell("run tests/scheduler_test_data/counter.c
+run tests/scheduler_test_data/print_a.c &
+ps
+kill 1
+kill 1
+ps
+mem
+");
+        let lines: Vec<&str> = out.split(PROMPT).map(|line| line.trim_end()).collect();
+        assert_eq!(lines, vec![
+            "",
+            "process 0 exited with 100",
+            "[1]",
+            "PID   STATE\n0     exited (100)\n1     ready",
+            "killed 1",
+            "no such process: 1",
+            "PID   STATE\n0     exited (100)\n1     killed",
+            "PID   SEGMENT         HEAP\n0     12000-23999     0\n1     24000-35999     0",
+            "",
+        ]);
+    }
+
+    #[test]
+    fn test_shell_background() {
+        let mut os = OS::new();
+        let mut out = Vec::new();
+        let commands = "run tests/scheduler_test_data/print_a.c &\nrun tests/scheduler_test_data/print_b.c &\nwait\nexit\nps\n";
+        os.run_shell_from(&mut Cursor::new(commands), &mut out).unwrap();
+        assert_eq!(os.get_output().len(), 40);
+        // the shell stops at exit
+        assert!(!String::from_utf8(out).unwrap().contains("PID"));
+    }
+
+    #[test]
+    fn test_shell_errors() {
+        let out = run_shell("run nothing.c\nfoo\n");
+        assert!(out.contains("no such file: nothing.c"));
+        assert!(out.contains(HELP));
+    }
+}
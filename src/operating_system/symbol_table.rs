@@ -0,0 +1,167 @@
+// the addresses of an assembled program's labels. in an object they're relative to the object's code (or data),
+// in an executable to the executable's, & in the os to the memory the program was loaded to
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u32,
+    pub exported: bool, // declared with .global, so other objects can reference it
+}
+
+/// serialized as the list of its symbols, sorted by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<Symbol>", into = "Vec<Symbol>")]
+pub struct SymbolTable {
+    symbols: BTreeMap<String, Symbol>,
+    by_address: BTreeSet<(u32, String)>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    /// returns the symbol that had the name, if there was one
+    pub fn insert(&mut self, name: &str, address: u32, exported: bool) -> Option<Symbol> {
+        let previous = self.symbols.insert(name.to_string(), Symbol { name: name.to_string(), address, exported });
+        if let Some(previous) = &previous {
+            self.by_address.remove(&(previous.address, previous.name.clone()));
+        }
+        self.by_address.insert((address, name.to_string()));
+        previous
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name)
+    }
+
+    pub fn address_of(&self, name: &str) -> Option<u32> {
+        self.get(name).map(|symbol| symbol.address)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.symbols.contains_key(name)
+    }
+
+    pub fn is_exported(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|symbol| symbol.exported)
+    }
+
+    /// returns false if there's no symbol with the name
+    pub fn set_exported(&mut self, name: &str) -> bool {
+        match self.symbols.get_mut(name) {
+            Some(symbol) => {
+                symbol.exported = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// the symbols sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.values()
+    }
+
+    pub fn exported(&self) -> impl Iterator<Item = &Symbol> {
+        self.iter().filter(|symbol| symbol.exported)
+    }
+
+    /// the symbol at the address, or else the closest one before it, e.g the label of a loop an instruction is in
+    /// of the symbols at the same address, exported ones come first
+    pub fn nearest_symbol(&self, address: u32) -> Option<&Symbol> {
+        let (nearest_address, _) = self.before(address).next()?;
+        self.before(*nearest_address)
+            .take_while(|(symbol_address, _)| symbol_address == nearest_address)
+            .map(|(_, name)| &self.symbols[name])
+            .min_by_key(|symbol| (!symbol.exported, &symbol.name))
+    }
+
+    /// like nearest_symbol, but only of the exported symbols, e.g the function that contains an instruction
+    pub fn nearest_exported(&self, address: u32) -> Option<&Symbol> {
+        self.before(address)
+            .map(|(_, name)| &self.symbols[name])
+            .filter(|symbol| symbol.exported)
+            .fold(None, |nearest: Option<&Symbol>, symbol| match nearest {
+                Some(nearest) if nearest.address != symbol.address => Some(nearest),
+                Some(nearest) if nearest.name < symbol.name => Some(nearest),
+                _ => Some(symbol),
+            })
+    }
+
+    /// the table with the offset added to each address, e.g to get the addresses in memory of an executable's symbols
+    pub fn relocated(&self, offset: u32) -> SymbolTable {
+        self.iter().map(|symbol| Symbol { address: symbol.address + offset, ..symbol.clone() }).collect::<Vec<Symbol>>().into()
+    }
+
+    /// the symbols at or before the address, closest first
+    fn before(&self, address: u32) -> impl Iterator<Item = &(u32, String)> {
+        let symbols = match address.checked_add(1) {
+            Some(end) => self.by_address.range(..(end, String::new())),
+            None => self.by_address.range(..),
+        };
+        symbols.rev()
+    }
+}
+
+impl From<Vec<Symbol>> for SymbolTable {
+    fn from(symbols: Vec<Symbol>) -> SymbolTable {
+        let mut table = SymbolTable::new();
+        for symbol in symbols {
+            table.insert(&symbol.name, symbol.address, symbol.exported);
+        }
+        table
+    }
+}
+
+impl From<SymbolTable> for Vec<Symbol> {
+    fn from(table: SymbolTable) -> Vec<Symbol> {
+        table.symbols.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> SymbolTable {
+        let mut table = SymbolTable::new();
+        table.insert("main", 0, true);
+        table.insert("loop", 3, false);
+        table.insert("fib", 6, true);
+        table.insert("fib_end", 6, false);
+        table
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut table = table();
+        assert_eq!(table.address_of("loop"), Some(3));
+        assert_eq!(table.address_of("foo"), None);
+        assert!(table.is_exported("fib") && !table.is_exported("loop"));
+        assert_eq!(table.nearest_symbol(4).unwrap().name, "loop");
+        assert_eq!(table.nearest_symbol(7).unwrap().name, "fib");
+        assert_eq!(table.nearest_exported(4).unwrap().name, "main");
+        assert_eq!(table.nearest_exported(u32::MAX).unwrap().name, "fib");
+        assert_eq!(table.exported().map(|symbol| symbol.name.as_str()).collect::<Vec<&str>>(), vec!["fib", "main"]);
+
+        assert_eq!(table.insert("loop", 1, false).unwrap().address, 3);
+        assert_eq!(table.nearest_symbol(4).unwrap().name, "loop");
+        assert!(table.set_exported("loop") && !table.set_exported("foo"));
+        assert_eq!(table.nearest_exported(4).unwrap().name, "loop");
+        assert_eq!(table.relocated(100).nearest_symbol(100).unwrap().name, "main");
+        assert_eq!(SymbolTable::new().nearest_symbol(0), None);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let table = table();
+        let json = serde_json::to_string(&table).unwrap();
+        assert!(json.starts_with(r#"[{"name":"fib","address":6,"exported":true}"#));
+        assert_eq!(serde_json::from_str::<SymbolTable>(&json).unwrap(), table);
+    }
+}
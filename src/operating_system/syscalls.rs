@@ -0,0 +1,273 @@
+use super::OS;
+use super::random::Random;
+use super::layout::KEYBOARD_HANDLER_ADDRESS;
+use crate::cpu::interrupts::KEYBOARD_INTERRUPT;
+use crate::cpu::MemEntry;
+
+// syscall numbers, passed to SYSCALL in R1
+pub const SYS_EXIT: i32 = 1;
+pub const SYS_PUTCHAR: i32 = 2;
+pub const SYS_GETCHAR: i32 = 3;
+pub const SYS_BRK: i32 = 4;
+pub const SYS_TIMER: i32 = 5; // called by the timer interrupt handler
+pub const SYS_OPEN: i32 = 6;
+pub const SYS_READ: i32 = 7;
+pub const SYS_WRITE: i32 = 8;
+pub const SYS_CLOSE: i32 = 9;
+pub const SYS_UNLINK: i32 = 10;
+pub const SYS_KEYBOARD: i32 = 11;
+pub const SYS_SPAWN: i32 = 12;
+pub const SYS_WAITPID: i32 = 13;
+pub const SYS_SHM_CREATE: i32 = 14;
+pub const SYS_SHM_ATTACH: i32 = 15;
+pub const SYS_MUTEX_LOCK: i32 = 16;
+pub const SYS_MUTEX_UNLOCK: i32 = 17;
+pub const SYS_SLEEP: i32 = 18;
+pub const SYS_CLOCK: i32 = 19;
+pub const SYS_RAND: i32 = 20;
+pub const SYS_SRAND: i32 = 21;
+
+// file descriptors of the terminal, other descriptors are files in os.fs
+const STDIN_FD: i32 = 0;
+const STDOUT_FD: i32 = 1;
+
+/// receives the syscall argument, returns the syscall's result
+pub type SyscallHandler = fn(&mut OS, i32) -> i32;
+
+pub fn default_syscalls() -> Vec<(i32, SyscallHandler)> {
+    vec![
+        (SYS_EXIT, sys_exit),
+        (SYS_PUTCHAR, sys_putchar),
+        (SYS_GETCHAR, sys_getchar),
+        (SYS_BRK, sys_brk),
+        (SYS_TIMER, sys_timer),
+        (SYS_OPEN, sys_open),
+        (SYS_READ, sys_read),
+        (SYS_WRITE, sys_write),
+        (SYS_CLOSE, sys_close),
+        (SYS_UNLINK, sys_unlink),
+        (SYS_KEYBOARD, sys_keyboard),
+        (SYS_SPAWN, sys_spawn),
+        (SYS_WAITPID, sys_waitpid),
+        (SYS_SHM_CREATE, sys_shm_create),
+        (SYS_SHM_ATTACH, sys_shm_attach),
+        (SYS_MUTEX_LOCK, sys_mutex_lock),
+        (SYS_MUTEX_UNLOCK, sys_mutex_unlock),
+        (SYS_SLEEP, sys_sleep),
+        (SYS_CLOCK, sys_clock),
+        (SYS_RAND, sys_rand),
+        (SYS_SRAND, sys_srand),
+    ]
+}
+
+fn sys_exit(os: &mut OS, status: i32) -> i32 {
+    os.exit_code = Some(status);
+    status
+}
+
+fn sys_putchar(os: &mut OS, c: i32) -> i32 {
+    os.write_char(c as u8 as char);
+    c
+}
+
+/// returns -1 on end of input
+fn sys_getchar(os: &mut OS, _: i32) -> i32 {
+    match os.read_char() {
+        Some(c) => c as i32,
+        None => -1,
+    }
+}
+
+/// moves the program break by the given increment (like sbrk)
+/// returns the previous break, or -1 if there's not enough memory
+fn sys_brk(os: &mut OS, increment: i32) -> i32 {
+    match os.heap.sbrk(increment) {
+        Some(prev_brk) => prev_brk as i32,
+        None => -1,
+    }
+}
+
+/// switches to the next process if processes are running, returns the number of timer interrupts so far
+fn sys_timer(os: &mut OS, _: i32) -> i32 {
+    os.timer_ticks += 1;
+    os.reschedule = true;
+    os.timer_ticks as i32
+}
+
+/// syscalls with several arguments receive the address of the first one, the others follow it in memory
+/// returns None if the program can't read them. like on unix, a syscall that's passed an invalid address fails
+/// with -1 (EFAULT) rather than faulting the program
+fn get_args(os: &OS, args_address: i32, count: u32) -> Option<Vec<i32>> {
+    (0..count).map(|i| os.user_get_num((args_address as u32).checked_add(i)?)).collect()
+}
+
+/// args: path, flags. returns the file descriptor or -1
+fn sys_open(os: &mut OS, args_address: i32) -> i32 {
+    let args = match get_args(os, args_address, 2) {
+        Some(args) => args,
+        None => return -1,
+    };
+    let path = match os.read_string(args[0] as u32) {
+        Some(path) => path,
+        None => return -1,
+    };
+    os.fs.open(&path, args[1]).unwrap_or(-1)
+}
+
+/// args: fd, buffer, count. returns the number of read chars, 0 at the end of the file, or -1
+fn sys_read(os: &mut OS, args_address: i32) -> i32 {
+    let args = match get_args(os, args_address, 3) {
+        Some(args) => args,
+        None => return -1,
+    };
+    let (fd, buf, count) = (args[0], args[1] as u32, args[2].max(0) as usize);
+    // checked before reading, so the input isn't lost
+    if !os.user_can_write(buf, count as u32) {
+        return -1;
+    }
+    let bytes = if fd == STDIN_FD {
+        let mut bytes = Vec::new();
+        while bytes.len() < count {
+            match os.read_char() {
+                Some(c) => bytes.push(c as u8),
+                None => break,
+            }
+        }
+        bytes
+    } else {
+        match os.fs.read(fd, count) {
+            Some(bytes) => bytes,
+            None => return -1,
+        }
+    };
+    for (i, byte) in bytes.iter().enumerate() {
+        os.user_set(buf + i as u32, *byte as i32);
+    }
+    bytes.len() as i32
+}
+
+/// args: fd, buffer, count. returns the number of written chars or -1
+fn sys_write(os: &mut OS, args_address: i32) -> i32 {
+    let args = match get_args(os, args_address, 3) {
+        Some(args) => args,
+        None => return -1,
+    };
+    let (fd, buf, count) = (args[0], args[1] as u32, args[2].max(0) as u32);
+    let bytes = match (0..count).map(|i| os.user_get_num(buf.checked_add(i)?).map(|c| c as u8)).collect::<Option<Vec<u8>>>() {
+        Some(bytes) => bytes,
+        None => return -1,
+    };
+    if fd == STDOUT_FD {
+        for byte in bytes.iter() {
+            os.write_char(*byte as char);
+        }
+        return count as i32;
+    }
+    match os.fs.write(fd, &bytes) {
+        Some(written) => written as i32,
+        None => -1,
+    }
+}
+
+/// returns 0 or -1
+fn sys_close(os: &mut OS, fd: i32) -> i32 {
+    if os.fs.close(fd) { 0 } else { -1 }
+}
+
+/// returns 0 or -1
+fn sys_unlink(os: &mut OS, path: i32) -> i32 {
+    let path = match os.read_string(path as u32) {
+        Some(path) => path,
+        None => return -1,
+    };
+    if os.fs.unlink(&path) { 0 } else { -1 }
+}
+
+/// sets the function that the keyboard interrupt calls (0 removes it) & enables interrupts
+/// the function is called right away if keys were pressed before it was set. returns 0
+fn sys_keyboard(os: &mut OS, handler: i32) -> i32 {
+    os.mem_set(KEYBOARD_HANDLER_ADDRESS, MemEntry::Num(handler));
+    if handler != 0 {
+        os.cpu.interrupts_enabled = true;
+        if os.keyboard.has_keys() {
+            os.cpu.raise_interrupt(KEYBOARD_INTERRUPT);
+        }
+    }
+    0
+}
+
+/// starts a child process that runs the binary image at the path in os.fs, see OS::spawn_file
+/// returns the child's pid, or -1 if there's no such image
+fn sys_spawn(os: &mut OS, path: i32) -> i32 {
+    let path = match os.read_string(path as u32) {
+        Some(path) => path,
+        None => return -1,
+    };
+    os.spawn_file(&path).map_or(-1, |pid| pid as i32)
+}
+
+/// waits until the child exits & reaps it, the other processes run meanwhile
+/// returns the child's exit value, or -1 if it's not a child of the caller or it was already waited for
+fn sys_waitpid(os: &mut OS, pid: i32) -> i32 {
+    if pid < 0 {
+        return -1;
+    }
+    os.waitpid(pid as u32).unwrap_or(-1)
+}
+
+/// args: key, size. creates a zeroed block of shared memory that processes can attach with the key
+/// returns 0, or -1 if the key is taken or there's not enough shared memory
+fn sys_shm_create(os: &mut OS, args_address: i32) -> i32 {
+    let args = match get_args(os, args_address, 2) {
+        Some(args) => args,
+        None => return -1,
+    };
+    let size = args[1].max(0) as u32;
+    let address = match os.shared_memory.create(args[0], size) {
+        Some(address) => address,
+        None => return -1,
+    };
+    for i in 0..size {
+        os.mem_set(address + i, MemEntry::Num(0));
+    }
+    0
+}
+
+/// returns the address of the shared memory block with the key, or 0 if there's none
+fn sys_shm_attach(os: &mut OS, key: i32) -> i32 {
+    os.shared_memory.attach(key).map_or(0, |address| address as i32)
+}
+
+/// called by mutex_lock when the mutex is locked, see libc.c
+/// takes the mutex once it's unlocked, the other processes run meanwhile. returns 0, or -1 if it would wait forever
+fn sys_mutex_lock(os: &mut OS, mutex: i32) -> i32 {
+    if os.mutex_lock(mutex as u32) { 0 } else { -1 }
+}
+
+/// wakes a process that's waiting for the mutex & hands it over, or unlocks it if none is waiting. returns 0
+fn sys_mutex_unlock(os: &mut OS, mutex: i32) -> i32 {
+    os.mutex_unlock(mutex as u32);
+    0
+}
+
+/// the other processes run while the caller sleeps for at least the given number of cycles, 0 just yields. returns 0
+fn sys_sleep(os: &mut OS, cycles: i32) -> i32 {
+    os.sleep(cycles.max(0) as u32);
+    0
+}
+
+/// returns the low 32 bits of the cpu's cycle counter, it counts the cycles of all the processes
+fn sys_clock(os: &mut OS, _: i32) -> i32 {
+    os.cpu.cycles as i32
+}
+
+/// returns a pseudo random number in [0, RAND_MAX], the numbers of a program are the same in each run, see OS::set_random_seed
+fn sys_rand(os: &mut OS, _: i32) -> i32 {
+    os.random.next_number()
+}
+
+/// restarts the random numbers from the seed. returns 0
+fn sys_srand(os: &mut OS, seed: i32) -> i32 {
+    os.random = Random::new(seed as u32 as u64);
+    0
+}
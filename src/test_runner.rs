@@ -0,0 +1,261 @@
+// runs the golden tests of the compiler: each <dir>/<category>/inputs/<name>.c is compiled with std & run,
+// & its exit code & output are compared to <category>/targets/<name>.res & <name>.out (either may be missing).
+// <name>.in is given to the program as stdin. categories & inputs whose name starts with _ are skipped,
+// they're helpers of other tests. with bless, the targets are written from the results instead, e.g
+//     BLESS=1 cargo test --test compiler_tests
+// coverage_all reports which lines of the cases & the std the cases executed
+
+use std::env;
+use std::fs;
+use std::io::{self, Cursor};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::cpu::trace::TraceConfig;
+use crate::operating_system::compiler::format_errors;
+use crate::operating_system::coverage::CoverageReport;
+use crate::operating_system::OS;
+
+// number of last executed instructions that are shown when a test fails
+const TRACE_SIZE: usize = 30;
+
+pub struct TestCase {
+    pub name: String, // <category>/<name>
+    pub input: PathBuf,
+    targets: PathBuf, // the targets dir & the case's name, without an extension
+}
+
+impl TestCase {
+    fn target(&self, extension: &str) -> PathBuf {
+        self.targets.with_extension(extension)
+    }
+}
+
+/// what a test's program did
+pub struct Outcome {
+    pub exit_code: i32,
+    pub output: String,
+    pub trace: Vec<String>, // the last executed instructions
+}
+
+/// the test cases in the dir, sorted by name
+pub fn find_cases(dir: &Path) -> io::Result<Vec<TestCase>> {
+    let mut cases = Vec::new();
+    for category in fs::read_dir(dir)? {
+        let category = category?.path();
+        let category_name = file_name(&category);
+        if category_name.starts_with('_') || !category.join("inputs").is_dir() {
+            continue;
+        }
+        for input in fs::read_dir(category.join("inputs"))? {
+            let input = input?.path();
+            if file_name(&input).starts_with('_') || input.extension().is_none_or(|extension| extension != "c") {
+                continue;
+            }
+            let stem = input.file_stem().unwrap().to_string_lossy().to_string();
+            cases.push(TestCase {
+                name: format!("{}/{}", category_name, stem),
+                targets: category.join("targets").join(&stem),
+                input,
+            });
+        }
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// compiles & runs the case's program, returns an error if it doesn't compile or the os panics
+pub fn run_case(case: &TestCase) -> Result<Outcome, String> {
+    let mut os = case_os(case);
+    os.set_trace(TraceConfig::new(TRACE_SIZE));
+    let object = os.try_compile_object(&case.input.to_string_lossy()).map_err(|errors| format_errors(&errors))?;
+    match panic::catch_unwind(AssertUnwindSafe(|| os.link_and_run(vec![&object]))) {
+        Ok(exit_code) => Ok(Outcome { exit_code, output: os.get_output(), trace: os.dump_trace() }),
+        Err(err) => {
+            let message = err.downcast_ref::<String>().cloned()
+                .or_else(|| err.downcast_ref::<&str>().map(|message| message.to_string()))
+                .unwrap_or_default();
+            Err(format!("panicked: {}\n{}", message, os.dump_trace().join("\n")))
+        },
+    }
+}
+
+/// an os whose stdin is the case's
+fn case_os(case: &TestCase) -> OS {
+    let mut os = OS::new();
+    if let Ok(stdin) = fs::read(case.target("in")) {
+        os.set_input(Box::new(Cursor::new(stdin)));
+    }
+    os
+}
+
+/// runs the case & compares the results to its targets, returns what didn't match
+/// with bless, the targets are written instead: the existing ones, or .res (& .out if there's output) for a new case
+pub fn check_case(case: &TestCase, bless: bool) -> Result<(), String> {
+    let outcome = run_case(case)?;
+    let exit_code = outcome.exit_code.to_string();
+    let targets = [("res", &exit_code), ("out", &outcome.output)];
+    if bless {
+        let is_new = targets.iter().all(|(extension, _)| !case.target(extension).is_file());
+        for (extension, actual) in targets.iter() {
+            let path = case.target(extension);
+            if path.is_file() || (is_new && (*extension == "res" || !actual.is_empty())) {
+                fs::create_dir_all(path.parent().unwrap()).map_err(|err| err.to_string())?;
+                fs::write(&path, actual).map_err(|err| format!("{}: {}", path.display(), err))?;
+            }
+        }
+        return Ok(());
+    }
+    let mut mismatches = Vec::new();
+    let mut found_target = false;
+    for (extension, actual) in targets.iter() {
+        if let Ok(expected) = fs::read_to_string(case.target(extension)) {
+            found_target = true;
+            if expected.trim() != actual.as_str() {
+                mismatches.push(format!("{}: expected {:?}, got {:?}", extension, expected.trim(), actual));
+            }
+        }
+    }
+    if !found_target {
+        return Err("no targets, run with BLESS=1 to create them".to_string());
+    }
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    mismatches.push(format!("last {} executed instructions:", TRACE_SIZE));
+    mismatches.extend(outcome.trace);
+    Err(mismatches.join("\n"))
+}
+
+/// checks the cases in parallel, returns the name & error of each failed case, sorted by name
+/// only cases whose name contains the filter are run
+pub fn check_all(cases: &[TestCase], bless: bool, filter: Option<&str>) -> Vec<(String, String)> {
+    let mut failures = run_parallel(cases, filter, |case| check_case(case, bless).err().map(|err| (case.name.clone(), err)));
+    failures.sort();
+    failures
+}
+
+/// runs the cases in parallel & merges which source lines they executed, e.g to find code the cases don't test
+/// the cases that don't compile or panic are skipped, check_all reports them
+pub fn coverage_all(cases: &[TestCase], filter: Option<&str>) -> CoverageReport {
+    let reports = run_parallel(cases, filter, |case| {
+        let mut os = case_os(case);
+        let object = os.try_compile_object(&case.input.to_string_lossy()).ok()?;
+        panic::catch_unwind(AssertUnwindSafe(|| os.link_and_run_with_coverage(vec![&object]))).ok().map(|(_, report)| report)
+    });
+    let mut coverage = CoverageReport::new();
+    for report in reports.iter() {
+        coverage.merge(report);
+    }
+    coverage
+}
+
+/// the exit code of the case's program when it's compiled with the host's c compiler ($CC, or cc), & run
+/// returns None if the host's compiler can't compile it, e.g if it uses the vm's libc
+pub fn run_host(case: &TestCase, work_dir: &Path) -> Option<Result<i32, String>> {
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let binary = work_dir.join(case.name.replace('/', "_"));
+    let compiled = Command::new(compiler).arg("-w").arg("-o").arg(&binary).arg(&case.input)
+        .stdout(Stdio::null()).stderr(Stdio::null()).status();
+    if !compiled.is_ok_and(|status| status.success()) {
+        return None;
+    }
+    let stdin = match fs::File::open(case.target("in")) {
+        Ok(file) => Stdio::from(file),
+        Err(_) => Stdio::null(),
+    };
+    Some(match Command::new(&binary).stdin(stdin).stdout(Stdio::null()).status() {
+        Ok(status) => status.code().ok_or_else(|| format!("the host's program was killed: {}", status)),
+        Err(err) => Err(err.to_string()),
+    })
+}
+
+/// compiles & runs the cases with both the vm & the host's c compiler, & compares their exit codes
+/// the host's exit code is a byte, so only the vm's low byte is compared
+/// returns the name & error of each diverging case, & the names of the cases the host can't compile, sorted by name
+pub fn check_host_all(cases: &[TestCase], filter: Option<&str>) -> (Vec<(String, String)>, Vec<String>) {
+    let work_dir = tempfile::tempdir().unwrap();
+    let results = run_parallel(cases, filter, |case| {
+        let host = match run_host(case, work_dir.path()) {
+            Some(host) => host,
+            None => return Some((case.name.clone(), None)),
+        };
+        let diverges = match (run_case(case), host) {
+            (Ok(vm), Ok(host)) if vm.exit_code & 0xff == host => None,
+            (Ok(vm), Ok(host)) => Some(format!("the vm returned {}, the host returned {}", vm.exit_code, host)),
+            (vm, host) => Some(format!("vm: {}\nhost: {}", vm.map(|vm| vm.exit_code.to_string()).unwrap_or_else(|err| err), host.map(|host| host.to_string()).unwrap_or_else(|err| err))),
+        };
+        diverges.map(|err| (case.name.clone(), Some(err)))
+    });
+    let mut divergences = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, err) in results {
+        match err {
+            Some(err) => divergences.push((name, err)),
+            None => skipped.push(name),
+        }
+    }
+    divergences.sort();
+    skipped.sort();
+    (divergences, skipped)
+}
+
+/// runs f on the cases whose name contains the filter, on several threads, returns the results that aren't None
+fn run_parallel<T: Send>(cases: &[TestCase], filter: Option<&str>, f: impl Fn(&TestCase) -> Option<T> + Sync) -> Vec<T> {
+    let cases: Vec<&TestCase> = cases.iter().filter(|case| filter.is_none_or(|filter| case.name.contains(filter))).collect();
+    let next_case = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::new());
+    let workers = thread::available_parallelism().map(|workers| workers.get()).unwrap_or(1);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(case) = cases.get(next_case.fetch_add(1, Ordering::SeqCst)) {
+                    if let Some(result) = f(case) {
+                        results.lock().unwrap().push(result);
+                    }
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bless() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = dir.path().join("category").join("inputs");
+        fs::create_dir_all(&inputs).unwrap();
+        fs::write(inputs.join("print.c"), "#include <libc.h>\nint main(){ printf(\"hi\"); return 3; }").unwrap();
+        fs::write(inputs.join("_helper.c"), "int helper(){ return 1; }").unwrap();
+        let cases = find_cases(dir.path()).unwrap();
+        assert_eq!(cases.iter().map(|case| case.name.as_str()).collect::<Vec<&str>>(), vec!["category/print"]);
+
+        assert!(check_case(&cases[0], false).unwrap_err().contains("BLESS"));
+        assert!(check_all(&cases, true, None).is_empty());
+        let targets = dir.path().join("category").join("targets");
+        assert_eq!(fs::read_to_string(targets.join("print.res")).unwrap(), "3");
+        assert_eq!(fs::read_to_string(targets.join("print.out")).unwrap(), "hi");
+        assert!(check_all(&cases, false, None).is_empty());
+
+        fs::write(targets.join("print.res"), "4").unwrap();
+        let failures = check_all(&cases, false, None);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].1.starts_with("res: expected \"4\", got \"3\""));
+        assert!(check_all(&cases, false, Some("other")).is_empty());
+
+        let coverage = coverage_all(&cases, None);
+        assert_eq!(coverage.files[&*cases[0].input.to_string_lossy()][&2].hits, 1);
+    }
+}
@@ -0,0 +1,123 @@
+// a simple api for embedding the vm, without using the os, the compiler & the linker directly, e.g
+//     let mut vm = Vm::builder().compile_file("a.c").stdin("5\n").max_cycles(1_000_000).build()?;
+//     let result = vm.run();
+
+use std::io::Cursor;
+
+use crate::operating_system::assembler::Executable;
+use crate::operating_system::compiler::{CompileError, CompilerOptions};
+use crate::operating_system::linker::ObjectFile;
+use crate::operating_system::{RunResult, OS};
+
+/// a c file on disk, or a (name, source) that's compiled from memory
+enum Source {
+    File(String),
+    Memory(String, String),
+}
+
+impl Source {
+    fn name(&self) -> &str {
+        match self {
+            Source::File(path) => path,
+            Source::Memory(name, _) => name,
+        }
+    }
+}
+
+pub struct VmBuilder {
+    sources: Vec<Source>,
+    stdin: String,
+    args: Vec<String>,
+    max_cycles: Option<u64>,
+    random_seed: Option<u64>,
+    compiler_options: CompilerOptions,
+}
+
+impl VmBuilder {
+    /// can be given several times, the files are linked together & with std, one of them should define main
+    pub fn compile_file(mut self, path: &str) -> VmBuilder {
+        self.sources.push(Source::File(path.to_string()));
+        self
+    }
+
+    /// like compile_file, with a source that's in memory, the name is its file name in errors & in argv
+    pub fn compile_source(mut self, name: &str, source: &str) -> VmBuilder {
+        self.sources.push(Source::Memory(name.to_string(), source.to_string()));
+        self
+    }
+
+    /// the input that the program reads, it gets EOF after it
+    pub fn stdin(mut self, stdin: &str) -> VmBuilder {
+        self.stdin = stdin.to_string();
+        self
+    }
+
+    /// can be given several times, main's argv is the first file's path (or name) & then the args
+    pub fn arg(mut self, arg: &str) -> VmBuilder {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// the program is stopped with RunOutcome::Timeout if it runs longer
+    pub fn max_cycles(mut self, max_cycles: u64) -> VmBuilder {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// the seed of the program's random numbers, each run gets the same numbers
+    pub fn random_seed(mut self, seed: u64) -> VmBuilder {
+        self.random_seed = Some(seed);
+        self
+    }
+
+    pub fn compiler_options(mut self, compiler_options: CompilerOptions) -> VmBuilder {
+        self.compiler_options = compiler_options;
+        self
+    }
+
+    /// compiles the files, returns the errors of the first file that doesn't compile
+    pub fn build(self) -> Result<Vm, Vec<CompileError>> {
+        let mut os = OS::new();
+        os.compiler_options = self.compiler_options;
+        os.max_cycles = self.max_cycles;
+        if let Some(seed) = self.random_seed {
+            os.set_random_seed(seed);
+        }
+        os.args = self.sources.first().map(|source| source.name().to_string()).into_iter().chain(self.args).collect();
+        let objects = self.sources.iter()
+            .map(|source| match source {
+                Source::File(path) => os.try_compile_object(path),
+                Source::Memory(name, code) => os.try_compile_object_str(name, code),
+            })
+            .collect::<Result<Vec<ObjectFile>, Vec<CompileError>>>()?;
+        let exec = os.link_executable(objects.iter().collect());
+        Ok(Vm { os, exec, stdin: self.stdin })
+    }
+}
+
+/// a compiled program that can be run, see Vm::builder
+pub struct Vm {
+    os: OS,
+    exec: Executable,
+    stdin: String,
+}
+
+impl Vm {
+    pub fn builder() -> VmBuilder {
+        VmBuilder {
+            sources: Vec::new(),
+            stdin: String::new(),
+            args: Vec::new(),
+            max_cycles: None,
+            random_seed: None,
+            compiler_options: CompilerOptions::new(),
+        }
+    }
+
+    /// runs the program from the start, each run gets the whole stdin
+    pub fn run(&mut self) -> RunResult {
+        self.os.set_input(Box::new(Cursor::new(self.stdin.clone().into_bytes())));
+        self.os.clear_output();
+        self.os.run_program(&self.exec)
+    }
+}
@@ -0,0 +1,15 @@
+#include <libc.h>
+#include <string.h>
+
+// prints the args after the program's name & returns the sum of their lengths
+int main(int argc, char** argv){
+    int total = 0;
+    for(int i = 1; i < argc; i++){
+        printf("%s\n", argv[i]);
+        total += strlen(argv[i]);
+    }
+    if(argv[argc] != 0){
+        return -1;
+    }
+    return total;
+}
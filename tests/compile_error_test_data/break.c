@@ -0,0 +1,7 @@
+int main(){
+    int a = 1;
+    if(a){
+        break;
+    }
+    return a;
+}
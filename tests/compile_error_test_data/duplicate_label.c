@@ -0,0 +1,7 @@
+int main(){
+    int a = 1;
+end:
+    a++;
+end:
+    return a;
+}
@@ -0,0 +1,5 @@
+int main(){
+    int a = 1;
+    a = b + 1;
+    return a;
+}
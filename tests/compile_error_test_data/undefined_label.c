@@ -0,0 +1,5 @@
+int main(){
+    int a = 1;
+    goto end;
+    return a;
+}
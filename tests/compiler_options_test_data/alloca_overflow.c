@@ -0,0 +1,14 @@
+#include <libc.h>
+
+int main(){
+    // the end of the heap is close to the bottom of the stack
+    int* block = malloc(1900);
+    int* last = block + 1899;
+    *last = 7;
+    int size = 4500;
+    int* frame = alloca(size * sizeof(int));
+    for(int i = 0; i < size; i++){
+        *(frame + i) = 0;
+    }
+    return *last;
+}
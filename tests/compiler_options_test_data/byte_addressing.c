@@ -0,0 +1,70 @@
+#include <libc.h>
+#include <string.h>
+
+struct item {
+    char tag;
+    int value;
+    char name[6];
+};
+
+struct item make_item(char tag, int value){
+    struct item it;
+    it.tag = tag;
+    it.value = value;
+    it.name[0] = 'i';
+    it.name[1] = 't';
+    it.name[2] = 0;
+    return it;
+}
+
+int sum_values(struct item a, struct item b){
+    return a.value + b.value;
+}
+
+char last;
+
+int main(){
+    // chars are packed 4 to a word, & struct fields are aligned
+    printf("%d ", sizeof(char));
+    printf("%d ", sizeof(int));
+    printf("%d ", sizeof(struct item));
+
+    char word[4] = {'a', 'b', 'c', 0};
+    int* as_int = (int*)word;
+    printf("%d ", *as_int == 'a' + ('b' << 8) + ('c' << 16));
+    // arithmetic on a pointer that's cast to char* moves in bytes
+    printf("%d ", (char*)(as_int + 1) - word);
+    printf("%c ", *((char*)as_int + 1));
+
+    char* s = "hello";
+    char* end = s;
+    while(*end != 0){
+        end++;
+    }
+    printf("%d ", end - s);
+
+    struct item items[2];
+    items[0] = make_item('x', 10);
+    items[1] = make_item('y', 32);
+    struct item* p = items + 1;
+    p->name[0] = 'I';
+    p->value += 1;
+    printf("%c", p->name[0]);
+    printf("%c ", p->name[1]);
+    printf("%d ", sum_values(items[0], *p));
+    printf("%c", items[0].tag);
+    printf("%c ", p->tag);
+
+    // alloca rounds the size up to whole words
+    char* tmp = alloca(3);
+    strcpy(tmp, "ok");
+    printf("%s ", tmp);
+
+    char* copy = malloc(strlen(s) + 1);
+    strcpy(copy, s);
+    *copy -= 32;
+    last = *(copy + 4);
+    printf("%s ", copy);
+    free(copy);
+    return last;
+}
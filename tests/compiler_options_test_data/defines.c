@@ -0,0 +1,13 @@
+#ifndef SIZE
+#define SIZE 4
+#endif
+
+int main() {
+#if defined(DEBUG) && SIZE > 8
+    return SIZE * 2;
+#elif defined DEBUG
+    return SIZE + 1;
+#else
+    return SIZE;
+#endif
+}
@@ -0,0 +1,11 @@
+int from_char(char c){
+    return c;
+}
+
+char to_char(int x){
+    return x;
+}
+
+int main(){
+    return from_char(366) + to_char(366) * 1000;
+}
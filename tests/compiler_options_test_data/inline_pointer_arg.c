@@ -0,0 +1,16 @@
+struct Point{
+    int x;
+    int y;
+};
+
+int plus_one(int x){
+    return x + 1;
+}
+
+struct Point point;
+
+int main(){
+    struct Point* p = &point;
+    // in plus_one x is an int, so x + 1 isn't scaled by the size of a point
+    return plus_one(p) - (int)p;
+}
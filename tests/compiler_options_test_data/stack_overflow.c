@@ -0,0 +1,20 @@
+#include <libc.h>
+
+// each call uses about 100 words of stack
+int recurse(int n){
+    int frame[100];
+    frame[0] = n;
+    if(n == 0){
+        return 0;
+    }
+    return recurse(n - 1) + frame[0];
+}
+
+int main(){
+    // the end of the heap is close to the bottom of the stack
+    int* block = malloc(1900);
+    int* last = block + 1899;
+    *last = 7;
+    recurse(45);
+    return *last;
+}
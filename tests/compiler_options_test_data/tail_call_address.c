@@ -0,0 +1,16 @@
+#include <libc.h>
+
+int g(int* p){
+    int z = 100;
+    return *p + z;
+}
+
+// g's frame would replace f's, so p would point into g's locals
+int f(int x){
+    int y = x * 2;
+    return g(&y);
+}
+
+int main(){
+    return f(5);
+}
@@ -0,0 +1,30 @@
+#include <libc.h>
+
+// without tail calls, each call uses a few words of stack, so the recursions overflow it
+int sum(int n, int acc){
+    if(n == 0){
+        return acc;
+    }
+    return sum(n - 1, acc + n);
+}
+
+int is_even(int n);
+
+int is_odd(int n){
+    if(n == 0){
+        return 0;
+    }
+    return is_even(n - 1);
+}
+
+int is_even(int n){
+    int result = 1;
+    if(n == 0){
+        return result;
+    }
+    return is_odd(n - 1);
+}
+
+int main(){
+    return sum(3000, 0) + is_odd(3001);
+}
@@ -0,0 +1,39 @@
+#include <libc.h>
+
+// fills an array that's allocated on the stack with the squares up to n & sums them
+int sum_squares(int n){
+    int* squares = alloca(n * sizeof(int));
+    for (int i = 0; i < n; i++) {
+        *(squares + i) = i * i;
+    }
+    int total = 0;
+    for (int i = 0; i < n; i++) {
+        total += *(squares + i);
+    }
+    return total;
+}
+
+// allocates in each call, the frames are still restored when the calls return
+int depth_sum(int depth){
+    if (depth == 0) {
+        return 0;
+    }
+    int* cell = alloca(sizeof(int));
+    *cell = depth;
+    int below = depth_sum(depth - 1);
+    return *cell + below;
+}
+
+int main(){
+    int before = 7;
+    int res = 0;
+    for (int i = 0; i < 3; i++) {
+        char* s = alloca(3);
+        *s = 'a' + i;
+        *(s + 1) = 0;
+        res += *s - 'a';
+    }
+    res += sum_squares(4) * 10;
+    res += depth_sum(5) * 100;
+    return res + before * 10000;
+}
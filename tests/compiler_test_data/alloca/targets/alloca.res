@@ -0,0 +1 @@
+71643
\ No newline at end of file
@@ -0,0 +1,6 @@
+int main(){
+    int x = 5;
+    int y = ~x;
+    // ~x == -x - 1, and ~~x == x
+    return (y == -6) + (~y == x) * 2 + (~0 == -1) * 4 + ((x & ~1) == 4) * 8;
+}
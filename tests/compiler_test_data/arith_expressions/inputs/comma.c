@@ -0,0 +1,8 @@
+int main(){
+    int a = 1, b = 2, *c = &b;
+    int x = (a++, b += a, *c + a);
+    int i, j;
+    for(i = 0, j = 10; i < j; i++, j--);
+    i++, j++;
+    return x * 100 + i * 10 + a;
+}
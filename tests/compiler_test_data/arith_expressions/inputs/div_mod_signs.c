@@ -0,0 +1,17 @@
+int check(int x, int y, int quotient, int remainder){
+    return x / y == quotient && x % y == remainder && quotient * y + remainder == x;
+}
+
+int main(){
+    // division rounds toward zero, the remainder has the sign of the dividend
+    int ok = 0;
+    ok += check(7, 2, 3, 1);
+    ok += check(-7, 2, -3, -1);
+    ok += check(7, -2, -3, 1);
+    ok += check(-7, -2, 3, -1);
+    ok += check(-6, 3, -2, 0);
+    ok += check(1, -5, 0, 1);
+    // constant expressions are evaluated by the compiler, with the same semantics
+    ok += -7 / 2 == -3 && -7 % 2 == -1 && 7 % -2 == 1;
+    return ok;
+}
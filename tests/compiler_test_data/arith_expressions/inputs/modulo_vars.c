@@ -0,0 +1,10 @@
+int mod(int a, int b){
+    return a % b;
+}
+
+int main(){
+    int x = 17;
+    x %= 10;
+    // 7, 2, 0
+    return x * 100 + mod(x, 5) * 10 + mod(12, 4);
+}
@@ -0,0 +1,10 @@
+int main(){
+    // wraps around rather than stopping the vm
+    int big = 2000000000;
+    int sum = big + big;
+    int min = -2147483647 - 1;
+    int count = 33;
+    // only the low 5 bits of the shift count are used
+    int shifted = 1 << count;
+    return (sum == -294967296) + (-min == min) + (min - 1 == 2147483647) + shifted;
+}
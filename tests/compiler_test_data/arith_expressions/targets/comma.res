@@ -0,0 +1 @@
+662
\ No newline at end of file
@@ -0,0 +1 @@
+720
\ No newline at end of file
@@ -0,0 +1,13 @@
+int main(){
+    int grid[2][3] = {{1, 2, 3}, {4, 5, 6}};
+    // the braces of the rows may be left out
+    int flat[2][2] = {7, 8, 9, 10};
+    int cube[2][2][2] = {{{1, 2}, {3, 4}}, {{5, 6}, {7, 8}}};
+    int res = 0;
+    for (int i = 0; i < 2; i++) {
+        for (int j = 0; j < 3; j++) {
+            res = res * 2 + grid[i][j];
+        }
+    }
+    return res + flat[1][0] * 1000 + cube[1][0][1] * 10000 + cube[0][1][1] * 100000;
+}
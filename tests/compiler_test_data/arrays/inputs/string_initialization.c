@@ -0,0 +1,16 @@
+#include <string.h>
+
+int main(){
+    char greeting[8] = "hello";
+    // the null is left out if only the chars fit
+    char exact[3] = "abc";
+    char names[3][4] = {"ab", "cde"};
+    int res = 0;
+    res += strcmp(greeting, "hello") == 0;
+    res += (greeting[5] == 0 && greeting[7] == 0) * 2;
+    res += (exact[2] == 'c') * 4;
+    res += (strcmp(&names[1][0], "cde") == 0 && names[0][2] == 0 && names[2][0] == 0) * 8;
+    greeting[0] = 'j';
+    res += (strcmp(greeting, "jello") == 0) * 16;
+    return res;
+}
@@ -0,0 +1,33 @@
+int sum(int* items, int count){
+    int total = 0;
+    for (int i = 0; i < count; i++) {
+        total += *(items + i);
+    }
+    return total;
+}
+
+// leaves non zero values on the stack, where the frame of the next call will be
+int dirty(){
+    int garbage[32];
+    for (int i = 0; i < 32; i++) {
+        garbage[i] = 9;
+    }
+    return sum(garbage, 32);
+}
+
+int check(){
+    int zeros[5] = {};
+    int partial[6] = {1, 2};
+    int rows[3][3] = {{1}, {2, 3}};
+    long longs[3] = {-1};
+    int res = sum(zeros, 5) == 0;
+    res += (sum(partial, 6) == 3) * 2;
+    res += (sum(rows, 9) == 6 && rows[1][2] == 0 && rows[2][0] == 0) * 4;
+    res += (longs[0] == -1 && longs[1] == 0 && longs[2] == 0) * 8;
+    return res;
+}
+
+int main(){
+    dirty();
+    return check();
+}
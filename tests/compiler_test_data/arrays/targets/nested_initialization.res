@@ -0,0 +1 @@
+469120
\ No newline at end of file
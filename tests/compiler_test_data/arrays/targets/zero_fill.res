@@ -0,0 +1 @@
+15
\ No newline at end of file
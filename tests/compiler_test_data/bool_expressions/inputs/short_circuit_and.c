@@ -0,0 +1,18 @@
+int calls = 0;
+
+int count(int x){
+    calls++;
+    return x;
+}
+
+int main(){
+    int* p = 0;
+    int ok = p != 0 && *p == 5;
+    int x = 5;
+    p = &x;
+    ok += p != 0 && *p == 5;
+    ok += count(0) && count(1);
+    ok += count(2) && count(3);
+    // 1 + 1 + 2 calls
+    return ok * 10 + calls;
+}
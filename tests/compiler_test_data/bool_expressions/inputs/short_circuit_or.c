@@ -0,0 +1,16 @@
+int calls = 0;
+
+int count(int x){
+    calls++;
+    return x;
+}
+
+int main(){
+    int* p = 0;
+    int ok = p == 0 || *p == 5;
+    ok += count(7) || count(1);
+    ok += count(0) || count(0);
+    ok += 0 || count(4);
+    // 1 + 2 + 1 calls
+    return ok * 10 + calls;
+}
@@ -0,0 +1 @@
+23
\ No newline at end of file
@@ -0,0 +1 @@
+34
\ No newline at end of file
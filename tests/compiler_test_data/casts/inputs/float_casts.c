@@ -0,0 +1,6 @@
+int main(){
+    float f = 2.75;
+    int i = (int)f;
+    float g = (float)7 / 2;
+    return i + (int)(g * 2) + (char)-f;
+}
@@ -0,0 +1,13 @@
+int main(){
+    int arr[3];
+    int* p = arr;
+    arr[1] = 7;
+    arr[2] = 5;
+    char* bytes = (char*)p;
+    int* second = (int*)(bytes + sizeof(int));
+    int res = *second;
+    if ((char*)(p + 2) - bytes == 2 * sizeof(int)) {
+        res += *(int*)((char*)p + 2 * sizeof(int));
+    }
+    return res;
+}
@@ -0,0 +1,6 @@
+int main(){
+    int x = 300;
+    char c = (char)x;
+    int y = (char)(x - 100);
+    return c + (y == -56) + (char)556;
+}
@@ -0,0 +1 @@
+7
\ No newline at end of file
@@ -0,0 +1 @@
+12
\ No newline at end of file
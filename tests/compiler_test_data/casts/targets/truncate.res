@@ -0,0 +1 @@
+89
\ No newline at end of file
@@ -0,0 +1,29 @@
+struct Inner{
+    int a;
+    int arr[3];
+};
+
+struct Middle{
+    int x;
+    struct Inner in;
+    struct Inner ins[2];
+};
+
+struct Outer{
+    struct Middle m;
+    int y;
+};
+
+int main(){
+    struct Outer o;
+    o.y = 7;
+    o.m.x = 1;
+    o.m.in.a = 2;
+    o.m.in.arr[2] = 3;
+    o.m.ins[1].a = 4;
+    o.m.ins[1].arr[0] = 5;
+    struct Outer* p = &o;
+    p->m.in.arr[1] = 6;
+    struct Middle* pm = &o.m;
+    return sizeof(struct Outer) * 1000 + o.m.in.arr[2] * 100 + o.m.ins[1].arr[0] * 10 + pm->in.arr[1] + o.y + pm->ins[1].a;
+}
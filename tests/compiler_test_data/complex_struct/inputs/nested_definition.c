@@ -0,0 +1,25 @@
+typedef struct {
+    int v[2];
+} Pair;
+
+struct Outer{
+    int y;
+    struct Inner{
+        int a;
+        int b;
+    } in;
+    Pair pair;
+    struct Outer* next;
+};
+
+int main(){
+    struct Outer o;
+    struct Outer o2;
+    o.next = &o2;
+    o2.in.b = 3;
+    o.pair.v[1] = 4;
+    struct Inner i;
+    i.b = 5;
+    o.next->pair.v[0] = 6;
+    return sizeof(struct Outer) * 1000 + o.next->in.b * 100 + o.pair.v[1] * 10 + i.b + o2.pair.v[0];
+}
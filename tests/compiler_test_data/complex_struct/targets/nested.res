@@ -0,0 +1 @@
+14367
\ No newline at end of file
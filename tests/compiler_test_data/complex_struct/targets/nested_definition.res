@@ -0,0 +1 @@
+6351
\ No newline at end of file
@@ -0,0 +1,5 @@
+int main(){
+    int x = 4;
+    int y = 2 * 3 + x;
+    return y + (10 - 4) / 2 * (1 << 2) - -5 % 3;
+}
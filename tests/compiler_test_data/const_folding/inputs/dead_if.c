@@ -0,0 +1,15 @@
+int main(){
+    int x = 1;
+    if (2 > 3) {
+        x = 100;
+    } else {
+        x = x + 5;
+    }
+    if (!0 && 'a' == 97) {
+        x = x * 2;
+    }
+    if (0) {
+        x = 0;
+    }
+    return x;
+}
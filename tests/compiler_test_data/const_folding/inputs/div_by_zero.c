@@ -0,0 +1,7 @@
+int main(){
+    int x = 3;
+    if (0) {
+        x = 1 / 0;
+    }
+    return x;
+}
@@ -0,0 +1,5 @@
+int main(){
+    // folded to a MOV of -2147483648
+    int m = -2147483647 - 1;
+    return (m < -2147483647) + (m + 1 == -2147483647);
+}
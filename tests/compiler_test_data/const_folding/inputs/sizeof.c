@@ -0,0 +1,9 @@
+struct A{
+    int x;
+    int arr[3];
+};
+
+int main(){
+    int n = sizeof(struct A) * 2 + (sizeof(char) ? 1 : 2);
+    return n;
+}
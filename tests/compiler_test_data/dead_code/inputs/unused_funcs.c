@@ -0,0 +1,23 @@
+int unused_helper(int x){
+    return x * 1000;
+}
+
+int unused(int x){
+    return unused_helper(x) + 1;
+}
+
+int square(int x){
+    return x * x;
+}
+
+int apply(int (*f)(int), int x){
+    return f(x);
+}
+
+int add_one(int x){
+    return x + 1;
+}
+
+int main(){
+    return apply(square, 3) + add_one(2);
+}
@@ -0,0 +1,11 @@
+#include <libc.h>
+
+// reads the file written by read_write.c, used by test_files_persist_between_programs
+int main(){
+    char buf[20];
+    int fd = open("notes.txt", 0);
+    if(fd == -1){
+        return -1;
+    }
+    return read(fd, buf, 20);
+}
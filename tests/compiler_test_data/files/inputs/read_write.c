@@ -0,0 +1,14 @@
+#include <libc.h>
+
+int main(){
+    int fd = open("notes.txt", 66);
+    write(fd, "hello files", 11);
+    close(fd);
+
+    char buf[20];
+    fd = open("notes.txt", 0);
+    int count = read(fd, buf, 20);
+    close(fd);
+    write(1, buf, count);
+    return count;
+}
@@ -0,0 +1,10 @@
+#include <libc.h>
+
+int main(){
+    int fd = open("tmp", 65);
+    close(fd);
+    int missing = open("other", 0);
+    int removed = unlink("tmp");
+    int removed_again = unlink("tmp");
+    return (fd == 3) + (missing == -1) + (removed == 0) + (removed_again == -1) + (open("tmp", 0) == -1) + (close(fd) == -1);
+}
@@ -0,0 +1,6 @@
+int main(){
+    float x = 1.5;
+    float y = x * 4 - 0.5;
+    y /= 2;
+    return y * 10;
+}
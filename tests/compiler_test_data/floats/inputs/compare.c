@@ -0,0 +1,11 @@
+int main(){
+    float a = 0.1;
+    float b = -2.5e-1f;
+    int res = 0;
+    if (a > b) res += 1;
+    if (b <= -0.25) res += 2;
+    if (a == 0.1f) res += 4;
+    if (!(a - 0.1)) res += 8;
+    if (-b >= a) res += 16;
+    return res;
+}
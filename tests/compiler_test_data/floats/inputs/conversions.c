@@ -0,0 +1,15 @@
+float half(float x){
+    return x / 2;
+}
+
+int floor_div(int a, int b){
+    return (float)a / b;
+}
+
+int main(){
+    int i = 7.9;
+    float f = i;
+    i *= 1.5;
+    char c = half(9);
+    return i + c + (int)(f * 2) + floor_div(7, 2) + (i > 5 ? 0.5 : 1) * 2;
+}
@@ -0,0 +1,12 @@
+#include <libc.h>
+
+int main(){
+    puts(ftos(1.5));
+    putc('\n');
+    puts(ftos(-0.125));
+    putc('\n');
+    puts(ftos(3));
+    putc('\n');
+    puts(ftos(2.9999999));
+    return 0;
+}
@@ -0,0 +1,18 @@
+int main(){
+    float zero = 0.0;
+    float nan = zero / zero;
+    float x = 1.5;
+    int res = 0;
+    // only != is true for NaN
+    if (nan < x) res += 1;
+    if (nan > x) res += 2;
+    if (nan <= x) res += 4;
+    if (nan >= x) res += 8;
+    if (nan == nan) res += 16;
+    if (x < nan || x > nan || x <= nan || x >= nan) res += 32;
+    if (nan != nan) res += 64;
+    // NaN isn't zero, so it's true
+    if (nan) res += 128;
+    if (x >= x && x <= x && !(x > x) && !(x < x)) res += 256;
+    return res;
+}
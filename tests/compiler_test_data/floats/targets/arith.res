@@ -0,0 +1 @@
+27
\ No newline at end of file
@@ -0,0 +1 @@
+32
\ No newline at end of file
@@ -0,0 +1 @@
+448
\ No newline at end of file
@@ -0,0 +1,23 @@
+int square(int x){
+    return x * x;
+}
+
+int negate(int x){
+    return -x;
+}
+
+int sum_map(int* arr, int size, int (*f)(int)){
+    int sum = 0;
+    for(int i = 0; i < size; i++){
+        sum += f(*(arr + i));
+    }
+    return sum;
+}
+
+int main(){
+    int arr[3];
+    arr[0] = 1;
+    arr[1] = 2;
+    arr[2] = 3;
+    return sum_map(arr, 3, square) * 100 + sum_map(arr, 3, negate);
+}
@@ -0,0 +1,14 @@
+int add(int a, int b){
+    return a + b;
+}
+
+int sub(int a, int b){
+    return a - b;
+}
+
+int main(){
+    int (*op)(int, int) = add;
+    int res = op(5, 3);
+    op = &sub;
+    return res * 10 + (*op)(5, 3);
+}
@@ -0,0 +1,30 @@
+#include <libc.h>
+
+void print_a(){
+    putchar('a');
+}
+
+void print_b(){
+    putchar('b');
+}
+
+struct handler{
+    int key;
+    void (*func)(void);
+};
+
+int main(){
+    void (*funcs[2])(void);
+    funcs[0] = print_a;
+    funcs[1] = print_b;
+    for(int i = 0; i < 2; i++){
+        void (*f)(void) = funcs[i];
+        f();
+    }
+    struct handler h;
+    h.key = 3;
+    h.func = print_b;
+    void (*g)() = h.func;
+    g();
+    return h.key;
+}
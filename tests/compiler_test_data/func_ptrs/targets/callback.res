@@ -0,0 +1 @@
+1394
\ No newline at end of file
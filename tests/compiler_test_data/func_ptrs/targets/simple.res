@@ -0,0 +1 @@
+82
\ No newline at end of file
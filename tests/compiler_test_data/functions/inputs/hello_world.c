@@ -1,5 +1,4 @@
-int putchar(int c){
-}
+#include <libc.h>
 
 int main() {
     putchar(72);
@@ -15,6 +14,5 @@ int main() {
     putchar(108);
     putchar(100);
     putchar(33);
-    putchar(10);
     return 0;
-}
\ No newline at end of file
+}
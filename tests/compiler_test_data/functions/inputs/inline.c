@@ -0,0 +1,27 @@
+int square(int x){
+    return x * x;
+}
+
+int max(int a, int b){
+    return a > b ? a : b;
+}
+
+int is_digit(int c){
+    return c >= '0' && c <= '9';
+}
+
+int counter;
+
+int next(){
+    counter++;
+    return counter;
+}
+
+int main(){
+    int i = 3;
+    int res = square(i) + square(i + 1);
+    res += max(i++, 2);
+    res += max(i, 10);
+    res += is_digit('7') + is_digit(next() + '0' + 9);
+    return res * 10 + i;
+}
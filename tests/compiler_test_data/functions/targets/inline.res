@@ -0,0 +1 @@
+394
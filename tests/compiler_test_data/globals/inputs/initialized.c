@@ -0,0 +1,21 @@
+int answer = 6 * 7;
+int negative = -3;
+int min = -2147483647 - 1;
+char c = 'a' + 1;
+int primes[5] = {2, 3, 5};
+int counter;
+
+int sum(int *arr, int n){
+    int s = 0;
+    for(int i = 0; i < n; i++){
+        s += arr[i];
+    }
+    return s;
+}
+
+int main(){
+    counter += 1;
+    primes[3] = 7;
+    // the rest of primes is zero
+    return answer + negative + (min < -2147483647) + (c == 'b') + sum(primes, 5) + counter;
+}
@@ -0,0 +1 @@
+59
\ No newline at end of file
@@ -0,0 +1,58 @@
+// a threaded code interpreter: each instruction jumps straight to the next instruction's handler
+
+#define PUSH 0
+#define ADD 1
+#define MUL 2
+#define DUP 3
+#define HALT 4
+
+int run(int *program){
+    void *handlers[5] = {&&push, &&add, &&mul, &&dup, &&halt};
+    int stack[16];
+    int sp = 0;
+    int pc = 0;
+    goto *handlers[program[pc]];
+push:
+    stack[sp] = program[pc + 1];
+    sp++;
+    pc += 2;
+    goto *handlers[program[pc]];
+add:
+    sp--;
+    stack[sp - 1] = stack[sp - 1] + stack[sp];
+    pc++;
+    goto *handlers[program[pc]];
+mul:
+    sp--;
+    stack[sp - 1] = stack[sp - 1] * stack[sp];
+    pc++;
+    goto *handlers[program[pc]];
+dup:
+    stack[sp] = stack[sp - 1];
+    sp++;
+    pc++;
+    goto *handlers[program[pc]];
+halt:
+    return stack[sp - 1];
+}
+
+int main(){
+    // (3 + 4) * (3 + 4) + 1
+    int program[10] = {PUSH, 3, PUSH, 4, ADD, DUP, MUL, PUSH, 1, ADD};
+    int with_halt[11];
+    for (int i = 0; i < 10; i++) {
+        with_halt[i] = program[i];
+    }
+    with_halt[10] = HALT;
+    void *target = 0;
+    if (run(with_halt) == 50) {
+        target = &&ok;
+    } else {
+        target = &&fail;
+    }
+    goto *target;
+ok:
+    return 0;
+fail:
+    return 1;
+}
@@ -0,0 +1,11 @@
+int main(){
+    int i = 0;
+    int sum = 0;
+again:
+    sum += i;
+    i++;
+    if(i < 5){
+        goto again;
+    }
+    return sum;
+}
@@ -0,0 +1,31 @@
+int find(int target){
+    for(int i = 0; i < 10; i++){
+        for(int j = 0; j < 10; j++){
+            if(i * j == target){
+                int res = i * 10 + j;
+                goto found;
+            }
+        }
+    }
+    return -1;
+found:
+    // labels are scoped to their function, so other functions can use the same names
+    return 100;
+}
+
+int count(int n){
+    int res = 0;
+found:
+    if(n == 0){
+        goto done;
+    }
+    res++;
+    n--;
+    goto found;
+done:
+    return res;
+}
+
+int main(){
+    return find(12) + find(1000) + count(7);
+}
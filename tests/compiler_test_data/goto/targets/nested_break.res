@@ -0,0 +1 @@
+106
\ No newline at end of file
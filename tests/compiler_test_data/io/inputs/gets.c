@@ -0,0 +1,19 @@
+#include <libc.h>
+#include <string.h>
+
+// prints each input line reversed, returns the number of lines
+int main(){
+    char line[50];
+    int lines = 0;
+    while(gets(line) != 0){
+        if(lines > 0){
+            putchar(',');
+        }
+        int len = strlen(line);
+        for(int i = len - 1; i >= 0; i--){
+            putchar(line[i]);
+        }
+        lines++;
+    }
+    return lines;
+}
@@ -0,0 +1,6 @@
+#include <libc.h>
+
+int main(){
+    printf("%d %d", -2147483647 - 1, 2147483647);
+    return 0;
+}
@@ -0,0 +1,9 @@
+#include <libc.h>
+
+int main(){
+    printf("num: %d", -42);
+    printf(", char: %c", 'x');
+    printf(", str: %s", "abc");
+    printf(", percent: 100%%", 0);
+    return printf("! done", 0);
+}
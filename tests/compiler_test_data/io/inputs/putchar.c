@@ -0,0 +1,6 @@
+#include <libc.h>
+
+int main(){
+    putchar('o');
+    return putchar('k');
+}
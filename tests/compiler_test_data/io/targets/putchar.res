@@ -0,0 +1 @@
+107
\ No newline at end of file
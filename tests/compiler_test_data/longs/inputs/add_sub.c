@@ -0,0 +1,14 @@
+int main(){
+    long a = 4294967295;
+    long b = a + 1;
+    long c = b - 2;
+    long d = -b;
+    int res = 0;
+    if (b == 4294967296L) res += 1;
+    if (c == a - 1) res += 2;
+    if (d + b == 0) res += 4;
+    // the low word
+    if ((int)b == 0) res += 8;
+    if ((int)c == -2) res += 16;
+    return res;
+}
@@ -0,0 +1,41 @@
+struct Acc {
+    int id;
+    long total;
+};
+
+long add(long x, int y){
+    return x + y;
+}
+
+long twice(long x){
+    return x + x;
+}
+
+int main(){
+    long arr[3] = {1, 4294967296, -1};
+    struct Acc acc;
+    acc.id = 7;
+    acc.total = 0;
+    for (int i = 0; i < 3; i++) {
+        acc.total += arr[i];
+    }
+    long l = 4294967295;
+    l++;
+    long before = l--;
+    l -= before;
+    l = add(l, 3);
+    long t = twice(2147483648);
+    long m = t & 4294967295L;
+    m |= 5;
+    m ^= 1;
+    long cond = m ? t : 1;
+    int res = 0;
+    if (acc.total == 4294967296) res += 1;
+    if (before == 4294967296) res += 2;
+    if (l == 2) res += 4;
+    if (t == 4294967296) res += 8;
+    if (m == 4 && ~m == -5) res += 16;
+    if (cond == t && acc.id == 7) res += 32;
+    if (sizeof(long) == 2 * sizeof(int)) res += 64;
+    return res;
+}
@@ -0,0 +1,16 @@
+int main(){
+    long big = 5000000000;
+    long neg = -5000000000;
+    long small = 1;
+    int res = 0;
+    if (neg < small) res += 1;
+    if (big > small) res += 2;
+    if (small < 4294967296) res += 4;
+    if (!(big <= neg)) res += 8;
+    if (big >= big && neg <= neg) res += 16;
+    // the low words are compared as unsigned words
+    if (-1L < 0 && 4294967295L > 0) res += 32;
+    // the low word of 5000000000
+    if (big != 705032704) res += 64;
+    return res;
+}
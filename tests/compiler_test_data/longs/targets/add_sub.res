@@ -0,0 +1 @@
+31
\ No newline at end of file
@@ -0,0 +1,13 @@
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+#define MIN(a, b) ((a) < (b) ? (a) : (b))
+#define CLAMP(x, lo, hi) MIN(MAX(x, lo), hi)
+#define SQUARE(x) ((x) * (x))
+#define LARGER MAX
+
+int main() {
+    int a = CLAMP(-3, 0, 10);
+    int b = CLAMP(20, 0, 10);
+    int c = SQUARE(1 + 2);
+    int d = LARGER(1, 2);
+    return a + b + c + d;
+}
@@ -0,0 +1,10 @@
+#define SIZE 5
+#define LAST (SIZE - 1)
+
+int main() {
+    int arr[SIZE];
+    for (int i = 0; i < SIZE; i++) {
+        arr[i] = i * 2;
+    }
+    return arr[LAST];
+}
@@ -0,0 +1,14 @@
+#define VALUE 3
+#define SWAP(a, b) { \
+    int tmp = a; \
+    a = b; \
+    b = tmp; \
+}
+
+int main() {
+    int x = VALUE;
+#undef VALUE
+    int VALUE = 4;
+    SWAP(x, VALUE);
+    return x * 10 + VALUE;
+}
@@ -0,0 +1 @@
+21
\ No newline at end of file
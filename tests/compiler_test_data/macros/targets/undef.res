@@ -0,0 +1 @@
+43
\ No newline at end of file
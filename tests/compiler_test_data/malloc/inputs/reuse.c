@@ -0,0 +1,8 @@
+#include <libc.h>
+
+int main(){
+    int* p1 = malloc(10);
+    free(p1);
+    int* p2 = malloc(5);
+    return p1 == p2;
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+struct Point{
+    int x;
+    int y;
+};
+
+int main(){
+    struct Point points[4];
+    for(int i = 0; i < 4; i++){
+        points[i].x = i;
+        points[i].y = i * 10;
+    }
+    struct Point* p = points;
+    struct Point* q = p + 3;
+    int ok = q->y == 30;
+    struct Point* t = q - 1;
+    ok += t->x == 2;
+    ok += 1 + p == &points[1];
+    ok += q - p == 3;
+    p += 2;
+    ok += p->y == 20;
+    p -= 1;
+    ok += p == points + 1;
+    int arr[3];
+    arr[2] = 5;
+    int* r = arr + 2;
+    ok += r - arr == 2 && *r == 5;
+    return ok;
+}
@@ -0,0 +1,20 @@
+int sum(int* items, int count){
+    int total = 0;
+    for(int i = 0; i < count; i++){
+        total += items[i];
+    }
+    return total;
+}
+
+int main(){
+    int arr[4];
+    int* p = arr;
+    for(int i = 0; i < 4; i++){
+        p[i] = i + 1;
+    }
+    char* str = "abc";
+    long longs[2];
+    long* l = longs;
+    l[1] = 5;
+    return sum(arr, 4) * 1000 + (str[2] - 'a') * 100 + longs[1];
+}
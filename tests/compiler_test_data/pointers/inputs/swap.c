@@ -4,7 +4,7 @@ void swap(int* x, int* y){
     *y = tmp;
 }
 
-void main(){
+int main(){
     int x = 1;
     int y = 2;
     swap(&x, &y);
@@ -0,0 +1 @@
+10205
\ No newline at end of file
@@ -0,0 +1,29 @@
+struct A{
+    int x;
+    int arr[4];
+};
+
+int main(){
+    int i = 0;
+    char c;
+    struct A a;
+    struct A* p = &a;
+    struct A as[3];
+    int grid[2][5];
+    int ok = sizeof(i) == 1;
+    ok += sizeof(c) == 1;
+    ok += sizeof(a) == 5;
+    ok += sizeof(p) == 1;
+    ok += sizeof(*p) == 5;
+    ok += sizeof(p[0]) == 5;
+    ok += sizeof(a.arr) == 4;
+    ok += sizeof(p->x) == 1;
+    ok += sizeof(as) == 15;
+    ok += sizeof(as[1]) == 5;
+    ok += sizeof(grid) == 10;
+    ok += sizeof(grid[1]) == 5;
+    ok += sizeof(grid[1][2]) == 1;
+    // the operand of sizeof is not evaluated
+    ok += sizeof(i++) == 1 && i == 0;
+    return ok;
+}
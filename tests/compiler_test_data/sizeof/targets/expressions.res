@@ -0,0 +1 @@
+14
\ No newline at end of file
@@ -0,0 +1,19 @@
+#include <string.h>
+
+int main(){
+    char s[] = "hello";
+    char empty[] = "";
+    int nums[] = {4, 5, 6};
+    int rows[][2] = {{1, 2}, {3}, 4};
+    char names[][4] = {"ab", "cd", "ef"};
+    int res = 0;
+    res += sizeof(s) == 6 * sizeof(char) && strlen(s) == 5;
+    res += (sizeof(empty) == sizeof(char) && empty[0] == 0) * 2;
+    res += (sizeof(nums) == 3 * sizeof(int) && nums[2] == 6) * 4;
+    res += (sizeof(rows) == 6 * sizeof(int) && rows[2][0] == 4 && rows[1][1] == 0) * 8;
+    res += (sizeof(names) == 12 * sizeof(char) && strcmp(&names[2][0], "ef") == 0) * 16;
+    // the array holds a copy of the string
+    s[0] = 'j';
+    res += (strcmp(s, "jello") == 0) * 32;
+    return res;
+}
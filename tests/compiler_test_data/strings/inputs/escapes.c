@@ -0,0 +1,15 @@
+#include <libc.h>
+#include <string.h>
+
+int main(){
+    char* quoted = "say \"hi\" \\o/";
+    char* empty = "";
+    puts(quoted);
+    putc(' ');
+    putc('\x41');
+    putc('\'');
+    putc(' ');
+    puts("two  spaces\tand a tab");
+    // 0, 1 and 2
+    return strlen(empty) * 100 + strlen("a\0b") * 10 + strlen("\r\n");
+}
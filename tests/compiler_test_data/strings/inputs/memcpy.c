@@ -0,0 +1,11 @@
+#include <string.h>
+
+int main(){
+    int src[3];
+    int dst[3];
+    src[0] = 4;
+    src[1] = 5;
+    src[2] = 6;
+    memcpy(dst, src, 3);
+    return dst[0] * 100 + dst[1] * 10 + dst[2];
+}
@@ -0,0 +1,5 @@
+#include <string.h>
+
+int main(){
+    return (strcmp("abc", "abc") == 0) + (strcmp("abc", "abd") < 0) * 10 + (strcmp("b", "abc") > 0) * 100 + (strcmp("ab", "abc") < 0) * 1000;
+}
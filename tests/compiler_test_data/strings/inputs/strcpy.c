@@ -0,0 +1,9 @@
+#include <libc.h>
+#include <string.h>
+
+int main(){
+    char buf[10];
+    char* res = strcpy(buf, "copy");
+    puts(buf);
+    return res == buf && buf[4] == 0;
+}
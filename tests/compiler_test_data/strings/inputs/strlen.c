@@ -0,0 +1,6 @@
+#include <string.h>
+
+int main(){
+    char* s = "hello";
+    return strlen(s) * 10 + strlen("a");
+}
@@ -0,0 +1 @@
+63
\ No newline at end of file
@@ -0,0 +1 @@
+456
\ No newline at end of file
@@ -0,0 +1 @@
+1111
\ No newline at end of file
@@ -0,0 +1 @@
+51
\ No newline at end of file
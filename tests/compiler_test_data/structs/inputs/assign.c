@@ -0,0 +1,22 @@
+struct Point{
+    int x;
+    int y;
+    int z;
+};
+
+struct Point g;
+
+int main(){
+    struct Point a;
+    a.x = 1;
+    a.y = 2;
+    a.z = 3;
+    struct Point b = a;
+    a.x = 9;
+    g = b;
+    struct Point arr[2];
+    arr[1] = g;
+    struct Point* p = &arr[0];
+    *p = a;
+    return b.x * 1000 + arr[1].z * 100 + p->x * 10 + arr[0].y;
+}
@@ -0,0 +1,18 @@
+struct Pair{
+    int first;
+    int second;
+};
+
+int sum(int scale, struct Pair p, int offset){
+    p.first = p.first * scale;
+    return p.first + p.second + offset;
+}
+
+int main(){
+    struct Pair a;
+    a.first = 5;
+    a.second = 7;
+    int res = sum(10, a, 100);
+    // the callee got a copy
+    return res + a.first;
+}
@@ -0,0 +1,35 @@
+struct Vec{
+    int x;
+    int y;
+    int z;
+};
+
+struct Vec make(int x, int y, int z){
+    struct Vec v;
+    v.x = x;
+    v.y = y;
+    v.z = z;
+    return v;
+}
+
+struct Vec add(struct Vec a, struct Vec b){
+    struct Vec res;
+    res.x = a.x + b.x;
+    res.y = a.y + b.y;
+    res.z = a.z + b.z;
+    return res;
+}
+
+struct Vec scaled(int factor){
+    // returning the result of another call
+    return make(factor, 2 * factor, 3 * factor);
+}
+
+int main(){
+    struct Vec a = make(1, 2, 3);
+    struct Vec b;
+    b = scaled(10);
+    struct Vec c = add(a, b);
+    make(7, 8, 9);
+    return c.x * 10000 + c.y * 100 + c.z;
+}
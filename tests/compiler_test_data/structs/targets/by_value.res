@@ -0,0 +1 @@
+162
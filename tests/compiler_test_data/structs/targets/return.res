@@ -0,0 +1 @@
+112233
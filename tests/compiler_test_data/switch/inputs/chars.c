@@ -0,0 +1,15 @@
+int value(char c){
+    switch(c){
+        case 'a':
+            return 1;
+        case 'b':
+            return 2;
+        case -1:
+            return 3;
+    }
+    return 0;
+}
+
+int main(){
+    return value('a') + value('b') * 10 + value(-1) * 100 + value('z') * 1000;
+}
@@ -0,0 +1,15 @@
+int classify(int x){
+    switch(x){
+        case 0:
+            return 1;
+        default:
+            return 2;
+        case 5:
+            return 3;
+    }
+    return 4;
+}
+
+int main(){
+    return classify(0) * 100 + classify(7) * 10 + classify(5);
+}
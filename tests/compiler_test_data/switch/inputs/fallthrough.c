@@ -0,0 +1,20 @@
+int count(int x){
+    int res = 0;
+    switch(x){
+        case 1:
+            res += 1;
+        case 2:
+        case 3:
+            res += 10;
+        case 4:
+            res += 100;
+            break;
+        case 5:
+            res += 1000;
+    }
+    return res;
+}
+
+int main(){
+    return count(1) == 111 && count(2) == 110 && count(3) == 110 && count(4) == 100 && count(5) == 1000 && count(6) == 0;
+}
@@ -0,0 +1,69 @@
+// dense cases are dispatched through a jump table, sparse ones with comparisons
+
+int days_in_month(int month){
+    switch(month){
+        case 2:
+            return 28;
+        case 4:
+        case 6:
+        case 9:
+        case 11:
+            return 30;
+        case 1:
+        case 3:
+        case 5:
+        case 7:
+        case 8:
+        case 10:
+        case 12:
+            return 31;
+    }
+    return -1;
+}
+
+int offset(int x){
+    int res = 0;
+    switch(x){
+        case -2:
+            res += 1;
+        case -1:
+            res += 10;
+            break;
+        case 1:
+            res += 100;
+            break;
+        case 2:
+            res += 1000;
+            break;
+        default:
+            res = -5;
+    }
+    return res;
+}
+
+int sparse(int x){
+    switch(x){
+        case 1: return 1;
+        case 100: return 2;
+        case 10000: return 3;
+        case 1000000: return 4;
+        default: return 0;
+    }
+}
+
+int main(){
+    int days = 0;
+    for(int month = 0; month <= 13; month++){
+        days += days_in_month(month);
+    }
+    if (days != 365 - 2) {
+        return 1;
+    }
+    if (offset(-3) != -5 || offset(-2) != 11 || offset(-1) != 10 || offset(0) != -5 || offset(2) != 1000 || offset(3) != -5) {
+        return 2;
+    }
+    if (sparse(100) != 2 || sparse(1000000) != 4 || sparse(5) != 0) {
+        return 3;
+    }
+    return 0;
+}
@@ -0,0 +1,18 @@
+int main(){
+    int sum = 0;
+    for(int i = 0; i < 10; i++){
+        switch(i % 3){
+            case 0:
+                continue;
+            case 1:
+                sum += i;
+                break;
+            default: {
+                int doubled = i * 2;
+                sum += doubled;
+            }
+        }
+        sum += 100;
+    }
+    return sum;
+}
@@ -0,0 +1 @@
+321
\ No newline at end of file
@@ -0,0 +1 @@
+123
\ No newline at end of file
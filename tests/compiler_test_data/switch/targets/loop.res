@@ -0,0 +1 @@
+642
\ No newline at end of file
@@ -0,0 +1 @@
+20
\ No newline at end of file
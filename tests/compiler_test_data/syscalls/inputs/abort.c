@@ -0,0 +1,10 @@
+int divide(int a, int b){
+    if(b == 0){
+        abort();
+    }
+    return a / b;
+}
+
+int main(){
+    return divide(6, 3) + divide(1, 0);
+}
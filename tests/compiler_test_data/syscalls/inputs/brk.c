@@ -0,0 +1,10 @@
+#include <libc.h>
+
+int main(){
+    int* start = sbrk(0);
+    int* p = sbrk(10);
+    *p = 5;
+    int* end = sbrk(0);
+    int* m = malloc(1);
+    return (p == start) + (end - start == 10) + (m >= end) + (sbrk(5000) == -1) + (sbrk(-20) == -1);
+}
@@ -0,0 +1,20 @@
+#include <libc.h>
+
+int sum(int n){
+    int total = 0;
+    for(int i = 0; i < n; i++){
+        total += i;
+    }
+    return total;
+}
+
+// returns 1 if the longer loop takes more cycles
+int main(){
+    int start = clock();
+    sum(10);
+    int short_loop = clock() - start;
+    start = clock();
+    sum(100);
+    int long_loop = clock() - start;
+    return short_loop > 0 && long_loop > short_loop;
+}
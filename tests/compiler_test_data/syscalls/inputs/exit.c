@@ -0,0 +1,11 @@
+#include <libc.h>
+
+int foo(){
+    exit(3);
+    return 1;
+}
+
+int main(){
+    foo();
+    return 2;
+}
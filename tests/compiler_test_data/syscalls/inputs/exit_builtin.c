@@ -0,0 +1,13 @@
+// exit is a builtin, so it works without including libc.h
+void check(float x){
+    if(x > 2){
+        exit(x * 2);
+    }
+}
+
+int main(){
+    for(int i = 0; i < 10; i++){
+        check(i);
+    }
+    return 1;
+}
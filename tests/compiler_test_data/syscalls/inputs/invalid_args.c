@@ -0,0 +1,13 @@
+#include <libc.h>
+
+int main(){
+    int n = 0;
+    // syscalls fail rather than crash on an unknown number or an invalid address
+    if(__syscall(99, 0) == -1) n += 1;
+    if(write(1, (char*)50000, 3) == -1) n += 2;
+    // the code is read only
+    if(read(0, (char*)main, 1) == -1) n += 4;
+    if(open((char*)50000, 0) == -1) n += 8;
+    if(unlink((char*)-1) == -1) n += 16;
+    return n;
+}
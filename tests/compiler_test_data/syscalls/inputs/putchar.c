@@ -0,0 +1,5 @@
+int main(){
+    __syscall(2, 'h');
+    __syscall(2, 'i');
+    return __syscall(2, '!') == '!';
+}
@@ -0,0 +1,25 @@
+#include <libc.h>
+
+// sorts random numbers, returns 1 if they're sorted & in range
+int main(){
+    int nums[50];
+    for(int i = 0; i < 50; i++){
+        nums[i] = rand() % 1000;
+    }
+    for(int i = 0; i < 50; i++){
+        for(int j = 0; j < 49 - i; j++){
+            if(nums[j] > nums[j + 1]){
+                int tmp = nums[j];
+                nums[j] = nums[j + 1];
+                nums[j + 1] = tmp;
+            }
+        }
+    }
+    for(int i = 0; i < 49; i++){
+        if(nums[i] > nums[i + 1] || nums[i] < 0){
+            return 0;
+        }
+    }
+    // the numbers aren't all the same
+    return nums[0] < nums[49];
+}
@@ -0,0 +1 @@
+134
\ No newline at end of file
@@ -0,0 +1,13 @@
+typedef int word;
+typedef word* word_ptr;
+
+word twice(word x){
+    return 2 * x;
+}
+
+int main(){
+    word a = 5;
+    word_ptr p = &a;
+    *p = twice(*p);
+    return a + sizeof(word);
+}
@@ -0,0 +1,29 @@
+typedef struct {
+    int x;
+    int y;
+} Point;
+
+typedef struct Node {
+    int val;
+    struct Node* next;
+} Node;
+
+typedef Point* PointPtr;
+
+int sum(Point p){
+    return p.x + p.y;
+}
+
+int main(){
+    Point p;
+    p.x = 3;
+    p.y = 4;
+    PointPtr pp = &p;
+    pp->y = 40;
+    Node second;
+    second.val = 200;
+    Node first;
+    first.val = 100;
+    first.next = &second;
+    return sum(p) + first.next->val + sizeof(Point) * 1000;
+}
@@ -0,0 +1,19 @@
+int main(){
+    long big = 0x100000000;
+    int hex = 0x1f;
+    int upper_hex = 0XFF;
+    int octal = 017;
+    int zero = 0;
+    int unsigned_int = 10u;
+    long suffixed = 0x10L;
+    long both = 7ul;
+    int n = 0;
+    if(hex == 31) n += 1;
+    if(upper_hex == 255) n += 2;
+    if(octal == 15) n += 4;
+    if(zero == 0 && 00 == 0) n += 8;
+    if(unsigned_int == 10) n += 16;
+    if(suffixed == 16 && both == 7 && sizeof(suffixed) == sizeof(long)) n += 32;
+    if(big == 4294967296L && big > 0xffffffff) n += 64;
+    return n;
+}
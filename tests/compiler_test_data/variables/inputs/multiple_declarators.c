@@ -0,0 +1,7 @@
+int main(){
+    int a = 1, b, *c = &b;
+    char d = 'x', e[3];
+    *c = 2;
+    e[2] = d;
+    return a + b + (e[2] == 'x');
+}
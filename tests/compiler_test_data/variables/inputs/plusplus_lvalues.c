@@ -0,0 +1,30 @@
+struct Point{
+    int x;
+    int y;
+};
+
+int main(){
+    int a[3];
+    a[0] = 5;
+    a[0]++;
+    ++a[0];
+    struct Point s;
+    s.x = 1;
+    s.x++;
+    struct Point* p = &s;
+    p->y = 10;
+    p->y--;
+    int* q = a;
+    (*q)++;
+    // a pointer lvalue moves by the size of the pointed type
+    struct Point points[3];
+    struct Point* cur = points;
+    cur++;
+    int n = 0;
+    if(a[0] == 8) n += 1;
+    if(s.x == 2) n += 2;
+    if(s.y == 9) n += 4;
+    if(p->y-- == 9 && s.y == 8) n += 8;
+    if(cur == &points[1]) n += 16;
+    return n;
+}
@@ -0,0 +1 @@
+127
\ No newline at end of file
@@ -0,0 +1,35 @@
+#include <stdarg.h>
+
+struct Point {
+    int x;
+    int y;
+};
+
+// the types of the args are given by the chars of types: c char, l long, f float, p struct Point
+int weigh(char first, char* types, ...){
+    va_list args;
+    va_start(args, types);
+    int total = first;
+    for(; *types != 0; types++){
+        if(*types == 'c'){
+            total += va_arg(args, char);
+        } else if(*types == 'l'){
+            long l = va_arg(args, long);
+            total += l == 4294967296 ? 1000 : 0;
+        } else if(*types == 'f'){
+            total += (int)(va_arg(args, float) * 10);
+        } else if(*types == 'p'){
+            struct Point p = va_arg(args, struct Point);
+            total += p.x - p.y;
+        }
+    }
+    va_end(args);
+    return total;
+}
+
+int main(){
+    struct Point p;
+    p.x = 50;
+    p.y = 8;
+    return weigh(1, "clfpc", 'a', 4294967296, 1.5, p, -2);
+}
@@ -0,0 +1,9 @@
+#include <libc.h>
+
+int main(){
+    char* name = "vm";
+    printf("%s has %d regs & %c%c%c\n", name, 8, 'S', 'P', '!');
+    printf("hex: %x %x %x\n", 255, 0, -1);
+    printf("float: %f, %d%%\n", 2.5, 100);
+    return printf("%i%s", -7, "");
+}
@@ -0,0 +1,17 @@
+#include <stdarg.h>
+
+// sums count ints
+int sum(int count, ...){
+    va_list args;
+    va_start(args, count);
+    int total = 0;
+    for(int i = 0; i < count; i++){
+        total += va_arg(args, int);
+    }
+    va_end(args);
+    return total;
+}
+
+int main(){
+    return sum(0) + sum(1, 5) + sum(4, 10, 20, 30, -3);
+}
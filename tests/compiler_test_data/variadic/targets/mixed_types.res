@@ -0,0 +1 @@
+1153
\ No newline at end of file
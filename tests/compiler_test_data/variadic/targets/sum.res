@@ -0,0 +1 @@
+62
\ No newline at end of file
@@ -0,0 +1,75 @@
+extern crate simple_vm;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[cfg(feature = "differential")]
+use simple_vm::test_runner::check_host_all;
+use simple_vm::test_runner::{check_all, coverage_all, find_cases};
+
+// some of the test cases are from: https://github.com/nlsandler/write_a_c_compiler/
+const TESTS_DIR: &str = "tests/compiler_test_data";
+
+/// runs every case of tests/compiler_test_data, see test_runner.rs
+/// BLESS=1 writes the expected results from the actual ones, COMPILER_TESTS=<filter> runs only the matching cases,
+/// COVERAGE=<file.info> also writes an lcov tracefile of the lines the cases executed
+#[test]
+fn test_compiler() {
+    let bless = env::var("BLESS").is_ok_and(|bless| !bless.is_empty() && bless != "0");
+    let filter = env::var("COMPILER_TESTS").ok();
+    let cases = find_cases(Path::new(TESTS_DIR)).unwrap();
+    assert!(!cases.is_empty());
+    let failures = check_all(&cases, bless, filter.as_deref());
+    for (name, err) in failures.iter() {
+        println!("---- {} ----\n{}\n", name, err);
+    }
+    assert!(failures.is_empty(), "{} of {} compiler tests failed: {}",
+        failures.len(), cases.len(), failures.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>().join(", "));
+    if let Ok(path) = env::var("COVERAGE") {
+        let coverage = coverage_all(&cases, filter.as_deref());
+        let (lines, lines_hit) = coverage.lines_hit();
+        println!("the cases executed {} of {} lines", lines_hit, lines);
+        fs::write(&path, coverage.to_lcov()).unwrap();
+    }
+}
+
+// cases whose results differ on the host because of the vm's design, rather than a bug in its code generation
+#[cfg(feature = "differential")]
+const HOST_DIVERGENCES: &[&str] = &[
+    // sizes are in words on the vm, e.g sizeof(int) == 1
+    "complex_struct/nested", "complex_struct/nested_definition", "const_folding/sizeof", "pointer_arith/1",
+    "pointer_arith/2", "pointer_arith/3", "sizeof/1", "sizeof/expressions", "strings/memcpy", "typedef/basic",
+    "typedef/structs",
+    // va_arg of char & float, the host promotes them to int & double
+    "variadic/mixed_types",
+    // writes out of the array's bounds, which the vm allows
+    "arrays/2",
+    // uses the vm's memory mapped io
+    "io/echo", "io/print",
+    // float arithmetic isn't promoted to double on the vm
+    "floats/compare",
+    // abort kills the host's program with a signal, the vm exits with 134
+    "syscalls/abort",
+    // signed overflow & shifts by 32 or more are undefined in c, the vm wraps around & masks the shift count
+    "arith_expressions/overflow",
+];
+
+/// compares the vm's exit codes with the host's c compiler's, for the cases it can compile, except HOST_DIVERGENCES
+/// needs a host c compiler ($CC, or cc): cargo test --features differential --test compiler_tests
+#[cfg(feature = "differential")]
+#[test]
+fn test_against_host_compiler() {
+    let filter = env::var("COMPILER_TESTS").ok();
+    let cases = find_cases(Path::new(TESTS_DIR)).unwrap();
+    let (divergences, skipped) = check_host_all(&cases, filter.as_deref());
+    let (known, divergences): (Vec<_>, Vec<_>) =
+        divergences.into_iter().partition(|(name, _)| HOST_DIVERGENCES.contains(&name.as_str()));
+    println!("{} cases diverge as expected: {}", known.len(), known.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>().join(", "));
+    println!("the host can't compile {} cases: {}", skipped.len(), skipped.join(", "));
+    for (name, err) in divergences.iter() {
+        println!("---- {} ----\n{}\n", name, err);
+    }
+    assert!(divergences.is_empty(), "{} compiler tests diverge from the host's compiler: {}",
+        divergences.len(), divergences.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>().join(", "));
+}
@@ -0,0 +1,24 @@
+struct point {
+    int x;
+    int y;
+};
+
+int counter;
+
+int area(struct point p, int scale) {
+    int res = p.x * p.y;
+    return res * scale;
+}
+
+int main() {
+    char c = 'a';
+    struct point p;
+    int arr[3];
+    p.x = 2;
+    p.y = 3;
+    arr[0] = 1;
+    arr[1] = 2;
+    arr[2] = 3;
+    counter = 7;
+    return area(p, arr[2]);
+}
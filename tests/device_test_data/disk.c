@@ -0,0 +1,27 @@
+#include <disk.h>
+
+// writes a pattern to the last block of the disk, which the file system doesn't use yet, & reads it back
+int main(){
+    int buf[DISK_BLOCK_SIZE];
+    int last_block = 1023;
+    for(int i = 0; i < DISK_BLOCK_SIZE; i++){
+        buf[i] = i % 100;
+    }
+    if(disk_write(last_block, buf) != 0){
+        return -1;
+    }
+    for(int i = 0; i < DISK_BLOCK_SIZE; i++){
+        buf[i] = 0;
+    }
+    if(disk_read(last_block, buf) != 0){
+        return -2;
+    }
+    if(disk_read(last_block + 1, buf) != -1){
+        return -3;
+    }
+    int sum = 0;
+    for(int i = 0; i < DISK_BLOCK_SIZE; i++){
+        sum += buf[i];
+    }
+    return sum;
+}
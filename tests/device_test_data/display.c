@@ -0,0 +1,13 @@
+#include <display.h>
+
+int main(){
+    display_clear();
+    display_print(0, 0, "score: 42");
+    for(int column = 0; column < DISPLAY_COLUMNS; column++){
+        display_put(DISPLAY_ROWS - 1, column, '=');
+    }
+    display_put(2, 78, '[');
+    display_put(2, 79, ']');
+    display_put(2, 80, 'x'); // outside the display
+    return 0;
+}
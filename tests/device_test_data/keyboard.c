@@ -0,0 +1,19 @@
+#include <keyboard.h>
+
+int keys[8];
+int num_keys;
+
+void handle_keys(){
+    int key;
+    while((key = read_key()) != 0){
+        keys[num_keys] = key;
+        num_keys++;
+    }
+}
+
+int main(){
+    on_key(handle_keys);
+    while(num_keys < 3){
+    }
+    return keys[0] * 10000 + keys[1] * 100 + keys[2];
+}
@@ -0,0 +1,10 @@
+#include <keyboard.h>
+
+int main(){
+    int sum = 0;
+    int key;
+    while((key = read_key()) != 0){
+        sum = sum * 10 + key - '0';
+    }
+    return sum;
+}
@@ -0,0 +1,8 @@
+int average(int sum, int count){
+    return sum / count;
+}
+
+int main(){
+    int count = 0;
+    return average(10, count);
+}
@@ -0,0 +1,12 @@
+#include <libc.h>
+
+void release(int* p){
+    free(p);
+}
+
+int main(){
+    int* p = malloc(2 * sizeof(int));
+    release(p);
+    release(p);
+    return 0;
+}
@@ -0,0 +1,19 @@
+#include <libc.h>
+
+int* make_array(int size){
+    return malloc(size * sizeof(int));
+}
+
+int main(){
+    int* freed = make_array(2);
+    int* leaked = make_array(3);
+    int* also_leaked = malloc(sizeof(int));
+    free(freed);
+    // a failed allocation isn't a leak
+    if(malloc(-1) != 0){
+        return 1;
+    }
+    // printf allocates & frees the strings of the numbers
+    printf("%d\n", 7);
+    return 0;
+}
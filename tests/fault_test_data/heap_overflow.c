@@ -0,0 +1,12 @@
+#include <libc.h>
+
+int main(){
+    int* squares = malloc(3 * sizeof(int));
+    // writes one int past the end of the block
+    for(int i = 0; i <= 3; i++){
+        squares[i] = i * i;
+    }
+    int sum = squares[0] + squares[1] + squares[2];
+    free(squares);
+    return sum;
+}
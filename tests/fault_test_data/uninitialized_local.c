@@ -0,0 +1,15 @@
+int set(){
+    int secret = 42;
+    return secret;
+}
+
+int get(){
+    // on the stack where set's variable was, but it's a new variable
+    int value;
+    return value;
+}
+
+int main(){
+    set();
+    return get();
+}
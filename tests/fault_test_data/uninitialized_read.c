@@ -0,0 +1,9 @@
+int load(int* p){
+    return *p;
+}
+
+int main(){
+    // the heap is never written before it's allocated
+    int* p = 5000;
+    return load(p) + 1;
+}
@@ -0,0 +1,13 @@
+#include <libc.h>
+
+int* make_counter(){
+    int* counter = malloc(sizeof(int));
+    *counter = 1;
+    return counter;
+}
+
+int main(){
+    int* counter = make_counter();
+    free(counter);
+    return *counter;
+}
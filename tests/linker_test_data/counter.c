@@ -0,0 +1,6 @@
+int counter;
+
+int increment(int amount){
+    counter = counter + amount;
+    return counter;
+}
@@ -0,0 +1,11 @@
+#include <libc.h>
+
+extern int counter;
+int increment(int amount);
+
+int main(){
+    counter = 2;
+    increment(3);
+    putchar('0' + counter);
+    return increment(7);
+}
@@ -0,0 +1,11 @@
+#ifndef CONFIG_H
+#define CONFIG_H
+
+#define VERSION 2
+#ifdef BIG
+#define SIZE 100
+#else
+#define SIZE 10
+#endif
+
+#endif
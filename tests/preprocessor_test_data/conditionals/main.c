@@ -0,0 +1,27 @@
+#include "config.h"
+#include "config.h"
+
+#if VERSION >= 2
+int new_api(int x);
+#  if SIZE > 50
+int big_buffer[SIZE];
+#  elif SIZE > 5
+int buffer[SIZE];
+#  else
+int small_buffer[SIZE];
+#  endif
+#elif VERSION == 1
+int old_api(int x);
+#else
+#error "unknown version"
+#endif
+
+#ifndef PLATFORM
+#define PLATFORM 0
+#endif
+#if 0
+#if UNDEFINED_MACRO(1 / 0)
+int never;
+#endif
+#endif
+int platform = PLATFORM;
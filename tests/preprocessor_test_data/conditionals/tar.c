@@ -0,0 +1,41 @@
+#line 1 "tests/preprocessor_test_data/conditionals/main.c"
+#line 1 "tests/preprocessor_test_data/conditionals/config.h"
+
+
+
+
+
+
+
+
+
+
+
+
+#line 2 "tests/preprocessor_test_data/conditionals/main.c"
+#line 3 "tests/preprocessor_test_data/conditionals/main.c"
+
+
+int new_api(int x);
+
+
+
+int buffer[10];
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+int platform = 0;
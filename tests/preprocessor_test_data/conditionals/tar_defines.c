@@ -0,0 +1,41 @@
+#line 1 "tests/preprocessor_test_data/conditionals/main.c"
+#line 1 "tests/preprocessor_test_data/conditionals/config.h"
+
+
+
+
+
+
+
+
+
+
+
+
+#line 2 "tests/preprocessor_test_data/conditionals/main.c"
+#line 3 "tests/preprocessor_test_data/conditionals/main.c"
+
+
+int new_api(int x);
+
+int big_buffer[100];
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+
+int platform = 3;
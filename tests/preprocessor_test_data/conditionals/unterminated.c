@@ -0,0 +1,4 @@
+#ifdef X
+int x;
+#else
+int y;
@@ -1,4 +1,7 @@
+#line 1 "tests/preprocessor_test_data/include/main1.c"
+#line 1 "tests/preprocessor_test_data/include/a.h"
 int foo(int x);
+#line 2 "tests/preprocessor_test_data/include/main1.c"
 
 int main(){
     int x = 2;
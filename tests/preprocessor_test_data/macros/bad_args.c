@@ -0,0 +1,3 @@
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+
+int x = MAX(1, 2, 3);
@@ -0,0 +1,20 @@
+#include "util.h"
+#define SIZE 10
+#define LAST (SIZE - 1)
+#define SWAP(a, b) { \
+    int tmp = a; \
+    a = b; \
+    b = tmp; \
+}
+
+int arr[SIZE];
+
+int main(){
+    int x = CLAMP(arr[LAST], 0, SIZE);
+    int y = MAX(x, 2);
+    SWAP(x, y);
+    printf("SIZE: %d\n", SIZE); // SIZE
+#undef SIZE
+    int SIZE = 3;
+    return SIZE;
+}
@@ -0,0 +1,26 @@
+#line 1 "tests/preprocessor_test_data/macros/main.c"
+#line 1 "tests/preprocessor_test_data/macros/util.h"
+
+
+
+
+#line 2 "tests/preprocessor_test_data/macros/main.c"
+
+
+
+
+
+
+
+
+int arr[10];
+
+int main(){
+    int x = ((((arr[(10 - 1)]) > (0) ? (arr[(10 - 1)]) : (0))) < (10) ? (((arr[(10 - 1)]) > (0) ? (arr[(10 - 1)]) : (0))) : (10));
+    int y = ((x) > (2) ? (x) : (2));
+    {     int tmp = x;     x = y;     y = tmp; };
+    printf("SIZE: %d\n", 10); // SIZE
+
+    int SIZE = 3;
+    return SIZE;
+}
@@ -0,0 +1,4 @@
+#pragma once
+#include "vec.h"
+
+int dist(struct vec a, struct vec b);
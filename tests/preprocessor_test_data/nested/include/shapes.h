@@ -0,0 +1,4 @@
+#include "vec.h"
+#include "point.h"
+
+int area(struct vec size);
@@ -0,0 +1,10 @@
+// a 2d vector
+#ifndef VEC_H
+#define VEC_H
+
+struct vec {
+    int x;
+    int y;
+};
+
+#endif
@@ -0,0 +1,6 @@
+#include <shapes.h>
+#include "point.h"
+
+int main(){
+    return 0;
+}
@@ -0,0 +1,31 @@
+#line 1 "tests/preprocessor_test_data/nested/main.c"
+#line 1 "tests/preprocessor_test_data/nested/include/shapes.h"
+#line 1 "tests/preprocessor_test_data/nested/include/vec.h"
+// a 2d vector
+
+
+
+struct vec {
+    int x;
+    int y;
+};
+
+
+
+#line 2 "tests/preprocessor_test_data/nested/include/shapes.h"
+#line 1 "tests/preprocessor_test_data/nested/include/point.h"
+
+#line 3 "tests/preprocessor_test_data/nested/include/point.h"
+
+int dist(struct vec a, struct vec b);
+
+#line 3 "tests/preprocessor_test_data/nested/include/shapes.h"
+
+int area(struct vec size);
+
+#line 2 "tests/preprocessor_test_data/nested/main.c"
+#line 3 "tests/preprocessor_test_data/nested/main.c"
+
+int main(){
+    return 0;
+}
@@ -0,0 +1,11 @@
+#include <libc.h>
+
+// argv[0] is the path of the program's image
+int main(int argc, char** argv){
+    char* name = argv[0];
+    int i;
+    for(i = 0; i < 10; i++){
+        putchar(name[0]);
+    }
+    return argc + 6;
+}
@@ -0,0 +1,11 @@
+#include <libc.h>
+
+int counter = 0;
+
+int main(){
+    int i;
+    for(i = 0; i < 100; i++){
+        counter++;
+    }
+    return counter;
+}
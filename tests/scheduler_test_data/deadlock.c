@@ -0,0 +1,8 @@
+#include <libc.h>
+
+// locks a mutex twice, so it waits for itself
+int main(){
+    int mutex = 0;
+    mutex_lock(&mutex);
+    return mutex_lock(&mutex);
+}
@@ -0,0 +1,17 @@
+#include <libc.h>
+
+// spawns the images "x" & "y" (of child.c), they run while the parent waits
+int main(){
+    int x = spawn("x");
+    int y = spawn("y");
+    if(spawn("missing") != -1){
+        return 1;
+    }
+    int x_status = waitpid(x);
+    if(waitpid(x) != -1){
+        return 2;
+    }
+    int y_status = waitpid(y);
+    putchar('p');
+    return x_status + y_status;
+}
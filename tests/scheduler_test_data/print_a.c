@@ -0,0 +1,9 @@
+#include <libc.h>
+
+int main(){
+    int i;
+    for(i = 0; i < 20; i++){
+        putchar('a');
+    }
+    return 20;
+}
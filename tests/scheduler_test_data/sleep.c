@@ -0,0 +1,11 @@
+#include <libc.h>
+
+// returns 1 if the sleep took at least 1000 cycles
+int main(){
+    putchar('z');
+    int start = clock();
+    sleep(1000);
+    int elapsed = clock() - start;
+    putchar('Z');
+    return elapsed >= 1000;
+}
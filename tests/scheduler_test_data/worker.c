@@ -0,0 +1,19 @@
+#include <libc.h>
+
+// increments the shared counter 100 times, the mutex keeps increments of other processes from getting lost
+int main(){
+    int* shared = shm_attach(1);
+    int* mutex = shared;
+    int* counter = shared + 1;
+    int i;
+    for(i = 0; i < 100; i++){
+        mutex_lock(mutex);
+        int count = *counter;
+        // the process is often preempted here, while it holds the mutex
+        int j;
+        for(j = 0; j < 5; j++){}
+        *counter = count + 1;
+        mutex_unlock(mutex);
+    }
+    return 0;
+}
@@ -0,0 +1,27 @@
+#include <libc.h>
+
+// spawns 3 workers (worker.c's image) that increment a shared counter, & increments it 100 times too
+int main(){
+    if(shm_create(1, 2) != 0 || shm_create(1, 2) != -1){
+        return -1;
+    }
+    int* shared = shm_attach(1);
+    int* mutex = shared;
+    int* counter = shared + 1;
+    int pids[3];
+    int i;
+    for(i = 0; i < 3; i++){
+        pids[i] = spawn("worker");
+    }
+    // a worker may hold the mutex when the first one exits
+    waitpid(pids[0]);
+    for(i = 0; i < 100; i++){
+        mutex_lock(mutex);
+        *counter = *counter + 1;
+        mutex_unlock(mutex);
+    }
+    for(i = 1; i < 3; i++){
+        waitpid(pids[i]);
+    }
+    return *counter;
+}
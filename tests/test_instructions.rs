@@ -56,6 +56,47 @@ fn test_div() {
     assert_eq!(os.cpu.regs.get(&Register::R1), 2);
 }
 #[test]
+fn test_float_arith() {
+    // 7 / 2.0 + 0.25 = 3.75, which truncates to 3
+    let program = "
+    MOV R1 7
+    ITOF R1
+    MOV R2 2
+    ITOF R2
+    FDIV R1 R1 R2
+    MOV R3 1048576000
+    FADD R1 R1 R3
+    FCMP R4 R1 R2
+    FTOI R1
+    HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.cpu.regs.get(&Register::R1), 3);
+    assert_eq!(os.cpu.regs.get(&Register::R4), 1);
+}
+#[test]
+fn test_carry_arith() {
+    // (2^32 - 1) + 1 = 2^32, held in R1 (low word) & R2 (high word), then 2^32 - 2 = 2^32 - 2
+    let program = "
+    MOV R1 -1
+    MOV R2 0
+    MOV ZR 0
+    ADC R1 R1 1
+    ADC R2 R2 0
+    MOV R3 ZR
+    SBC R1 R1 2
+    SBC R2 R2 0
+    HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.cpu.regs.get(&Register::R1), -2);
+    assert_eq!(os.cpu.regs.get(&Register::R2), 0);
+    assert_eq!(os.cpu.regs.get(&Register::R3), 0);
+    assert_eq!(os.cpu.regs.get(&Register::ZR), 0);
+}
+#[test]
 fn test_mod() {
     let program = "
     MOV R1 5
@@ -68,6 +109,57 @@ fn test_mod() {
     assert_eq!(os.cpu.regs.get(&Register::R1), 1);
 }
 #[test]
+fn test_signed_arith() {
+    // (x, y, x / y, x % y)
+    let cases = [
+        (7, 2, 3, 1),
+        (-7, 2, -3, -1),
+        (7, -2, -3, 1),
+        (-7, -2, 3, -1),
+        (-6, 3, -2, 0),
+        (1, -5, 0, 1),
+        (i32::MIN, -1, i32::MIN, 0),
+    ];
+    for (x, y, quotient, remainder) in cases.iter() {
+        let program = format!("
+        MOV R1 {}
+        MOV R2 {}
+        DIV R3 R1 R2
+        MOD R4 R1 R2
+        HALT
+        ", x, y);
+        let mut os = OS::new();
+        let _res = os.assemble_and_run_no_std(&program);
+        assert_eq!(os.cpu.regs.get(&Register::R3), *quotient, "{} / {}", x, y);
+        assert_eq!(os.cpu.regs.get(&Register::R4), *remainder, "{} % {}", x, y);
+    }
+    // overflow wraps around
+    let overflows = [
+        ("ADD", i32::MAX, 1, i32::MIN),
+        ("ADD", 2000000000, 2000000000, -294967296),
+        ("ADD", i32::MIN, -1, i32::MAX),
+        ("SUB", i32::MIN, 1, i32::MAX),
+        ("SUB", 0, i32::MIN, i32::MIN),
+        ("SUB", i32::MAX, -1, i32::MIN),
+        ("MUL", i32::MAX, 2, -2),
+        ("MUL", i32::MIN, -1, i32::MIN),
+        ("MUL", 65536, -65536, 0),
+        // only the low 5 bits of the shift count are used
+        ("SHL", 1, 33, 2),
+        ("SHR", -8, 33, -4),
+        ("SHL", 1, -1, i32::MIN),
+    ];
+    for (op, x, y, res) in overflows.iter() {
+        let program = format!("MOV R1 {}\nMOV R2 {}\n{} R3 R1 R2\nHALT", x, y, op);
+        let mut os = OS::new();
+        let _res = os.assemble_and_run_no_std(&program);
+        assert_eq!(os.cpu.regs.get(&Register::R3), *res, "{} {} {}", op, x, y);
+    }
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(&format!("MOV R1 {}\nNEG R1\nHALT", i32::MIN));
+    assert_eq!(os.cpu.regs.get(&Register::R1), i32::MIN);
+}
+#[test]
 fn test_and() {
     let program = "
     MOV R1 6
@@ -140,6 +232,17 @@ fn test_neg() {
     let _res = os.assemble_and_run_no_std(program);
     assert_eq!(os.cpu.regs.get(&Register::R1), -4);
 }
+#[test]
+fn test_not() {
+    let program = "
+    MOV R1 5
+    NOT R1
+    HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.cpu.regs.get(&Register::R1), -6);
+}
 
 #[test]
 fn test_add_imm() {
@@ -208,6 +311,50 @@ fn test_load_imm() {
     assert_eq!(os.cpu.regs.get(&Register::R2), 7);
 }
 #[test]
+fn test_strb() {
+    let program = "
+        MOV R1 32001
+        STRB R1 -1
+        ADD R1 R1 1
+        STRB R1 258
+        HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    // bytes 1 & 2 of word 8000, the word's other bytes are zero
+    assert_eq!(os.cpu.mem.get_num(8000), 0x02ff00);
+}
+#[test]
+fn test_loadb() {
+    let program = "
+        MOV R1 8000
+        STR R1 -2139160321
+        LOADB R2 32000
+        LOADB R3 32003
+        HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    // 0x807f00ff, the bytes are sign extended
+    assert_eq!(os.cpu.regs.get(&Register::R2), -1);
+    assert_eq!(os.cpu.regs.get(&Register::R3), -128);
+}
+#[test]
+fn test_tset() {
+    let program = "
+        MOV R1 8000
+        STR R1 0
+        TSET R2 R1
+        TSET R3 8000
+        HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.cpu.regs.get(&Register::R2), 0);
+    assert_eq!(os.cpu.regs.get(&Register::R3), 1);
+    assert_eq!(os.cpu.mem.get_num(8000), 1);
+}
+#[test]
 fn test_mov() {
     let program = "
         MOV R1 3
@@ -522,6 +669,66 @@ fn test_call_ret_with_args_and_retval() {
     assert_eq!(os.cpu.regs.get(&Register::R1), 3);
 }
 
+#[test]
+fn test_callr() {
+    let program = "
+        JUMP MAIN
+        ADD:
+        ADD R1 BP 3
+        LOAD R1 R1
+        ADD R2 BP 4
+        LOAD R2 R2
+        ADD R1 R1 R2
+        ADD R2 BP 2
+        STR R2 R1
+        RET
+        MAIN:
+        MOV R3 1
+        PUSH R3
+        MOV R3 2
+        PUSH R3
+        MOV R3 0
+        PUSH R3
+        LEA R4 ADD
+        CALLR R4
+        POP R1
+        HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.cpu.regs.get(&Register::R1), 3);
+}
+
+#[test]
+fn test_jmpr() {
+    // jumps through a table of labels, like a switch's jump table
+    let program = "
+        .word TABLE ONE TWO THREE
+        JUMP MAIN
+        ONE:
+        MOV R1 10
+        HALT
+        TWO:
+        MOV R1 20
+        HALT
+        THREE:
+        MOV R1 30
+        HALT
+        MAIN:
+        MOV R2 SP
+        LEA R3 TABLE+1
+        LOAD R3 R3
+        JMPR R3
+        MOV R1 0
+        HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.cpu.regs.get(&Register::R1), 20);
+    // unlike CALLR, nothing is pushed
+    assert_eq!(os.cpu.regs.get(&Register::SP), os.cpu.regs.get(&Register::R2));
+}
+
 #[test]
 fn test_call_multiple() {
     let program = "
@@ -628,3 +835,81 @@ fn test_program_ret_val() {
     let res = os.assemble_and_run_no_std(program);
     assert_eq!(res, 3);
 }
+
+#[test]
+fn test_syscall_putchar() {
+    let program = "
+    MOV R1 2
+    MOV R2 97
+    SYSCALL
+    HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.out_chars, vec!['a']);
+    assert_eq!(os.cpu.regs.get(&Register::R1), 97);
+}
+
+#[test]
+fn test_syscall_exit() {
+    let program = "
+    MAIN:
+    MOV R1 1
+    MOV R2 7
+    SYSCALL
+    MOV R1 2
+    MOV R2 97
+    SYSCALL
+    RET
+    ";
+    let mut os = OS::new();
+    let res = os.assemble_and_run_no_std(program);
+    assert_eq!(res, 7);
+    assert!(os.out_chars.is_empty());
+}
+
+#[test]
+fn test_timer_interrupt() {
+    // the timer interrupt handler must not change the registers of the loop
+    let program = "
+    .define TMS 208
+    .define TMD 209
+    MOV R1 TMD
+    STR R1 10
+    MOV R1 TMS
+    STR R1 1
+    STI
+    MOV R1 7
+    MOV R2 0
+    loop:
+    ADD R2 R2 1
+    TSTL R2 100
+    TJMP loop
+    CLI
+    HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert!(os.timer_ticks >= 20);
+    assert_eq!(os.cpu.regs.get(&Register::R1), 7);
+    assert_eq!(os.cpu.regs.get(&Register::R2), 100);
+}
+
+#[test]
+fn test_interrupts_disabled() {
+    let program = "
+    MOV R1 209
+    STR R1 10
+    MOV R1 208
+    STR R1 1
+    MOV R2 0
+    loop:
+    ADD R2 R2 1
+    TSTL R2 100
+    TJMP loop
+    HALT
+    ";
+    let mut os = OS::new();
+    let _res = os.assemble_and_run_no_std(program);
+    assert_eq!(os.timer_ticks, 0);
+}
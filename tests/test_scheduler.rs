@@ -0,0 +1,204 @@
+extern crate simple_vm;
+
+use simple_vm::operating_system::process::KILLED_EXIT_CODE;
+use simple_vm::operating_system::{FAULT_EXIT_CODE, OS};
+
+// prints the char in R2 10 times, returns 5
+fn print_loop_program(c: char) -> String {
+    format!("
+    .global main
+    main:
+    MOV R3 10
+    LOOP:
+    MOV R1 2
+    MOV R2 {}
+    SYSCALL
+    SUB R3 R3 1
+    TSTE R3 0
+    FJMP LOOP
+    ADD R1 BP 2
+    STR R1 5
+    RET
+    ", c as i32)
+}
+
+#[test]
+fn test_run_all_round_robin() {
+    let mut os = OS::new();
+    let pid1 = os.spawn(&print_loop_program('a'));
+    let pid2 = os.spawn(&print_loop_program('b'));
+    assert_eq!((pid1, pid2), (0, 1));
+    let exit_values = os.run_all();
+    assert_eq!(exit_values, vec![5, 5]);
+    let out = os.get_output();
+    assert_eq!(out.matches('a').count(), 10);
+    assert_eq!(out.matches('b').count(), 10);
+    // the processes are time sliced, so their outputs are interleaved
+    assert_ne!(out, "a".repeat(10) + &"b".repeat(10));
+}
+
+#[test]
+fn test_run_all_compiled_programs() {
+    let mut os = OS::new();
+    let program1 = os.compile("tests/compiler_test_data/globals/inputs/1.c");
+    let program2 = os.compile("tests/compiler_test_data/globals/inputs/2.c");
+    let program3 = os.compile("tests/compiler_test_data/io/inputs/printstr.c");
+    os.spawn(&program1);
+    os.spawn(&program2);
+    os.spawn(&program3);
+    assert_eq!(os.run_all(), vec![8, 1, 0]);
+    assert_eq!(os.get_output(), "hello, world!");
+}
+
+#[test]
+fn test_run_all_exit_syscall() {
+    let program = "
+    .global main
+    main:
+    MOV R1 1
+    MOV R2 3
+    SYSCALL
+    HALT
+    ";
+    let mut os = OS::new();
+    os.spawn(program);
+    os.spawn(&print_loop_program('a'));
+    assert_eq!(os.run_all(), vec![3, 5]);
+}
+
+#[test]
+fn test_preempt_compiled_programs() {
+    let mut os = OS::new();
+    let program1 = os.compile("tests/scheduler_test_data/print_a.c");
+    let program2 = os.compile("tests/scheduler_test_data/print_b.c");
+    os.spawn(&program1);
+    os.spawn(&program2);
+    assert_eq!(os.run_all(), vec![20, 20]);
+    let out = os.get_output();
+    assert_eq!(out.matches('a').count(), 20);
+    assert_eq!(out.matches('b').count(), 20);
+    // neither program yields, so the output is interleaved only if the timer preempts them
+    assert!(out.starts_with('a'));
+    assert!(out.contains("ab") && out.contains("ba"));
+    assert!(os.timer_ticks > 2);
+}
+
+#[test]
+fn test_processes_have_separate_address_spaces() {
+    // both processes use the same addresses for the global counter, but each has its own copy
+    let mut os = OS::new();
+    let program = os.compile("tests/scheduler_test_data/counter.c");
+    os.spawn(&program);
+    os.spawn(&program);
+    assert_eq!(os.run_all(), vec![100, 100]);
+    assert!(os.timer_ticks > 2);
+}
+
+#[test]
+fn test_process_segmentation_fault() {
+    // the address is inside the second process's region, but out of the first process's segment
+    let program = "
+    .global main
+    main:
+    MOV R1 15000
+    STR R1 1
+    HALT
+    ";
+    let mut os = OS::new();
+    os.spawn(program);
+    os.spawn(&print_loop_program('a'));
+    assert_eq!(os.run_all(), vec![FAULT_EXIT_CODE, 5]);
+    assert_eq!(os.get_output(), "a".repeat(10));
+}
+
+// installs child.c's image in the file system as x & y
+fn install_child_images(os: &mut OS) {
+    let child = os.compile_object("tests/scheduler_test_data/child.c");
+    let image = os.link_image(vec![&child]);
+    os.fs.write_file("x", &image);
+    os.fs.write_file("y", &image);
+}
+
+#[test]
+fn test_program_waits_for_children() {
+    let mut os = OS::new();
+    install_child_images(&mut os);
+    let parent = os.compile_object("tests/scheduler_test_data/parent.c");
+    assert_eq!(os.link_and_run(vec![&parent]), 14);
+    let out = os.get_output();
+    assert_eq!(out.matches('x').count(), 10);
+    assert_eq!(out.matches('y').count(), 10);
+    assert!(out.contains("xy") || out.contains("yx"));
+    assert!(out.ends_with('p'));
+}
+
+#[test]
+fn test_process_waits_for_children() {
+    let mut os = OS::new();
+    install_child_images(&mut os);
+    let parent = os.compile("tests/scheduler_test_data/parent.c");
+    os.spawn(&parent);
+    os.spawn(&print_loop_program('a'));
+    // the children get the next pids
+    assert_eq!(os.run_all(), vec![14, 5, 7, 7]);
+    let out = os.get_output();
+    assert_eq!(out.matches('x').count(), 10);
+    assert_eq!(out.matches('a').count(), 10);
+    assert!(out.ends_with('p'));
+}
+
+fn install_worker_image(os: &mut OS) {
+    let worker = os.compile_object("tests/scheduler_test_data/worker.c");
+    let image = os.link_image(vec![&worker]);
+    os.fs.write_file("worker", &image);
+}
+
+#[test]
+fn test_program_shares_memory_with_processes() {
+    let mut os = OS::new();
+    install_worker_image(&mut os);
+    let workers = os.compile_object("tests/scheduler_test_data/workers.c");
+    assert_eq!(os.link_and_run(vec![&workers]), 400);
+}
+
+#[test]
+fn test_processes_share_memory() {
+    let mut os = OS::new();
+    install_worker_image(&mut os);
+    let workers = os.compile("tests/scheduler_test_data/workers.c");
+    os.spawn(&workers);
+    assert_eq!(os.run_all(), vec![400, 0, 0, 0]);
+    assert!(os.timer_ticks > 10);
+}
+
+#[test]
+fn test_deadlock() {
+    // the os's program can't wait for itself, & a deadlocked process is killed
+    let mut os = OS::new();
+    let deadlock = os.compile_object("tests/scheduler_test_data/deadlock.c");
+    assert_eq!(os.link_and_run(vec![&deadlock]), -1);
+    let deadlock = os.compile("tests/scheduler_test_data/deadlock.c");
+    os.spawn(&deadlock);
+    os.spawn(&print_loop_program('a'));
+    assert_eq!(os.run_all(), vec![KILLED_EXIT_CODE, 5]);
+}
+
+#[test]
+fn test_program_sleeps() {
+    let mut os = OS::new();
+    let sleep = os.compile_object("tests/scheduler_test_data/sleep.c");
+    assert_eq!(os.link_and_run(vec![&sleep]), 1);
+    assert_eq!(os.get_output(), "zZ");
+    assert!(os.cpu.cycles > 1000);
+}
+
+#[test]
+fn test_sleeping_process_lets_others_run() {
+    let mut os = OS::new();
+    let sleep = os.compile("tests/scheduler_test_data/sleep.c");
+    os.spawn(&sleep);
+    os.spawn(&print_loop_program('a'));
+    assert_eq!(os.run_all(), vec![1, 5]);
+    // the other process finishes long before the sleeping one wakes, & then the cpu idles
+    assert_eq!(os.get_output(), format!("z{}Z", "a".repeat(10)));
+}
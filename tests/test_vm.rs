@@ -0,0 +1,98 @@
+extern crate simple_vm;
+
+use simple_vm::cpu::RunOutcome;
+use simple_vm::operating_system::compiler::{CompilerOptions, OptLevel};
+use simple_vm::operating_system::FAULT_EXIT_CODE;
+use simple_vm::Vm;
+
+#[test]
+fn test_run() {
+    let mut vm = Vm::builder()
+        .compile_file("tests/vm_test_data/digit_sum.c")
+        .compile_file("tests/vm_test_data/sum.c")
+        .stdin("1234\n")
+        .build()
+        .unwrap();
+    let result = vm.run();
+    assert_eq!((result.exit_code, result.outcome, result.stdout.as_str()), (4, RunOutcome::Halted, "10"));
+    assert!(result.cycles > 0);
+    // each run reads the input from its start
+    assert_eq!(vm.run(), result);
+}
+
+#[test]
+fn test_compiler_options() {
+    let build = |options: CompilerOptions| Vm::builder()
+        .compile_file("tests/vm_test_data/digit_sum.c")
+        .compile_file("tests/vm_test_data/sum.c")
+        .stdin("99")
+        .compiler_options(options)
+        .build()
+        .unwrap();
+    let unoptimized = build(CompilerOptions::new()).run();
+    let optimized = build(CompilerOptions::new().opt_level(OptLevel::O2)).run();
+    assert_eq!(optimized.stdout, "18");
+    assert!(optimized.cycles < unoptimized.cycles);
+}
+
+#[test]
+fn test_max_cycles() {
+    let mut vm = Vm::builder()
+        .compile_file("tests/vm_test_data/forever.c")
+        .max_cycles(10_000)
+        .build()
+        .unwrap();
+    let result = vm.run();
+    assert_eq!((result.exit_code, result.outcome, result.stdout.as_str()), (FAULT_EXIT_CODE, RunOutcome::Timeout, "a"));
+    assert_eq!(result.cycles, 10_000);
+}
+
+#[test]
+fn test_compile_errors() {
+    let errors = Vm::builder().compile_file("tests/vm_test_data/bad.c").build().err().unwrap();
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_args() {
+    let mut vm = Vm::builder()
+        .compile_file("tests/args_test_data/args.c")
+        .arg("-O2")
+        .arg("two words")
+        .build()
+        .unwrap();
+    let result = vm.run();
+    assert_eq!((result.exit_code, result.stdout.as_str()), (12, "-O2\ntwo words\n"));
+}
+
+#[test]
+fn test_random_seed() {
+    let run = |seed: Option<u64>| {
+        let builder = Vm::builder().compile_file("tests/vm_test_data/random.c");
+        let builder = match seed {
+            Some(seed) => builder.random_seed(seed),
+            None => builder,
+        };
+        builder.build().unwrap().run()
+    };
+    let result = run(None);
+    assert_eq!(result.exit_code, 1);
+    assert_eq!(result.stdout.split(' ').count(), 3);
+    // the numbers are reproducible, & the seed changes them
+    assert_eq!(run(None).stdout, result.stdout);
+    assert_eq!(run(Some(5)).stdout, run(Some(5)).stdout);
+    assert_ne!(run(Some(5)).stdout, result.stdout);
+}
+
+#[test]
+fn test_compile_source() {
+    let mut vm = Vm::builder()
+        .compile_source("main.c", "#include <libc.h>\nint sum(int a, int b);\nint main(int argc, char** argv){ printf(\"%s\", argv[0]); return sum(3, 4); }")
+        .compile_file("tests/vm_test_data/sum.c")
+        .build()
+        .unwrap();
+    let result = vm.run();
+    assert_eq!((result.exit_code, result.stdout.as_str()), (7, "main.c"));
+    let errors = Vm::builder().compile_source("bad.c", "int main(){\n    return x;\n}").build().err().unwrap();
+    assert_eq!(errors[0].to_string(), "bad.c:2: error: 'x' undeclared");
+}
@@ -0,0 +1,18 @@
+struct A{
+    int x;
+};
+
+struct B{
+    int y;
+};
+
+int main(){
+    struct A a;
+    struct B b;
+    int* p;
+    char* s = "abc";
+    a = b;
+    p = s;
+    int x = a;
+    return x;
+}
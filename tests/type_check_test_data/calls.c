@@ -0,0 +1,31 @@
+struct A{
+    int x;
+};
+
+int foo(int x, char* s){
+    return x;
+}
+
+void bar(int x){
+    return x;
+}
+
+int log(char* fmt, ...);
+
+struct A make(){
+    struct A a;
+    return 5;
+}
+
+int main(){
+    struct A a;
+    foo(1);
+    foo(a, "s");
+    int (*f)(int, char*) = foo;
+    f(1, 2, 3);
+    log("%d %d", 1, 2);
+    log();
+    exit(a);
+    abort(1);
+    return 0;
+}
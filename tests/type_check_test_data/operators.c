@@ -0,0 +1,36 @@
+struct A{
+    int x;
+    int arr[2][3];
+};
+
+int main(){
+    int* p;
+    int* q;
+    int arr[2][3];
+    struct A a;
+    struct A* pa = &a;
+    p + q;
+    p * 2;
+    a + 1;
+    arr[1];
+    a[0];
+    a.y;
+    pa.x;
+    a->x;
+    *a;
+    ~p;
+    p - q;
+    p[1] = arr[1][2] + a.arr[0][1] + pa->x;
+    1.5 % 2;
+    ~0.5f;
+    p = p + 0.5;
+    (int)a;
+    (float)p;
+    (char*)pa;
+    (void)a;
+    long l;
+    l * 2;
+    l + 0.5;
+    l << 1;
+    return 0;
+}
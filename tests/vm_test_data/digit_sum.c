@@ -0,0 +1,17 @@
+#include <libc.h>
+
+int sum(int a, int b);
+
+// prints the sum of the digits in the input, returns the number of digits
+int main(){
+    int total = 0;
+    int digits = 0;
+    int c = getchar();
+    while(c >= '0' && c <= '9'){
+        total = sum(total, c - '0');
+        digits++;
+        c = getchar();
+    }
+    printf("%d", total);
+    return digits;
+}
@@ -0,0 +1,8 @@
+#include <libc.h>
+
+int main(){
+    putchar('a');
+    while(1){
+    }
+    return 0;
+}
@@ -0,0 +1,11 @@
+#include <libc.h>
+
+// prints 3 random numbers, & returns 1 if srand restarts them
+int main(){
+    int first = rand();
+    printf("%d %d %d", first, rand(), rand());
+    srand(7);
+    int after_srand = rand();
+    srand(7);
+    return rand() == after_srand;
+}